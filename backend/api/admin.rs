@@ -0,0 +1,228 @@
+use crate::auth::AuthenticatedUser;
+use crate::error::ApiError;
+use crate::extract::ApiJson;
+use crate::state::AppState;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use team_operation_system::db::{
+    record_request_log, seed_defaults, server_now, BackupService, RestoreOutcome,
+};
+
+/// 导出全部业务数据（含密码哈希）为 JSON 备份文件
+pub async fn backup(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Response, ApiError> {
+    auth_user.require_permission("系统管理")?;
+
+    let document = match BackupService::export(&state.pool).await {
+        Ok(document) => document,
+        Err(e) => {
+            log::error!("导出备份失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/admin/backup",
+                Some(auth_user.qq()),
+                Some(format!("导出备份失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("导出备份失败"));
+        }
+    };
+
+    log::info!("数据备份已导出: 操作人={}", auth_user.qq());
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        "/admin/backup",
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    let filename = format!("backup-{}.json", server_now().format("%Y%m%d-%H%M%S"));
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "application/json; charset=utf-8".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        document.to_string(),
+    )
+        .into_response())
+}
+
+/// 将备份文件恢复到空数据库，整个过程在一个事务中完成，失败时不写入任何数据
+pub async fn restore(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    ApiJson(document): ApiJson<Value>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("系统管理")?;
+
+    let restored = match BackupService::restore(&state.pool, &document).await {
+        Ok(RestoreOutcome::Restored(restored)) => restored,
+        Ok(RestoreOutcome::NotEmpty) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/admin/restore",
+                Some(auth_user.qq()),
+                Some("数据库非空，拒绝恢复".to_string()),
+                StatusCode::CONFLICT.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::conflict(
+                "只能恢复到空数据库（不能已有LP、商品、抽奖记录或除默认管理员外的用户）",
+            ));
+        }
+        Ok(RestoreOutcome::Invalid(message)) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/admin/restore",
+                Some(auth_user.qq()),
+                Some(message.clone()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+        Err(e) => {
+            log::error!("恢复备份失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/admin/restore",
+                Some(auth_user.qq()),
+                Some(format!("恢复备份失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("恢复备份失败"));
+        }
+    };
+
+    let tables = restored
+        .iter()
+        .map(|(table, count)| (table.clone(), json!(count)))
+        .collect::<serde_json::Map<_, _>>();
+    log::info!("数据备份已恢复: 操作人={}, {:?}", auth_user.qq(), restored);
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/admin/restore",
+        Some(auth_user.qq()),
+        Some(Value::Object(tables.clone()).to_string()),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "message": "数据恢复成功", "tables": tables })))
+}
+
+/// 重新写入内置的权限、角色和LP类型，只补齐缺失的条目，不修改已有数据和用户
+pub async fn reseed_defaults(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("系统管理")?;
+
+    let seeded = match seed_defaults(&state.pool).await {
+        Ok(seeded) => seeded,
+        Err(e) => {
+            log::error!("恢复默认数据失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/admin/reseed-defaults",
+                Some(auth_user.qq()),
+                Some(format!("恢复默认数据失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("恢复默认数据失败"));
+        }
+    };
+
+    let restored = json!({
+        "permissions": seeded.permissions,
+        "roles": seeded.roles,
+        "lp_types": seeded.lp_types,
+    });
+    if !seeded.is_empty() {
+        log::warn!(
+            "已恢复缺失的默认数据: 操作人={}, {}",
+            auth_user.qq(),
+            restored
+        );
+    }
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/admin/reseed-defaults",
+        Some(auth_user.qq()),
+        Some(restored.to_string()),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    let message = if seeded.is_empty() {
+        "默认数据完整，无需恢复"
+    } else {
+        "已恢复缺失的默认数据"
+    };
+    Ok(Json(json!({ "message": message, "restored": restored })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_user, test_state};
+
+    #[tokio::test]
+    async fn reseed_restores_deleted_default_permission() {
+        let state = test_state().await;
+        sqlx::query("DELETE FROM permission WHERE name = '查看日志'")
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        let Json(body) = reseed_defaults(auth_user("10001", &["系统管理"]), State(state.clone()))
+            .await
+            .unwrap();
+        assert_eq!(body["message"], "已恢复缺失的默认数据");
+        assert_eq!(
+            body["restored"],
+            json!({ "permissions": ["查看日志"], "roles": [], "lp_types": [] })
+        );
+
+        // 删除权限时级联删除的管理员授权也一并恢复
+        let (granted,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM rolepermissionlink l JOIN role r ON r.role_id = l.role_id
+             WHERE r.name = '管理员' AND l.permission_name = '查看日志'",
+        )
+        .fetch_one(&state.pool)
+        .await
+        .unwrap();
+        assert_eq!(granted, 1);
+
+        let Json(body) = reseed_defaults(auth_user("10001", &["系统管理"]), State(state))
+            .await
+            .unwrap();
+        assert_eq!(body["message"], "默认数据完整，无需恢复");
+    }
+}
@@ -1,17 +1,47 @@
-use crate::{auth::AuthenticatedUser, state::AppState};
+use crate::{
+    auth::AuthenticatedUser, error::ApiError, pagination::set_pagination_headers, state::AppState,
+    validation::parse_window_bound,
+};
 use axum::{
-    extract::{Query, State},
+    extract::{OriginalUri, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use serde::{Deserialize, Serialize};
-use team_operation_system::{db, models::RequestLog};
+use serde_json::{json, Value};
+use team_operation_system::{
+    db::{self, record_request_log, server_now, TIMESTAMP_FORMAT},
+    models::RequestLog,
+};
+
+/// 单次查询日志的最大条数，防止一次拉取过多数据
+const MAX_LOG_LIMIT: i32 = 200;
 
 #[derive(Debug, Deserialize)]
 pub struct LogQuery {
     limit: Option<i32>,
     offset: Option<i32>,
     user_qq: Option<String>,
+    /// 只返回 ID 大于该值的新日志，用于前端轮询；指定后忽略 `offset`
+    since_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    limit: Option<i32>,
+    offset: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DenialsQuery {
+    /// 统计最近多少天，默认 7 天
+    days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteLogsQuery {
+    user_qq: Option<String>,
+    path: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -24,6 +54,7 @@ pub struct LogListResponse {
 pub async fn list_logs(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
     Query(query): Query<LogQuery>,
 ) -> impl IntoResponse {
     // 检查权限
@@ -40,9 +71,10 @@ pub async fn list_logs(
         limit,
         offset,
         user_qq,
+        since_id,
     } = query;
 
-    let limit_value = limit.unwrap_or(100).clamp(1, 500) as i64;
+    let limit_value = limit.unwrap_or(100).clamp(1, MAX_LOG_LIMIT) as i64;
     let offset_value = offset.unwrap_or(0).max(0) as i64;
     let user_filter = user_qq
         .as_deref()
@@ -50,8 +82,11 @@ pub async fn list_logs(
         .filter(|qq| !qq.is_empty())
         .map(|qq| qq.to_string());
 
-    // 如果指定了用户QQ，则查询该用户的日志
-    let logs = if let Some(user_qq) = user_filter.as_ref() {
+    // 增量查询优先；否则如果指定了用户QQ，则查询该用户的日志
+    let logs = if let Some(since_id) = since_id {
+        db::list_request_logs_since(&state.pool, since_id, user_filter.as_deref(), limit_value)
+            .await
+    } else if let Some(user_qq) = user_filter.as_ref() {
         db::list_request_logs_by_user(&state.pool, user_qq, limit_value, offset_value).await
     } else {
         db::list_request_logs(&state.pool, limit_value, offset_value).await
@@ -91,5 +126,305 @@ pub async fn list_logs(
         },
     };
 
-    (StatusCode::OK, Json(LogListResponse { logs, total })).into_response()
+    let mut response = (StatusCode::OK, Json(LogListResponse { logs, total })).into_response();
+    // 增量查询不按页翻动，只返回总数
+    let (page_limit, page_offset) = if since_id.is_some() {
+        (0, 0)
+    } else {
+        (limit_value, offset_value)
+    };
+    set_pagination_headers(response.headers_mut(), &uri, total, page_limit, page_offset);
+    response
+}
+
+/// 当前用户自己的操作记录，不需要查看日志权限；请求体中的密码、令牌等字段会被遮盖
+pub async fn my_activity(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<LogListResponse>, ApiError> {
+    let limit_value = query.limit.unwrap_or(20).clamp(1, MAX_LOG_LIMIT) as i64;
+    let offset_value = query.offset.unwrap_or(0).max(0) as i64;
+
+    let logs =
+        match db::list_request_logs_by_user(&state.pool, auth_user.qq(), limit_value, offset_value)
+            .await
+        {
+            Ok(logs) => logs,
+            Err(e) => {
+                log::error!("查询操作记录失败: {}", e);
+                return Err(ApiError::internal("查询操作记录失败"));
+            }
+        };
+
+    let total = match db::count_request_logs_by_user(&state.pool, auth_user.qq()).await {
+        Ok(total) => total,
+        Err(e) => {
+            log::error!("统计操作记录失败: {}", e);
+            return Err(ApiError::internal("统计操作记录失败"));
+        }
+    };
+
+    let logs = logs
+        .into_iter()
+        .map(|mut entry| {
+            entry.body = entry.body.map(|body| redact_body(&body));
+            entry
+        })
+        .collect();
+
+    Ok(Json(LogListResponse { logs, total }))
+}
+
+/// 字段名包含这些关键字时视为敏感字段
+const SENSITIVE_KEYS: [&str; 4] = ["password", "token", "secret", "totp"];
+
+/// 遮盖 JSON 请求体中的敏感字段，非 JSON 内容原样返回
+fn redact_body(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(mut value) => {
+            redact_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SENSITIVE_KEYS
+                    .iter()
+                    .any(|sensitive| key.contains(sensitive))
+                {
+                    *field = Value::String("***".to_string());
+                } else {
+                    redact_value(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// 统计窗口的最大天数
+const MAX_DENIAL_DAYS: i64 = 90;
+/// 拒绝访问汇总最多返回的用户数
+const DENIAL_SUMMARY_LIMIT: i64 = 50;
+
+/// 按用户汇总近期被拒绝访问（403）的请求，用于发现反复尝试越权的账号
+pub async fn list_denials(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Query(query): Query<DenialsQuery>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("查看日志")?;
+
+    let days = query.days.unwrap_or(7).clamp(1, MAX_DENIAL_DAYS);
+    let denials = match db::list_permission_denials(&state.pool, days, DENIAL_SUMMARY_LIMIT).await {
+        Ok(denials) => denials,
+        Err(e) => {
+            log::error!("统计拒绝访问记录失败: {}", e);
+            return Err(ApiError::internal("统计拒绝访问记录失败"));
+        }
+    };
+
+    Ok(Json(json!({ "days": days, "denials": denials })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogStatsQuery {
+    /// 统计起始时间（包含），默认为结束时间前 24 小时
+    start: Option<String>,
+    /// 统计结束时间（不包含），默认为当前时间
+    end: Option<String>,
+}
+
+/// 按状态码类别和请求方法统计一段时间内的请求数
+pub async fn log_stats(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Query(query): Query<LogStatsQuery>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("查看日志")?;
+
+    let end = match non_empty(query.end) {
+        Some(raw) => parse_window_bound("结束时间", &raw).map_err(ApiError::bad_request)?,
+        // 当前这一秒的请求也计入
+        None => server_now() + chrono::Duration::seconds(1),
+    };
+    let start = match non_empty(query.start) {
+        Some(raw) => parse_window_bound("起始时间", &raw).map_err(ApiError::bad_request)?,
+        None => end - chrono::Duration::hours(24),
+    };
+    if start >= end {
+        return Err(ApiError::bad_request("起始时间必须早于结束时间"));
+    }
+
+    let start = start.format(TIMESTAMP_FORMAT).to_string();
+    let end = end.format(TIMESTAMP_FORMAT).to_string();
+    match db::request_log_stats(&state.pool, &start, &end).await {
+        Ok(stats) => Ok(Json(json!({ "stats": stats }))),
+        Err(e) => {
+            log::error!("统计请求日志失败: {}", e);
+            Err(ApiError::internal("统计请求日志失败"))
+        }
+    }
+}
+
+/// 按用户QQ和/或请求路径批量删除日志，必须至少指定一个条件
+pub async fn delete_logs(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Query(query): Query<DeleteLogsQuery>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("查看日志")?;
+
+    let user_qq = non_empty(query.user_qq);
+    let path = non_empty(query.path);
+    let filter_desc = format!(
+        "user_qq={}, path={}",
+        user_qq.as_deref().unwrap_or("-"),
+        path.as_deref().unwrap_or("-")
+    );
+
+    if user_qq.is_none() && path.is_none() {
+        let _ = record_request_log(
+            &state.pool,
+            "DELETE",
+            "/logs",
+            Some(auth_user.qq()),
+            Some("未指定删除条件".to_string()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(
+            "请至少指定用户QQ或请求路径作为删除条件",
+        ));
+    }
+
+    let deleted =
+        match db::delete_request_logs(&state.pool, user_qq.as_deref(), path.as_deref()).await {
+            Ok(count) => count,
+            Err(e) => {
+                log::error!("删除请求日志失败: {}", e);
+                let _ = record_request_log(
+                    &state.pool,
+                    "DELETE",
+                    "/logs",
+                    Some(auth_user.qq()),
+                    Some(format!("{}: {}", filter_desc, e)),
+                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+                )
+                .await;
+                return Err(ApiError::internal("删除日志失败"));
+            }
+        };
+
+    log::info!(
+        "用户 {} 删除了 {} 条请求日志 ({})",
+        auth_user.qq(),
+        deleted,
+        filter_desc
+    );
+    let _ = record_request_log(
+        &state.pool,
+        "DELETE",
+        "/logs",
+        Some(auth_user.qq()),
+        Some(format!("{}, deleted={}", filter_desc, deleted)),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "message": format!("已删除 {} 条日志", deleted),
+        "deleted": deleted,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{add_user, auth_user, bearer_token, response_json, send, test_state};
+
+    #[tokio::test]
+    async fn activity_lists_only_own_rows_with_secrets_masked() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let own_body = json!({ "nickname": "张三", "password": "hunter2" }).to_string();
+        record_request_log(
+            &state.pool,
+            "PUT",
+            "/profile",
+            Some("10001"),
+            Some(own_body),
+            200,
+        )
+        .await
+        .unwrap();
+        record_request_log(&state.pool, "GET", "/shop/items", Some("10002"), None, 200)
+            .await
+            .unwrap();
+
+        let Json(response) = my_activity(
+            auth_user("10001", &[]),
+            State(state),
+            Query(ActivityQuery {
+                limit: None,
+                offset: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.total, 1);
+        assert_eq!(response.logs.len(), 1);
+        let entry = &response.logs[0];
+        assert_eq!(entry.user_qq.as_deref(), Some("10001"));
+        let body: Value = serde_json::from_str(entry.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body, json!({ "nickname": "张三", "password": "***" }));
+    }
+
+    #[tokio::test]
+    async fn pagination_headers_match_body_total() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        for _ in 0..5 {
+            db::record_request_log(&state.pool, "GET", "/shop/items", Some("10001"), None, 200)
+                .await
+                .unwrap();
+        }
+        let token = bearer_token("10002", &["查看日志"]);
+
+        let response = send(
+            &state,
+            "GET",
+            "/api/logs?user_qq=10001&limit=2&offset=2",
+            Some(&token),
+        )
+        .await;
+        let headers = response.headers().clone();
+        let (status, body) = response_json(response).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 5);
+        assert_eq!(headers["x-total-count"], "5");
+        assert_eq!(
+            headers[axum::http::header::LINK],
+            "</api/logs?user_qq=10001&limit=2&offset=0>; rel=\"prev\", \
+             </api/logs?user_qq=10001&limit=2&offset=4>; rel=\"next\""
+        );
+    }
 }
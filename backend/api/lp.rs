@@ -1,13 +1,27 @@
 use crate::auth::AuthenticatedUser;
+use crate::error::ApiError;
+use crate::export::{build_csv, csv_response};
+use crate::extract::ApiJson;
+use crate::idempotency;
+use crate::notifier::notify_user;
 use crate::state::AppState;
+use crate::validation::{
+    batch_lp_max_ids, check_max_chars, LP_DRAFT_MAX_PER_USER, LP_PICTURE_MAX_CHARS,
+    LP_REASON_MAX_CHARS, LP_ROLE_MAX_CHARS,
+};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
     Json,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
-use team_operation_system::db::{record_request_log, LpService};
+use std::collections::HashMap;
+use team_operation_system::db::{
+    record_request_log, LpService, LpTransferOutcome, UserService, NOTIFICATION_KIND_LP_PROCESSED,
+};
+use team_operation_system::models::LpDraft;
 
 #[derive(Deserialize)]
 pub struct SubmitLpRequest {
@@ -20,16 +34,44 @@ pub struct SubmitLpRequest {
     pub role: Option<String>,
 }
 
+/// 保存LP草稿，`id` 为空时新建，否则更新自己的已有草稿；草稿可以只填写部分字段
+#[derive(Deserialize)]
+pub struct SaveLpDraftRequest {
+    pub id: Option<i64>,
+    pub user_qq: Option<String>,
+    pub lp_type: Option<i64>,
+    pub num: Option<i32>,
+    #[serde(default)]
+    pub reason: String,
+    pub picture: Option<String>,
+    pub role: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CloneLpRequest {
+    pub user_qq: String,
+}
+
+#[derive(Deserialize)]
+pub struct TransferLpRequest {
+    pub from_qq: String,
+    pub to_qq: String,
+    pub num: i32,
+    pub reason: String,
+}
+
 #[derive(Deserialize)]
 pub struct ProcessLpRequest {
     pub id: i64,
     pub status: i32,
+    /// 拒绝理由，只在拒绝（status = 2）时保存
+    pub reason: Option<String>,
 }
 
 pub async fn list_lp_types(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     let types = match LpService::get_all_lp_types(&state.pool).await {
         Ok(types) => types,
         Err(e) => {
@@ -43,7 +85,7 @@ pub async fn list_lp_types(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("获取LP类型失败"));
         }
     };
 
@@ -63,8 +105,102 @@ pub async fn list_lp_types(
 pub async fn submit_lp(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-    Json(payload): Json<SubmitLpRequest>,
-) -> Result<Json<Value>, StatusCode> {
+    headers: HeaderMap,
+    ApiJson(payload): ApiJson<SubmitLpRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let is_reviewer = auth_user.has_permission("审核LP");
+    if payload.upload_user_qq != auth_user.qq() && !is_reviewer {
+        return Err(ApiError::forbidden("只能以自己的身份提交LP申请"));
+    }
+    // 仅限本人提交模式下，普通成员不能为其他用户提交LP申请
+    if state.lp_self_submit_only && !is_reviewer && payload.user_qq != auth_user.qq() {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/lp/submit",
+            Some(auth_user.qq()),
+            Some(format!("仅限本人提交，拒绝为 {} 提交", payload.user_qq)),
+            StatusCode::FORBIDDEN.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::forbidden("当前只允许为自己提交LP申请"));
+    }
+
+    if payload.num == 0 {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/lp/submit",
+            Some(auth_user.qq()),
+            Some("LP数量为0".to_string()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request("LP数量不能为0"));
+    }
+
+    let length_check = check_lp_text_lengths(
+        &payload.reason,
+        payload.picture.as_deref(),
+        payload.role.as_deref(),
+    );
+    if let Err(message) = length_check {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/lp/submit",
+            Some(auth_user.qq()),
+            Some(message.clone()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(message));
+    }
+
+    let idem_key = idempotency::idempotency_key(&headers);
+    if let Some(cached) = idempotency::replay_or_reserve(
+        &state.pool,
+        auth_user.qq(),
+        "/lp/submit",
+        idem_key.as_deref(),
+    )
+    .await?
+    {
+        return Ok(cached);
+    }
+
+    let result = execute_submit_lp(&auth_user, &state, payload).await;
+    idempotency::finish(
+        &state.pool,
+        auth_user.qq(),
+        "/lp/submit",
+        idem_key.as_deref(),
+        &result,
+    )
+    .await;
+    result
+}
+
+/// 检查LP申请理由、图片地址和角色的长度，提交申请和保存草稿共用
+fn check_lp_text_lengths(
+    reason: &str,
+    picture: Option<&str>,
+    role: Option<&str>,
+) -> Result<(), String> {
+    check_max_chars("申请理由", reason, LP_REASON_MAX_CHARS)?;
+    check_max_chars(
+        "图片地址",
+        picture.unwrap_or_default(),
+        LP_PICTURE_MAX_CHARS,
+    )?;
+    check_max_chars("角色", role.unwrap_or_default(), LP_ROLE_MAX_CHARS)
+}
+
+async fn execute_submit_lp(
+    auth_user: &AuthenticatedUser,
+    state: &AppState,
+    payload: SubmitLpRequest,
+) -> Result<Json<Value>, ApiError> {
     let SubmitLpRequest {
         upload_user_qq,
         user_qq,
@@ -75,10 +211,6 @@ pub async fn submit_lp(
         role,
     } = payload;
 
-    if upload_user_qq != auth_user.qq() && !auth_user.has_permission("审核LP") {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     let id = match LpService::submit_lp_request(
         &state.pool,
         &upload_user_qq,
@@ -103,7 +235,7 @@ pub async fn submit_lp(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("提交LP失败"));
         }
     };
 
@@ -130,243 +262,1106 @@ pub async fn submit_lp(
     Ok(Json(json!({ "message": "LP申请提交成功", "id": id })))
 }
 
-pub async fn list_lp_logs(
+/// 保存LP草稿。草稿只对创建者本人可见，不进入审批流程
+pub async fn save_lp_draft(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-) -> Result<Json<Value>, StatusCode> {
-    if !auth_user.has_permission("审核LP") && !auth_user.has_permission("查看日志") {
-        return Err(StatusCode::FORBIDDEN);
+    ApiJson(payload): ApiJson<SaveLpDraftRequest>,
+) -> Result<Json<Value>, ApiError> {
+    if let Err(message) = check_lp_text_lengths(
+        &payload.reason,
+        payload.picture.as_deref(),
+        payload.role.as_deref(),
+    ) {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/lp/draft",
+            Some(auth_user.qq()),
+            Some(message.clone()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(message));
     }
 
-    let logs = match LpService::get_all_lp_logs(&state.pool).await {
-        Ok(logs) => logs,
+    let draft = LpDraft {
+        id: payload.id.unwrap_or(0),
+        owner_qq: auth_user.qq().to_string(),
+        user_qq: payload
+            .user_qq
+            .map(|qq| qq.trim().to_string())
+            .filter(|qq| !qq.is_empty()),
+        lp_type: payload.lp_type,
+        num: payload.num,
+        reason: payload.reason,
+        picture: payload.picture.filter(|picture| !picture.is_empty()),
+        role: payload.role.filter(|role| !role.is_empty()),
+        updated_at: String::new(),
+    };
+
+    let result: Result<Result<i64, ApiError>, sqlx::Error> = async {
+        if draft.id == 0 {
+            let count = LpService::count_drafts(&state.pool, &draft.owner_qq).await?;
+            if count >= LP_DRAFT_MAX_PER_USER {
+                return Ok(Err(ApiError::conflict(format!(
+                    "最多只能保存{}份草稿，请先提交或删除已有草稿",
+                    LP_DRAFT_MAX_PER_USER
+                ))));
+            }
+        }
+        match LpService::save_draft(&state.pool, &draft).await? {
+            Some(id) => Ok(Ok(id)),
+            None => Ok(Err(ApiError::new(StatusCode::NOT_FOUND, "草稿不存在"))),
+        }
+    }
+    .await;
+    let id = match result {
+        Ok(Ok(id)) => id,
+        Ok(Err(error)) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lp/draft",
+                Some(auth_user.qq()),
+                Some(error.to_string()),
+                error.status().as_u16() as i32,
+            )
+            .await;
+            return Err(error);
+        }
         Err(e) => {
-            log::error!("获取LP日志失败: {}", e);
+            log::error!("保存LP草稿失败: {}", e);
             let _ = record_request_log(
                 &state.pool,
-                "GET",
-                "/lp/logs",
+                "POST",
+                "/lp/draft",
                 Some(auth_user.qq()),
-                Some(format!("获取LP日志失败: {}", e)),
+                Some(format!("保存LP草稿失败: {}", e)),
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("保存LP草稿失败"));
         }
     };
 
     let _ = record_request_log(
         &state.pool,
-        "GET",
-        "/lp/logs",
+        "POST",
+        "/lp/draft",
         Some(auth_user.qq()),
-        None,
+        Some(format!("草稿ID: {}", id)),
         StatusCode::OK.as_u16() as i32,
     )
     .await;
 
-    Ok(Json(json!({ "logs": logs })))
+    Ok(Json(json!({ "message": "草稿已保存", "id": id })))
 }
 
-pub async fn process_lp(
+/// 列出当前用户自己的LP草稿
+pub async fn list_lp_drafts(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-    Json(payload): Json<ProcessLpRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    auth_user.require_permission("审核LP")?;
-
-    if let Err(e) =
-        LpService::process_lp_request(&state.pool, payload.id, auth_user.qq(), payload.status).await
-    {
-        log::error!("处理LP失败: {}", e);
-        let _ = record_request_log(
-            &state.pool,
-            "POST",
-            "/lp/process",
-            Some(auth_user.qq()),
-            Some(format!("处理LP失败: {}", e)),
-            StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
-        )
-        .await;
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
+) -> Result<Json<Value>, ApiError> {
+    let drafts = match LpService::list_drafts(&state.pool, auth_user.qq()).await {
+        Ok(drafts) => drafts,
+        Err(e) => {
+            log::error!("获取LP草稿失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/lp/drafts",
+                Some(auth_user.qq()),
+                Some(format!("获取LP草稿失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取LP草稿失败"));
+        }
+    };
 
     let _ = record_request_log(
         &state.pool,
-        "POST",
-        "/lp/process",
+        "GET",
+        "/lp/drafts",
         Some(auth_user.qq()),
-        Some(
-            serde_json::to_string(&json!({
-                "id": payload.id,
-                "status": payload.status,
-            }))
-            .unwrap_or_default(),
-        ),
+        None,
         StatusCode::OK.as_u16() as i32,
     )
     .await;
 
-    Ok(Json(json!({ "message": "LP审批完成" })))
-}
-
-#[derive(Deserialize)]
-pub struct BatchProcessLpRequest {
-    pub ids: Vec<i64>,
-    pub status: i32,
+    Ok(Json(json!({ "drafts": drafts })))
 }
 
-/// 批量审批LP申请
-pub async fn batch_process_lp(
+/// 删除当前用户自己的LP草稿
+pub async fn delete_lp_draft(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-    Json(payload): Json<BatchProcessLpRequest>,
-) -> Result<Json<Value>, StatusCode> {
-    auth_user.require_permission("审核LP")?;
-
-    if payload.ids.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-
-    let approved_count = match team_operation_system::db::batch_approve_lp(
-        &state.pool,
-        &payload.ids,
-        auth_user.qq(),
-        payload.status,
-    )
-    .await
-    {
-        Ok(count) => count,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/lp/drafts/{}", id);
+    match LpService::delete_draft(&state.pool, auth_user.qq(), id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = record_request_log(
+                &state.pool,
+                "DELETE",
+                &path,
+                Some(auth_user.qq()),
+                Some("草稿不存在".to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "草稿不存在"));
+        }
         Err(e) => {
-            log::error!("批量处理LP失败: {}", e);
+            log::error!("删除LP草稿失败: {}", e);
             let _ = record_request_log(
                 &state.pool,
-                "POST",
-                "/lp/batch-process",
+                "DELETE",
+                &path,
                 Some(auth_user.qq()),
-                Some(format!("批量处理LP失败: {}", e)),
+                Some(format!("删除LP草稿失败: {}", e)),
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("删除LP草稿失败"));
         }
-    };
+    }
 
     let _ = record_request_log(
         &state.pool,
-        "POST",
-        "/lp/batch-process",
+        "DELETE",
+        &path,
         Some(auth_user.qq()),
-        Some(
-            serde_json::to_string(&json!({
-                "ids": payload.ids,
-                "status": payload.status,
-                "approved_count": approved_count,
-            }))
-            .unwrap_or_default(),
-        ),
+        None,
         StatusCode::OK.as_u16() as i32,
     )
     .await;
 
-    Ok(Json(json!({
-        "message": "批量审批完成",
-        "approved_count": approved_count,
-        "requested_count": payload.ids.len(),
-    })))
+    Ok(Json(json!({ "message": "草稿已删除" })))
 }
 
-pub async fn user_lp_detail(
+/// 记录草稿提交被拒绝的请求日志并返回对应错误
+async fn reject_draft_submit(
+    state: &AppState,
+    auth_user: &AuthenticatedUser,
+    path: &str,
+    status: StatusCode,
+    message: String,
+) -> ApiError {
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        path,
+        Some(auth_user.qq()),
+        Some(message.clone()),
+        status.as_u16() as i32,
+    )
+    .await;
+    ApiError::new(status, message)
+}
+
+/// 提交草稿：检查字段完整后按普通LP申请的规则转为待审批申请，并删除草稿
+pub async fn submit_lp_draft(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-    Path(user_qq): Path<String>,
-) -> Result<Json<Value>, StatusCode> {
-    if auth_user.qq() != user_qq
-        && !auth_user.has_permission("审核LP")
-        && !auth_user.has_permission("查看日志")
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/lp/drafts/{}/submit", id);
+    let draft = match LpService::get_draft(&state.pool, auth_user.qq(), id).await {
+        Ok(Some(draft)) => draft,
+        Ok(None) => {
+            return Err(reject_draft_submit(
+                &state,
+                &auth_user,
+                &path,
+                StatusCode::NOT_FOUND,
+                "草稿不存在".to_string(),
+            )
+            .await);
+        }
+        Err(e) => {
+            log::error!("获取LP草稿失败: {}", e);
+            let _ = reject_draft_submit(
+                &state,
+                &auth_user,
+                &path,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("获取LP草稿失败: {}", e),
+            )
+            .await;
+            return Err(ApiError::internal("获取LP草稿失败"));
+        }
+    };
+
+    let (Some(user_qq), Some(lp_type), Some(num)) =
+        (draft.user_qq.clone(), draft.lp_type, draft.num)
+    else {
+        return Err(reject_draft_submit(
+            &state,
+            &auth_user,
+            &path,
+            StatusCode::BAD_REQUEST,
+            "草稿未填写完整：需要关联用户、LP类型和数量".to_string(),
+        )
+        .await);
+    };
+    if draft.reason.trim().is_empty() {
+        return Err(reject_draft_submit(
+            &state,
+            &auth_user,
+            &path,
+            StatusCode::BAD_REQUEST,
+            "草稿未填写申请理由".to_string(),
+        )
+        .await);
+    }
+    if num == 0 {
+        return Err(reject_draft_submit(
+            &state,
+            &auth_user,
+            &path,
+            StatusCode::BAD_REQUEST,
+            "LP数量不能为0".to_string(),
+        )
+        .await);
+    }
+    // 与直接提交相同：仅限本人提交模式下，普通成员不能为其他用户提交
+    if state.lp_self_submit_only && !auth_user.has_permission("审核LP") && user_qq != auth_user.qq()
     {
-        return Err(StatusCode::FORBIDDEN);
+        return Err(reject_draft_submit(
+            &state,
+            &auth_user,
+            &path,
+            StatusCode::FORBIDDEN,
+            "当前只允许为自己提交LP申请".to_string(),
+        )
+        .await);
     }
-
-    let summary = match LpService::get_user_lp_summary(&state.pool, &user_qq).await {
-        Ok(summary) => summary,
+    if let Err(message) = check_lp_text_lengths(
+        &draft.reason,
+        draft.picture.as_deref(),
+        draft.role.as_deref(),
+    ) {
+        return Err(reject_draft_submit(
+            &state,
+            &auth_user,
+            &path,
+            StatusCode::BAD_REQUEST,
+            message,
+        )
+        .await);
+    }
+    match LpService::lp_type_exists(&state.pool, lp_type).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(reject_draft_submit(
+                &state,
+                &auth_user,
+                &path,
+                StatusCode::BAD_REQUEST,
+                "LP类型不存在".to_string(),
+            )
+            .await);
+        }
         Err(e) => {
-            log::error!("获取用户LP汇总失败: {}", e);
-            let _ = record_request_log(
-                &state.pool,
-                "GET",
-                &format!("/lp/user/{}", user_qq),
-                Some(auth_user.qq()),
-                Some(format!("获取LP汇总失败: {}", e)),
-                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            log::error!("检查LP类型失败: {}", e);
+            let _ = reject_draft_submit(
+                &state,
+                &auth_user,
+                &path,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("检查LP类型失败: {}", e),
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("提交LP草稿失败"));
         }
-    };
+    }
 
-    let history = match LpService::get_user_lp_history(&state.pool, &user_qq).await {
-        Ok(history) => history,
+    let lp_id = match LpService::promote_draft(&state.pool, &draft, &user_qq, lp_type, num).await {
+        Ok(Some(lp_id)) => lp_id,
+        // 并发提交或删除时草稿已不存在
+        Ok(None) => {
+            return Err(reject_draft_submit(
+                &state,
+                &auth_user,
+                &path,
+                StatusCode::NOT_FOUND,
+                "草稿不存在".to_string(),
+            )
+            .await);
+        }
         Err(e) => {
-            log::error!("获取用户LP历史失败: {}", e);
-            let _ = record_request_log(
-                &state.pool,
-                "GET",
-                &format!("/lp/user/{}", user_qq),
-                Some(auth_user.qq()),
-                Some(format!("获取LP历史失败: {}", e)),
-                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            log::error!("提交LP草稿失败: {}", e);
+            let _ = reject_draft_submit(
+                &state,
+                &auth_user,
+                &path,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("提交LP草稿失败: {}", e),
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("提交LP草稿失败"));
         }
     };
 
     let _ = record_request_log(
         &state.pool,
-        "GET",
-        &format!("/lp/user/{}", user_qq),
+        "POST",
+        &path,
         Some(auth_user.qq()),
-        None,
+        Some(format!("LP申请ID: {}", lp_id)),
         StatusCode::OK.as_u16() as i32,
     )
     .await;
 
-    Ok(Json(json!({
-        "summary": summary,
-        "history": history,
-    })))
+    Ok(Json(json!({ "message": "LP申请提交成功", "id": lp_id })))
 }
 
-pub async fn list_lp_summaries(
+pub async fn list_lp_logs(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     if !auth_user.has_permission("审核LP") && !auth_user.has_permission("查看日志") {
-        return Err(StatusCode::FORBIDDEN);
+        return Err(ApiError::forbidden("需要审核LP或查看日志权限"));
     }
 
-    let summaries = match LpService::get_all_lp_summaries(&state.pool).await {
-        Ok(summaries) => summaries,
+    let logs = match LpService::get_all_lp_logs(&state.pool).await {
+        Ok(logs) => logs,
         Err(e) => {
-            log::error!("获取LP汇总列表失败: {}", e);
+            log::error!("获取LP日志失败: {}", e);
             let _ = record_request_log(
                 &state.pool,
                 "GET",
-                "/lp/summaries",
+                "/lp/logs",
                 Some(auth_user.qq()),
-                Some(format!("获取LP汇总列表失败: {}", e)),
+                Some(format!("获取LP日志失败: {}", e)),
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("获取LP日志失败"));
         }
     };
 
     let _ = record_request_log(
         &state.pool,
         "GET",
-        "/lp/summaries",
+        "/lp/logs",
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "logs": logs })))
+}
+
+#[derive(Deserialize)]
+pub struct ProcessedByMeQuery {
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
+}
+
+/// 分页返回当前审批人处理过的LP申请
+pub async fn list_processed_by_me(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Query(query): Query<ProcessedByMeQuery>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("审核LP")?;
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 100) as i64;
+    let offset = query.offset.unwrap_or(0).max(0) as i64;
+
+    let result = async {
+        let logs = LpService::get_processed_by(&state.pool, auth_user.qq(), limit, offset).await?;
+        let total = LpService::count_processed_by(&state.pool, auth_user.qq()).await?;
+        Ok::<_, sqlx::Error>((logs, total))
+    };
+    let (logs, total) = match result.await {
+        Ok(result) => result,
+        Err(e) => {
+            log::error!("获取审批记录失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/lp/processed-by-me",
+                Some(auth_user.qq()),
+                Some(format!("获取审批记录失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取审批记录失败"));
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        "/lp/processed-by-me",
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "logs": logs, "total": total })))
+}
+
+/// 只返回待审核的LP申请，供“只显示待处理”使用
+pub async fn list_pending_lp_logs(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    if !auth_user.has_permission("审核LP") && !auth_user.has_permission("查看日志") {
+        return Err(ApiError::forbidden("需要审核LP或查看日志权限"));
+    }
+
+    let logs = match LpService::get_pending_lp_logs(&state.pool).await {
+        Ok(logs) => logs,
+        Err(e) => {
+            log::error!("获取待处理LP失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/lp/logs/pending",
+                Some(auth_user.qq()),
+                Some(format!("获取待处理LP失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取待处理LP失败"));
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        "/lp/logs/pending",
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "logs": logs })))
+}
+
+pub async fn process_lp(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<ProcessLpRequest>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("审核LP")?;
+
+    let reject_reason = match check_reject_reason(&state, payload.status, payload.reason.as_deref())
+    {
+        Ok(reason) => reason,
+        Err(message) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lp/process",
+                Some(auth_user.qq()),
+                Some(format!("{}: 申请ID={}", message, payload.id)),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+    };
+
+    if let Err(e) = LpService::process_lp_request(
+        &state.pool,
+        payload.id,
+        auth_user.qq(),
+        payload.status,
+        reject_reason.as_deref(),
+    )
+    .await
+    {
+        log::error!("处理LP失败: {}", e);
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/lp/process",
+            Some(auth_user.qq()),
+            Some(format!("处理LP失败: {}", e)),
+            StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::internal("处理LP失败"));
+    }
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/lp/process",
+        Some(auth_user.qq()),
+        Some(
+            serde_json::to_string(&json!({
+                "id": payload.id,
+                "status": payload.status,
+                "reason": reject_reason,
+            }))
+            .unwrap_or_default(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    notify_lp_processed(&state, &[payload.id]).await;
+
+    Ok(Json(json!({ "message": "LP审批完成" })))
+}
+
+/// 检查拒绝理由：只在拒绝时保留，开启 `LP_REJECT_REASON_REQUIRED` 后拒绝必须填写。
+/// 返回去除首尾空白后的理由，未填写时为 None
+fn check_reject_reason(
+    state: &AppState,
+    status: i32,
+    reason: Option<&str>,
+) -> Result<Option<String>, String> {
+    if status != 2 {
+        return Ok(None);
+    }
+
+    let reason = reason.map(str::trim).filter(|reason| !reason.is_empty());
+    match reason {
+        Some(reason) => {
+            check_max_chars("拒绝理由", reason, LP_REASON_MAX_CHARS)?;
+            Ok(Some(reason.to_string()))
+        }
+        None if state.lp_reject_reason_required => Err("拒绝LP申请时必须填写理由".to_string()),
+        None => Ok(None),
+    }
+}
+
+/// 当前的LP审批规则，前端据此决定拒绝时是否必须填写理由
+pub async fn review_policy(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("审核LP")?;
+
+    Ok(Json(json!({
+        "reject_reason_required": state.lp_reject_reason_required,
+    })))
+}
+
+/// 通知LP申请对应的用户审批结果（站内通知，配置了通知渠道时同时发送邮件）
+async fn notify_lp_processed(state: &AppState, ids: &[i64]) {
+    for id in ids {
+        match LpService::get_lp_log(&state.pool, *id).await {
+            Ok(Some(log)) if log.status == 1 => notify_user(
+                state.notifier.clone(),
+                state.pool.clone(),
+                log.user_qq,
+                NOTIFICATION_KIND_LP_PROCESSED,
+                "LP申请已通过".to_string(),
+                format!(
+                    "你的LP申请（#{}，{} 点）已审批通过。理由：{}",
+                    id, log.num, log.reason
+                ),
+            ),
+            Ok(Some(log)) if log.status == 2 => notify_user(
+                state.notifier.clone(),
+                state.pool.clone(),
+                log.user_qq,
+                NOTIFICATION_KIND_LP_PROCESSED,
+                "LP申请未通过".to_string(),
+                match log.reject_reason {
+                    Some(reject_reason) => format!(
+                        "你的LP申请（#{}，{} 点）未通过审批。拒绝理由：{}",
+                        id, log.num, reject_reason
+                    ),
+                    None => format!("你的LP申请（#{}，{} 点）未通过审批。", id, log.num),
+                },
+            ),
+            Ok(_) => {}
+            Err(e) => log::error!("查询LP申请失败: {}", e),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BatchProcessLpRequest {
+    pub ids: Vec<i64>,
+    pub status: i32,
+    /// 拒绝理由，只在拒绝（status = 2）时保存
+    pub reason: Option<String>,
+}
+
+/// 批量审批LP申请
+pub async fn batch_process_lp(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<BatchProcessLpRequest>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("审核LP")?;
+
+    if payload.ids.is_empty() {
+        return Err(ApiError::bad_request("请选择要审批的LP申请"));
+    }
+    let max_ids = batch_lp_max_ids();
+    if payload.ids.len() > max_ids {
+        let message = format!("单次最多批量审批 {} 条LP申请", max_ids);
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/lp/batch-process",
+            Some(auth_user.qq()),
+            Some(format!("{}，本次 {} 条", message, payload.ids.len())),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(message));
+    }
+
+    let reject_reason = match check_reject_reason(&state, payload.status, payload.reason.as_deref())
+    {
+        Ok(reason) => reason,
+        Err(message) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lp/batch-process",
+                Some(auth_user.qq()),
+                Some(format!("{}，本次 {} 条", message, payload.ids.len())),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+    };
+
+    // 只通知本次实际更新的申请，避免给早已处理过的申请重复发通知
+    let approved_ids = match team_operation_system::db::batch_approve_lp(
+        &state.pool,
+        &payload.ids,
+        auth_user.qq(),
+        payload.status,
+        reject_reason.as_deref(),
+    )
+    .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            log::error!("批量处理LP失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lp/batch-process",
+                Some(auth_user.qq()),
+                Some(format!("批量处理LP失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("批量处理LP失败"));
+        }
+    };
+    let approved_count = approved_ids.len();
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/lp/batch-process",
+        Some(auth_user.qq()),
+        Some(
+            serde_json::to_string(&json!({
+                "ids": payload.ids,
+                "status": payload.status,
+                "reason": reject_reason,
+                "approved_count": approved_count,
+            }))
+            .unwrap_or_default(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    notify_lp_processed(&state, &approved_ids).await;
+
+    Ok(Json(json!({
+        "message": "批量审批完成",
+        "approved_count": approved_count,
+        "requested_count": payload.ids.len(),
+    })))
+}
+
+/// 按ID查看单条LP申请，提交人、关联用户或拥有审核LP/查看日志权限的用户可查看
+pub async fn get_lp(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/lp/{}", id);
+    let lp_log = match LpService::get_lp_log(&state.pool, id).await {
+        Ok(Some(lp_log)) => lp_log,
+        Ok(None) => {
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &path,
+                Some(auth_user.qq()),
+                Some("LP申请不存在".to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "LP申请不存在"));
+        }
+        Err(e) => {
+            log::error!("获取LP申请失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("获取LP申请失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取LP申请失败"));
+        }
+    };
+
+    if auth_user.qq() != lp_log.upload_user_qq
+        && auth_user.qq() != lp_log.user_qq
+        && !auth_user.has_permission("审核LP")
+        && !auth_user.has_permission("查看日志")
+    {
+        let _ = record_request_log(
+            &state.pool,
+            "GET",
+            &path,
+            Some(auth_user.qq()),
+            Some("无权查看该LP申请".to_string()),
+            StatusCode::FORBIDDEN.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::forbidden("只能查看自己提交或与自己相关的LP申请"));
+    }
+
+    let type_name = LpService::get_lp_type_name(&state.pool, lp_log.lp_type)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("获取LP类型名称失败: {}", e);
+            None
+        });
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &path,
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "log": lp_log, "type_name": type_name })))
+}
+
+/// 以已有LP申请为模板，为另一位用户创建新的待审批申请（用于每周考勤等重复奖励）
+pub async fn clone_lp(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    ApiJson(payload): ApiJson<CloneLpRequest>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("审核LP")?;
+    let path = format!("/lp/{}/clone", id);
+    let user_qq = payload.user_qq.trim().to_string();
+
+    match UserService::get_user(&state.pool, &user_qq).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("目标用户 {} 不存在", user_qq)),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request("目标用户不存在"));
+        }
+        Err(e) => {
+            log::error!("查询用户失败: {}", e);
+            return Err(ApiError::internal("克隆LP申请失败"));
+        }
+    }
+
+    let new_id = match LpService::clone_lp_request(&state.pool, id, auth_user.qq(), &user_qq).await
+    {
+        Ok(Some(new_id)) => new_id,
+        Ok(None) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some("LP申请不存在".to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "LP申请不存在"));
+        }
+        Err(e) => {
+            log::error!("克隆LP申请失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("克隆LP申请失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("克隆LP申请失败"));
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        &path,
+        Some(auth_user.qq()),
+        Some(
+            serde_json::to_string(&json!({ "user_qq": user_qq, "new_id": new_id }))
+                .unwrap_or_default(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "message": "LP申请克隆成功", "id": new_id })))
+}
+
+/// 在两个用户之间转移LP，生成一对互相关联的已通过记录
+pub async fn transfer_lp(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<TransferLpRequest>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("审核LP")?;
+    let from_qq = payload.from_qq.trim().to_string();
+    let to_qq = payload.to_qq.trim().to_string();
+    let reason = payload.reason.trim().to_string();
+
+    let validation = if payload.num <= 0 {
+        Err("转移数量必须大于0".to_string())
+    } else if from_qq == to_qq {
+        Err("不能向同一用户转移LP".to_string())
+    } else if reason.is_empty() {
+        Err("转移理由不能为空".to_string())
+    } else {
+        check_max_chars("转移理由", &reason, LP_REASON_MAX_CHARS)
+    };
+    if let Err(message) = validation {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/lp/transfer",
+            Some(auth_user.qq()),
+            Some(message.clone()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(message));
+    }
+
+    let outcome = LpService::transfer_lp(
+        &state.pool,
+        auth_user.qq(),
+        &from_qq,
+        &to_qq,
+        payload.num,
+        &reason,
+    )
+    .await;
+    let (debit_id, credit_id) = match outcome {
+        Ok(LpTransferOutcome::Transferred {
+            debit_id,
+            credit_id,
+        }) => (debit_id, credit_id),
+        Ok(LpTransferOutcome::InsufficientLp { available }) => {
+            let message = format!("转出用户LP不足，当前余额 {}", available);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lp/transfer",
+                Some(auth_user.qq()),
+                Some(message.clone()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+        Ok(LpTransferOutcome::UserNotFound(qq)) => {
+            let message = format!("用户 {} 不存在", qq);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lp/transfer",
+                Some(auth_user.qq()),
+                Some(message.clone()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+        Err(e) => {
+            log::error!("LP转移失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lp/transfer",
+                Some(auth_user.qq()),
+                Some(format!("LP转移失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("LP转移失败"));
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/lp/transfer",
+        Some(auth_user.qq()),
+        Some(
+            serde_json::to_string(&json!({
+                "from_qq": from_qq,
+                "to_qq": to_qq,
+                "num": payload.num,
+                "reason": reason,
+                "debit_id": debit_id,
+                "credit_id": credit_id,
+            }))
+            .unwrap_or_default(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "message": "LP转移成功",
+        "debit_id": debit_id,
+        "credit_id": credit_id,
+    })))
+}
+
+pub async fn user_lp_detail(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(user_qq): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    if auth_user.qq() != user_qq
+        && !auth_user.has_permission("审核LP")
+        && !auth_user.has_permission("查看日志")
+    {
+        return Err(ApiError::forbidden("只能查看自己的LP记录"));
+    }
+
+    let summary = match LpService::get_user_lp_summary(&state.pool, &user_qq).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            log::error!("获取用户LP汇总失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &format!("/lp/user/{}", user_qq),
+                Some(auth_user.qq()),
+                Some(format!("获取LP汇总失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取用户LP汇总失败"));
+        }
+    };
+
+    let history = match LpService::get_user_lp_history(&state.pool, &user_qq).await {
+        Ok(history) => history,
+        Err(e) => {
+            log::error!("获取用户LP历史失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &format!("/lp/user/{}", user_qq),
+                Some(auth_user.qq()),
+                Some(format!("获取LP历史失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取用户LP历史失败"));
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &format!("/lp/user/{}", user_qq),
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "summary": summary,
+        "history": history,
+    })))
+}
+
+/// 公开LP排行榜，无需登录，只返回昵称和LP总数；未开启时返回 404
+pub async fn public_leaderboard(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    let Some(size) = state.public_leaderboard_size else {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "公开排行榜未开启"));
+    };
+
+    match LpService::get_leaderboard(&state.pool, size).await {
+        Ok(entries) => Ok(Json(json!({ "entries": entries }))),
+        Err(e) => {
+            log::error!("获取公开排行榜失败: {}", e);
+            Err(ApiError::internal("获取排行榜失败"))
+        }
+    }
+}
+
+pub async fn list_lp_summaries(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    if !auth_user.has_permission("审核LP") && !auth_user.has_permission("查看日志") {
+        return Err(ApiError::forbidden("需要审核LP或查看日志权限"));
+    }
+    state.rate_limiter.check(auth_user.qq(), "/lp/summaries")?;
+
+    let summaries = match LpService::get_all_lp_summaries(&state.pool).await {
+        Ok(summaries) => summaries,
+        Err(e) => {
+            log::error!("获取LP汇总列表失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/lp/summaries",
+                Some(auth_user.qq()),
+                Some(format!("获取LP汇总列表失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取LP汇总列表失败"));
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        "/lp/summaries",
         Some(auth_user.qq()),
         None,
         StatusCode::OK.as_u16() as i32,
@@ -375,3 +1370,647 @@ pub async fn list_lp_summaries(
 
     Ok(Json(json!({ "summaries": summaries })))
 }
+
+fn lp_status_text(status: i32) -> &'static str {
+    match status {
+        0 => "待处理",
+        1 => "已通过",
+        2 => "已拒绝",
+        _ => "未知",
+    }
+}
+
+/// 导出所有用户的LP汇总为CSV
+pub async fn export_lp_summaries(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Response, ApiError> {
+    if !auth_user.has_permission("审核LP") && !auth_user.has_permission("查看日志") {
+        return Err(ApiError::forbidden("需要审核LP或查看日志权限"));
+    }
+    state
+        .rate_limiter
+        .check(auth_user.qq(), "/lp/summaries/export")?;
+
+    let summaries = match LpService::get_all_lp_summaries(&state.pool).await {
+        Ok(summaries) => summaries,
+        Err(e) => {
+            log::error!("导出LP汇总失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/lp/summaries/export",
+                Some(auth_user.qq()),
+                Some(format!("导出LP汇总失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("导出LP汇总失败"));
+        }
+    };
+
+    let rows: Vec<Vec<String>> = summaries
+        .into_iter()
+        .map(|summary| {
+            vec![
+                summary.qq,
+                summary.nickname,
+                summary.total_lp.to_string(),
+                summary.pending_count.to_string(),
+                summary.approved_count.to_string(),
+                summary.rejected_count.to_string(),
+            ]
+        })
+        .collect();
+    let csv = build_csv(
+        &["QQ", "昵称", "LP总计", "待处理", "已通过", "已拒绝"],
+        &rows,
+    );
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        "/lp/summaries/export",
+        Some(auth_user.qq()),
+        Some(format!("导出 {} 条", rows.len())),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(csv_response("lp_summaries.csv", csv))
+}
+
+/// 导出指定用户的LP历史记录为CSV
+pub async fn export_user_lp_history(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(user_qq): Path<String>,
+) -> Result<Response, ApiError> {
+    if auth_user.qq() != user_qq
+        && !auth_user.has_permission("审核LP")
+        && !auth_user.has_permission("查看日志")
+    {
+        return Err(ApiError::forbidden("只能导出自己的LP记录"));
+    }
+
+    let path = format!("/lp/user/{}/export", user_qq);
+    let history = match LpService::get_user_lp_history(&state.pool, &user_qq).await {
+        Ok(history) => history,
+        Err(e) => {
+            log::error!("导出用户LP历史失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("导出LP历史失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("导出用户LP历史失败"));
+        }
+    };
+
+    // 类型名称查询失败时退回显示类型ID，不影响导出
+    let type_names: HashMap<i64, String> = LpService::get_all_lp_types(&state.pool)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("获取LP类型失败: {}", e);
+            Vec::new()
+        })
+        .into_iter()
+        .filter_map(|lp_type| lp_type.id.map(|id| (id, lp_type.name)))
+        .collect();
+
+    let rows: Vec<Vec<String>> = history
+        .into_iter()
+        .map(|log| {
+            vec![
+                log.id.map(|id| id.to_string()).unwrap_or_default(),
+                log.upload_time,
+                log.upload_user_qq,
+                log.user_qq,
+                type_names
+                    .get(&log.lp_type)
+                    .cloned()
+                    .unwrap_or_else(|| log.lp_type.to_string()),
+                log.num.to_string(),
+                log.reason,
+                lp_status_text(log.status).to_string(),
+                log.process_user_qq.unwrap_or_default(),
+                log.process_time.unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let csv = build_csv(
+        &[
+            "ID",
+            "提交时间",
+            "提交人QQ",
+            "用户QQ",
+            "LP类型",
+            "数量",
+            "理由",
+            "状态",
+            "审核人QQ",
+            "审核时间",
+        ],
+        &rows,
+    );
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &path,
+        Some(auth_user.qq()),
+        Some(format!("导出 {} 条", rows.len())),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    let safe_qq: String = user_qq
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    Ok(csv_response(&format!("lp_history_{}.csv", safe_qq), csv))
+}
+
+/// 待审核LP数量，供导航栏角标定时轮询。轮询频繁，成功时不写请求日志
+pub async fn pending_count(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    if !auth_user.has_permission("审核LP") {
+        return Err(ApiError::forbidden("需要审核LP权限"));
+    }
+
+    match LpService::count_pending(&state.pool).await {
+        Ok(count) => Ok(Json(json!({ "count": count }))),
+        Err(e) => {
+            log::error!("统计待审核LP失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/lp/pending-count",
+                Some(auth_user.qq()),
+                Some(format!("统计待审核LP失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            Err(ApiError::internal("统计待审核LP失败"))
+        }
+    }
+}
+
+/// 按LP类型统计申请数量和已通过的LP总和
+pub async fn lp_stats_by_type(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    if !auth_user.has_permission("审核LP") && !auth_user.has_permission("查看日志") {
+        return Err(ApiError::forbidden("需要审核LP或查看日志权限"));
+    }
+
+    let stats = match LpService::get_stats_by_type(&state.pool).await {
+        Ok(stats) => stats,
+        Err(e) => {
+            log::error!("获取LP类型统计失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/lp/stats/by-type",
+                Some(auth_user.qq()),
+                Some(format!("获取LP类型统计失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取LP类型统计失败"));
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        "/lp/stats/by-type",
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "stats": stats })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotQuery {
+    /// 快照月份（YYYY-MM），不指定时返回最近一个月
+    period: Option<String>,
+}
+
+/// 查看某个月份的LP汇总快照，同时返回所有已有快照的月份
+pub async fn list_lp_snapshots(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Query(query): Query<SnapshotQuery>,
+) -> Result<Json<Value>, ApiError> {
+    if !auth_user.has_permission("审核LP") && !auth_user.has_permission("查看日志") {
+        return Err(ApiError::forbidden("需要审核LP或查看日志权限"));
+    }
+
+    let periods = match LpService::list_snapshot_periods(&state.pool).await {
+        Ok(periods) => periods,
+        Err(e) => {
+            log::error!("获取LP快照月份失败: {}", e);
+            return Err(ApiError::internal("获取LP快照失败"));
+        }
+    };
+
+    let period = query
+        .period
+        .map(|period| period.trim().to_string())
+        .filter(|period| !period.is_empty())
+        .or_else(|| periods.first().cloned());
+    let snapshots = match period.as_deref() {
+        Some(period) => match LpService::get_snapshots(&state.pool, period).await {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                log::error!("获取LP快照失败: {}", e);
+                let _ = record_request_log(
+                    &state.pool,
+                    "GET",
+                    "/lp/snapshots",
+                    Some(auth_user.qq()),
+                    Some(format!("获取LP快照失败: {}", e)),
+                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+                )
+                .await;
+                return Err(ApiError::internal("获取LP快照失败"));
+            }
+        },
+        None => Vec::new(),
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        "/lp/snapshots",
+        Some(auth_user.qq()),
+        period.clone(),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "period": period,
+        "periods": periods,
+        "snapshots": snapshots,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::parse_csv;
+    use crate::test_support::{
+        add_user, auth_user, bearer_token, insert_lp_log, response_json, response_text, send,
+        send_json, test_state,
+    };
+    use axum::response::IntoResponse;
+    use sqlx::SqlitePool;
+    use team_operation_system::db::NotificationService;
+    use team_operation_system::models::UserNotification;
+
+    #[tokio::test]
+    async fn missing_permission_returns_json_403() {
+        let state = test_state().await;
+
+        let error = review_policy(auth_user("10001", &[]), State(state))
+            .await
+            .unwrap_err();
+        let (status, body) = response_json(error.into_response()).await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body, json!({ "message": "没有权限执行此操作" }));
+    }
+
+    #[tokio::test]
+    async fn lp_detail_is_visible_to_parties_and_reviewers_only() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let id = insert_lp_log(&state.pool, "10001", 1, 5, 0).await;
+        sqlx::query("UPDATE lplog SET upload_user_qq = '10002' WHERE id = ?")
+            .bind(id)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        for viewer in [
+            auth_user("10001", &[]),
+            auth_user("10002", &[]),
+            auth_user("10003", &["审核LP"]),
+            auth_user("10004", &["查看日志"]),
+        ] {
+            let Json(body) = get_lp(viewer, State(state.clone()), Path(id))
+                .await
+                .unwrap();
+            assert_eq!(body["log"]["id"], id);
+            assert_eq!(body["type_name"], "奖励");
+        }
+
+        let error = get_lp(auth_user("10005", &[]), State(state.clone()), Path(id))
+            .await
+            .unwrap_err();
+        assert_eq!(error.into_response().status(), StatusCode::FORBIDDEN);
+
+        let error = get_lp(auth_user("10001", &[]), State(state), Path(id + 1))
+            .await
+            .unwrap_err();
+        let (status, body) = response_json(error.into_response()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["message"], "LP申请不存在");
+    }
+
+    #[tokio::test]
+    async fn public_leaderboard_is_404_unless_enabled() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        insert_lp_log(&state.pool, "10001", 1, 5, 1).await;
+
+        let (status, body) =
+            response_json(send(&state, "GET", "/api/public/leaderboard", None).await).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["message"], "公开排行榜未开启");
+
+        let state = AppState {
+            public_leaderboard_size: Some(10),
+            ..state
+        };
+        let (status, body) =
+            response_json(send(&state, "GET", "/api/public/leaderboard", None).await).await;
+        assert_eq!(status, StatusCode::OK);
+        let first = &body["entries"][0];
+        assert_eq!(first["nickname"], "用户10001");
+        assert_eq!(first["total_lp"], 5);
+        assert!(first.get("qq").is_none());
+    }
+
+    #[tokio::test]
+    async fn oversized_batch_is_rejected_before_processing() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let id = insert_lp_log(&state.pool, "10001", 1, 5, 0).await;
+        let mut ids = vec![id];
+        ids.resize(batch_lp_max_ids() + 1, id);
+
+        let error = batch_process_lp(
+            auth_user("10002", &["审核LP"]),
+            State(state.clone()),
+            ApiJson(BatchProcessLpRequest {
+                ids,
+                status: 1,
+                reason: None,
+            }),
+        )
+        .await
+        .unwrap_err();
+        let (status, body) = response_json(error.into_response()).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["message"],
+            format!("单次最多批量审批 {} 条LP申请", batch_lp_max_ids())
+        );
+        let log = LpService::get_lp_log(&state.pool, id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(log.status, 0);
+    }
+
+    #[tokio::test]
+    async fn self_submit_only_mode_blocks_members_filing_for_others() {
+        let state = test_state().await;
+        for qq in ["10001", "10002", "10003"] {
+            add_user(&state.pool, qq).await;
+        }
+        let submit = |state: AppState, auth: AuthenticatedUser, user_qq: &str| {
+            let payload = SubmitLpRequest {
+                upload_user_qq: auth.qq().to_string(),
+                user_qq: user_qq.to_string(),
+                lp_type: 1,
+                num: 5,
+                reason: "协助活动".to_string(),
+                picture: None,
+                role: None,
+            };
+            submit_lp(auth, State(state), HeaderMap::new(), ApiJson(payload))
+        };
+
+        // 默认模式：成员可以为他人提交
+        assert!(submit(state.clone(), auth_user("10001", &[]), "10002")
+            .await
+            .is_ok());
+
+        let state = AppState {
+            lp_self_submit_only: true,
+            ..state
+        };
+        let error = submit(state.clone(), auth_user("10001", &[]), "10002")
+            .await
+            .unwrap_err();
+        let (status, body) = response_json(error.into_response()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["message"], "当前只允许为自己提交LP申请");
+
+        assert!(submit(state.clone(), auth_user("10001", &[]), "10001")
+            .await
+            .is_ok());
+        assert!(submit(state, auth_user("10003", &["审核LP"]), "10002")
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejection_requires_reason_only_when_enforced() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let process = |state: AppState, id: i64, status: i32, reason: Option<&str>| {
+            let payload = ProcessLpRequest {
+                id,
+                status,
+                reason: reason.map(str::to_string),
+            };
+            process_lp(
+                auth_user("10002", &["审核LP"]),
+                State(state),
+                ApiJson(payload),
+            )
+        };
+
+        // 默认模式：可以不填理由直接拒绝
+        let relaxed = insert_lp_log(&state.pool, "10001", 1, 5, 0).await;
+        assert!(process(state.clone(), relaxed, 2, None).await.is_ok());
+
+        let state = AppState {
+            lp_reject_reason_required: true,
+            ..state
+        };
+        let enforced = insert_lp_log(&state.pool, "10001", 1, 5, 0).await;
+        let error = process(state.clone(), enforced, 2, Some("  "))
+            .await
+            .unwrap_err();
+        let (status, body) = response_json(error.into_response()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "拒绝LP申请时必须填写理由");
+        let log = LpService::get_lp_log(&state.pool, enforced)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(log.status, 0);
+
+        assert!(process(state.clone(), enforced, 2, Some("材料不全"))
+            .await
+            .is_ok());
+        let approved = insert_lp_log(&state.pool, "10001", 1, 5, 0).await;
+        assert!(process(state, approved, 1, None).await.is_ok());
+    }
+
+    /// 站内通知在后台任务中写入，等到至少有 `expected` 条（或超时）后返回用户的全部通知
+    async fn wait_for_notifications(
+        pool: &SqlitePool,
+        qq: &str,
+        expected: usize,
+    ) -> Vec<UserNotification> {
+        let mut notifications = Vec::new();
+        for _ in 0..50 {
+            notifications = NotificationService::list_for_user(pool, qq, 10, 0)
+                .await
+                .unwrap();
+            if notifications.len() >= expected {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        notifications
+    }
+
+    #[tokio::test]
+    async fn processing_lp_notifies_the_target_user() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let approved = insert_lp_log(&state.pool, "10001", 1, 5, 0).await;
+        let rejected = insert_lp_log(&state.pool, "10001", 1, 3, 0).await;
+
+        for (id, status, reason) in [(approved, 1, None), (rejected, 2, Some("材料不全"))] {
+            let payload = ProcessLpRequest {
+                id,
+                status,
+                reason: reason.map(str::to_string),
+            };
+            let result = process_lp(
+                auth_user("10002", &["审核LP"]),
+                State(state.clone()),
+                ApiJson(payload),
+            )
+            .await;
+            assert!(result.is_ok());
+        }
+
+        let notifications = wait_for_notifications(&state.pool, "10001", 2).await;
+        assert_eq!(notifications.len(), 2);
+        assert!(notifications
+            .iter()
+            .all(|n| n.kind == NOTIFICATION_KIND_LP_PROCESSED && !n.read));
+        assert!(notifications[0].message.contains("拒绝理由：材料不全"));
+        assert!(notifications[1].message.contains("已审批通过"));
+        assert_eq!(
+            NotificationService::count_for_user(&state.pool, "10002")
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_notifies_only_requests_it_processed() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let pending = insert_lp_log(&state.pool, "10001", 1, 5, 0).await;
+        let processed = insert_lp_log(&state.pool, "10001", 1, 3, 1).await;
+
+        let Json(body) = batch_process_lp(
+            auth_user("10002", &["审核LP"]),
+            State(state.clone()),
+            ApiJson(BatchProcessLpRequest {
+                ids: vec![pending, processed, pending],
+                status: 1,
+                reason: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["approved_count"], 1);
+        assert_eq!(body["requested_count"], 3);
+
+        let notifications = wait_for_notifications(&state.pool, "10001", 1).await;
+        // 多等一会，确认没有给已处理或重复的ID补发通知
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(
+            NotificationService::count_for_user(&state.pool, "10001")
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(notifications[0].message.contains(&format!("#{}", pending)));
+    }
+
+    #[tokio::test]
+    async fn zero_num_submission_is_rejected() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let body =
+            r#"{"upload_user_qq":"10001","user_qq":"10001","lp_type":1,"num":0,"reason":"测试"}"#;
+
+        let response = send_json(
+            &state,
+            "POST",
+            "/api/lp/submit",
+            Some(&bearer_token("10001", &[])),
+            body,
+        )
+        .await;
+        let (status, body) = response_json(response).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "LP数量不能为0");
+        assert_eq!(LpService::count_pending(&state.pool).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn summary_export_has_bom_header_and_user_rows() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        insert_lp_log(&state.pool, "10001", 1, 5, 1).await;
+        insert_lp_log(&state.pool, "10001", 1, 3, 0).await;
+        insert_lp_log(&state.pool, "10001", 2, -2, 2).await;
+
+        let response = export_lp_summaries(auth_user("10002", &["审核LP"]), State(state))
+            .await
+            .unwrap();
+        let csv = response_text(response).await;
+
+        assert!(csv.starts_with('\u{feff}'));
+        let rows = parse_csv(&csv);
+        assert_eq!(
+            rows[0],
+            ["QQ", "昵称", "LP总计", "待处理", "已通过", "已拒绝"]
+        );
+        let row = rows.iter().find(|row| row[0] == "10001").unwrap();
+        assert_eq!(row, &["10001", "用户10001", "5", "1", "1", "1"]);
+    }
+}
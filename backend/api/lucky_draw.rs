@@ -1,13 +1,19 @@
 use crate::auth::AuthenticatedUser;
+use crate::error::ApiError;
+use crate::notifier::notify_user;
 use crate::state::AppState;
+use crate::validation::normalize_plan_time;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
-use team_operation_system::db::{record_request_log, LuckyDrawService};
+use team_operation_system::db::{
+    record_request_log, CancelDrawOutcome, DrawFilter, DrawOutcome, DrawRecurrence,
+    LuckyDrawService, ServiceError, MAX_DRAW_OCCURRENCES, NOTIFICATION_KIND_DRAW_WON,
+};
 
 #[derive(Deserialize)]
 pub struct CreateDrawRequest {
@@ -18,6 +24,31 @@ pub struct CreateDrawRequest {
     pub min_lp_require: i32,
     pub plan_time: String,
     pub description: Option<String>,
+    /// 重复周期：none（默认）/daily/weekly
+    pub recurrence: Option<String>,
+    /// 重复抽奖的总期数（含本期），重复时必填
+    pub occurrences: Option<i32>,
+}
+
+/// 修改抽奖的请求，未提供的字段保持不变，`description` 传空字符串表示清除
+#[derive(Deserialize)]
+pub struct UpdateDrawRequest {
+    pub num: Option<i32>,
+    pub min_lp_require: Option<i32>,
+    pub plan_time: Option<String>,
+    pub description: Option<String>,
+}
+
+/// 重新开奖的请求，`min_lp_require` 只能低于或等于抽奖当前的要求
+#[derive(Deserialize)]
+pub struct RetryDrawRequest {
+    pub min_lp_require: Option<i32>,
+}
+
+/// 复制抽奖的请求，只需要给出新的计划开奖时间
+#[derive(Deserialize)]
+pub struct DuplicateDrawRequest {
+    pub plan_time: String,
 }
 
 #[derive(Deserialize)]
@@ -25,16 +56,46 @@ pub struct SetWinnerRequest {
     pub winner_qq: String,
 }
 
+#[derive(Deserialize)]
+pub struct ListDrawsQuery {
+    pub status: Option<i32>,
+    pub create_qq: Option<String>,
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct EligibleQuery {
+    pub include_users: Option<bool>,
+}
+
 pub async fn list_draws(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
+    Query(query): Query<ListDrawsQuery>,
 ) -> Result<Json<Value>, StatusCode> {
     if !auth_user.has_permission("发起抽奖") && !auth_user.has_permission("查看日志") {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    let draws = match LuckyDrawService::get_all_draws(&state.pool).await {
-        Ok(draws) => draws,
+    let limit = query.limit.unwrap_or(50).clamp(1, 200) as i64;
+    let offset = query.offset.unwrap_or(0).max(0) as i64;
+    let filter = DrawFilter {
+        status: query.status,
+        create_qq: query
+            .create_qq
+            .map(|qq| qq.trim().to_string())
+            .filter(|qq| !qq.is_empty()),
+    };
+
+    let result = match LuckyDrawService::list_draws(&state.pool, &filter, limit, offset).await {
+        Ok(draws) => LuckyDrawService::count_draws(&state.pool, &filter)
+            .await
+            .map(|total| (draws, total)),
+        Err(e) => Err(e),
+    };
+    let (draws, total) = match result {
+        Ok(result) => result,
         Err(e) => {
             log::error!("获取抽奖列表失败: {}", e);
             let _ = record_request_log(
@@ -60,17 +121,17 @@ pub async fn list_draws(
     )
     .await;
 
-    Ok(Json(json!({ "draws": draws })))
+    Ok(Json(json!({ "draws": draws, "total": total })))
 }
 
 pub async fn create_draw(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Json(payload): Json<CreateDrawRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     auth_user.require_permission("发起抽奖")?;
     if auth_user.qq() != payload.create_qq {
-        return Err(StatusCode::FORBIDDEN);
+        return Err(ApiError::forbidden("只能以自己的身份发起抽奖"));
     }
 
     let CreateDrawRequest {
@@ -81,8 +142,43 @@ pub async fn create_draw(
         min_lp_require,
         plan_time,
         description,
+        recurrence,
+        occurrences,
     } = payload;
 
+    let (recurrence, recurrence_remaining) =
+        match parse_recurrence(recurrence.as_deref(), occurrences) {
+            Ok(parsed) => parsed,
+            Err(reason) => {
+                let _ = record_request_log(
+                    &state.pool,
+                    "POST",
+                    "/lucky-draw/create",
+                    Some(auth_user.qq()),
+                    Some(reason.clone()),
+                    StatusCode::BAD_REQUEST.as_u16() as i32,
+                )
+                .await;
+                return Err(ApiError::bad_request(reason));
+            }
+        };
+
+    let plan_time = match normalize_plan_time(&plan_time) {
+        Ok(plan_time) => plan_time,
+        Err(reason) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lucky-draw/create",
+                Some(auth_user.qq()),
+                Some(format!("{}: {}", reason, plan_time)),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(reason));
+        }
+    };
+
     let id = match LuckyDrawService::create_draw(
         &state.pool,
         &create_qq,
@@ -92,10 +188,48 @@ pub async fn create_draw(
         min_lp_require,
         &plan_time,
         description.clone(),
+        recurrence,
+        recurrence_remaining,
     )
     .await
     {
         Ok(id) => id,
+        Err(e @ ServiceError::Forbidden(_)) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lucky-draw/create",
+                Some(auth_user.qq()),
+                Some(format!("商品 {:?} 不属于发起人", item_id)),
+                StatusCode::FORBIDDEN.as_u16() as i32,
+            )
+            .await;
+            return Err(e.into());
+        }
+        Err(e @ ServiceError::NotFound(_)) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lucky-draw/create",
+                Some(auth_user.qq()),
+                Some(e.to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            return Err(e.into());
+        }
+        Err(e @ ServiceError::Validation(_)) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/lucky-draw/create",
+                Some(auth_user.qq()),
+                Some(e.to_string()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(e.into());
+        }
         Err(e) => {
             log::error!("创建抽奖失败: {}", e);
             let _ = record_request_log(
@@ -107,7 +241,7 @@ pub async fn create_draw(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("创建抽奖失败"));
         }
     };
 
@@ -125,6 +259,8 @@ pub async fn create_draw(
                 "min_lp_require": min_lp_require,
                 "plan_time": plan_time,
                 "description": description,
+                "recurrence": recurrence.as_str(),
+                "occurrences": recurrence_remaining + 1,
             }))
             .unwrap_or_default(),
         ),
@@ -134,36 +270,146 @@ pub async fn create_draw(
     Ok(Json(json!({ "message": "抽奖活动创建成功", "id": id })))
 }
 
+pub async fn duplicate_draw(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(payload): Json<DuplicateDrawRequest>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("发起抽奖")?;
+    let path = format!("/lucky-draw/{}/duplicate", id);
+
+    let plan_time = match normalize_plan_time(&payload.plan_time) {
+        Ok(plan_time) => plan_time,
+        Err(reason) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("{}: {}", reason, payload.plan_time)),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(reason));
+        }
+    };
+
+    let new_id =
+        match LuckyDrawService::duplicate_draw(&state.pool, id, auth_user.qq(), &plan_time).await {
+            Ok(Some(new_id)) => new_id,
+            Ok(None) => return Err(ApiError::new(StatusCode::NOT_FOUND, "抽奖不存在")),
+            Err(e @ ServiceError::Forbidden(_)) => {
+                let _ = record_request_log(
+                    &state.pool,
+                    "POST",
+                    &path,
+                    Some(auth_user.qq()),
+                    Some(e.to_string()),
+                    StatusCode::FORBIDDEN.as_u16() as i32,
+                )
+                .await;
+                return Err(e.into());
+            }
+            Err(e @ ServiceError::NotFound(_)) => {
+                let _ = record_request_log(
+                    &state.pool,
+                    "POST",
+                    &path,
+                    Some(auth_user.qq()),
+                    Some(e.to_string()),
+                    StatusCode::NOT_FOUND.as_u16() as i32,
+                )
+                .await;
+                return Err(e.into());
+            }
+            Err(e @ ServiceError::Validation(_)) => {
+                let _ = record_request_log(
+                    &state.pool,
+                    "POST",
+                    &path,
+                    Some(auth_user.qq()),
+                    Some(e.to_string()),
+                    StatusCode::BAD_REQUEST.as_u16() as i32,
+                )
+                .await;
+                return Err(e.into());
+            }
+            Err(e) => {
+                log::error!("复制抽奖失败: {}", e);
+                let _ = record_request_log(
+                    &state.pool,
+                    "POST",
+                    &path,
+                    Some(auth_user.qq()),
+                    Some(format!("复制抽奖失败: {}", e)),
+                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+                )
+                .await;
+                return Err(ApiError::internal("复制抽奖失败"));
+            }
+        };
+
+    log::info!(
+        "复制抽奖活动: 源ID={}, 新ID={}, 创建人={}",
+        id,
+        new_id,
+        auth_user.qq()
+    );
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        &path,
+        Some(auth_user.qq()),
+        Some(json!({ "new_id": new_id, "plan_time": plan_time }).to_string()),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+    Ok(Json(json!({ "message": "抽奖活动复制成功", "id": new_id })))
+}
+
 pub async fn execute_draw(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Path(id): Path<i64>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     auth_user.require_permission("发起抽奖")?;
-    let winner = match LuckyDrawService::execute_draw(&state.pool, id).await {
-        Ok(winner) => winner,
+    let path = format!("/lucky-draw/execute/{}", id);
+
+    let outcome = match LuckyDrawService::execute_draw(&state.pool, id).await {
+        Ok(outcome) => outcome,
         Err(e) => {
             log::error!("执行抽奖失败: {}", e);
             let _ = record_request_log(
                 &state.pool,
                 "POST",
-                &format!("/lucky-draw/execute/{}", id),
+                &path,
                 Some(auth_user.qq()),
                 Some(format!("执行抽奖失败: {}", e)),
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("执行抽奖失败"));
         }
     };
 
-    match winner {
-        Some(winners) => {
+    match outcome {
+        DrawOutcome::Drawn(winners) => {
             log::info!("抽奖活动 {} 开奖，中奖者: {:?}", id, winners);
+            for winner in &winners {
+                notify_user(
+                    state.notifier.clone(),
+                    state.pool.clone(),
+                    winner.clone(),
+                    NOTIFICATION_KIND_DRAW_WON,
+                    "抽奖中奖通知".to_string(),
+                    format!("恭喜！你在抽奖活动 #{} 中中奖了。", id),
+                );
+            }
             let _ = record_request_log(
                 &state.pool,
                 "POST",
-                &format!("/lucky-draw/execute/{}", id),
+                &path,
                 Some(auth_user.qq()),
                 Some(json!({ "winners": winners.clone() }).to_string()),
                 StatusCode::OK.as_u16() as i32,
@@ -171,23 +417,249 @@ pub async fn execute_draw(
             .await;
             Ok(Json(json!({
                 "message": "开奖成功",
+                "outcome": "drawn",
                 "winners": winners,
                 "count": winners.len()
             })))
         }
-        None => {
+        DrawOutcome::NoEligibleUsers => {
             let _ = record_request_log(
                 &state.pool,
                 "POST",
-                &format!("/lucky-draw/execute/{}", id),
+                &path,
                 Some(auth_user.qq()),
-                None,
+                Some("没有符合条件的参与者".to_string()),
                 StatusCode::OK.as_u16() as i32,
             )
             .await;
-            Ok(Json(json!({ "message": "没有符合条件的参与者" })))
+            Ok(Json(json!({
+                "message": "没有符合条件的参与者，抽奖保持未开奖，可降低最低LP要求后重试",
+                "outcome": "no_eligible_users",
+                "winners": [],
+                "count": 0
+            })))
+        }
+        DrawOutcome::AlreadyExecuted => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some("抽奖已开奖".to_string()),
+                StatusCode::CONFLICT.as_u16() as i32,
+            )
+            .await;
+            Err(ApiError::conflict("抽奖已开奖，不能重复开奖"))
         }
+        DrawOutcome::NotFound => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some("抽奖不存在".to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            Err(ApiError::new(StatusCode::NOT_FOUND, "抽奖不存在"))
+        }
+    }
+}
+
+/// 对没有产生中奖者的未开奖抽奖重新开奖，可同时降低最低LP要求
+pub async fn retry_draw(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(payload): Json<RetryDrawRequest>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("发起抽奖")?;
+    let path = format!("/lucky-draw/{}/retry", id);
+    let reject = |status: StatusCode, message: &str| {
+        reject_draw_request(
+            &state,
+            &auth_user,
+            "POST",
+            &path,
+            status,
+            message.to_string(),
+        )
+    };
+
+    let draw = match LuckyDrawService::get_draw(&state.pool, id).await {
+        Ok(Some(draw)) => draw,
+        Ok(None) => return Err(reject(StatusCode::NOT_FOUND, "抽奖不存在").await),
+        Err(e) => {
+            log::error!("查询抽奖失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("查询抽奖失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("查询抽奖失败"));
+        }
+    };
+
+    if draw.status != 0 {
+        return Err(reject(StatusCode::CONFLICT, "抽奖已开奖，不能重新开奖").await);
+    }
+    if let Some(min_lp_require) = payload.min_lp_require {
+        if min_lp_require < 0 {
+            return Err(reject(StatusCode::BAD_REQUEST, "最低LP要求不能为负数").await);
+        }
+        if min_lp_require > draw.min_lp_require {
+            return Err(reject(StatusCode::BAD_REQUEST, "重新开奖时只能降低最低LP要求").await);
+        }
+    }
+
+    let outcome = match LuckyDrawService::retry_draw(&state.pool, id, payload.min_lp_require).await
+    {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            log::error!("重新开奖失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("重新开奖失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("重新开奖失败"));
+        }
+    };
+
+    let winners = match outcome {
+        DrawOutcome::Drawn(winners) => winners,
+        DrawOutcome::NoEligibleUsers => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(json!({ "min_lp_require": payload.min_lp_require }).to_string()),
+                StatusCode::UNPROCESSABLE_ENTITY.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "仍然没有符合条件的参与者",
+            ));
+        }
+        DrawOutcome::AlreadyExecuted => {
+            return Err(reject(StatusCode::CONFLICT, "抽奖已开奖，不能重新开奖").await);
+        }
+        DrawOutcome::NotFound => {
+            return Err(reject(StatusCode::NOT_FOUND, "抽奖不存在").await);
+        }
+    };
+
+    log::info!("抽奖活动 {} 重新开奖，中奖者: {:?}", id, winners);
+    for winner in &winners {
+        notify_user(
+            state.notifier.clone(),
+            state.pool.clone(),
+            winner.clone(),
+            NOTIFICATION_KIND_DRAW_WON,
+            "抽奖中奖通知".to_string(),
+            format!("恭喜！你在抽奖活动 #{} 中中奖了。", id),
+        );
     }
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        &path,
+        Some(auth_user.qq()),
+        Some(
+            json!({
+                "min_lp_require": payload.min_lp_require,
+                "winners": winners.clone(),
+            })
+            .to_string(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "message": "重新开奖成功",
+        "outcome": "drawn",
+        "winners": winners,
+        "count": winners.len()
+    })))
+}
+
+/// 预览抽奖当前符合 LP 门槛的人数，拥有用户管理权限时可附带名单
+pub async fn eligible_users(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<EligibleQuery>,
+) -> Result<Json<Value>, StatusCode> {
+    if !auth_user.has_permission("发起抽奖") && !auth_user.has_permission("查看日志") {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let include_users = query.include_users.unwrap_or(false);
+    if include_users {
+        auth_user.require_permission("用户管理")?;
+    }
+
+    let eligible = match LuckyDrawService::get_eligible_users(&state.pool, id).await {
+        Ok(eligible) => eligible,
+        Err(e) => {
+            log::error!("查询抽奖参与者失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &format!("/lucky-draw/{}/eligible", id),
+                Some(auth_user.qq()),
+                Some(format!("查询抽奖参与者失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let Some((min_lp_require, users)) = eligible else {
+        let _ = record_request_log(
+            &state.pool,
+            "GET",
+            &format!("/lucky-draw/{}/eligible", id),
+            Some(auth_user.qq()),
+            Some("抽奖不存在".to_string()),
+            StatusCode::NOT_FOUND.as_u16() as i32,
+        )
+        .await;
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &format!("/lucky-draw/{}/eligible", id),
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    let mut body = json!({
+        "draw_id": id,
+        "min_lp_require": min_lp_require,
+        "count": users.len(),
+    });
+    if include_users {
+        body["users"] = json!(users);
+    }
+
+    Ok(Json(body))
 }
 
 pub async fn set_manual_winner(
@@ -258,3 +730,477 @@ pub async fn delete_draw(
 
     Ok(Json(json!({ "message": "抽奖活动删除成功" })))
 }
+
+#[derive(Debug, Deserialize)]
+pub struct BatchDeleteDrawsRequest {
+    pub ids: Vec<i64>,
+    /// 是否同时删除未开奖的抽奖（会恢复关联商品库存），默认跳过
+    #[serde(default)]
+    pub include_pending: bool,
+}
+
+/// 批量删除抽奖，返回实际删除、因未开奖跳过以及不存在的ID
+pub async fn batch_delete_draws(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Json(payload): Json<BatchDeleteDrawsRequest>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("发起抽奖")?;
+
+    if payload.ids.is_empty() {
+        return Err(ApiError::bad_request("请选择要删除的抽奖"));
+    }
+
+    let result =
+        match LuckyDrawService::delete_draws(&state.pool, &payload.ids, payload.include_pending)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                log::error!("批量删除抽奖失败: {}", e);
+                let _ = record_request_log(
+                    &state.pool,
+                    "DELETE",
+                    "/lucky-draw/batch",
+                    Some(auth_user.qq()),
+                    Some(format!("批量删除抽奖失败: {}", e)),
+                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+                )
+                .await;
+                return Err(ApiError::internal("批量删除抽奖失败"));
+            }
+        };
+
+    log::info!(
+        "批量删除抽奖: 删除 {:?}, 操作人={}",
+        result.deleted,
+        auth_user.qq()
+    );
+    let _ = record_request_log(
+        &state.pool,
+        "DELETE",
+        "/lucky-draw/batch",
+        Some(auth_user.qq()),
+        Some(serde_json::to_string(&result).unwrap_or_default()),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "message": format!("已删除 {} 个抽奖", result.deleted.len()),
+        "deleted": result.deleted,
+        "skipped_pending": result.skipped_pending,
+        "not_found": result.not_found,
+    })))
+}
+
+/// 取消未开奖的抽奖，保留记录并恢复预留的商品库存，发起人或拥有发起抽奖权限的用户可操作
+pub async fn cancel_draw(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/lucky-draw/{}/cancel", id);
+    let reject = |status: StatusCode, message: &str| {
+        reject_draw_request(
+            &state,
+            &auth_user,
+            "POST",
+            &path,
+            status,
+            message.to_string(),
+        )
+    };
+
+    let draw = match LuckyDrawService::get_draw(&state.pool, id).await {
+        Ok(Some(draw)) => draw,
+        Ok(None) => return Err(reject(StatusCode::NOT_FOUND, "抽奖不存在").await),
+        Err(e) => {
+            log::error!("查询抽奖失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("查询抽奖失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("查询抽奖失败"));
+        }
+    };
+    if auth_user.qq() != draw.create_qq && !auth_user.has_permission("发起抽奖") {
+        return Err(reject(StatusCode::FORBIDDEN, "只能取消自己发起的抽奖").await);
+    }
+
+    match LuckyDrawService::cancel_draw(&state.pool, id).await {
+        Ok(CancelDrawOutcome::Cancelled) => {}
+        Ok(CancelDrawOutcome::NotPending) => {
+            return Err(reject(StatusCode::CONFLICT, "抽奖已开奖或已关闭，不能取消").await);
+        }
+        Ok(CancelDrawOutcome::NotFound) => {
+            return Err(reject(StatusCode::NOT_FOUND, "抽奖不存在").await);
+        }
+        Err(e) => {
+            log::error!("取消抽奖失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("取消抽奖失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("取消抽奖失败"));
+        }
+    }
+
+    log::info!("取消抽奖活动: ID={}, 操作人={}", id, auth_user.qq());
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        &path,
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "message": "抽奖已取消" })))
+}
+
+/// 修改未开奖的抽奖：奖品数量、最低LP要求、计划开奖时间和描述
+pub async fn update_draw(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(payload): Json<UpdateDrawRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/lucky-draw/{}", id);
+    let reject = |status: StatusCode, message: String| {
+        reject_draw_request(&state, &auth_user, "PATCH", &path, status, message)
+    };
+
+    let draw = match LuckyDrawService::get_draw(&state.pool, id).await {
+        Ok(Some(draw)) => draw,
+        Ok(None) => return Err(reject(StatusCode::NOT_FOUND, "抽奖不存在".to_string()).await),
+        Err(e) => {
+            log::error!("查询抽奖失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("查询抽奖失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("查询抽奖失败"));
+        }
+    };
+
+    if auth_user.qq() != draw.create_qq && !auth_user.has_permission("发起抽奖") {
+        return Err(reject(StatusCode::FORBIDDEN, "只能修改自己发起的抽奖".to_string()).await);
+    }
+    if draw.status != 0 {
+        return Err(reject(StatusCode::CONFLICT, "抽奖已开奖，不能再修改".to_string()).await);
+    }
+
+    let num = payload.num.unwrap_or(draw.num);
+    if num <= 0 {
+        return Err(reject(StatusCode::BAD_REQUEST, "数量必须大于0".to_string()).await);
+    }
+    let min_lp_require = payload.min_lp_require.unwrap_or(draw.min_lp_require);
+    if min_lp_require < 0 {
+        return Err(reject(StatusCode::BAD_REQUEST, "最低LP要求不能为负数".to_string()).await);
+    }
+    let plan_time = match payload.plan_time.as_deref().map(normalize_plan_time) {
+        Some(Ok(plan_time)) => plan_time,
+        Some(Err(message)) => return Err(reject(StatusCode::BAD_REQUEST, message).await),
+        None => draw.plan_time.clone(),
+    };
+    let description = match payload.description {
+        Some(text) => Some(text.trim().to_string()).filter(|text| !text.is_empty()),
+        None => draw.description.clone(),
+    };
+
+    match LuckyDrawService::update_draw(
+        &state.pool,
+        id,
+        num,
+        min_lp_require,
+        &plan_time,
+        description.clone(),
+    )
+    .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(reject(StatusCode::CONFLICT, "抽奖已开奖，不能再修改".to_string()).await)
+        }
+        Err(e @ ServiceError::Validation(_)) => {
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                &path,
+                Some(auth_user.qq()),
+                Some(e.to_string()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(e.into());
+        }
+        Err(e) => {
+            log::error!("修改抽奖失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("修改抽奖失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("修改抽奖失败"));
+        }
+    }
+
+    log::info!("修改抽奖活动: ID={}, 操作人={}", id, auth_user.qq());
+    let _ = record_request_log(
+        &state.pool,
+        "PATCH",
+        &path,
+        Some(auth_user.qq()),
+        Some(
+            serde_json::to_string(&json!({
+                "num": num,
+                "min_lp_require": min_lp_require,
+                "plan_time": plan_time,
+                "description": description,
+            }))
+            .unwrap_or_default(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "message": "抽奖活动已更新" })))
+}
+
+/// 记录被拒绝的抽奖请求日志并返回对应错误
+async fn reject_draw_request(
+    state: &AppState,
+    auth_user: &AuthenticatedUser,
+    method: &str,
+    path: &str,
+    status: StatusCode,
+    message: String,
+) -> ApiError {
+    let _ = record_request_log(
+        &state.pool,
+        method,
+        path,
+        Some(auth_user.qq()),
+        Some(message.clone()),
+        status.as_u16() as i32,
+    )
+    .await;
+    ApiError::new(status, message)
+}
+
+/// 解析重复设置，返回重复周期和开奖后还要自动创建的期数
+fn parse_recurrence(
+    recurrence: Option<&str>,
+    occurrences: Option<i32>,
+) -> Result<(DrawRecurrence, i32), String> {
+    let recurrence = match recurrence {
+        None | Some("") => DrawRecurrence::None,
+        Some(value) => {
+            DrawRecurrence::parse(value).ok_or_else(|| format!("不支持的重复周期: {}", value))?
+        }
+    };
+    if recurrence == DrawRecurrence::None {
+        return Ok((recurrence, 0));
+    }
+
+    match occurrences {
+        Some(count) if (2..=MAX_DRAW_OCCURRENCES).contains(&count) => Ok((recurrence, count - 1)),
+        Some(_) => Err(format!(
+            "重复抽奖的总期数必须在 2 到 {} 之间",
+            MAX_DRAW_OCCURRENCES
+        )),
+        None => Err("重复抽奖需要指定总期数".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{add_user, auth_user, response_json, test_state};
+    use axum::response::IntoResponse;
+    use team_operation_system::db::ShopService;
+
+    fn draw_request(create_qq: &str, item_id: i64) -> CreateDrawRequest {
+        CreateDrawRequest {
+            create_qq: create_qq.to_string(),
+            item_id: Some(item_id),
+            fitting: None,
+            num: 1,
+            min_lp_require: 0,
+            plan_time: "2099-01-01 12:00:00".to_string(),
+            description: None,
+            recurrence: None,
+            occurrences: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn draw_can_only_use_the_creators_own_item() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let own = ShopService::add_item(&state.pool, 5, "10", "咖啡", "10001", "A区", None, None)
+            .await
+            .unwrap();
+        let others = ShopService::add_item(&state.pool, 5, "10", "茶", "10002", "A区", None, None)
+            .await
+            .unwrap();
+        let creator = || auth_user("10001", &["发起抽奖"]);
+
+        let Json(body) = create_draw(
+            creator(),
+            State(state.clone()),
+            Json(draw_request("10001", own)),
+        )
+        .await
+        .unwrap();
+        assert!(body["id"].as_i64().is_some());
+
+        let error = create_draw(
+            creator(),
+            State(state.clone()),
+            Json(draw_request("10001", others)),
+        )
+        .await
+        .unwrap_err();
+        let (status, body) = response_json(error.into_response()).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["message"], "只能抽取您自己发布的商品");
+        let item = ShopService::get_item(&state.pool, others)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.count, 5);
+    }
+
+    async fn create_fitting_draw(state: &AppState, create_qq: &str) -> i64 {
+        LuckyDrawService::create_draw(
+            &state.pool,
+            create_qq,
+            None,
+            Some("奖品".to_string()),
+            1,
+            0,
+            "2099-01-01 12:00:00",
+            None,
+            DrawRecurrence::None,
+            0,
+        )
+        .await
+        .unwrap()
+    }
+
+    /// 按写入顺序返回某个路径的请求日志状态码
+    async fn logged_statuses(state: &AppState, path: &str) -> Vec<i32> {
+        sqlx::query_scalar("SELECT status FROM requestlog WHERE path = ? ORDER BY id")
+            .bind(path)
+            .fetch_all(&state.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejected_updates_are_logged() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let id = create_fitting_draw(&state, "10001").await;
+        let update = |qq: &str, payload: UpdateDrawRequest| {
+            update_draw(
+                auth_user(qq, &[]),
+                State(state.clone()),
+                Path(id),
+                Json(payload),
+            )
+        };
+        let payload = |num: Option<i32>, plan_time: Option<&str>| UpdateDrawRequest {
+            num,
+            min_lp_require: None,
+            plan_time: plan_time.map(str::to_string),
+            description: None,
+        };
+
+        assert!(update("10002", payload(Some(2), None)).await.is_err());
+        assert!(update("10001", payload(Some(0), None)).await.is_err());
+        assert!(update("10001", payload(None, Some("明天"))).await.is_err());
+        assert!(update("10001", payload(Some(2), None)).await.is_ok());
+
+        assert_eq!(
+            logged_statuses(&state, &format!("/lucky-draw/{}", id)).await,
+            [403, 400, 400, 200]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_retries_are_logged() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let id = create_fitting_draw(&state, "10001").await;
+        let retry = |id: i64, min_lp_require: Option<i32>| {
+            retry_draw(
+                auth_user("10001", &["发起抽奖"]),
+                State(state.clone()),
+                Path(id),
+                Json(RetryDrawRequest { min_lp_require }),
+            )
+        };
+
+        assert!(retry(id, Some(-1)).await.is_err());
+        assert!(retry(id, Some(10)).await.is_err());
+        assert!(retry(id + 1, None).await.is_err());
+
+        assert_eq!(
+            logged_statuses(&state, &format!("/lucky-draw/{}/retry", id)).await,
+            [400, 400]
+        );
+        assert_eq!(
+            logged_statuses(&state, &format!("/lucky-draw/{}/retry", id + 1)).await,
+            [404]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_cancels_are_logged() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let id = create_fitting_draw(&state, "10001").await;
+        let cancel =
+            |qq: &str, id: i64| cancel_draw(auth_user(qq, &[]), State(state.clone()), Path(id));
+
+        assert!(cancel("10002", id).await.is_err());
+        assert!(cancel("10001", id).await.is_ok());
+        assert!(cancel("10001", id).await.is_err());
+        assert!(cancel("10001", id + 1).await.is_err());
+
+        assert_eq!(
+            logged_statuses(&state, &format!("/lucky-draw/{}/cancel", id)).await,
+            [403, 200, 409]
+        );
+        assert_eq!(
+            logged_statuses(&state, &format!("/lucky-draw/{}/cancel", id + 1)).await,
+            [404]
+        );
+    }
+}
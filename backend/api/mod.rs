@@ -1,9 +1,14 @@
+mod admin;
 mod log;
 mod lp;
 mod lucky_draw;
+mod notification;
 mod permission;
 mod role;
 mod shop;
+mod stats;
+mod time;
+mod upload;
 mod user;
 
 use crate::state::AppState;
@@ -15,17 +20,30 @@ use axum::{
 pub fn routes() -> Router<AppState> {
     Router::new()
         // 用户相关
+        .route("/auth/challenge", get(user::register_challenge))
         .route("/auth/register", post(user::register))
         .route("/auth/login", post(user::login))
         .route("/profile", get(user::profile).patch(user::update_profile))
         .route("/profile/password", post(user::change_password))
+        .route("/profile/2fa/enable", post(user::enable_totp))
+        .route("/profile/2fa/verify", post(user::verify_totp))
+        .route("/profile/activity", get(log::my_activity))
         .route("/users", get(user::list_users))
+        .route("/users/search", get(user::search_users))
         .route(
             "/users/{qq}",
             patch(user::update_user).delete(user::delete_user),
         )
+        .route("/users/{qq}/permissions", get(user::user_permissions))
+        .route("/users/{qq}/timeline", get(user::user_timeline))
+        .route("/users/{qq}/disable", post(user::disable_user))
+        .route("/users/{qq}/enable", post(user::enable_user))
         // 权限相关（仅用于角色管理中获取权限列表）
         .route("/permissions", get(permission::list_permissions))
+        .route(
+            "/permissions/{name}/roles",
+            get(permission::permission_roles),
+        )
         // 角色相关
         .route("/roles", get(role::list_roles))
         .route("/roles/create", post(role::create_role))
@@ -42,20 +60,49 @@ pub fn routes() -> Router<AppState> {
             "/roles/{role_id}/permissions",
             get(role::get_role_permissions),
         )
+        .route("/roles/{role_id}/users", get(role::get_role_users))
         .route("/roles/assign", post(role::assign_role_to_user))
+        .route("/roles/assign-bulk", post(role::assign_role_bulk))
         // LP 相关
         .route("/lp/types", get(lp::list_lp_types))
         .route("/lp/submit", post(lp::submit_lp))
+        .route("/lp/draft", post(lp::save_lp_draft))
+        .route("/lp/drafts", get(lp::list_lp_drafts))
+        .route("/lp/drafts/{id}", delete(lp::delete_lp_draft))
+        .route("/lp/drafts/{id}/submit", post(lp::submit_lp_draft))
         .route("/lp/logs", get(lp::list_lp_logs))
+        .route("/lp/logs/pending", get(lp::list_pending_lp_logs))
+        .route("/lp/processed-by-me", get(lp::list_processed_by_me))
         .route("/lp/process", post(lp::process_lp))
+        .route("/lp/review-policy", get(lp::review_policy))
         .route("/lp/batch-process", post(lp::batch_process_lp))
+        .route("/lp/transfer", post(lp::transfer_lp))
         .route("/lp/user/{qq}", get(lp::user_lp_detail))
+        .route("/lp/user/{qq}/export", get(lp::export_user_lp_history))
         .route("/lp/summaries", get(lp::list_lp_summaries))
+        .route("/public/leaderboard", get(lp::public_leaderboard))
+        .route("/lp/summaries/export", get(lp::export_lp_summaries))
+        .route("/lp/stats/by-type", get(lp::lp_stats_by_type))
+        .route("/lp/snapshots", get(lp::list_lp_snapshots))
+        .route("/lp/pending-count", get(lp::pending_count))
+        .route("/lp/{id}", get(lp::get_lp))
+        .route("/lp/{id}/clone", post(lp::clone_lp))
         // 抽奖相关
         .route("/lucky-draw", get(lucky_draw::list_draws))
         .route("/lucky-draw/create", post(lucky_draw::create_draw))
+        .route("/lucky-draw/batch", delete(lucky_draw::batch_delete_draws))
         .route("/lucky-draw/execute/{id}", post(lucky_draw::execute_draw))
-        .route("/lucky-draw/{id}", delete(lucky_draw::delete_draw))
+        .route(
+            "/lucky-draw/{id}",
+            delete(lucky_draw::delete_draw).patch(lucky_draw::update_draw),
+        )
+        .route("/lucky-draw/{id}/eligible", get(lucky_draw::eligible_users))
+        .route("/lucky-draw/{id}/retry", post(lucky_draw::retry_draw))
+        .route("/lucky-draw/{id}/cancel", post(lucky_draw::cancel_draw))
+        .route(
+            "/lucky-draw/{id}/duplicate",
+            post(lucky_draw::duplicate_draw),
+        )
         .route(
             "/lucky-draw/winner/{id}",
             post(lucky_draw::set_manual_winner),
@@ -64,8 +111,42 @@ pub fn routes() -> Router<AppState> {
         .route("/shop/items", get(shop::list_items))
         .route("/shop/items/my", get(shop::my_items))
         .route("/shop/items/create", post(shop::create_item))
+        .route("/shop/items/import", post(shop::import_items))
+        .route("/shop/items/{id}", patch(shop::update_item))
+        .route("/shop/items/{id}/transfer", post(shop::transfer_item))
+        .route("/shop/items/{id}/restock", post(shop::restock_item))
         .route("/shop/purchase", post(shop::purchase_item))
+        .route("/shop/purchase/batch", post(shop::purchase_items_batch))
         .route("/shop/transactions", get(shop::get_user_transactions))
+        .route("/shop/transactions/{id}", get(shop::get_transaction))
+        .route("/shop/sellers", get(shop::list_sellers))
+        .route("/shop/sellers/{qq}/stats", get(shop::seller_stats))
         // 日志相关
-        .route("/logs", get(log::list_logs))
+        .route("/logs", get(log::list_logs).delete(log::delete_logs))
+        .route("/logs/denials", get(log::list_denials))
+        .route("/logs/stats", get(log::log_stats))
+        // 站内通知
+        .route("/notifications", get(notification::list_notifications))
+        .route(
+            "/notifications/unread-count",
+            get(notification::unread_count),
+        )
+        .route("/notifications/{id}/read", post(notification::mark_read))
+        // 统计相关
+        .route("/stats/overview", get(stats::overview))
+        // 服务器时间
+        .route("/time", get(time::server_time))
+}
+
+/// 数据备份与恢复路由，恢复时需要上传完整的备份文件，请求体上限由调用方单独设置
+pub fn backup_routes() -> Router<AppState> {
+    Router::new()
+        .route("/admin/backup", get(admin::backup))
+        .route("/admin/restore", post(admin::restore))
+        .route("/admin/reseed-defaults", post(admin::reseed_defaults))
+}
+
+/// 文件上传路由，请求体上限与普通 API 不同，由调用方单独设置
+pub fn upload_routes() -> Router<AppState> {
+    Router::new().route("/uploads", post(upload::upload_image))
 }
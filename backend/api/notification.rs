@@ -0,0 +1,129 @@
+use crate::auth::AuthenticatedUser;
+use crate::error::ApiError;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use team_operation_system::db::{record_request_log, NotificationService};
+
+#[derive(Deserialize)]
+pub struct NotificationQuery {
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
+}
+
+/// 分页返回当前用户自己的站内通知，附带未读数量
+pub async fn list_notifications(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Query(query): Query<NotificationQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100) as i64;
+    let offset = query.offset.unwrap_or(0).max(0) as i64;
+
+    let result = async {
+        let notifications =
+            NotificationService::list_for_user(&state.pool, auth_user.qq(), limit, offset).await?;
+        let total = NotificationService::count_for_user(&state.pool, auth_user.qq()).await?;
+        let unread = NotificationService::count_unread(&state.pool, auth_user.qq()).await?;
+        Ok::<_, sqlx::Error>((notifications, total, unread))
+    };
+    let (notifications, total, unread) = match result.await {
+        Ok(result) => result,
+        Err(e) => {
+            log::error!("获取站内通知失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/notifications",
+                Some(auth_user.qq()),
+                Some(format!("获取站内通知失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取站内通知失败"));
+        }
+    };
+
+    Ok(Json(json!({
+        "notifications": notifications,
+        "total": total,
+        "unread": unread,
+    })))
+}
+
+/// 当前用户的未读通知数量，供导航栏定时刷新
+pub async fn unread_count(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    match NotificationService::count_unread(&state.pool, auth_user.qq()).await {
+        Ok(count) => Ok(Json(json!({ "count": count }))),
+        Err(e) => {
+            log::error!("统计未读通知失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/notifications/unread-count",
+                Some(auth_user.qq()),
+                Some(format!("统计未读通知失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            Err(ApiError::internal("统计未读通知失败"))
+        }
+    }
+}
+
+/// 把自己的一条通知标记为已读
+pub async fn mark_read(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/notifications/{}/read", id);
+    match NotificationService::mark_read(&state.pool, auth_user.qq(), id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some("通知不存在".to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "通知不存在"));
+        }
+        Err(e) => {
+            log::error!("标记通知已读失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("标记通知已读失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("标记通知已读失败"));
+        }
+    }
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        &path,
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "message": "已标记为已读" })))
+}
@@ -1,8 +1,12 @@
 use crate::auth::AuthenticatedUser;
 use crate::state::AppState;
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
 use serde_json::{json, Value};
-use team_operation_system::db::{record_request_log, PermissionService};
+use team_operation_system::db::{record_request_log, PermissionService, RoleService};
 
 pub async fn list_permissions(
     auth_user: AuthenticatedUser,
@@ -38,3 +42,41 @@ pub async fn list_permissions(
 
     Ok(Json(json!({ "permissions": permissions })))
 }
+
+/// 获取拥有指定权限的所有角色
+pub async fn permission_roles(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    auth_user.require_permission("管理角色")?;
+    let path = format!("/permissions/{}/roles", name);
+    let roles = match RoleService::get_roles_with_permission(&state.pool, &name).await {
+        Ok(roles) => roles,
+        Err(e) => {
+            log::error!("获取权限对应角色失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("获取权限对应角色失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &path,
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "permission": name, "roles": roles })))
+}
@@ -1,24 +1,22 @@
 use crate::auth::AuthenticatedUser;
+use crate::error::ApiError;
 use crate::state::AppState;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
-use team_operation_system::db::{record_request_log, RoleService};
+use team_operation_system::db::{
+    record_request_log, BulkAssignStatus, DeleteRoleOutcome, RoleService, ServiceError, UserService,
+};
 
 /// 检查指定用户是否是第一个默认管理员（数据库中第一个创建的用户）
 async fn is_first_admin(pool: &sqlx::SqlitePool, qq: &str) -> bool {
-    let first_user_qq: Option<String> =
-        sqlx::query_scalar("SELECT qq FROM user ORDER BY rowid LIMIT 1")
-            .fetch_optional(pool)
-            .await
-            .ok()
-            .flatten();
-
-    Some(qq) == first_user_qq.as_deref()
+    UserService::is_protected_admin(pool, qq)
+        .await
+        .unwrap_or(false)
 }
 
 #[derive(Deserialize, serde::Serialize)]
@@ -33,6 +31,15 @@ pub struct AssignRoleRequest {
     pub role_id: i64,
 }
 
+#[derive(Deserialize, serde::Serialize)]
+pub struct BulkAssignRoleRequest {
+    pub role_id: i64,
+    pub user_qqs: Vec<String>,
+}
+
+/// 单次批量分配角色最多包含的用户数
+const BULK_ASSIGN_MAX_USERS: usize = 200;
+
 #[derive(Deserialize, serde::Serialize)]
 pub struct RolePermissionRequest {
     pub role_id: i64,
@@ -45,9 +52,7 @@ pub async fn list_roles(
     State(state): State<AppState>,
 ) -> Result<Json<Value>, StatusCode> {
     // 需要"管理角色"权限
-    if !auth_user.has_permission("管理角色") {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    auth_user.require_permission("管理角色")?;
 
     let roles = match RoleService::get_all_roles(&state.pool).await {
         Ok(roles) => roles,
@@ -84,17 +89,28 @@ pub async fn create_role(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Json(payload): Json<CreateRoleRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     // 需要"管理角色"权限
-    if !auth_user.has_permission("管理角色") {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    auth_user.require_permission("管理角色")?;
 
     let role_id =
         match RoleService::create_role(&state.pool, &payload.name, payload.description.as_deref())
             .await
         {
             Ok(id) => id,
+            // 角色名有唯一约束，直接依据约束错误判断，避免先查后插的竞争
+            Err(ServiceError::Conflict(_)) => {
+                let _ = record_request_log(
+                    &state.pool,
+                    "POST",
+                    "/roles/create",
+                    Some(auth_user.qq()),
+                    Some(format!("角色名称已存在: {}", payload.name)),
+                    StatusCode::CONFLICT.as_u16() as i32,
+                )
+                .await;
+                return Err(ApiError::conflict("角色名称已存在"));
+            }
             Err(e) => {
                 log::error!("创建角色失败: {}", e);
                 let _ = record_request_log(
@@ -106,7 +122,7 @@ pub async fn create_role(
                     StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
                 )
                 .await;
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                return Err(ApiError::internal("创建角色失败"));
             }
         };
 
@@ -133,29 +149,75 @@ pub async fn create_role(
 }
 
 /// 删除角色
+#[derive(Deserialize)]
+pub struct DeleteRoleQuery {
+    pub force: Option<bool>,
+}
+
 pub async fn delete_role(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Path(role_id): Path<i64>,
-) -> Result<Json<Value>, StatusCode> {
+    Query(query): Query<DeleteRoleQuery>,
+) -> Result<Json<Value>, ApiError> {
     // 需要"管理角色"权限
     if !auth_user.has_permission("管理角色") {
-        return Err(StatusCode::FORBIDDEN);
+        return Err(ApiError::forbidden("需要管理角色权限"));
     }
 
-    match RoleService::delete_role(&state.pool, role_id).await {
-        Ok(_) => {
-            log::info!("角色删除: ID={}", role_id);
+    let force = query.force.unwrap_or(false);
+    match RoleService::delete_role(&state.pool, role_id, force, auth_user.qq()).await {
+        Ok(DeleteRoleOutcome::Deleted { reassigned }) => {
+            log::info!(
+                "角色删除: ID={}, 改为成员角色的用户数={}",
+                role_id,
+                reassigned
+            );
             let _ = record_request_log(
                 &state.pool,
                 "DELETE",
                 &format!("/roles/{}", role_id),
                 Some(auth_user.qq()),
-                None,
+                Some(json!({ "force": force, "reassigned": reassigned }).to_string()),
                 StatusCode::OK.as_u16() as i32,
             )
             .await;
-            Ok(Json(json!({ "message": "角色删除成功" })))
+            Ok(Json(
+                json!({ "message": "角色删除成功", "reassigned": reassigned }),
+            ))
+        }
+        Ok(DeleteRoleOutcome::InUse(user_count)) => {
+            let message = format!(
+                "仍有 {} 名用户使用该角色，请先调整这些用户的角色或选择强制删除",
+                user_count
+            );
+            let _ = record_request_log(
+                &state.pool,
+                "DELETE",
+                &format!("/roles/{}", role_id),
+                Some(auth_user.qq()),
+                Some(message.clone()),
+                StatusCode::CONFLICT.as_u16() as i32,
+            )
+            .await;
+            Err(ApiError::conflict(message))
+        }
+        Err(
+            e @ (ServiceError::NotFound(_)
+            | ServiceError::Validation(_)
+            | ServiceError::Conflict(_)),
+        ) => {
+            let error = ApiError::from(e);
+            let _ = record_request_log(
+                &state.pool,
+                "DELETE",
+                &format!("/roles/{}", role_id),
+                Some(auth_user.qq()),
+                Some(error.to_string()),
+                error.status().as_u16() as i32,
+            )
+            .await;
+            Err(error)
         }
         Err(e) => {
             log::error!("删除角色失败: {}", e);
@@ -168,7 +230,7 @@ pub async fn delete_role(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("删除角色失败"))
         }
     }
 }
@@ -178,11 +240,9 @@ pub async fn grant_permission_to_role(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Json(payload): Json<RolePermissionRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     // 需要"管理角色"权限
-    if !auth_user.has_permission("管理角色") {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    auth_user.require_permission("管理角色")?;
 
     match RoleService::grant_permission_to_role(
         &state.pool,
@@ -208,6 +268,19 @@ pub async fn grant_permission_to_role(
             .await;
             Ok(Json(json!({ "message": "权限分配成功" })))
         }
+        Err(e @ ServiceError::NotFound(_)) => {
+            let error = ApiError::from(e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/roles/grant-permission",
+                Some(auth_user.qq()),
+                Some(error.to_string()),
+                error.status().as_u16() as i32,
+            )
+            .await;
+            Err(error)
+        }
         Err(e) => {
             log::error!("角色权限分配失败: {}", e);
             let _ = record_request_log(
@@ -219,7 +292,7 @@ pub async fn grant_permission_to_role(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("权限分配失败"))
         }
     }
 }
@@ -229,16 +302,15 @@ pub async fn revoke_permission_from_role(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Json(payload): Json<RolePermissionRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     // 需要"管理角色"权限
-    if !auth_user.has_permission("管理角色") {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    auth_user.require_permission("管理角色")?;
 
     match RoleService::revoke_permission_from_role(
         &state.pool,
         payload.role_id,
         &payload.permission_name,
+        auth_user.qq(),
     )
     .await
     {
@@ -259,6 +331,25 @@ pub async fn revoke_permission_from_role(
             .await;
             Ok(Json(json!({ "message": "权限移除成功" })))
         }
+        Err(e @ ServiceError::Conflict(_)) => {
+            let error = ApiError::from(e);
+            log::warn!(
+                "拒绝移除角色权限: 操作人={}, role_id={}, {}",
+                auth_user.qq(),
+                payload.role_id,
+                error
+            );
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/roles/revoke-permission",
+                Some(auth_user.qq()),
+                Some(error.to_string()),
+                error.status().as_u16() as i32,
+            )
+            .await;
+            Err(error)
+        }
         Err(e) => {
             log::error!("角色权限移除失败: {}", e);
             let _ = record_request_log(
@@ -270,7 +361,7 @@ pub async fn revoke_permission_from_role(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("权限移除失败"))
         }
     }
 }
@@ -282,9 +373,7 @@ pub async fn get_role_permissions(
     Path(role_id): Path<i64>,
 ) -> Result<Json<Value>, StatusCode> {
     // 需要"管理角色"权限
-    if !auth_user.has_permission("管理角色") {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    auth_user.require_permission("管理角色")?;
 
     let permissions = match RoleService::get_role_permissions(&state.pool, role_id).await {
         Ok(perms) => perms,
@@ -316,16 +405,53 @@ pub async fn get_role_permissions(
     Ok(Json(json!({ "permissions": permissions })))
 }
 
+/// 获取拥有该角色的用户
+pub async fn get_role_users(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(role_id): Path<i64>,
+) -> Result<Json<Value>, StatusCode> {
+    // 需要"管理角色"权限
+    auth_user.require_permission("管理角色")?;
+
+    let users = match RoleService::get_users_by_role(&state.pool, role_id).await {
+        Ok(users) => users,
+        Err(e) => {
+            log::error!("获取角色成员失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &format!("/roles/{}/users", role_id),
+                Some(auth_user.qq()),
+                Some(format!("获取角色成员失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &format!("/roles/{}/users", role_id),
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "users": users })))
+}
+
 /// 给用户分配角色
 pub async fn assign_role_to_user(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Json(payload): Json<AssignRoleRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     // 需要"管理角色"权限
-    if !auth_user.has_permission("管理角色") {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    auth_user.require_permission("管理角色")?;
 
     // 保护第一个默认管理员账号，不允许修改其角色
     if is_first_admin(&state.pool, &payload.user_qq).await {
@@ -339,10 +465,17 @@ pub async fn assign_role_to_user(
             StatusCode::FORBIDDEN.as_u16() as i32,
         )
         .await;
-        return Err(StatusCode::FORBIDDEN);
+        return Err(ApiError::forbidden("不能修改第一个默认管理员的角色"));
     }
 
-    match RoleService::assign_main_role(&state.pool, &payload.user_qq, payload.role_id).await {
+    match RoleService::assign_main_role(
+        &state.pool,
+        &payload.user_qq,
+        payload.role_id,
+        auth_user.qq(),
+    )
+    .await
+    {
         Ok(_) => {
             log::info!(
                 "用户角色分配: user={}, role_id={}",
@@ -360,6 +493,19 @@ pub async fn assign_role_to_user(
             .await;
             Ok(Json(json!({ "message": "角色分配成功" })))
         }
+        Err(e @ (ServiceError::NotFound(_) | ServiceError::Conflict(_))) => {
+            let error = ApiError::from(e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/roles/assign",
+                Some(auth_user.qq()),
+                Some(error.to_string()),
+                error.status().as_u16() as i32,
+            )
+            .await;
+            Err(error)
+        }
         Err(e) => {
             log::error!("用户角色分配失败: {}", e);
             let _ = record_request_log(
@@ -371,7 +517,151 @@ pub async fn assign_role_to_user(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(ApiError::internal("角色分配失败"))
         }
     }
 }
+
+/// 批量给用户分配角色：在同一事务中处理，逐个返回分配或跳过的结果
+pub async fn assign_role_bulk(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Json(payload): Json<BulkAssignRoleRequest>,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("管理角色")?;
+
+    let user_qqs: Vec<String> = payload
+        .user_qqs
+        .iter()
+        .map(|qq| qq.trim().to_string())
+        .filter(|qq| !qq.is_empty())
+        .collect();
+    let invalid = if user_qqs.is_empty() {
+        Some("请至少选择一个用户".to_string())
+    } else if user_qqs.len() > BULK_ASSIGN_MAX_USERS {
+        Some(format!("单次最多为{}个用户分配角色", BULK_ASSIGN_MAX_USERS))
+    } else {
+        None
+    };
+    if let Some(message) = invalid {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/roles/assign-bulk",
+            Some(auth_user.qq()),
+            Some(message.clone()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(message));
+    }
+
+    let results = match RoleService::assign_main_role_bulk(
+        &state.pool,
+        payload.role_id,
+        &user_qqs,
+        auth_user.qq(),
+    )
+    .await
+    {
+        Ok(Some(results)) => results,
+        Ok(None) => return Err(ApiError::new(StatusCode::NOT_FOUND, "角色不存在")),
+        Err(e @ ServiceError::Conflict(_)) => {
+            let error = ApiError::from(e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/roles/assign-bulk",
+                Some(auth_user.qq()),
+                Some(error.to_string()),
+                error.status().as_u16() as i32,
+            )
+            .await;
+            return Err(error);
+        }
+        Err(e) => {
+            log::error!("批量分配角色失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/roles/assign-bulk",
+                Some(auth_user.qq()),
+                Some(format!("批量分配角色失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("批量分配角色失败"));
+        }
+    };
+
+    let assigned = results
+        .iter()
+        .filter(|line| line.status == BulkAssignStatus::Assigned)
+        .count();
+    let skipped = results.len() - assigned;
+    if results
+        .iter()
+        .any(|line| line.status == BulkAssignStatus::SkippedProtected)
+    {
+        log::warn!("批量分配角色时跳过了第一个默认管理员");
+    }
+    log::info!(
+        "批量分配角色: role_id={}, 成功={}, 跳过={}",
+        payload.role_id,
+        assigned,
+        skipped
+    );
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/roles/assign-bulk",
+        Some(auth_user.qq()),
+        Some(json!({ "role_id": payload.role_id, "results": results }).to_string()),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "message": format!("已为 {} 个用户分配角色，跳过 {} 个", assigned, skipped),
+        "assigned": assigned,
+        "skipped": skipped,
+        "results": results,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{auth_user, response_json, test_state};
+    use axum::response::IntoResponse;
+
+    fn new_role(name: &str) -> Json<CreateRoleRequest> {
+        Json(CreateRoleRequest {
+            name: name.to_string(),
+            description: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn duplicate_role_name_is_a_conflict() {
+        let state = test_state().await;
+        let admin = || auth_user("9999", &["管理角色"]);
+
+        assert!(create_role(admin(), State(state.clone()), new_role("仓管"))
+            .await
+            .is_ok());
+        let error = create_role(admin(), State(state.clone()), new_role("仓管"))
+            .await
+            .unwrap_err();
+        let (status, body) = response_json(error.into_response()).await;
+
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["message"], "角色名称已存在");
+        // 内置角色同样不能重复创建
+        let error = create_role(admin(), State(state), new_role("成员"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+    }
+}
@@ -1,13 +1,29 @@
 use crate::auth::AuthenticatedUser;
+use crate::error::ApiError;
+use crate::export::parse_csv;
+use crate::idempotency;
+use crate::notifier::notify_user;
+use crate::pagination::set_pagination_headers;
 use crate::state::AppState;
+use crate::validation::{
+    check_max_chars, normalize_expires_at, validate_item_price, ITEM_IMPORT_MAX_ROWS,
+    ITEM_LOCATION_MAX_CHARS, ITEM_NAME_MAX_CHARS, ITEM_PRICE_MAX_CHARS,
+};
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
-use team_operation_system::db::{record_request_log, ShopService};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use team_operation_system::db::{
+    record_request_log, NewShopItem, ServiceError, ShopItemFilter, ShopService, StockAlert,
+    UserService, NOTIFICATION_KIND_LOW_STOCK,
+};
 
 #[derive(Deserialize)]
 pub struct CreateItemRequest {
@@ -16,6 +32,10 @@ pub struct CreateItemRequest {
     pub name: String,
     pub seller: String,
     pub location: String,
+    /// 库存降到该数量及以下时提醒卖家，不填则不提醒
+    pub low_stock_threshold: Option<i32>,
+    /// 下架时间，过期后不再公开展示和出售，不填则长期有效
+    pub expires_at: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -25,16 +45,119 @@ pub struct PurchaseRequest {
     pub count: i32,
 }
 
+#[derive(Deserialize)]
+pub struct BatchPurchaseItem {
+    pub item_id: i64,
+    pub count: i32,
+}
+
+#[derive(Deserialize)]
+pub struct BatchPurchaseRequest {
+    pub buyer: String,
+    pub items: Vec<BatchPurchaseItem>,
+}
+
+/// CSV 导入中单行的处理结果，`line` 为文件中的行号（从 1 开始）
+#[derive(Serialize)]
+pub struct ImportItemLine {
+    pub line: usize,
+    pub name: String,
+    pub id: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// 单次批量购买最多包含的商品条目数
+const BATCH_PURCHASE_MAX_ITEMS: usize = 50;
+
 #[derive(Deserialize)]
 pub struct MyItemsQuery {
     pub seller: String,
 }
 
+#[derive(Deserialize)]
+pub struct ShopItemsQuery {
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
+    pub keyword: Option<String>,
+    pub in_stock_only: Option<bool>,
+    pub seller: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
+
+/// 价格区间必须是非负数，且下限不能高于上限
+fn check_price_range(min_price: Option<f64>, max_price: Option<f64>) -> Result<(), String> {
+    for price in [min_price, max_price].into_iter().flatten() {
+        if !price.is_finite() || price < 0.0 {
+            return Err("价格区间必须是非负数".to_string());
+        }
+    }
+    if let (Some(min), Some(max)) = (min_price, max_price) {
+        if min > max {
+            return Err("最低价格不能高于最高价格".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// 根据响应内容计算弱 ETag，内容不变时前端可通过 `If-None-Match` 复用缓存
+fn weak_etag(body: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("W/\"{:016x}\"", hasher.finish())
+}
+
+/// `If-None-Match` 可能包含多个以逗号分隔的 ETag，或为 `*`
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .any(|candidate| candidate == "*" || candidate == etag)
+        })
+        .unwrap_or(false)
+}
+
 pub async fn list_items(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-) -> Result<Json<Value>, StatusCode> {
-    let items = match ShopService::get_all_items(&state.pool).await {
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    Query(query): Query<ShopItemsQuery>,
+) -> Result<Response, ApiError> {
+    state.rate_limiter.check(auth_user.qq(), "/shop/items")?;
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 100) as i64;
+    let offset = query.offset.unwrap_or(0).max(0) as i64;
+    if let Err(message) = check_price_range(query.min_price, query.max_price) {
+        let _ = record_request_log(
+            &state.pool,
+            "GET",
+            "/shop/items",
+            Some(auth_user.qq()),
+            Some(message.clone()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(message));
+    }
+    let filter = ShopItemFilter {
+        keyword: query.keyword.clone(),
+        in_stock_only: query.in_stock_only.unwrap_or(true),
+        seller: query
+            .seller
+            .as_deref()
+            .map(str::trim)
+            .filter(|seller| !seller.is_empty())
+            .map(str::to_string),
+        min_price: query.min_price,
+        max_price: query.max_price,
+    };
+
+    let items = match ShopService::list_items(&state.pool, &filter, limit, offset).await {
         Ok(items) => items,
         Err(e) => {
             log::error!("获取商品列表失败: {}", e);
@@ -47,30 +170,65 @@ pub async fn list_items(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("获取商品列表失败"));
+        }
+    };
+
+    let total = match ShopService::count_items(&state.pool, &filter).await {
+        Ok(total) => total,
+        Err(e) => {
+            log::error!("统计商品数量失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/shop/items",
+                Some(auth_user.qq()),
+                Some(format!("统计商品失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("统计商品数量失败"));
         }
     };
 
+    let body = json!({ "items": items, "total": total });
+    let etag = weak_etag(&body.to_string());
+    let status = if etag_matches(&headers, &etag) {
+        StatusCode::NOT_MODIFIED
+    } else {
+        StatusCode::OK
+    };
+
     let _ = record_request_log(
         &state.pool,
         "GET",
         "/shop/items",
         Some(auth_user.qq()),
         None,
-        StatusCode::OK.as_u16() as i32,
+        status.as_u16() as i32,
     )
     .await;
 
-    Ok(Json(json!({ "items": items })))
+    let mut response = if status == StatusCode::NOT_MODIFIED {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(body).into_response()
+    };
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    set_pagination_headers(response.headers_mut(), &uri, total, limit, offset);
+
+    Ok(response)
 }
 
 pub async fn my_items(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Query(params): Query<MyItemsQuery>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     if auth_user.qq() != params.seller && !auth_user.has_permission("管理商品") {
-        return Err(StatusCode::FORBIDDEN);
+        return Err(ApiError::forbidden("只能查看自己上架的商品"));
     }
 
     let items = match ShopService::get_user_items(&state.pool, &params.seller).await {
@@ -86,7 +244,7 @@ pub async fn my_items(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("获取用户商品失败"));
         }
     };
 
@@ -103,40 +261,89 @@ pub async fn my_items(
     Ok(Json(json!({ "items": items })))
 }
 
+/// 库存提醒阈值不能为负数，不设置（None）时不提醒
+fn check_low_stock_threshold(threshold: Option<i32>) -> Result<(), String> {
+    match threshold {
+        Some(threshold) if threshold < 0 => Err("库存提醒阈值不能为负数".to_string()),
+        _ => Ok(()),
+    }
+}
+
 pub async fn create_item(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Json(payload): Json<CreateItemRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     if auth_user.qq() != payload.seller && !auth_user.has_permission("管理商品") {
-        return Err(StatusCode::FORBIDDEN);
+        return Err(ApiError::forbidden("只能以自己的身份上架商品"));
     }
 
+    let length_check = check_max_chars("商品名称", &payload.name, ITEM_NAME_MAX_CHARS)
+        .and_then(|_| check_max_chars("商品位置", &payload.location, ITEM_LOCATION_MAX_CHARS))
+        .and_then(|_| check_max_chars("商品价格", &payload.price, ITEM_PRICE_MAX_CHARS))
+        .and_then(|_| check_low_stock_threshold(payload.low_stock_threshold));
+    // 空字符串视为不设置下架时间
+    let expires_at = length_check.and_then(|_| {
+        payload
+            .expires_at
+            .as_deref()
+            .filter(|raw| !raw.trim().is_empty())
+            .map(normalize_expires_at)
+            .transpose()
+    });
+    let expires_at = match expires_at {
+        Ok(expires_at) => expires_at,
+        Err(message) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/shop/items/create",
+                Some(auth_user.qq()),
+                Some(message.clone()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+    };
+
     let CreateItemRequest {
         count,
         price,
         name,
         seller,
         location,
+        low_stock_threshold,
+        ..
     } = payload;
 
-    let id =
-        match ShopService::add_item(&state.pool, count, &price, &name, &seller, &location).await {
-            Ok(id) => id,
-            Err(e) => {
-                log::error!("商品上架失败: {}", e);
-                let _ = record_request_log(
-                    &state.pool,
-                    "POST",
-                    "/shop/items/create",
-                    Some(auth_user.qq()),
-                    Some(format!("商品上架失败: {}", e)),
-                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
-                )
-                .await;
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        };
+    let id = match ShopService::add_item(
+        &state.pool,
+        count,
+        &price,
+        &name,
+        &seller,
+        &location,
+        low_stock_threshold,
+        expires_at.as_deref(),
+    )
+    .await
+    {
+        Ok(id) => id,
+        Err(e) => {
+            log::error!("商品上架失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/shop/items/create",
+                Some(auth_user.qq()),
+                Some(format!("商品上架失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("商品上架失败"));
+        }
+    };
 
     log::info!("商品上架: ID={}, 卖家={}", id, seller);
     let _ = record_request_log(
@@ -150,6 +357,8 @@ pub async fn create_item(
                 "count": count,
                 "price": price,
                 "location": location,
+                "low_stock_threshold": low_stock_threshold,
+                "expires_at": expires_at,
             }))
             .unwrap_or_default(),
         ),
@@ -159,16 +368,223 @@ pub async fn create_item(
     Ok(Json(json!({ "message": "商品上架成功", "id": id })))
 }
 
+/// 解析导入 CSV 的一行（`name,price,count,location`），返回待上架商品或错误原因
+fn parse_import_row(fields: &[String]) -> Result<NewShopItem, String> {
+    let [name, price, count, location] = fields else {
+        return Err(format!(
+            "应为 4 列（name,price,count,location），实际 {} 列",
+            fields.len()
+        ));
+    };
+    let (name, price, location) = (name.trim(), price.trim(), location.trim());
+
+    if name.is_empty() {
+        return Err("商品名称不能为空".to_string());
+    }
+    check_max_chars("商品名称", name, ITEM_NAME_MAX_CHARS)?;
+    check_max_chars("商品价格", price, ITEM_PRICE_MAX_CHARS)?;
+    validate_item_price(price)?;
+    check_max_chars("商品位置", location, ITEM_LOCATION_MAX_CHARS)?;
+    let count = match count.trim().parse::<i32>() {
+        Ok(count) if count > 0 => count,
+        _ => return Err(format!("库存必须是正整数: {}", count.trim())),
+    };
+
+    Ok(NewShopItem {
+        name: name.to_string(),
+        price: price.to_string(),
+        count,
+        location: location.to_string(),
+    })
+}
+
+/// 记录导入被拒绝的请求日志并返回 400
+async fn reject_import(
+    state: &AppState,
+    auth_user: &AuthenticatedUser,
+    message: String,
+) -> ApiError {
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/shop/items/import",
+        Some(auth_user.qq()),
+        Some(message.clone()),
+        StatusCode::BAD_REQUEST.as_u16() as i32,
+    )
+    .await;
+    ApiError::bad_request(message)
+}
+
+/// 以当前用户为卖家从 CSV 批量上架商品（请求体为 `name,price,count,location` 格式的 CSV，
+/// 可带表头）。先校验全部行，任一行无效则不上架任何商品，返回逐行结果
+pub async fn import_items(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    body: Bytes,
+) -> Result<Json<Value>, ApiError> {
+    let Ok(text) = std::str::from_utf8(&body) else {
+        return Err(reject_import(
+            &state,
+            &auth_user,
+            "CSV 文件必须使用 UTF-8 编码".to_string(),
+        )
+        .await);
+    };
+
+    let mut rows: Vec<(usize, Vec<String>)> = parse_csv(text)
+        .into_iter()
+        .enumerate()
+        .map(|(index, fields)| (index + 1, fields))
+        .filter(|(_, fields)| fields.iter().any(|field| !field.trim().is_empty()))
+        .collect();
+    // 第一行为表头时跳过
+    if rows
+        .first()
+        .and_then(|(_, fields)| fields.first())
+        .is_some_and(|first| first.trim().eq_ignore_ascii_case("name"))
+    {
+        rows.remove(0);
+    }
+
+    if rows.is_empty() {
+        return Err(reject_import(&state, &auth_user, "CSV 中没有商品数据".to_string()).await);
+    }
+    if rows.len() > ITEM_IMPORT_MAX_ROWS {
+        let message = format!(
+            "单次最多导入 {} 个商品，本次 {} 个",
+            ITEM_IMPORT_MAX_ROWS,
+            rows.len()
+        );
+        return Err(reject_import(&state, &auth_user, message).await);
+    }
+
+    let mut lines = Vec::with_capacity(rows.len());
+    let mut items = Vec::with_capacity(rows.len());
+    for (line, fields) in &rows {
+        let name = fields
+            .first()
+            .map(|name| name.trim().to_string())
+            .unwrap_or_default();
+        let error = match parse_import_row(fields) {
+            Ok(item) => {
+                items.push(item);
+                None
+            }
+            Err(message) => Some(message),
+        };
+        lines.push(ImportItemLine {
+            line: *line,
+            name,
+            id: None,
+            error,
+        });
+    }
+
+    let invalid_count = lines.iter().filter(|line| line.error.is_some()).count();
+    if invalid_count > 0 {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/shop/items/import",
+            Some(auth_user.qq()),
+            Some(format!(
+                "共 {} 行，{} 行无效，未导入",
+                lines.len(),
+                invalid_count
+            )),
+            StatusCode::OK.as_u16() as i32,
+        )
+        .await;
+        return Ok(Json(json!({
+            "message": format!("{} 行数据无效，本次未导入任何商品", invalid_count),
+            "success": false,
+            "results": lines,
+        })));
+    }
+
+    let ids = match ShopService::import_items(&state.pool, auth_user.qq(), &items).await {
+        Ok(ids) => ids,
+        Err(e) => {
+            log::error!("批量导入商品失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/shop/items/import",
+                Some(auth_user.qq()),
+                Some(format!("批量导入商品失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("批量导入商品失败"));
+        }
+    };
+    for (line, id) in lines.iter_mut().zip(ids) {
+        line.id = Some(id);
+    }
+
+    log::info!(
+        "批量导入商品: 卖家={}, 数量={}",
+        auth_user.qq(),
+        lines.len()
+    );
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/shop/items/import",
+        Some(auth_user.qq()),
+        Some(format!("导入 {} 个商品", lines.len())),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "message": format!("成功导入 {} 个商品", lines.len()),
+        "success": true,
+        "results": lines,
+    })))
+}
+
 pub async fn purchase_item(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<PurchaseRequest>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     if auth_user.qq() != payload.buyer {
-        return Err(StatusCode::FORBIDDEN);
+        return Err(ApiError::forbidden("只能以自己的身份购买商品"));
     }
 
-    let success = match ShopService::purchase_item(
+    let idem_key = idempotency::idempotency_key(&headers);
+    if let Some(cached) = idempotency::replay_or_reserve(
+        &state.pool,
+        auth_user.qq(),
+        "/shop/purchase",
+        idem_key.as_deref(),
+    )
+    .await?
+    {
+        return Ok(cached);
+    }
+
+    let result = execute_purchase(&auth_user, &state, &payload).await;
+    idempotency::finish(
+        &state.pool,
+        auth_user.qq(),
+        "/shop/purchase",
+        idem_key.as_deref(),
+        &result,
+    )
+    .await;
+    result
+}
+
+async fn execute_purchase(
+    auth_user: &AuthenticatedUser,
+    state: &AppState,
+    payload: &PurchaseRequest,
+) -> Result<Json<Value>, ApiError> {
+    let (success, alert) = match ShopService::purchase_item(
         &state.pool,
         &payload.buyer,
         payload.item_id,
@@ -188,7 +604,7 @@ pub async fn purchase_item(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("购买失败"));
         }
     };
 
@@ -198,6 +614,9 @@ pub async fn purchase_item(
             payload.buyer,
             payload.item_id
         );
+        if let Some(alert) = alert {
+            notify_low_stock(state, alert);
+        }
         let _ = record_request_log(
             &state.pool,
             "POST",
@@ -228,24 +647,193 @@ pub async fn purchase_item(
     }
 }
 
+/// 批量购买：全部成功或全部回滚，返回逐项结果
+pub async fn purchase_items_batch(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<BatchPurchaseRequest>,
+) -> Result<Json<Value>, ApiError> {
+    if auth_user.qq() != payload.buyer {
+        return Err(ApiError::forbidden("只能以自己的身份购买商品"));
+    }
+
+    let invalid = if payload.items.is_empty() {
+        Some("请至少选择一件商品".to_string())
+    } else if payload.items.len() > BATCH_PURCHASE_MAX_ITEMS {
+        Some(format!("单次最多购买{}种商品", BATCH_PURCHASE_MAX_ITEMS))
+    } else if payload.items.iter().any(|item| item.count <= 0) {
+        Some("购买数量必须大于0".to_string())
+    } else {
+        None
+    };
+    if let Some(message) = invalid {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/shop/purchase/batch",
+            Some(auth_user.qq()),
+            Some(message.clone()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(message));
+    }
+
+    let idem_key = idempotency::idempotency_key(&headers);
+    if let Some(cached) = idempotency::replay_or_reserve(
+        &state.pool,
+        auth_user.qq(),
+        "/shop/purchase/batch",
+        idem_key.as_deref(),
+    )
+    .await?
+    {
+        return Ok(cached);
+    }
+
+    let result = execute_batch_purchase(&auth_user, &state, &payload).await;
+    idempotency::finish(
+        &state.pool,
+        auth_user.qq(),
+        "/shop/purchase/batch",
+        idem_key.as_deref(),
+        &result,
+    )
+    .await;
+    result
+}
+
+async fn execute_batch_purchase(
+    auth_user: &AuthenticatedUser,
+    state: &AppState,
+    payload: &BatchPurchaseRequest,
+) -> Result<Json<Value>, ApiError> {
+    let items: Vec<(i64, i32)> = payload
+        .items
+        .iter()
+        .map(|item| (item.item_id, item.count))
+        .collect();
+
+    let (success, results, alerts) =
+        match ShopService::purchase_items(&state.pool, &payload.buyer, &items).await {
+            Ok(outcome) => outcome,
+            Err(e @ ServiceError::Validation(_)) => {
+                let _ = record_request_log(
+                    &state.pool,
+                    "POST",
+                    "/shop/purchase/batch",
+                    Some(auth_user.qq()),
+                    Some(e.to_string()),
+                    StatusCode::BAD_REQUEST.as_u16() as i32,
+                )
+                .await;
+                return Err(e.into());
+            }
+            Err(e) => {
+                log::error!("批量购买失败: {}", e);
+                let _ = record_request_log(
+                    &state.pool,
+                    "POST",
+                    "/shop/purchase/batch",
+                    Some(auth_user.qq()),
+                    Some(format!("批量购买失败: {}", e)),
+                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+                )
+                .await;
+                return Err(ApiError::internal("批量购买失败"));
+            }
+        };
+
+    let message = if success {
+        log::info!(
+            "批量购买成功: 买家={}, 商品数={}",
+            payload.buyer,
+            results.len()
+        );
+        for alert in alerts {
+            notify_low_stock(state, alert);
+        }
+        "购买成功"
+    } else {
+        "部分商品库存不足或已下架，本次购买已全部取消"
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/shop/purchase/batch",
+        Some(auth_user.qq()),
+        Some(
+            serde_json::to_string(&json!({
+                "items": items,
+                "success": success,
+            }))
+            .unwrap_or_default(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "message": message,
+        "success": success,
+        "results": results,
+    })))
+}
+
+/// 商品库存降到提醒阈值时记录日志并通知卖家
+fn notify_low_stock(state: &AppState, alert: StockAlert) {
+    log::info!(
+        "商品库存偏低: ID={}, 名称={}, 剩余={}, 阈值={}",
+        alert.item_id,
+        alert.name,
+        alert.remaining,
+        alert.threshold
+    );
+    notify_user(
+        state.notifier.clone(),
+        state.pool.clone(),
+        alert.seller,
+        NOTIFICATION_KIND_LOW_STOCK,
+        "库存偏低提醒".to_string(),
+        format!(
+            "你的商品「{}」(#{}) 剩余库存 {} 件，已达到提醒阈值 {} 件。",
+            alert.name, alert.item_id, alert.remaining, alert.threshold
+        ),
+    );
+}
+
 #[derive(Deserialize)]
 pub struct UserTransactionsQuery {
     pub user_qq: String,
+    /// 购买和销售记录各自的分页大小
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
 }
 
 pub async fn get_user_transactions(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Query(params): Query<UserTransactionsQuery>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<Value>, ApiError> {
     // 用户只能查看自己的交易记录，除非有管理商品权限
     if auth_user.qq() != params.user_qq && !auth_user.has_permission("管理商品") {
-        return Err(StatusCode::FORBIDDEN);
+        return Err(ApiError::forbidden("只能查看自己的交易记录"));
     }
 
+    let limit = params.limit.unwrap_or(20).clamp(1, 100) as i64;
+    let offset = params.offset.unwrap_or(0).max(0) as i64;
+
     // 获取购买记录
-    let purchases = match ShopService::get_user_purchases(&state.pool, &params.user_qq).await {
-        Ok(logs) => logs,
+    let purchases = async {
+        let logs =
+            ShopService::get_user_purchases(&state.pool, &params.user_qq, limit, offset).await?;
+        let total = ShopService::count_user_purchases(&state.pool, &params.user_qq).await?;
+        Ok::<_, sqlx::Error>((logs, total))
+    };
+    let (purchases, purchases_total) = match purchases.await {
+        Ok(result) => result,
         Err(e) => {
             log::error!("获取购买记录失败: {}", e);
             let _ = record_request_log(
@@ -257,13 +845,18 @@ pub async fn get_user_transactions(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("获取购买记录失败"));
         }
     };
 
     // 获取销售记录
-    let sales = match ShopService::get_user_sales(&state.pool, &params.user_qq).await {
-        Ok(logs) => logs,
+    let sales = async {
+        let logs = ShopService::get_user_sales(&state.pool, &params.user_qq, limit, offset).await?;
+        let total = ShopService::count_user_sales(&state.pool, &params.user_qq).await?;
+        Ok::<_, sqlx::Error>((logs, total))
+    };
+    let (sales, sales_total) = match sales.await {
+        Ok(result) => result,
         Err(e) => {
             log::error!("获取销售记录失败: {}", e);
             let _ = record_request_log(
@@ -275,7 +868,7 @@ pub async fn get_user_transactions(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("获取销售记录失败"));
         }
     };
 
@@ -291,6 +884,753 @@ pub async fn get_user_transactions(
 
     Ok(Json(json!({
         "purchases": purchases,
-        "sales": sales
+        "sales": sales,
+        "purchases_total": purchases_total,
+        "sales_total": sales_total
     })))
 }
+
+/// 按ID查看单条交易记录，仅买家、卖家或拥有管理商品权限的用户可查看
+pub async fn get_transaction(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/shop/transactions/{}", id);
+    let transaction = match ShopService::get_transaction(&state.pool, id).await {
+        Ok(Some(transaction)) => transaction,
+        Ok(None) => {
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &path,
+                Some(auth_user.qq()),
+                Some("交易记录不存在".to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "交易记录不存在"));
+        }
+        Err(e) => {
+            log::error!("获取交易记录失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("获取交易记录失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取交易记录失败"));
+        }
+    };
+
+    let is_party = auth_user.qq() == transaction.buyer || auth_user.qq() == transaction.seller;
+    if !is_party && !auth_user.has_permission("管理商品") {
+        let _ = record_request_log(
+            &state.pool,
+            "GET",
+            &path,
+            Some(auth_user.qq()),
+            Some("无权查看该交易记录".to_string()),
+            StatusCode::FORBIDDEN.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::forbidden("只能查看自己参与的交易记录"));
+    }
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &path,
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "transaction": transaction })))
+}
+
+/// 列出有在售商品的卖家，供商店按卖家筛选
+pub async fn list_sellers(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let sellers = match ShopService::list_sellers(&state.pool).await {
+        Ok(sellers) => sellers,
+        Err(e) => {
+            log::error!("获取卖家列表失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/shop/sellers",
+                Some(auth_user.qq()),
+                Some(format!("获取卖家列表失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取卖家列表失败"));
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        "/shop/sellers",
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "sellers": sellers })))
+}
+
+/// 卖家累计销售统计，仅卖家本人或拥有管理商品权限的用户可查看
+pub async fn seller_stats(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(qq): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    if auth_user.qq() != qq && !auth_user.has_permission("管理商品") {
+        return Err(ApiError::forbidden("只能查看自己的销售统计"));
+    }
+
+    let path = format!("/shop/sellers/{}/stats", qq);
+    let stats = match ShopService::get_seller_stats(&state.pool, &qq).await {
+        Ok(stats) => stats,
+        Err(e) => {
+            log::error!("获取销售统计失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("获取销售统计失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("获取销售统计失败"));
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &path,
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!(stats)))
+}
+
+#[derive(Deserialize)]
+pub struct TransferItemRequest {
+    pub new_seller: String,
+}
+
+/// 将商品转让给其他卖家，仅当前卖家或拥有管理商品权限的用户可操作
+pub async fn transfer_item(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(payload): Json<TransferItemRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/shop/items/{}/transfer", id);
+    let new_seller = payload.new_seller.trim().to_string();
+
+    let item = match ShopService::get_item(&state.pool, id).await {
+        Ok(Some(item)) => item,
+        Ok(None) => return Err(ApiError::new(StatusCode::NOT_FOUND, "商品不存在")),
+        Err(e) => {
+            log::error!("查询商品失败: {}", e);
+            return Err(ApiError::internal("查询商品失败"));
+        }
+    };
+
+    if auth_user.qq() != item.seller && !auth_user.has_permission("管理商品") {
+        return Err(ApiError::forbidden("只能转让自己上架的商品"));
+    }
+
+    if new_seller.is_empty() {
+        return Err(ApiError::bad_request("请填写新卖家QQ"));
+    }
+    if new_seller == item.seller {
+        return Err(ApiError::bad_request("新卖家与当前卖家相同"));
+    }
+
+    match UserService::get_user(&state.pool, &new_seller).await {
+        Ok(Some(user)) if user.is_active => {}
+        Ok(Some(_)) => return Err(ApiError::bad_request("目标用户已被禁用")),
+        Ok(None) => return Err(ApiError::bad_request("目标用户不存在")),
+        Err(e) => {
+            log::error!("查询目标用户失败: {}", e);
+            return Err(ApiError::internal("查询目标用户失败"));
+        }
+    }
+
+    if let Err(e) = ShopService::transfer_item(&state.pool, id, &new_seller).await {
+        log::error!("转让商品失败: {}", e);
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            &path,
+            Some(auth_user.qq()),
+            Some(format!("转让商品失败: {}", e)),
+            StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::internal("转让商品失败"));
+    }
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        &path,
+        Some(auth_user.qq()),
+        Some(
+            serde_json::to_string(&json!({
+                "from": item.seller,
+                "to": new_seller,
+            }))
+            .unwrap_or_default(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "message": "商品已转让" })))
+}
+
+#[derive(Deserialize)]
+pub struct RestockItemRequest {
+    pub delta: i32,
+}
+
+/// 调整商品库存（补货），仅当前卖家或拥有管理商品权限的用户可操作。
+/// 在数据库中原子地累加，并发补货不会互相覆盖
+pub async fn restock_item(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(payload): Json<RestockItemRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/shop/items/{}/restock", id);
+
+    let item = match ShopService::get_item(&state.pool, id).await {
+        Ok(Some(item)) => item,
+        Ok(None) => return Err(ApiError::new(StatusCode::NOT_FOUND, "商品不存在")),
+        Err(e) => {
+            log::error!("查询商品失败: {}", e);
+            return Err(ApiError::internal("查询商品失败"));
+        }
+    };
+
+    if auth_user.qq() != item.seller && !auth_user.has_permission("管理商品") {
+        return Err(ApiError::forbidden("只能为自己上架的商品补货"));
+    }
+    if payload.delta == 0 {
+        return Err(ApiError::bad_request("补货数量不能为0"));
+    }
+
+    let count = match ShopService::restock_item(&state.pool, id, payload.delta).await {
+        Ok(Some(count)) => count,
+        Ok(None) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("库存调整后为负: delta={}", payload.delta)),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request("调整后库存不能为负数"));
+        }
+        Err(e) => {
+            log::error!("商品补货失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("商品补货失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("商品补货失败"));
+        }
+    };
+
+    log::info!(
+        "商品补货: item_id={}, 操作人={}, 变化={}, 当前库存={}",
+        id,
+        auth_user.qq(),
+        payload.delta,
+        count
+    );
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        &path,
+        Some(auth_user.qq()),
+        Some(
+            serde_json::to_string(&json!({
+                "delta": payload.delta,
+                "count": count,
+            }))
+            .unwrap_or_default(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "message": "补货成功", "count": count })))
+}
+
+/// 区分请求中未提供的字段（外层 None，表示不修改）和显式的 null（Some(None)，表示清除）
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+pub struct UpdateItemRequest {
+    /// 新的库存提醒阈值，null 表示关闭提醒，不提供则不修改
+    #[serde(default, deserialize_with = "nullable")]
+    pub low_stock_threshold: Option<Option<i32>>,
+    /// 新的下架时间，null 或空字符串表示取消下架时间，不提供则不修改
+    #[serde(default, deserialize_with = "nullable")]
+    pub expires_at: Option<Option<String>>,
+}
+
+/// 修改商品设置，仅当前卖家或拥有管理商品权限的用户可操作
+pub async fn update_item(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(payload): Json<UpdateItemRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let path = format!("/shop/items/{}", id);
+
+    let item = match ShopService::get_item(&state.pool, id).await {
+        Ok(Some(item)) => item,
+        Ok(None) => {
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                &path,
+                Some(auth_user.qq()),
+                Some("商品不存在".to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "商品不存在"));
+        }
+        Err(e) => {
+            log::error!("查询商品失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("查询商品失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("查询商品失败"));
+        }
+    };
+
+    if auth_user.qq() != item.seller && !auth_user.has_permission("管理商品") {
+        let _ = record_request_log(
+            &state.pool,
+            "PATCH",
+            &path,
+            Some(auth_user.qq()),
+            Some("只能修改自己上架的商品".to_string()),
+            StatusCode::FORBIDDEN.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::forbidden("只能修改自己上架的商品"));
+    }
+
+    let threshold_check = payload
+        .low_stock_threshold
+        .map_or(Ok(()), check_low_stock_threshold);
+    // 与上架时一致，空字符串视为不设置下架时间
+    let expires_at = threshold_check.and_then(|_| {
+        payload
+            .expires_at
+            .map(|raw| {
+                raw.filter(|raw| !raw.trim().is_empty())
+                    .as_deref()
+                    .map(normalize_expires_at)
+                    .transpose()
+            })
+            .transpose()
+    });
+    let expires_at = match expires_at {
+        Ok(expires_at) => expires_at,
+        Err(message) => {
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                &path,
+                Some(auth_user.qq()),
+                Some(message.clone()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+    };
+
+    if let Err(e) = ShopService::update_item(
+        &state.pool,
+        id,
+        None,
+        None,
+        None,
+        payload.low_stock_threshold,
+        expires_at.clone(),
+    )
+    .await
+    {
+        log::error!("修改商品失败: {}", e);
+        let _ = record_request_log(
+            &state.pool,
+            "PATCH",
+            &path,
+            Some(auth_user.qq()),
+            Some(format!("修改商品失败: {}", e)),
+            StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::internal("修改商品失败"));
+    }
+
+    let _ = record_request_log(
+        &state.pool,
+        "PATCH",
+        &path,
+        Some(auth_user.qq()),
+        Some(
+            json!({
+                "low_stock_threshold": payload.low_stock_threshold,
+                "expires_at": expires_at,
+            })
+            .to_string(),
+        ),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "message": "商品已更新" })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        add_user, auth_user, bearer_token, request_builder, response_json, send, send_json,
+        send_request, test_state,
+    };
+
+    async fn count_rows(pool: &team_operation_system::db::DbPool, sql: &str) -> i64 {
+        sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn csv_import_reports_invalid_rows_and_imports_only_clean_files() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let seller = || auth_user("10001", &[]);
+        let items_of = |pool| {
+            count_rows(
+                pool,
+                "SELECT COUNT(*) FROM shopitems WHERE seller = '10001'",
+            )
+        };
+
+        let mixed =
+            "name,price,count,location\n咖啡,10,5,A区\n,10,5,A区\n茶,abc,5,A区\n饼干,3,0,B区\n";
+        let Json(body) = import_items(seller(), State(state.clone()), Bytes::from(mixed))
+            .await
+            .unwrap();
+        assert_eq!(body["success"], false);
+        let errors: Vec<bool> = body["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|line| line["error"].is_string())
+            .collect();
+        assert_eq!(errors, vec![false, true, true, true]);
+        assert_eq!(body["results"][1]["line"], 3);
+        assert_eq!(items_of(&state.pool).await, 0);
+
+        let clean = "咖啡,10,5,A区\n茶,2.5,3,B区\n";
+        let Json(body) = import_items(seller(), State(state.clone()), Bytes::from(clean))
+            .await
+            .unwrap();
+        assert_eq!(body["success"], true);
+        assert!(body["results"][0]["id"].as_i64().is_some());
+        assert_eq!(items_of(&state.pool).await, 2);
+    }
+
+    #[tokio::test]
+    async fn purchase_replayed_with_same_idempotency_key_runs_once() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let item_id =
+            ShopService::add_item(&state.pool, 5, "10", "咖啡", "9999", "A区", None, None)
+                .await
+                .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            idempotency::IDEMPOTENCY_HEADER,
+            HeaderValue::from_static("buy-1"),
+        );
+        let payload = || PurchaseRequest {
+            buyer: "10001".to_string(),
+            item_id,
+            count: 2,
+        };
+
+        let Json(first) = purchase_item(
+            auth_user("10001", &[]),
+            State(state.clone()),
+            headers.clone(),
+            Json(payload()),
+        )
+        .await
+        .unwrap();
+        let Json(second) = purchase_item(
+            auth_user("10001", &[]),
+            State(state.clone()),
+            headers,
+            Json(payload()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(
+            count_rows(&state.pool, "SELECT COUNT(*) FROM shoplog").await,
+            1
+        );
+        let item = ShopService::get_item(&state.pool, item_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.count, 3);
+    }
+
+    #[tokio::test]
+    async fn unchanged_item_list_returns_304() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        ShopService::add_item(&state.pool, 5, "10", "咖啡", "9999", "A区", None, None)
+            .await
+            .unwrap();
+        let token = bearer_token("10001", &[]);
+
+        let first = send(&state, "GET", "/api/shop/items", Some(&token)).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers()[header::ETAG].clone();
+
+        let conditional = || {
+            request_builder("GET", "/api/shop/items", Some(&token))
+                .header(header::IF_NONE_MATCH, etag.clone())
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        let second = send_request(&state, conditional()).await;
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(second.headers()[header::ETAG], etag);
+
+        // 商品变化后旧的 ETag 不再匹配
+        ShopService::add_item(&state.pool, 1, "8", "茶", "9999", "A区", None, None)
+            .await
+            .unwrap();
+        let third = send_request(&state, conditional()).await;
+        assert_eq!(third.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn transaction_is_visible_only_to_parties_and_shop_managers() {
+        let state = test_state().await;
+        for qq in ["10001", "10002", "10003"] {
+            add_user(&state.pool, qq).await;
+        }
+        let item_id =
+            ShopService::add_item(&state.pool, 5, "10", "咖啡", "10002", "A区", None, None)
+                .await
+                .unwrap();
+        ShopService::purchase_items(&state.pool, "10001", &[(item_id, 1)])
+            .await
+            .unwrap();
+        let id: i64 = sqlx::query_scalar("SELECT id FROM shoplog")
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+
+        let view = |user: AuthenticatedUser, id: i64| {
+            get_transaction(user, State(state.clone()), Path(id))
+        };
+        // 买家、卖家和拥有管理商品权限的用户均可查看
+        assert!(view(auth_user("10001", &[]), id).await.is_ok());
+        assert!(view(auth_user("10002", &[]), id).await.is_ok());
+        assert!(view(auth_user("10003", &["管理商品"]), id).await.is_ok());
+
+        let denied = view(auth_user("10003", &[]), id).await.unwrap_err();
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+        let missing = view(auth_user("10001", &[]), id + 1).await.unwrap_err();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn transferred_item_is_managed_by_new_seller() {
+        let state = test_state().await;
+        for qq in ["10001", "10002", "10003"] {
+            add_user(&state.pool, qq).await;
+        }
+        let item_id =
+            ShopService::add_item(&state.pool, 5, "10", "咖啡", "10001", "A区", None, None)
+                .await
+                .unwrap();
+        ShopService::purchase_items(&state.pool, "10003", &[(item_id, 1)])
+            .await
+            .unwrap();
+
+        let _ = transfer_item(
+            auth_user("10001", &[]),
+            State(state.clone()),
+            Path(item_id),
+            Json(TransferItemRequest {
+                new_seller: "10002".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let restock = |qq: &'static str| {
+            restock_item(
+                auth_user(qq, &[]),
+                State(state.clone()),
+                Path(item_id),
+                Json(RestockItemRequest { delta: 2 }),
+            )
+        };
+        assert!(restock("10002").await.is_ok());
+        let denied = restock("10001").await.unwrap_err();
+        assert_eq!(denied.status(), StatusCode::FORBIDDEN);
+
+        let item = ShopService::get_item(&state.pool, item_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((item.seller.as_str(), item.count), ("10002", 6));
+        // 历史交易仍记在原卖家名下
+        let seller: String = sqlx::query_scalar("SELECT seller FROM shoplog")
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(seller, "10001");
+    }
+
+    #[tokio::test]
+    async fn seller_updates_or_clears_expiry_after_creation() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let item_id =
+            ShopService::add_item(&state.pool, 5, "10", "咖啡", "10001", "A区", None, None)
+                .await
+                .unwrap();
+        let path = format!("/api/shop/items/{}", item_id);
+        let patch = |qq: &str, body: &str| {
+            let token = bearer_token(qq, &[]);
+            let state = state.clone();
+            let path = path.clone();
+            let body = body.to_string();
+            async move { send_json(&state, "PATCH", &path, Some(&token), body).await }
+        };
+        let expires_at = || async {
+            ShopService::get_item(&state.pool, item_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .expires_at
+        };
+
+        let response = patch("10001", r#"{"expires_at":"2999-01-01T08:30"}"#).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(expires_at().await.as_deref(), Some("2999-01-01 08:30:00"));
+
+        // 未提供字段时保持不变
+        let response = patch("10001", "{}").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(expires_at().await.as_deref(), Some("2999-01-01 08:30:00"));
+
+        let (status, body) =
+            response_json(patch("10001", r#"{"expires_at":"2000-01-01 00:00:00"}"#).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "下架时间不能早于当前时间");
+
+        let response = patch("10002", r#"{"expires_at":null}"#).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(expires_at().await.is_some());
+
+        let response = patch("10001", r#"{"expires_at":null}"#).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(expires_at().await, None);
+    }
+
+    #[tokio::test]
+    async fn low_stock_threshold_can_be_changed_after_creation() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let item_id =
+            ShopService::add_item(&state.pool, 5, "10", "咖啡", "10001", "A区", Some(1), None)
+                .await
+                .unwrap();
+        let update = |body: &str| {
+            let payload: UpdateItemRequest = serde_json::from_str(body).unwrap();
+            update_item(
+                auth_user("10001", &[]),
+                State(state.clone()),
+                Path(item_id),
+                Json(payload),
+            )
+        };
+        let threshold = || async {
+            ShopService::get_item(&state.pool, item_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .low_stock_threshold
+        };
+
+        assert!(update(r#"{"low_stock_threshold":3}"#).await.is_ok());
+        assert_eq!(threshold().await, Some(3));
+
+        let error = update(r#"{"low_stock_threshold":-1}"#).await.unwrap_err();
+        let (status, body) = response_json(error.into_response()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "库存提醒阈值不能为负数");
+        assert_eq!(threshold().await, Some(3));
+
+        assert!(update(r#"{"low_stock_threshold":null}"#).await.is_ok());
+        assert_eq!(threshold().await, None);
+    }
+}
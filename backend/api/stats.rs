@@ -0,0 +1,40 @@
+use crate::auth::AuthenticatedUser;
+use crate::state::AppState;
+use axum::{extract::State, http::StatusCode, Json};
+use team_operation_system::db::{record_request_log, StatsService};
+use team_operation_system::models::OverviewStats;
+
+/// 首页概览统计，登录即可查看
+pub async fn overview(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<OverviewStats>, StatusCode> {
+    let stats = match StatsService::get_overview(&state.pool).await {
+        Ok(stats) => stats,
+        Err(e) => {
+            log::error!("获取概览统计失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/stats/overview",
+                Some(auth_user.qq()),
+                Some(format!("获取概览统计失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        "/stats/overview",
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(stats))
+}
@@ -0,0 +1,16 @@
+use axum::Json;
+use chrono::Utc;
+use serde_json::{json, Value};
+use team_operation_system::db::{now_string, server_offset};
+
+/// 服务器当前时间和时区。计划开奖、商品下架等时间都按该时区解释，
+/// 前端据此换算 `datetime-local` 输入，避免浏览器与服务器时区不同导致时间错位
+pub async fn server_time() -> Json<Value> {
+    let offset = server_offset();
+    Json(json!({
+        "now": now_string(),
+        "timezone": offset.to_string(),
+        "utc_offset_seconds": offset.local_minus_utc(),
+        "timestamp": Utc::now().timestamp(),
+    }))
+}
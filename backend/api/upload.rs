@@ -0,0 +1,191 @@
+use crate::{
+    auth::AuthenticatedUser,
+    error::ApiError,
+    state::AppState,
+    upload::{detect_image_ext, upload_dir, UPLOAD_MAX_BYTES, UPLOAD_URL_PREFIX},
+};
+use axum::{
+    extract::{Multipart, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::{json, Value};
+use team_operation_system::db::record_request_log;
+
+/// 上传图片（multipart 表单中的 `file` 字段），返回可直接填入 LP 图片地址的访问路径
+pub async fn upload_image(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<Json<Value>, ApiError> {
+    state.rate_limiter.check(auth_user.qq(), "/uploads")?;
+
+    let data = match read_file_field(&mut multipart).await {
+        Ok(data) => data,
+        Err(message) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/uploads",
+                Some(auth_user.qq()),
+                Some(message.clone()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+    };
+
+    let Some(ext) = detect_image_ext(&data) else {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/uploads",
+            Some(auth_user.qq()),
+            Some("不支持的文件类型".to_string()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(
+            "仅支持 PNG、JPEG、GIF、WebP 格式的图片",
+        ));
+    };
+
+    let file_name = format!("{}.{}", uuid::Uuid::new_v4().simple(), ext);
+    let dir = upload_dir();
+    let saved = async {
+        tokio::fs::create_dir_all(dir).await?;
+        tokio::fs::write(dir.join(&file_name), &data).await
+    }
+    .await;
+    if let Err(e) = saved {
+        log::error!("保存上传图片失败: {}", e);
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/uploads",
+            Some(auth_user.qq()),
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::internal("保存图片失败"));
+    }
+
+    let url = format!("{}/{}", UPLOAD_URL_PREFIX, file_name);
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/uploads",
+        Some(auth_user.qq()),
+        Some(format!("上传图片 {} ({} 字节)", file_name, data.len())),
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "url": url })))
+}
+
+/// 读取表单中的 `file` 字段，边读边检查大小，超出上限立即中止
+async fn read_file_field(multipart: &mut Multipart) -> Result<Vec<u8>, String> {
+    loop {
+        let mut field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => return Err("缺少上传文件字段 file".to_string()),
+            Err(e) => return Err(format!("上传表单格式错误: {}", e)),
+        };
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| format!("读取上传文件失败: {}", e))?
+        {
+            if data.len() + chunk.len() > UPLOAD_MAX_BYTES {
+                return Err(format!(
+                    "图片大小不能超过 {}MB",
+                    UPLOAD_MAX_BYTES / 1024 / 1024
+                ));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        if data.is_empty() {
+            return Err("上传文件为空".to_string());
+        }
+        return Ok(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        add_user, bearer_token, request_builder, response_json, send_request, test_state,
+    };
+    use axum::body::Body;
+    use axum::http::header;
+    use axum::response::Response;
+
+    const BOUNDARY: &str = "test-boundary";
+
+    async fn upload(state: &AppState, token: &str, data: &[u8]) -> Response {
+        // 上传目录只在首次使用时读取，测试统一写入临时目录
+        std::env::set_var(
+            "UPLOAD_DIR",
+            std::env::temp_dir().join(format!("team-operation-uploads-{}", std::process::id())),
+        );
+        let mut body = format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.png\"\r\nContent-Type: image/png\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+        let request = request_builder("POST", "/api/uploads", Some(token))
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))
+            .unwrap();
+        send_request(state, request).await
+    }
+
+    #[tokio::test]
+    async fn png_upload_is_saved_and_returns_url() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &[]);
+        let png = b"\x89PNG\r\n\x1a\nrest-of-image";
+
+        let (status, body) = response_json(upload(&state, &token, png).await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let url = body["url"].as_str().unwrap();
+        let file_name = url.strip_prefix("/uploads/").unwrap();
+        assert!(file_name.ends_with(".png"));
+        let saved = upload_dir().join(file_name);
+        assert_eq!(std::fs::read(&saved).unwrap(), png);
+        let _ = std::fs::remove_file(saved);
+    }
+
+    #[tokio::test]
+    async fn non_image_and_oversized_uploads_are_rejected() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &[]);
+
+        let (status, body) = response_json(upload(&state, &token, b"plain text").await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "仅支持 PNG、JPEG、GIF、WebP 格式的图片");
+
+        let mut oversized = b"\x89PNG\r\n\x1a\n".to_vec();
+        oversized.resize(UPLOAD_MAX_BYTES + 1, 0);
+        let (status, body) = response_json(upload(&state, &token, &oversized).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "图片大小不能超过 5MB");
+    }
+}
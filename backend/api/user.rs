@@ -1,27 +1,31 @@
 use crate::auth::{
     AuthenticatedUser, Claims, LoginRequest, LoginResponse, RegisterRequest, UserInfo, JWT_SECRET,
 };
+use crate::error::ApiError;
+use crate::extract::ApiJson;
+use crate::pagination::set_pagination_headers;
 use crate::state::AppState;
+use crate::totp;
+use crate::validation::{validate_birthday, validate_email, validate_qq};
 use axum::{
-    extract::{Path, State},
+    extract::{OriginalUri, Path, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
 use jsonwebtoken::{encode, EncodingKey, Header};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use team_operation_system::db::{record_request_log, PermissionService, RoleService, UserService};
+use team_operation_system::db::{
+    count_user_timeline, list_user_timeline, record_request_log, LpService, PermissionService,
+    RoleService, ServiceError, UserFilter, UserService,
+};
 
 /// 检查指定用户是否是第一个默认管理员（数据库中第一个创建的用户）
 async fn is_first_admin(pool: &sqlx::SqlitePool, qq: &str) -> bool {
-    let first_user_qq: Option<String> =
-        sqlx::query_scalar("SELECT qq FROM user ORDER BY rowid LIMIT 1")
-            .fetch_optional(pool)
-            .await
-            .ok()
-            .flatten();
-
-    Some(qq) == first_user_qq.as_deref()
+    UserService::is_protected_admin(pool, qq)
+        .await
+        .unwrap_or(false)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,29 +33,126 @@ pub struct ProfileResponse {
     pub user: UserInfo,
 }
 
+/// 签发有效期 24 小时的登录凭证
+fn issue_token(qq: &str, nickname: &str, permissions: Vec<String>) -> Result<String, ApiError> {
+    let exp = chrono::Utc::now()
+        .checked_add_signed(chrono::Duration::hours(24))
+        .expect("有效时间")
+        .timestamp() as usize;
+
+    let claims = Claims {
+        sub: qq.to_string(),
+        nickname: nickname.to_string(),
+        exp,
+        permissions,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(JWT_SECRET),
+    )
+    .map_err(|_| ApiError::internal("生成登录凭证失败"))
+}
+
+/// 获取注册用的工作量证明挑战。未开启时返回 `enabled: false`，前端直接注册即可
+pub async fn register_challenge(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    let Some(guard) = &state.register_pow else {
+        return Ok(Json(json!({ "enabled": false })));
+    };
+
+    let nonce = guard
+        .issue()
+        .map_err(|message| ApiError::new(StatusCode::SERVICE_UNAVAILABLE, message))?;
+
+    Ok(Json(json!({
+        "enabled": true,
+        "nonce": nonce,
+        "difficulty": guard.difficulty(),
+    })))
+}
+
 pub async fn register(
     State(state): State<AppState>,
-    Json(payload): Json<RegisterRequest>,
-) -> Result<Json<Value>, StatusCode> {
+    ApiJson(payload): ApiJson<RegisterRequest>,
+) -> Result<Json<Value>, ApiError> {
     let RegisterRequest {
         qq,
         nickname,
         password,
         birthday,
+        email,
+        pow_nonce,
+        pow_solution,
     } = payload;
 
+    if let Some(guard) = &state.register_pow {
+        let verified = match (pow_nonce.as_deref(), pow_solution.as_deref()) {
+            (Some(nonce), Some(solution)) => guard.verify(nonce, solution),
+            _ => Err("缺少人机验证信息，请刷新页面后重试"),
+        };
+        if let Err(message) = verified {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/auth/register",
+                None,
+                Some(format!("注册失败: {} (QQ: {})", message, qq)),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+    }
+
+    let email = email
+        .map(|addr| addr.trim().to_string())
+        .filter(|addr| !addr.is_empty());
+    let birthday = match birthday.as_deref().map(validate_birthday).transpose() {
+        Ok(birthday) => birthday.filter(|date| !date.is_empty()),
+        Err(message) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/auth/register",
+                None,
+                Some(format!("注册失败: {}", message)),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(message));
+        }
+    };
+
     let log_body = json!({
         "qq": qq,
         "nickname": nickname,
         "birthday": birthday,
+        "email": email,
     });
 
+    if let Err(message) =
+        validate_qq(&qq).and_then(|_| validate_email(email.as_deref().unwrap_or_default()))
+    {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/auth/register",
+            None,
+            Some(format!("注册失败: {}", message)),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(message));
+    }
+
     match UserService::register(
         &state.pool,
         log_body["qq"].as_str().unwrap(),
         log_body["nickname"].as_str().unwrap(),
         &password,
         log_body["birthday"].as_str(),
+        email.as_deref(),
     )
     .await
     {
@@ -66,10 +167,53 @@ pub async fn register(
                 StatusCode::OK.as_u16() as i32,
             )
             .await;
-            Ok(Json(json!({ "message": "注册成功" })))
+
+            // 注册成功后直接签发登录凭证，前端无需再调用登录接口
+            let permissions = PermissionService::get_user_permissions(&state.pool, &qq)
+                .await
+                .unwrap_or_else(|e| {
+                    log::error!("查询用户权限失败: {}", e);
+                    Vec::new()
+                });
+            let role_name = RoleService::get_user_role(&state.pool, &qq)
+                .await
+                .ok()
+                .flatten()
+                .map(|r| r.name);
+            // 凭证签发失败不影响注册结果，用户仍可手动登录
+            let token = issue_token(&qq, &nickname, permissions.clone())
+                .inspect_err(|e| log::error!("注册后签发登录凭证失败: {}", e))
+                .ok();
+
+            let user = UserInfo {
+                qq,
+                nickname,
+                birthday,
+                avatar_url: None,
+                email,
+                role_name,
+                permissions,
+                is_default_password: false,
+                must_change_password: false,
+                totp_enabled: false,
+                default_lp_type: None,
+                is_protected_admin: false,
+            };
+
+            Ok(Json(json!({
+                "message": "注册成功",
+                "user": user,
+                "token": token,
+            })))
         }
         Err(e) => {
-            log::error!("注册失败: {}", e);
+            // qq 为主键，重复注册时 SQLite 返回唯一约束错误，服务层转换为 Conflict
+            let error = if matches!(e, ServiceError::Conflict(_)) {
+                ApiError::conflict("该QQ已注册")
+            } else {
+                log::error!("注册失败: {}", e);
+                ApiError::bad_request("注册失败")
+            };
             let error_body = format!("注册失败: {}", e);
             let _ = record_request_log(
                 &state.pool,
@@ -77,18 +221,18 @@ pub async fn register(
                 "/auth/register",
                 log_body["qq"].as_str(),
                 Some(error_body),
-                StatusCode::BAD_REQUEST.as_u16() as i32,
+                error.status().as_u16() as i32,
             )
             .await;
-            Err(StatusCode::BAD_REQUEST)
+            Err(error)
         }
     }
 }
 
 pub async fn login(
     State(state): State<AppState>,
-    Json(payload): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, StatusCode> {
+    ApiJson(payload): ApiJson<LoginRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
     let user = match UserService::login(&state.pool, &payload.qq, &payload.password).await {
         Ok(user) => user,
         Err(e) => {
@@ -103,7 +247,7 @@ pub async fn login(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(ApiError::internal("登录失败"));
         }
     };
 
@@ -119,9 +263,63 @@ pub async fn login(
                 StatusCode::UNAUTHORIZED.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::UNAUTHORIZED);
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "QQ号或密码错误"));
+        }
+    };
+
+    // 密码校验通过后再检查禁用状态，避免泄露账号是否存在
+    if !user.is_active {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/auth/login",
+            Some(&user.qq),
+            Some("登录失败: 账号已被禁用".to_string()),
+            StatusCode::FORBIDDEN.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::forbidden("账号已被禁用"));
+    }
+
+    // 开启了两步验证的账号还需校验验证码，未开启的账号不受影响
+    let (totp_enabled, totp_reject) = match UserService::get_totp(&state.pool, &user.qq).await {
+        Ok((Some(secret), true)) => {
+            let code = payload
+                .totp_code
+                .as_deref()
+                .map(str::trim)
+                .filter(|code| !code.is_empty());
+            let reject = match code {
+                None => Some("请输入两步验证码"),
+                Some(code) => match totp::verify_code(&secret, &user.qq, code) {
+                    Ok(true) => None,
+                    Ok(false) => Some("两步验证码错误"),
+                    Err(e) => {
+                        log::error!("校验两步验证码失败: {}", e);
+                        return Err(ApiError::internal("校验两步验证码失败"));
+                    }
+                },
+            };
+            (true, reject)
+        }
+        Ok(_) => (false, None),
+        Err(e) => {
+            log::error!("查询两步验证状态失败: {}", e);
+            return Err(ApiError::internal("登录失败"));
         }
     };
+    if let Some(reason) = totp_reject {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/auth/login",
+            Some(&user.qq),
+            Some(format!("登录失败: {}", reason)),
+            StatusCode::UNAUTHORIZED.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::new(StatusCode::UNAUTHORIZED, reason));
+    }
 
     let permissions = PermissionService::get_user_permissions(&state.pool, &user.qq)
         .await
@@ -136,51 +334,46 @@ pub async fn login(
         .flatten()
         .map(|r| r.name);
 
-    let exp = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::hours(24))
-        .expect("有效时间")
-        .timestamp() as usize;
-
-    let claims = Claims {
-        sub: user.qq.clone(),
-        nickname: user.nickname.clone(),
-        exp,
-        permissions: permissions.clone(),
-    };
-
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(JWT_SECRET),
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let token = issue_token(&user.qq, &user.nickname, permissions.clone())?;
 
     // 检测是否使用默认密码
     // 方法：检查是否是数据库中第一个创建的用户（初始化时创建的默认管理员）
-    let is_default_password = {
-        // 查询数据库中第一个用户的QQ号
-        let first_user_qq: Option<String> =
-            sqlx::query_scalar("SELECT qq FROM user ORDER BY rowid LIMIT 1")
-                .fetch_optional(&state.pool)
-                .await
-                .ok()
-                .flatten();
+    let is_protected_admin = is_first_admin(&state.pool, &user.qq).await;
+    // 如果当前用户是第一个用户，检查是否使用默认密码 "admin@666"
+    let is_default_password =
+        is_protected_admin && bcrypt::verify("admin@666", &user.password).unwrap_or(false);
 
-        // 如果当前用户是第一个用户，检查是否使用默认密码 "admin@666"
-        if Some(&user.qq) == first_user_qq.as_ref() {
-            bcrypt::verify("admin@666", &user.password).unwrap_or(false)
-        } else {
-            false
+    // 仍在使用默认密码的账号（包括旧数据库中的默认管理员）必须先修改密码
+    if is_default_password {
+        if let Err(e) = UserService::set_must_change_password(&state.pool, &user.qq, true).await {
+            log::error!("设置改密标记失败: {}", e);
         }
-    };
+    }
+    let must_change_password = is_default_password
+        || UserService::must_change_password(&state.pool, &user.qq)
+            .await
+            .unwrap_or(false);
+
+    let default_lp_type = UserService::get_default_lp_type(&state.pool, &user.qq)
+        .await
+        .unwrap_or_else(|e| {
+            log::error!("查询默认LP类型失败: {}", e);
+            None
+        });
 
     let user_info = UserInfo {
         qq: user.qq,
         nickname: user.nickname,
         birthday: user.birthday,
+        avatar_url: user.avatar_url,
+        email: user.email,
         role_name,
         permissions,
         is_default_password,
+        must_change_password,
+        totp_enabled,
+        default_lp_type,
+        is_protected_admin,
     };
 
     let login_body = json!({
@@ -203,12 +396,29 @@ pub async fn login(
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ListUsersQuery {
+    /// 只返回主角色为该角色的用户
+    pub role_id: Option<i64>,
+    /// 按 QQ 或昵称模糊搜索
+    pub search: Option<String>,
+}
+
 pub async fn list_users(
     user: AuthenticatedUser,
     State(state): State<AppState>,
-) -> Result<Json<Value>, StatusCode> {
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<Response, StatusCode> {
     user.require_permission("用户管理")?;
-    let users = match UserService::get_all_users(&state.pool).await {
+    let filter = UserFilter {
+        role_id: query.role_id,
+        search: query
+            .search
+            .map(|keyword| keyword.trim().to_string())
+            .filter(|keyword| !keyword.is_empty()),
+    };
+    let users = match UserService::list_users(&state.pool, &filter).await {
         Ok(users) => users,
         Err(e) => {
             log::error!("获取用户列表失败: {}", e);
@@ -235,9 +445,181 @@ pub async fn list_users(
     )
     .await;
 
+    // 用户列表不分页，一次返回全部匹配的用户
+    let total = users.len() as i64;
+    let mut response = Json(json!({ "users": users })).into_response();
+    set_pagination_headers(response.headers_mut(), &uri, total, total, 0);
+    Ok(response)
+}
+
+/// 自动补全最多返回的用户数
+const USER_SUGGESTION_LIMIT: i64 = 20;
+
+#[derive(Deserialize)]
+pub struct UserSearchQuery {
+    #[serde(default)]
+    pub q: String,
+}
+
+/// 按 QQ 或昵称前缀查找用户，供输入框自动补全，登录即可使用
+pub async fn search_users(
+    user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Query(query): Query<UserSearchQuery>,
+) -> Result<Json<Value>, StatusCode> {
+    let prefix = query.q.trim();
+    if prefix.is_empty() {
+        return Ok(Json(json!({ "users": [] })));
+    }
+
+    let users = match UserService::suggest_users(&state.pool, prefix, USER_SUGGESTION_LIMIT).await {
+        Ok(users) => users,
+        Err(e) => {
+            log::error!("搜索用户失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                "/users/search",
+                Some(user.qq()),
+                Some(format!("搜索用户失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // 自动补全随输入频繁触发，成功时不记录请求日志
     Ok(Json(json!({ "users": users })))
 }
 
+#[derive(Deserialize)]
+pub struct TimelineQuery {
+    pub limit: Option<i32>,
+    pub offset: Option<i32>,
+}
+
+/// 按时间倒序合并展示用户的LP记录、商店交易和请求日志，便于管理员排查
+pub async fn user_timeline(
+    user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(qq): Path<String>,
+    Query(query): Query<TimelineQuery>,
+) -> Result<Json<Value>, StatusCode> {
+    user.require_permission("用户管理")?;
+    let path = format!("/users/{}/timeline", qq);
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 100) as i64;
+    let offset = query.offset.unwrap_or(0).max(0) as i64;
+
+    let result = match list_user_timeline(&state.pool, &qq, limit, offset).await {
+        Ok(events) => count_user_timeline(&state.pool, &qq)
+            .await
+            .map(|total| (events, total)),
+        Err(e) => Err(e),
+    };
+    let (events, total) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            log::error!("获取用户时间线失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &path,
+                Some(user.qq()),
+                Some(format!("获取用户时间线失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &path,
+        Some(user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "events": events,
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+    })))
+}
+
+/// 查看用户实际拥有的权限及其来源角色
+pub async fn user_permissions(
+    user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(qq): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    user.require_permission("用户管理")?;
+
+    match UserService::get_user(&state.pool, &qq).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &format!("/users/{}/permissions", qq),
+                Some(user.qq()),
+                Some("用户不存在".to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::NOT_FOUND);
+        }
+        Err(e) => {
+            log::error!("查询用户信息失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &format!("/users/{}/permissions", qq),
+                Some(user.qq()),
+                Some(format!("查询用户信息失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let permissions = match PermissionService::get_user_permission_sources(&state.pool, &qq).await {
+        Ok(permissions) => permissions,
+        Err(e) => {
+            log::error!("获取用户权限来源失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "GET",
+                &format!("/users/{}/permissions", qq),
+                Some(user.qq()),
+                Some(format!("获取用户权限来源失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let _ = record_request_log(
+        &state.pool,
+        "GET",
+        &format!("/users/{}/permissions", qq),
+        Some(user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({ "qq": qq, "permissions": permissions })))
+}
+
 pub async fn delete_user(
     user: AuthenticatedUser,
     State(state): State<AppState>,
@@ -285,13 +667,144 @@ pub async fn delete_user(
     )
     .await;
 
-    Ok(Json(json!({ "message": "删除成功" })))
-}
+    Ok(Json(json!({ "message": "删除成功" })))
+}
+
+/// 禁用账号：保留数据但拒绝其登录
+pub async fn disable_user(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(qq): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    set_user_active(auth_user, state, qq, false).await
+}
+
+/// 重新启用被禁用的账号
+pub async fn enable_user(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    Path(qq): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    set_user_active(auth_user, state, qq, true).await
+}
+
+async fn set_user_active(
+    auth_user: AuthenticatedUser,
+    state: AppState,
+    qq: String,
+    active: bool,
+) -> Result<Json<Value>, ApiError> {
+    auth_user.require_permission("用户管理")?;
+
+    let path = if active {
+        format!("/users/{}/enable", qq)
+    } else {
+        format!("/users/{}/disable", qq)
+    };
+
+    if !active {
+        let reject = if is_first_admin(&state.pool, &qq).await {
+            Some("不能禁用第一个默认管理员账号")
+        } else if auth_user.qq() == qq {
+            Some("不能禁用自己的账号")
+        } else {
+            None
+        };
+        if let Some(reason) = reject {
+            log::warn!("拒绝禁用账号 {}: {}", qq, reason);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(reason.to_string()),
+                StatusCode::FORBIDDEN.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::forbidden(reason));
+        }
+    }
+
+    match UserService::set_active(&state.pool, &qq, active).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some("用户不存在".to_string()),
+                StatusCode::NOT_FOUND.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::new(StatusCode::NOT_FOUND, "用户不存在"));
+        }
+        Err(e) => {
+            log::error!("更新账号状态失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                &path,
+                Some(auth_user.qq()),
+                Some(format!("更新账号状态失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::internal("更新账号状态失败"));
+        }
+    }
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        &path,
+        Some(auth_user.qq()),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    let message = if active {
+        "账号已启用"
+    } else {
+        "账号已禁用"
+    };
+    Ok(Json(json!({ "message": message })))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateUserRequest {
+    pub nickname: Option<String>,
+    pub birthday: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email: Option<String>,
+    /// 仅个人信息接口使用，传入 0 表示清除默认LP类型
+    #[serde(default)]
+    pub default_lp_type: Option<i64>,
+}
+
+const AVATAR_URL_MAX_LEN: usize = 512;
+
+/// 校验头像地址：只接受 http(s) 链接且长度受限，空字符串表示清除头像
+fn validate_avatar_url(url: &str) -> Result<(), &'static str> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Ok(());
+    }
+    if url.len() > AVATAR_URL_MAX_LEN {
+        return Err("头像地址过长");
+    }
+
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or("头像地址必须以 http:// 或 https:// 开头")?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() || rest.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err("头像地址格式不正确");
+    }
 
-#[derive(Deserialize)]
-pub struct UpdateUserRequest {
-    pub nickname: Option<String>,
-    pub birthday: Option<String>,
+    Ok(())
 }
 
 #[derive(Deserialize)]
@@ -304,8 +817,8 @@ pub async fn update_user(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
     Path(qq): Path<String>,
-    Json(payload): Json<UpdateUserRequest>,
-) -> Result<Json<Value>, StatusCode> {
+    Json(mut payload): Json<UpdateUserRequest>,
+) -> Result<Json<Value>, ApiError> {
     let mut requires_admin = false;
     if auth_user.qq() != qq {
         requires_admin = true;
@@ -314,11 +827,94 @@ pub async fn update_user(
         auth_user.require_permission("用户管理")?;
     }
 
+    if let Some(Err(reason)) = payload.avatar_url.as_deref().map(validate_avatar_url) {
+        let _ = record_request_log(
+            &state.pool,
+            "PATCH",
+            &format!("/users/{}", qq),
+            Some(auth_user.qq()),
+            Some(reason.to_string()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(reason));
+    }
+
+    if let Some(Err(reason)) = payload.email.as_deref().map(validate_email) {
+        let _ = record_request_log(
+            &state.pool,
+            "PATCH",
+            &format!("/users/{}", qq),
+            Some(auth_user.qq()),
+            Some(reason.clone()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(reason));
+    }
+
+    match payload
+        .birthday
+        .as_deref()
+        .map(validate_birthday)
+        .transpose()
+    {
+        Ok(birthday) => payload.birthday = birthday,
+        Err(reason) => {
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                &format!("/users/{}", qq),
+                Some(auth_user.qq()),
+                Some(reason.clone()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(reason));
+        }
+    }
+
+    // 管理员修改他人资料时记录修改前的数据，用于审计
+    let before = if requires_admin {
+        match UserService::get_user(&state.pool, &qq).await {
+            Ok(Some(user)) => Some(user),
+            Ok(None) => {
+                let _ = record_request_log(
+                    &state.pool,
+                    "PATCH",
+                    &format!("/users/{}", qq),
+                    Some(auth_user.qq()),
+                    Some("用户不存在".to_string()),
+                    StatusCode::NOT_FOUND.as_u16() as i32,
+                )
+                .await;
+                return Err(StatusCode::NOT_FOUND.into());
+            }
+            Err(e) => {
+                log::error!("查询用户信息失败: {}", e);
+                let _ = record_request_log(
+                    &state.pool,
+                    "PATCH",
+                    &format!("/users/{}", qq),
+                    Some(auth_user.qq()),
+                    Some(format!("查询用户信息失败: {}", e)),
+                    StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+                )
+                .await;
+                return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+            }
+        }
+    } else {
+        None
+    };
+
     if let Err(e) = UserService::update_user(
         &state.pool,
         &qq,
         payload.nickname.clone(),
         payload.birthday.clone(),
+        payload.avatar_url.clone(),
+        payload.email.clone(),
     )
     .await
     {
@@ -333,12 +929,37 @@ pub async fn update_user(
             StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
         )
         .await;
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
+
+    if let Some(before) = before {
+        match UserService::get_user(&state.pool, &qq).await {
+            Ok(Some(after)) => {
+                match UserService::record_user_audit(&state.pool, auth_user.qq(), &before, &after)
+                    .await
+                {
+                    Ok(count) if count > 0 => {
+                        log::info!(
+                            "管理员 {} 修改了用户 {} 的 {} 项资料",
+                            auth_user.qq(),
+                            qq,
+                            count
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::error!("写入用户审计记录失败: {}", e),
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("查询用户信息失败: {}", e),
+        }
     }
 
     let log_body = json!({
         "nickname": payload.nickname,
         "birthday": payload.birthday,
+        "avatar_url": payload.avatar_url,
+        "email": payload.email,
     });
     let _ = record_request_log(
         &state.pool,
@@ -406,9 +1027,22 @@ pub async fn profile(
         qq: user.qq,
         nickname: user.nickname,
         birthday: user.birthday,
+        avatar_url: user.avatar_url,
+        email: user.email,
         role_name,
         permissions,
         is_default_password: false, // 个人信息接口不返回此字段
+        must_change_password: UserService::must_change_password(&state.pool, auth_user.qq())
+            .await
+            .unwrap_or(false),
+        totp_enabled: UserService::get_totp(&state.pool, auth_user.qq())
+            .await
+            .map(|(_, enabled)| enabled)
+            .unwrap_or(false),
+        default_lp_type: UserService::get_default_lp_type(&state.pool, auth_user.qq())
+            .await
+            .unwrap_or(None),
+        is_protected_admin: is_first_admin(&state.pool, auth_user.qq()).await,
     };
 
     let _ = record_request_log(
@@ -427,13 +1061,85 @@ pub async fn profile(
 pub async fn update_profile(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-    Json(payload): Json<UpdateUserRequest>,
-) -> Result<Json<ProfileResponse>, StatusCode> {
+    Json(mut payload): Json<UpdateUserRequest>,
+) -> Result<Json<ProfileResponse>, ApiError> {
+    if let Some(Err(reason)) = payload.avatar_url.as_deref().map(validate_avatar_url) {
+        let _ = record_request_log(
+            &state.pool,
+            "PATCH",
+            "/profile",
+            Some(auth_user.qq()),
+            Some(reason.to_string()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(reason));
+    }
+
+    if let Some(Err(reason)) = payload.email.as_deref().map(validate_email) {
+        let _ = record_request_log(
+            &state.pool,
+            "PATCH",
+            "/profile",
+            Some(auth_user.qq()),
+            Some(reason.clone()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::bad_request(reason));
+    }
+
+    match payload
+        .birthday
+        .as_deref()
+        .map(validate_birthday)
+        .transpose()
+    {
+        Ok(birthday) => payload.birthday = birthday,
+        Err(reason) => {
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                "/profile",
+                Some(auth_user.qq()),
+                Some(reason.clone()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(reason));
+        }
+    }
+
+    if let Some(lp_type) = payload.default_lp_type.filter(|id| *id != 0) {
+        let reject = match LpService::lp_type_exists(&state.pool, lp_type).await {
+            Ok(true) => None,
+            Ok(false) => Some(StatusCode::BAD_REQUEST),
+            Err(e) => {
+                log::error!("查询LP类型失败: {}", e);
+                Some(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        };
+        if let Some(status) = reject {
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                "/profile",
+                Some(auth_user.qq()),
+                Some(format!("默认LP类型无效: {}", lp_type)),
+                status.as_u16() as i32,
+            )
+            .await;
+            return Err(status.into());
+        }
+    }
+
     if let Err(e) = UserService::update_user(
         &state.pool,
         auth_user.qq(),
         payload.nickname.clone(),
         payload.birthday.clone(),
+        payload.avatar_url.clone(),
+        payload.email.clone(),
     )
     .await
     {
@@ -447,7 +1153,25 @@ pub async fn update_profile(
             StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
         )
         .await;
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
+
+    if let Some(lp_type) = payload.default_lp_type {
+        let lp_type = Some(lp_type).filter(|id| *id != 0);
+        if let Err(e) = UserService::set_default_lp_type(&state.pool, auth_user.qq(), lp_type).await
+        {
+            log::error!("更新默认LP类型失败: {}", e);
+            let _ = record_request_log(
+                &state.pool,
+                "PATCH",
+                "/profile",
+                Some(auth_user.qq()),
+                Some(format!("更新默认LP类型失败: {}", e)),
+                StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+            )
+            .await;
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+        }
     }
 
     let updated_user = match UserService::get_user(&state.pool, auth_user.qq()).await {
@@ -463,7 +1187,7 @@ pub async fn update_profile(
                 StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
         }
     };
 
@@ -479,7 +1203,7 @@ pub async fn update_profile(
                 StatusCode::NOT_FOUND.as_u16() as i32,
             )
             .await;
-            return Err(StatusCode::NOT_FOUND);
+            return Err(StatusCode::NOT_FOUND.into());
         }
     };
 
@@ -499,14 +1223,30 @@ pub async fn update_profile(
         qq: updated_user.qq,
         nickname: updated_user.nickname,
         birthday: updated_user.birthday,
+        avatar_url: updated_user.avatar_url,
+        email: updated_user.email,
         role_name,
         permissions,
         is_default_password: false, // 个人信息接口不返回此字段
+        must_change_password: UserService::must_change_password(&state.pool, auth_user.qq())
+            .await
+            .unwrap_or(false),
+        totp_enabled: UserService::get_totp(&state.pool, auth_user.qq())
+            .await
+            .map(|(_, enabled)| enabled)
+            .unwrap_or(false),
+        default_lp_type: UserService::get_default_lp_type(&state.pool, auth_user.qq())
+            .await
+            .unwrap_or(None),
+        is_protected_admin: is_first_admin(&state.pool, auth_user.qq()).await,
     };
 
     let body = json!({
         "nickname": payload.nickname,
         "birthday": payload.birthday,
+        "avatar_url": payload.avatar_url,
+        "email": payload.email,
+        "default_lp_type": payload.default_lp_type,
     });
     let _ = record_request_log(
         &state.pool,
@@ -524,7 +1264,7 @@ pub async fn update_profile(
 pub async fn change_password(
     auth_user: AuthenticatedUser,
     State(state): State<AppState>,
-    Json(payload): Json<ChangePasswordRequest>,
+    ApiJson(payload): ApiJson<ChangePasswordRequest>,
 ) -> Result<Json<Value>, StatusCode> {
     if payload.new_password.len() < 6 {
         let _ = record_request_log(
@@ -539,6 +1279,19 @@ pub async fn change_password(
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    if payload.new_password == payload.old_password {
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/profile/password",
+            Some(auth_user.qq()),
+            Some("新密码与原密码相同".to_string()),
+            StatusCode::BAD_REQUEST.as_u16() as i32,
+        )
+        .await;
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     match UserService::change_password(
         &state.pool,
         auth_user.qq(),
@@ -586,3 +1339,397 @@ pub async fn change_password(
         }
     }
 }
+
+#[derive(Deserialize)]
+pub struct VerifyTotpRequest {
+    pub code: String,
+}
+
+/// 申请开启两步验证：生成新密钥并返回 otpauth 地址，需调用验证接口确认后才会生效
+pub async fn enable_totp(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+) -> Result<Json<Value>, ApiError> {
+    let qq = auth_user.qq();
+
+    match UserService::get_totp(&state.pool, qq).await {
+        Ok((_, true)) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/profile/2fa/enable",
+                Some(qq),
+                Some("两步验证已开启".to_string()),
+                StatusCode::CONFLICT.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::conflict("两步验证已开启"));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("查询两步验证状态失败: {}", e);
+            return Err(ApiError::internal("查询两步验证状态失败"));
+        }
+    }
+
+    let secret = totp::generate_secret();
+    let url = totp::otpauth_url(&secret, qq).map_err(|e| {
+        log::error!("生成两步验证地址失败: {}", e);
+        ApiError::internal("生成两步验证密钥失败")
+    })?;
+
+    if let Err(e) = UserService::set_pending_totp(&state.pool, qq, &secret).await {
+        log::error!("保存两步验证密钥失败: {}", e);
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/profile/2fa/enable",
+            Some(qq),
+            Some(format!("保存两步验证密钥失败: {}", e)),
+            StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::internal("保存两步验证密钥失败"));
+    }
+
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/profile/2fa/enable",
+        Some(qq),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "message": "请使用身份验证器扫描或导入密钥，并输入验证码完成开启",
+        "otpauth_url": url,
+        "secret": secret,
+    })))
+}
+
+/// 校验身份验证器生成的验证码，通过后正式开启两步验证
+pub async fn verify_totp(
+    auth_user: AuthenticatedUser,
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<VerifyTotpRequest>,
+) -> Result<Json<Value>, ApiError> {
+    let qq = auth_user.qq();
+
+    let secret = match UserService::get_totp(&state.pool, qq).await {
+        Ok((_, true)) => return Err(ApiError::conflict("两步验证已开启")),
+        Ok((Some(secret), false)) => secret,
+        Ok((None, false)) => return Err(ApiError::bad_request("请先申请开启两步验证")),
+        Err(e) => {
+            log::error!("查询两步验证状态失败: {}", e);
+            return Err(ApiError::internal("查询两步验证状态失败"));
+        }
+    };
+
+    match totp::verify_code(&secret, qq, &payload.code) {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = record_request_log(
+                &state.pool,
+                "POST",
+                "/profile/2fa/verify",
+                Some(qq),
+                Some("两步验证码错误".to_string()),
+                StatusCode::BAD_REQUEST.as_u16() as i32,
+            )
+            .await;
+            return Err(ApiError::bad_request(
+                "验证码错误，请确认身份验证器时间同步后重试",
+            ));
+        }
+        Err(e) => {
+            log::error!("校验两步验证码失败: {}", e);
+            return Err(ApiError::internal("校验两步验证码失败"));
+        }
+    }
+
+    if let Err(e) = UserService::enable_totp(&state.pool, qq).await {
+        log::error!("开启两步验证失败: {}", e);
+        let _ = record_request_log(
+            &state.pool,
+            "POST",
+            "/profile/2fa/verify",
+            Some(qq),
+            Some(format!("开启两步验证失败: {}", e)),
+            StatusCode::INTERNAL_SERVER_ERROR.as_u16() as i32,
+        )
+        .await;
+        return Err(ApiError::internal("开启两步验证失败"));
+    }
+
+    log::info!("用户 {} 开启了两步验证", qq);
+    let _ = record_request_log(
+        &state.pool,
+        "POST",
+        "/profile/2fa/verify",
+        Some(qq),
+        None,
+        StatusCode::OK.as_u16() as i32,
+    )
+    .await;
+
+    Ok(Json(
+        json!({ "message": "两步验证已开启，下次登录需要输入验证码" }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        add_user, auth_user, bearer_token, response_json, send, send_json, test_state,
+    };
+
+    #[test]
+    fn avatar_url_accepts_http_links_and_empty() {
+        assert!(validate_avatar_url("https://example.com/a.png").is_ok());
+        assert!(validate_avatar_url("http://example.com").is_ok());
+        assert!(validate_avatar_url("  ").is_ok());
+    }
+
+    #[test]
+    fn avatar_url_rejects_invalid_values() {
+        assert!(validate_avatar_url("ftp://example.com/a.png").is_err());
+        assert!(validate_avatar_url("javascript:alert(1)").is_err());
+        assert!(validate_avatar_url("https://").is_err());
+        assert!(validate_avatar_url("https://example.com/a b.png").is_err());
+
+        let long = format!("https://example.com/{}", "a".repeat(AVATAR_URL_MAX_LEN));
+        assert_eq!(validate_avatar_url(&long), Err("头像地址过长"));
+    }
+
+    fn nickname_update(nickname: &str) -> UpdateUserRequest {
+        UpdateUserRequest {
+            nickname: Some(nickname.to_string()),
+            birthday: None,
+            avatar_url: None,
+            email: None,
+            default_lp_type: None,
+        }
+    }
+
+    async fn audit_rows(state: &AppState, target: &str) -> Vec<(String, String, Option<String>)> {
+        sqlx::query_as("SELECT operator_qq, field, new_value FROM useraudit WHERE target_qq = ?")
+            .bind(target)
+            .fetch_all(&state.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn admin_edit_is_audited_but_self_edit_is_not() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+
+        let _ = update_user(
+            auth_user("10001", &[]),
+            State(state.clone()),
+            Path("10001".to_string()),
+            Json(nickname_update("自己改的")),
+        )
+        .await
+        .unwrap();
+        assert!(audit_rows(&state, "10001").await.is_empty());
+
+        let _ = update_user(
+            auth_user("9999", &["用户管理"]),
+            State(state.clone()),
+            Path("10001".to_string()),
+            Json(nickname_update("管理员改的")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            audit_rows(&state, "10001").await,
+            vec![(
+                "9999".to_string(),
+                "nickname".to_string(),
+                Some("管理员改的".to_string())
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_avatar_url_reports_the_reason() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &[]);
+        let body = r#"{"avatar_url":"ftp://example.com/a.png"}"#;
+
+        for uri in ["/api/profile", "/api/users/10001"] {
+            let response = send_json(&state, "PATCH", uri, Some(&token), body).await;
+            let (status, body) = response_json(response).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["message"], "头像地址必须以 http:// 或 https:// 开头");
+        }
+    }
+
+    #[tokio::test]
+    async fn registering_an_existing_qq_is_a_conflict() {
+        let state = test_state().await;
+        let body = r#"{"qq":"10001","nickname":"张三","password":"password1"}"#;
+
+        let (status, _) =
+            response_json(send_json(&state, "POST", "/api/auth/register", None, body).await).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) =
+            response_json(send_json(&state, "POST", "/api/auth/register", None, body).await).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["message"], "该QQ已注册");
+    }
+
+    #[tokio::test]
+    async fn registering_an_invalid_qq_is_rejected() {
+        let state = test_state().await;
+        let body = r#"{"qq":"abc12","nickname":"张三","password":"password1"}"#;
+
+        let (status, body) =
+            response_json(send_json(&state, "POST", "/api/auth/register", None, body).await).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "QQ号只能包含数字");
+    }
+
+    async fn login_with_code(state: &AppState, code: Option<&str>) -> StatusCode {
+        let body = json!({ "qq": "10001", "password": "password", "totp_code": code });
+        send_json(state, "POST", "/api/auth/login", None, body.to_string())
+            .await
+            .status()
+    }
+
+    #[tokio::test]
+    async fn login_with_two_factor_requires_the_current_code() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        assert_eq!(login_with_code(&state, None).await, StatusCode::OK);
+
+        let secret = totp::generate_secret();
+        UserService::set_pending_totp(&state.pool, "10001", &secret)
+            .await
+            .unwrap();
+        UserService::enable_totp(&state.pool, "10001")
+            .await
+            .unwrap();
+        let code = totp::current_code(&secret, "10001");
+        let wrong = if code == "000000" { "111111" } else { "000000" };
+
+        assert_eq!(
+            login_with_code(&state, None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            login_with_code(&state, Some(wrong)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(login_with_code(&state, Some(&code)).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn register_returns_user_and_login_token() {
+        let state = test_state().await;
+        let body = r#"{"qq":"10001","nickname":"张三","password":"password1"}"#;
+
+        let (status, body) =
+            response_json(send_json(&state, "POST", "/api/auth/register", None, body).await).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "注册成功");
+        assert_eq!(body["user"]["qq"], "10001");
+        assert_eq!(body["user"]["nickname"], "张三");
+        assert_eq!(body["user"]["role_name"], "成员");
+        let token = body["token"].as_str().unwrap();
+        let claims = state.jwt.decode(token).unwrap();
+        assert_eq!(claims.sub, "10001");
+    }
+
+    #[tokio::test]
+    async fn register_pow_accepts_valid_solution_once_and_rejects_wrong_one() {
+        let state = AppState {
+            register_pow: Some(std::sync::Arc::new(crate::pow::PowGuard::new(4))),
+            ..test_state().await
+        };
+        let challenge = |state: AppState| async move {
+            let (_, body) =
+                response_json(send(&state, "GET", "/api/auth/challenge", None).await).await;
+            assert_eq!(body["enabled"], true);
+            body["nonce"].as_str().unwrap().to_string()
+        };
+
+        let nonce = challenge(state.clone()).await;
+        let wrong = (0u64..)
+            .map(|candidate| candidate.to_string())
+            .find(|candidate| !team_operation_system::pow::verify(&nonce, candidate, 4))
+            .unwrap();
+        let body = format!(
+            r#"{{"qq":"10001","nickname":"张三","password":"password1","pow_nonce":"{}","pow_solution":"{}"}}"#,
+            nonce, wrong
+        );
+        let (status, body) =
+            response_json(send_json(&state, "POST", "/api/auth/register", None, &body).await).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "人机验证未通过");
+
+        let nonce = challenge(state.clone()).await;
+        let solution = team_operation_system::pow::solve_range(&nonce, 4, 0, 1 << 16).unwrap();
+        let body = format!(
+            r#"{{"qq":"10001","nickname":"张三","password":"password1","pow_nonce":"{}","pow_solution":"{}"}}"#,
+            nonce, solution
+        );
+        let (status, _) =
+            response_json(send_json(&state, "POST", "/api/auth/register", None, &body).await).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let replay = body.replace("10001", "10002");
+        let (status, body) =
+            response_json(send_json(&state, "POST", "/api/auth/register", None, &replay).await)
+                .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "验证挑战不存在或已被使用，请重新获取");
+    }
+
+    #[tokio::test]
+    async fn only_seeded_admin_profile_is_protected() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+
+        for (qq, expected) in [("9999", true), ("10001", false)] {
+            let token = bearer_token(qq, &[]);
+            let (status, body) =
+                response_json(send(&state, "GET", "/api/profile", Some(&token)).await).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["user"]["is_protected_admin"], expected, "用户 {}", qq);
+        }
+    }
+
+    #[tokio::test]
+    async fn default_lp_type_round_trips_through_profile() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &[]);
+
+        let (status, body) = response_json(
+            send_json(
+                &state,
+                "PATCH",
+                "/api/profile",
+                Some(&token),
+                r#"{"default_lp_type":2}"#,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["user"]["default_lp_type"], 2);
+
+        let (_, body) =
+            response_json(send(&state, "GET", "/api/profile", Some(&token)).await).await;
+        assert_eq!(body["user"]["default_lp_type"], 2);
+    }
+}
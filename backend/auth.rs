@@ -1,7 +1,11 @@
-use axum::extract::FromRequestParts;
-use axum::http::{header, request::Parts, StatusCode};
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use crate::error::ApiError;
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::{header, request::Parts, HeaderMap, StatusCode};
+use jsonwebtoken::{decode, errors::ErrorKind, Algorithm, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
@@ -15,6 +19,9 @@ pub struct Claims {
 pub struct LoginRequest {
     pub qq: String,
     pub password: String,
+    /// 开启两步验证的账号登录时需要提供的 6 位验证码
+    #[serde(default)]
+    pub totp_code: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,9 +35,15 @@ pub struct UserInfo {
     pub qq: String,
     pub nickname: String,
     pub birthday: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email: Option<String>,
     pub role_name: Option<String>,
     pub permissions: Vec<String>,
-    pub is_default_password: bool, // 是否使用默认密码
+    pub is_default_password: bool,    // 是否使用默认密码
+    pub must_change_password: bool,   // 是否必须先修改密码
+    pub totp_enabled: bool,           // 是否已开启两步验证
+    pub default_lp_type: Option<i64>, // 提交LP时默认选中的类型
+    pub is_protected_admin: bool,     // 是否是受保护的默认管理员
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,8 +52,21 @@ pub struct RegisterRequest {
     pub nickname: String,
     pub password: String,
     pub birthday: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    /// 开启注册工作量证明时必填：`/auth/challenge` 返回的 nonce 及其解答
+    #[serde(default)]
+    pub pow_nonce: Option<String>,
+    #[serde(default)]
+    pub pow_solution: Option<String>,
 }
 
+/// 认证失败的错误码：未携带令牌、令牌无效、令牌已过期。
+/// 前端据此区分需要重新登录还是提示请求格式有误
+pub const TOKEN_MISSING_CODE: &str = "TOKEN_MISSING";
+pub const TOKEN_INVALID_CODE: &str = "TOKEN_INVALID";
+pub const TOKEN_EXPIRED_CODE: &str = "TOKEN_EXPIRED";
+
 pub const JWT_SECRET: &[u8] = b"team-operation-system-secret-key-change-in-production";
 
 #[derive(Debug, Clone)]
@@ -59,36 +85,152 @@ impl AuthenticatedUser {
         if self.has_permission(permission) {
             Ok(())
         } else {
+            let _ = DENIED_PERMISSION
+                .try_with(|denied| *denied.borrow_mut() = Some(permission.to_string()));
             Err(StatusCode::FORBIDDEN)
         }
     }
 }
 
+tokio::task_local! {
+    /// 当前请求中 `require_permission` 校验未通过的权限，由权限拒绝日志中间件读取
+    static DENIED_PERMISSION: RefCell<Option<String>>;
+}
+
+/// 执行 `future` 并返回其中 `require_permission` 最后一次拒绝的权限名
+pub async fn track_denied_permission<F: Future>(future: F) -> (F::Output, Option<String>) {
+    DENIED_PERMISSION
+        .scope(RefCell::new(None), async move {
+            let output = future.await;
+            let denied = DENIED_PERMISSION.with(|denied| denied.borrow_mut().take());
+            (output, denied)
+        })
+        .await
+}
+
+/// 预先构建的 JWT 校验密钥和校验规则，启动时创建一次并放入 `AppState`，
+/// 避免每个请求重新构造 `DecodingKey`
+pub struct JwtVerifier {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtVerifier {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: DecodingKey::from_secret(secret),
+            validation: Validation::new(Algorithm::HS256),
+        }
+    }
+
+    pub fn decode(&self, token: &str) -> jsonwebtoken::errors::Result<Claims> {
+        decode::<Claims>(token, &self.key, &self.validation).map(|data| data.claims)
+    }
+}
+
 impl<S> FromRequestParts<S> for AuthenticatedUser
 where
+    Arc<JwtVerifier>: FromRef<S>,
     S: Send + Sync,
 {
-    type Rejection = StatusCode;
-
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        let header_value = parts
-            .headers
-            .get(header::AUTHORIZATION)
-            .ok_or(StatusCode::UNAUTHORIZED)?
-            .to_str()
-            .map_err(|_| StatusCode::UNAUTHORIZED)?;
-
-        let token = header_value
-            .strip_prefix("Bearer ")
-            .ok_or(StatusCode::UNAUTHORIZED)?;
-
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(JWT_SECRET),
-            &Validation::new(Algorithm::HS256),
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let verifier = Arc::<JwtVerifier>::from_ref(state);
+        decode_bearer_claims(&parts.headers, &verifier).map(AuthenticatedUser)
+    }
+}
+
+/// 从 `Authorization: Bearer <token>` 请求头中解析 JWT，
+/// 失败时返回带错误码的 401
+pub fn decode_bearer_claims(
+    headers: &HeaderMap,
+    verifier: &JwtVerifier,
+) -> Result<Claims, ApiError> {
+    let header_value = headers
+        .get(header::AUTHORIZATION)
+        .ok_or_else(|| ApiError::unauthorized("未登录，请先登录").with_code(TOKEN_MISSING_CODE))?
+        .to_str()
+        .map_err(|_| ApiError::unauthorized("登录凭证格式错误").with_code(TOKEN_INVALID_CODE))?;
+
+    let token = header_value
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| ApiError::unauthorized("登录凭证格式错误").with_code(TOKEN_INVALID_CODE))?;
+
+    verifier.decode(token).map_err(|e| match e.kind() {
+        ErrorKind::ExpiredSignature => {
+            ApiError::unauthorized("登录已过期，请重新登录").with_code(TOKEN_EXPIRED_CODE)
+        }
+        _ => ApiError::unauthorized("登录凭证无效，请重新登录").with_code(TOKEN_INVALID_CODE),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{bearer_token, response_json, test_state};
+    use axum::response::IntoResponse;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    async fn rejection(authorization: Option<&str>) -> (StatusCode, serde_json::Value) {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = authorization {
+            headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+        }
+        let error = decode_bearer_claims(&headers, &JwtVerifier::new(JWT_SECRET)).unwrap_err();
+        response_json(error.into_response()).await
+    }
+
+    fn signed_token(secret: &[u8], exp: usize) -> String {
+        let claims = Claims {
+            sub: "10001".to_string(),
+            nickname: "用户10001".to_string(),
+            exp,
+            permissions: Vec::new(),
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret),
         )
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .unwrap();
+        format!("Bearer {}", token)
+    }
+
+    #[tokio::test]
+    async fn each_token_failure_has_its_own_code() {
+        let (status, body) = rejection(None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], TOKEN_MISSING_CODE);
+        assert_eq!(body["message"], "未登录，请先登录");
+
+        let (_, body) = rejection(Some("Token abc")).await;
+        assert_eq!(body["code"], TOKEN_INVALID_CODE);
+        assert_eq!(body["message"], "登录凭证格式错误");
+
+        let (_, body) = rejection(Some(&signed_token(b"other-secret", usize::MAX))).await;
+        assert_eq!(body["code"], TOKEN_INVALID_CODE);
+        assert_eq!(body["message"], "登录凭证无效，请重新登录");
 
-        Ok(AuthenticatedUser(token_data.claims))
+        let (status, body) = rejection(Some(&signed_token(JWT_SECRET, 1))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], TOKEN_EXPIRED_CODE);
+        assert_eq!(body["message"], "登录已过期，请重新登录");
+    }
+
+    #[tokio::test]
+    async fn shared_verifier_decodes_tokens_from_state() {
+        let state = test_state().await;
+        let mut headers = HeaderMap::new();
+        let token = bearer_token("10001", &["审核LP"]);
+        headers.insert(header::AUTHORIZATION, token.parse().unwrap());
+
+        // 同一个预构建的校验器可以反复使用
+        for _ in 0..2 {
+            let verifier = Arc::<JwtVerifier>::from_ref(&state);
+            let claims = decode_bearer_claims(&headers, &verifier).unwrap();
+            assert_eq!(claims.sub, "10001");
+            assert_eq!(claims.permissions, vec!["审核LP".to_string()]);
+        }
     }
 }
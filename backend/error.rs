@@ -0,0 +1,156 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::fmt;
+use team_operation_system::db::ServiceError;
+
+/// 接口错误：以 `{ "message": ... }` 的形式返回给前端，便于展示具体失败原因；
+/// 需要前端区分处理的错误额外带上机器可读的 `code`
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+    code: Option<&'static str>,
+    /// 限流时建议客户端等待的秒数，写入 `Retry-After` 响应头
+    retry_after: Option<u64>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            code: None,
+            retry_after: None,
+        }
+    }
+
+    /// 附加错误码，随响应体的 `code` 字段返回
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, message)
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+
+    pub fn too_many_requests(retry_after_secs: u64) -> Self {
+        Self {
+            retry_after: Some(retry_after_secs),
+            ..Self::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("请求过于频繁，请 {} 秒后重试", retry_after_secs),
+            )
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+/// 兼容仍返回 `StatusCode` 的辅助函数（如 `require_permission`），使用通用提示
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        let message = match status {
+            StatusCode::BAD_REQUEST => "请求参数错误",
+            StatusCode::UNAUTHORIZED => "未登录或登录已过期",
+            StatusCode::FORBIDDEN => "没有权限执行此操作",
+            StatusCode::NOT_FOUND => "请求的资源不存在",
+            StatusCode::CONFLICT => "请求冲突",
+            StatusCode::INTERNAL_SERVER_ERROR => "服务器内部错误",
+            _ => status.canonical_reason().unwrap_or("请求失败"),
+        };
+        Self::new(status, message)
+    }
+}
+
+/// 服务层错误映射为对应的状态码；数据库故障只记录日志，不把内部错误返回给前端
+impl From<ServiceError> for ApiError {
+    fn from(error: ServiceError) -> Self {
+        match error {
+            ServiceError::NotFound(message) => Self::new(StatusCode::NOT_FOUND, message),
+            ServiceError::Conflict(message) => Self::conflict(message),
+            ServiceError::Validation(message) => Self::bad_request(message),
+            ServiceError::Forbidden(message) => Self::forbidden(message),
+            ServiceError::Db(e) => {
+                log::error!("数据库操作失败: {}", e);
+                Self::internal("服务器内部错误")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.status.as_u16(), self.message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = match self.code {
+            Some(code) => json!({ "message": self.message, "code": code }),
+            None => json!({ "message": self.message }),
+        };
+        let mut response = (self.status, Json(body)).into_response();
+        if let Some(secs) = self.retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, header::HeaderValue::from(secs));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_errors_map_to_http_statuses() {
+        let cases = [
+            (ServiceError::NotFound("x".into()), StatusCode::NOT_FOUND),
+            (ServiceError::Conflict("x".into()), StatusCode::CONFLICT),
+            (
+                ServiceError::Validation("x".into()),
+                StatusCode::BAD_REQUEST,
+            ),
+            (ServiceError::Forbidden("x".into()), StatusCode::FORBIDDEN),
+            (
+                ServiceError::Db(sqlx::Error::PoolTimedOut),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        ];
+
+        for (error, status) in cases {
+            assert_eq!(ApiError::from(error).status(), status);
+        }
+    }
+
+    #[test]
+    fn bare_conflict_status_uses_neutral_message() {
+        let error = ApiError::from(StatusCode::CONFLICT);
+        assert_eq!(error.to_string(), "409 请求冲突");
+    }
+}
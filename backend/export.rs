@@ -0,0 +1,85 @@
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+/// UTF-8 BOM，Excel 依赖它识别编码，否则中文昵称会显示为乱码
+const UTF8_BOM: &str = "\u{feff}";
+
+/// 转义单个 CSV 字段：包含逗号、引号或换行时用双引号包裹，内部引号加倍
+fn escape_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 按表头和数据行生成带 BOM 的 CSV 文本，行尾使用 CRLF
+pub fn build_csv(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut csv = String::from(UTF8_BOM);
+    let header_line: Vec<String> = headers.iter().map(|h| escape_field(h)).collect();
+    csv.push_str(&header_line.join(","));
+    csv.push_str("\r\n");
+    for row in rows {
+        let line: Vec<String> = row.iter().map(|field| escape_field(field)).collect();
+        csv.push_str(&line.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// 以附件形式返回 CSV，`filename` 需为 ASCII
+pub fn csv_response(filename: &str, body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// 解析 CSV 文本，每行返回一个字段列表（空行返回单个空字段），
+/// 支持双引号包裹和 `""` 转义，忽略开头的 BOM 和行尾的 `\r`
+pub fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let text = text.strip_prefix(UTF8_BOM).unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c != '"' {
+                field.push(c);
+            } else if chars.peek() == Some(&'"') {
+                field.push('"');
+                chars.next();
+            } else {
+                in_quotes = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
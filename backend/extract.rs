@@ -0,0 +1,76 @@
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use crate::error::ApiError;
+
+/// 与 `axum::Json` 相同，但解析失败时返回带说明的 [`ApiError`]，
+/// 而不是 axum 默认的纯文本错误，前端可以直接展示失败原因
+pub struct ApiJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            Err(rejection) => {
+                let message = match &rejection {
+                    JsonRejection::MissingJsonContentType(_) => {
+                        "请求格式错误: Content-Type 必须为 application/json".to_string()
+                    }
+                    JsonRejection::JsonSyntaxError(_) => {
+                        "请求格式错误: JSON 语法不正确".to_string()
+                    }
+                    JsonRejection::JsonDataError(e) => format!("请求格式错误: {}", e.body_text()),
+                    _ => "请求格式错误".to_string(),
+                };
+                log::warn!("解析请求体失败: {}", rejection.body_text());
+                Err(ApiError::new(rejection.status(), message))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{
+        request_builder, response_json, send_json, send_request, test_state,
+    };
+    use axum::{body::Body, http::StatusCode};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn malformed_json_returns_structured_error() {
+        let state = test_state().await;
+
+        let response =
+            send_json(&state, "POST", "/api/auth/login", None, r#"{"qq":"10001","#).await;
+        let (status, body) = response_json(response).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body, json!({ "message": "请求格式错误: JSON 语法不正确" }));
+    }
+
+    #[tokio::test]
+    async fn missing_content_type_returns_structured_error() {
+        let state = test_state().await;
+        let request = request_builder("POST", "/api/auth/login", None)
+            .body(Body::from(r#"{"qq":"10001","password":"password"}"#))
+            .unwrap();
+
+        let (status, body) = response_json(send_request(&state, request).await).await;
+
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(
+            body["message"],
+            "请求格式错误: Content-Type 必须为 application/json"
+        );
+    }
+}
@@ -1,12 +1,12 @@
 use axum::{extract::State, http::StatusCode, Json};
-use chrono::Local;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use sqlx::SqlitePool;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use team_operation_system::db::DbPool;
+use team_operation_system::db::{server_offset, DbPool};
 
 /// 系统指标
 pub struct Metrics {
@@ -73,6 +73,33 @@ pub struct MetricsSnapshot {
     pub error_rate: f64,
 }
 
+/// `/metrics` 返回的详细指标，前端日志页按此结构解析
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricsReport {
+    pub timestamp: String,
+    pub uptime_seconds: u64,
+    pub uptime_human: String,
+    pub requests: RequestMetrics,
+    pub database: DatabaseMetrics,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequestMetrics {
+    pub total: u64,
+    pub errors: u64,
+    pub success: u64,
+    pub error_rate_percent: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DatabaseMetrics {
+    pub total_queries: u64,
+    pub pool_size: u32,
+    pub idle_connections: usize,
+    /// 各表行数，查询失败时为 `null`
+    pub stats: Option<Value>,
+}
+
 /// 健康检查端点
 pub async fn health_check(
     State(pool): State<DbPool>,
@@ -124,7 +151,7 @@ pub async fn health_check(
     let db_connected = db_health.connected;
     let response = HealthResponse {
         status: status.to_string(),
-        timestamp: Local::now().to_rfc3339(),
+        timestamp: Utc::now().with_timezone(&server_offset()).to_rfc3339(),
         version: env!("CARGO_PKG_VERSION").to_string(),
         database: db_health,
         metrics: metrics_snapshot,
@@ -141,7 +168,7 @@ pub async fn health_check(
 pub async fn metrics_endpoint(
     State(pool): State<DbPool>,
     State(metrics): State<Arc<Metrics>>,
-) -> Result<Json<Value>, StatusCode> {
+) -> Result<Json<MetricsReport>, StatusCode> {
     let total_requests = metrics.request_count.load(Ordering::Relaxed);
     let total_errors = metrics.error_count.load(Ordering::Relaxed);
     let total_db_queries = metrics.db_query_count.load(Ordering::Relaxed);
@@ -150,27 +177,27 @@ pub async fn metrics_endpoint(
     // 获取数据库统计信息
     let db_stats = get_database_stats(&pool).await.ok();
 
-    Ok(Json(json!({
-        "timestamp": Local::now().to_rfc3339(),
-        "uptime_seconds": uptime.as_secs(),
-        "uptime_human": format_duration(uptime),
-        "requests": {
-            "total": total_requests,
-            "errors": total_errors,
-            "success": total_requests.saturating_sub(total_errors),
-            "error_rate_percent": if total_requests > 0 {
+    Ok(Json(MetricsReport {
+        timestamp: Utc::now().with_timezone(&server_offset()).to_rfc3339(),
+        uptime_seconds: uptime.as_secs(),
+        uptime_human: format_duration(uptime),
+        requests: RequestMetrics {
+            total: total_requests,
+            errors: total_errors,
+            success: total_requests.saturating_sub(total_errors),
+            error_rate_percent: if total_requests > 0 {
                 (total_errors as f64 / total_requests as f64) * 100.0
             } else {
                 0.0
-            }
+            },
         },
-        "database": {
-            "total_queries": total_db_queries,
-            "pool_size": pool.size(),
-            "idle_connections": pool.num_idle(),
-            "stats": db_stats
-        }
-    })))
+        database: DatabaseMetrics {
+            total_queries: total_db_queries,
+            pool_size: pool.size(),
+            idle_connections: pool.num_idle(),
+            stats: db_stats,
+        },
+    }))
 }
 
 /// 获取数据库统计信息
@@ -247,3 +274,31 @@ personnel_system_uptime_seconds {}
 
     Ok(prometheus_output)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_pool;
+
+    #[tokio::test]
+    async fn metrics_report_has_fields_the_logs_page_reads() {
+        let pool = test_pool().await;
+        let metrics = Arc::new(Metrics::new());
+        for _ in 0..4 {
+            metrics.increment_requests();
+        }
+        metrics.increment_errors();
+
+        let Json(report) = metrics_endpoint(State(pool), State(metrics)).await.unwrap();
+        let body = serde_json::to_value(report).unwrap();
+
+        assert_eq!(body["requests"]["total"], 4);
+        assert_eq!(body["requests"]["errors"], 1);
+        assert_eq!(body["requests"]["success"], 3);
+        assert_eq!(body["requests"]["error_rate_percent"], 25.0);
+        assert!(body["uptime_seconds"].is_u64());
+        assert!(body["uptime_human"].is_string());
+        assert!(body["timestamp"].is_string());
+        assert_eq!(body["database"]["stats"]["users"], 1);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::error::ApiError;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use chrono::Duration;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use team_operation_system::db::{server_now, IdempotencyService, TIMESTAMP_FORMAT};
+
+/// 客户端传入的幂等键请求头
+pub const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+
+/// 幂等键保留时长（小时）
+const IDEMPOTENCY_TTL_HOURS: i64 = 24;
+
+const IDEMPOTENCY_KEY_MAX_LEN: usize = 128;
+
+/// 从请求头中读取幂等键，空值或过长的键视为未提供
+pub fn idempotency_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(IDEMPOTENCY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty() && key.len() <= IDEMPOTENCY_KEY_MAX_LEN)
+        .map(str::to_string)
+}
+
+/// 检查幂等键：已处理过则返回缓存的结果，否则占用该键以便继续处理。
+/// 同一个键的请求仍在处理中时返回 409。
+pub async fn replay_or_reserve(
+    pool: &SqlitePool,
+    user_qq: &str,
+    scope: &str,
+    key: Option<&str>,
+) -> Result<Option<Json<Value>>, ApiError> {
+    let Some(key) = key else {
+        return Ok(None);
+    };
+
+    let now = server_now();
+    let cutoff = (now - Duration::hours(IDEMPOTENCY_TTL_HOURS))
+        .format(TIMESTAMP_FORMAT)
+        .to_string();
+    if let Err(e) = IdempotencyService::purge_expired(pool, &cutoff).await {
+        log::error!("清理过期幂等键失败: {}", e);
+    }
+
+    let created_at = now.format(TIMESTAMP_FORMAT).to_string();
+    let reserved = IdempotencyService::reserve(pool, user_qq, scope, key, &created_at)
+        .await
+        .map_err(|e| {
+            log::error!("登记幂等键失败: {}", e);
+            ApiError::internal("登记幂等键失败")
+        })?;
+    if reserved {
+        return Ok(None);
+    }
+
+    let record = IdempotencyService::get_record(pool, user_qq, scope, key)
+        .await
+        .map_err(|e| {
+            log::error!("查询幂等键失败: {}", e);
+            ApiError::internal("查询幂等键失败")
+        })?;
+
+    match record.and_then(|r| r.response) {
+        Some(response) => {
+            log::info!(
+                "幂等键重复请求，返回缓存结果: 用户={}, 路径={}",
+                user_qq,
+                scope
+            );
+            let body = serde_json::from_str(&response).map_err(|e| {
+                log::error!("解析缓存结果失败: {}", e);
+                ApiError::internal("解析缓存结果失败")
+            })?;
+            Ok(Some(Json(body)))
+        }
+        None => Err(ApiError::conflict("相同的请求正在处理中，请稍后重试")),
+    }
+}
+
+/// 保存处理结果：成功时缓存响应，失败时释放幂等键以允许重试
+pub async fn finish<E>(
+    pool: &SqlitePool,
+    user_qq: &str,
+    scope: &str,
+    key: Option<&str>,
+    result: &Result<Json<Value>, E>,
+) {
+    let Some(key) = key else {
+        return;
+    };
+
+    let outcome = match result {
+        Ok(Json(body)) => {
+            IdempotencyService::store_response(
+                pool,
+                user_qq,
+                scope,
+                key,
+                StatusCode::OK.as_u16() as i32,
+                &body.to_string(),
+            )
+            .await
+        }
+        Err(_) => IdempotencyService::release(pool, user_qq, scope, key).await,
+    };
+
+    if let Err(e) = outcome {
+        log::error!("更新幂等键失败: {}", e);
+    }
+}
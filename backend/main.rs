@@ -2,29 +2,78 @@
 
 mod api;
 mod auth;
+mod error;
+mod export;
+mod extract;
 mod health;
+mod idempotency;
 mod middleware;
+mod notifier;
+mod pagination;
+mod pow;
+mod rate_limit;
 mod scheduler;
 mod state;
+#[cfg(test)]
+mod test_support;
+mod totp;
+mod upload;
+mod validation;
 
-use axum::{middleware as axum_middleware, routing::get, Router};
-use log::info;
+use axum::{extract::DefaultBodyLimit, middleware as axum_middleware, routing::get, Router};
+use log::{info, warn};
 use sqlx::Executor;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use team_operation_system::db;
 use tower_http::cors::{Any, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::services::ServeDir;
+
+/// 未配置 BIND_ADDR 时的监听地址
+const DEFAULT_BIND_ADDR: SocketAddr = SocketAddr::new(
+    std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)),
+    3000,
+);
+
+/// 请求体大小上限（字节）
+const MAX_REQUEST_BODY_BYTES: usize = 256 * 1024;
+
+/// 上传接口的请求体上限，在图片上限之外预留 multipart 表单的开销
+const MAX_UPLOAD_BODY_BYTES: usize = upload::UPLOAD_MAX_BYTES + 64 * 1024;
+
+/// 恢复数据备份时的请求体上限
+const MAX_RESTORE_BODY_BYTES: usize = 64 * 1024 * 1024;
 
 #[tokio::main]
 async fn main() {
-    // 初始化日志和追踪
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .with_target(false)
-        .init();
+    // 初始化日志和追踪，LOG_FORMAT=json 时输出结构化 JSON 日志，默认为可读格式
+    let json_logs = std::env::var("LOG_FORMAT")
+        .map(|format| format.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    if json_logs {
+        tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_target(false)
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .init();
+    } else {
+        tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_target(false)
+            .init();
+    }
 
     info!("团队运营管理系统后端启动中...");
 
+    // 服务器时区（SERVER_TZ），所有时间戳的写入和比较都按该时区计算
+    if let Some(offset) = server_tz_from_env() {
+        db::set_server_offset(offset);
+    }
+    info!("服务器时区: UTC{}", db::server_offset());
+
     // 初始化数据库连接池（启用外键约束）
     let database_url = "sqlite:team.db?mode=rwc";
     let pool = sqlx::sqlite::SqlitePoolOptions::new()
@@ -40,54 +89,355 @@ async fn main() {
 
     db::init_database(&pool).await.expect("数据库初始化失败");
     info!("数据库初始化完成（外键约束已启用）");
+    info!("密码哈希 bcrypt 强度: {}", db::bcrypt_cost());
+
+    // 邮件通知（未配置 SMTP_HOST 时关闭）
+    let notifier: Option<Arc<dyn notifier::Notifier>> = match notifier::SmtpNotifier::from_env() {
+        Ok(Some(smtp)) => {
+            info!("邮件通知已启用");
+            Some(Arc::new(smtp))
+        }
+        Ok(None) => {
+            info!("未配置 SMTP_HOST，邮件通知已关闭");
+            None
+        }
+        Err(e) => {
+            warn!("邮件通知配置错误，已关闭: {}", e);
+            None
+        }
+    };
+
+    // 停机信号：通知定时任务退出
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
 
     // 启动抽奖定时任务
-    scheduler::start_lottery_scheduler(pool.clone()).await;
+    let scheduler_handle =
+        scheduler::start_lottery_scheduler(pool.clone(), notifier.clone(), shutdown_rx.clone())
+            .await;
     info!("抽奖定时任务已启动（每分钟检查一次）");
 
+    // 启动LP汇总月度快照任务
+    let snapshot_handle =
+        scheduler::start_snapshot_scheduler(pool.clone(), shutdown_rx.clone()).await;
+    info!("LP汇总快照任务已启动（每小时检查一次）");
+
+    // 过期商品处理方式（SHOP_EXPIRED_ITEMS），默认只隐藏不删除
+    let expiry_handle = if delete_expired_items_from_env() {
+        let handle = scheduler::start_item_expiry_scheduler(pool.clone(), shutdown_rx).await;
+        info!("过期商品清理任务已启动（每 10 分钟检查一次）");
+        Some(handle)
+    } else {
+        info!("过期商品仅隐藏，不自动删除");
+        None
+    };
+
     // 创建指标收集器
     let metrics = Arc::new(health::Metrics::new());
     info!("指标收集器初始化完成");
 
-    let app_state = state::AppState::new(pool.clone(), metrics.clone());
+    // 开销较大接口的限流器（RATE_LIMIT_BURST / RATE_LIMIT_PER_MINUTE）
+    let rate_limiter = Arc::new(rate_limit::RateLimiter::from_env());
+
+    // 注册工作量证明（REGISTER_POW_DIFFICULTY，未配置时关闭）
+    let register_pow = pow::PowGuard::from_env().map(Arc::new);
+    match &register_pow {
+        Some(guard) => info!("注册工作量证明已开启，难度: {}", guard.difficulty()),
+        None => info!("注册工作量证明未开启"),
+    }
+
+    // 确保上传目录存在，静态文件服务从该目录读取
+    if let Err(e) = tokio::fs::create_dir_all(upload::upload_dir()).await {
+        warn!(
+            "创建上传目录 {} 失败: {}",
+            upload::upload_dir().display(),
+            e
+        );
+    }
+
+    // 公开排行榜（PUBLIC_LEADERBOARD_SIZE，未配置时关闭）
+    let public_leaderboard_size = public_leaderboard_size_from_env();
+    match public_leaderboard_size {
+        Some(size) => info!("公开排行榜已开启，展示前 {} 名", size),
+        None => info!("公开排行榜未开启"),
+    }
+
+    // LP仅限本人提交（LP_SELF_SUBMIT_ONLY，默认关闭）
+    let lp_self_submit_only = lp_self_submit_only_from_env();
+    if lp_self_submit_only {
+        info!("LP仅限本人提交模式已开启");
+    }
+
+    // 拒绝LP必须填写理由（LP_REJECT_REASON_REQUIRED，默认关闭）
+    let lp_reject_reason_required = lp_reject_reason_required_from_env();
+    if lp_reject_reason_required {
+        info!("拒绝LP必须填写理由");
+    }
+
+    let jwt = Arc::new(auth::JwtVerifier::new(auth::JWT_SECRET));
+
+    let app_state = state::AppState {
+        pool: pool.clone(),
+        jwt,
+        metrics,
+        notifier,
+        rate_limiter,
+        register_pow,
+        public_leaderboard_size,
+        lp_self_submit_only,
+        lp_reject_reason_required,
+    };
     info!("应用状态初始化完成");
 
+    let app = build_router(app_state);
+
+    // 启动服务器
+    let addr = bind_addr_from_env();
+    info!("后端服务运行在 http://{}", addr);
+    info!("健康检查: http://{}/health", addr);
+    info!("指标监控: http://{}/metrics", addr);
+    info!("Prometheus: http://{}/metrics/prometheus", addr);
+
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+    info!("HTTP 服务已停止，正在处理的请求均已完成");
+
+    // 等待定时任务结束当前一轮开奖，避免中断正在进行的数据库写入
+    let _ = shutdown_tx.send(true);
+    if let Err(e) = scheduler_handle.await {
+        log::error!("等待抽奖定时任务退出失败: {}", e);
+    }
+    if let Err(e) = snapshot_handle.await {
+        log::error!("等待LP汇总快照任务退出失败: {}", e);
+    }
+    if let Some(handle) = expiry_handle {
+        if let Err(e) = handle.await {
+            log::error!("等待过期商品清理任务退出失败: {}", e);
+        }
+    }
+
+    pool.close().await;
+    info!("数据库连接池已关闭，后端服务退出");
+}
+
+/// 读取监听地址 BIND_ADDR（如 `0.0.0.0:3000`），未配置或格式不正确时使用默认地址
+fn bind_addr_from_env() -> SocketAddr {
+    match std::env::var("BIND_ADDR") {
+        Ok(raw) => parse_bind_addr(&raw),
+        Err(_) => DEFAULT_BIND_ADDR,
+    }
+}
+
+/// 解析监听地址，格式不正确时记录错误并使用默认地址
+fn parse_bind_addr(raw: &str) -> SocketAddr {
+    match raw.trim().parse::<SocketAddr>() {
+        Ok(addr) => addr,
+        Err(e) => {
+            log::error!(
+                "BIND_ADDR 格式不正确: {} ({})，使用默认地址 {}",
+                raw,
+                e,
+                DEFAULT_BIND_ADDR
+            );
+            DEFAULT_BIND_ADDR
+        }
+    }
+}
+
+/// 读取 PUBLIC_LEADERBOARD_SIZE（1–100），未配置、为 0 或格式不正确时关闭公开排行榜
+fn public_leaderboard_size_from_env() -> Option<i64> {
+    let raw = std::env::var("PUBLIC_LEADERBOARD_SIZE").ok()?;
+    match raw.trim().parse::<i64>() {
+        Ok(0) => None,
+        Ok(size @ 1..=100) => Some(size),
+        _ => {
+            warn!(
+                "PUBLIC_LEADERBOARD_SIZE 应为 0-100 的整数: {}，已关闭公开排行榜",
+                raw
+            );
+            None
+        }
+    }
+}
+
+/// 读取 SERVER_TZ：`UTC` 或 `+08:00` 形式的固定偏移，未配置或格式不正确时使用系统本地时区
+fn server_tz_from_env() -> Option<chrono::FixedOffset> {
+    let raw = std::env::var("SERVER_TZ").ok()?;
+    if raw.trim().is_empty() {
+        return None;
+    }
+    let offset = db::parse_utc_offset(&raw);
+    if offset.is_none() {
+        warn!(
+            "SERVER_TZ 应为 UTC 或 +08:00 形式的时区偏移: {}，使用系统本地时区",
+            raw
+        );
+    }
+    offset
+}
+
+/// 读取 LP_SELF_SUBMIT_ONLY：`true`/`1` 时开启LP仅限本人提交，未配置时关闭
+fn lp_self_submit_only_from_env() -> bool {
+    let Ok(raw) = std::env::var("LP_SELF_SUBMIT_ONLY") else {
+        return false;
+    };
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => true,
+        "" | "false" | "0" => false,
+        _ => {
+            warn!(
+                "LP_SELF_SUBMIT_ONLY 应为 true 或 false: {}，LP仅限本人提交未开启",
+                raw
+            );
+            false
+        }
+    }
+}
+
+/// 读取 LP_REJECT_REASON_REQUIRED：`true`/`1` 时拒绝LP申请必须填写理由，未配置时关闭
+fn lp_reject_reason_required_from_env() -> bool {
+    let Ok(raw) = std::env::var("LP_REJECT_REASON_REQUIRED") else {
+        return false;
+    };
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => true,
+        "" | "false" | "0" => false,
+        _ => {
+            warn!(
+                "LP_REJECT_REASON_REQUIRED 应为 true 或 false: {}，拒绝理由不强制填写",
+                raw
+            );
+            false
+        }
+    }
+}
+
+/// 读取 SHOP_EXPIRED_ITEMS：`delete` 表示定时删除过期商品，`hide`（默认）表示仅不再展示
+fn delete_expired_items_from_env() -> bool {
+    let Ok(raw) = std::env::var("SHOP_EXPIRED_ITEMS") else {
+        return false;
+    };
+    match raw.trim() {
+        "delete" => true,
+        "" | "hide" => false,
+        _ => {
+            warn!(
+                "SHOP_EXPIRED_ITEMS 应为 hide 或 delete: {}，过期商品仅隐藏",
+                raw
+            );
+            false
+        }
+    }
+}
+
+/// 组装全部路由和中间件
+fn build_router(app_state: state::AppState) -> Router {
     // 配置 CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_headers(Any)
+        // 允许前端读取商品列表的 ETag 用于条件请求、限流时的 Retry-After、请求追踪 ID 和分页信息
+        .expose_headers([
+            axum::http::header::ETAG,
+            axum::http::header::RETRY_AFTER,
+            middleware::REQUEST_ID_HEADER,
+            pagination::TOTAL_COUNT_HEADER,
+            axum::http::header::LINK,
+        ]);
 
     // 构建 API 路由
-    let api_routes = api::routes().layer(axum_middleware::from_fn_with_state(
-        metrics.clone(),
-        middleware::metrics_middleware,
-    ));
+    let upload_routes = api::upload_routes()
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(MAX_UPLOAD_BODY_BYTES));
+    let backup_routes = api::backup_routes()
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(MAX_RESTORE_BODY_BYTES));
+    let api_routes = api::routes()
+        .layer(RequestBodyLimitLayer::new(MAX_REQUEST_BODY_BYTES))
+        .merge(upload_routes)
+        .merge(backup_routes)
+        .layer(axum_middleware::from_fn_with_state(
+            app_state.clone(),
+            middleware::permission_denied_middleware,
+        ))
+        .layer(axum_middleware::from_fn_with_state(
+            app_state.clone(),
+            middleware::must_change_password_middleware,
+        ))
+        .layer(axum_middleware::from_fn(
+            middleware::payload_too_large_middleware,
+        ))
+        .layer(axum_middleware::from_fn_with_state(
+            app_state.metrics.clone(),
+            middleware::metrics_middleware,
+        ));
 
     // 组合所有路由
-    let app = Router::new()
+    Router::new()
         .route("/", get(root_handler))
         .route("/health", get(health::health_check))
         .route("/metrics", get(health::metrics_endpoint))
         .route("/metrics/prometheus", get(health::prometheus_metrics))
         .nest("/api", api_routes)
-        .layer(axum_middleware::from_fn(
+        .nest_service(
+            upload::UPLOAD_URL_PREFIX,
+            ServeDir::new(upload::upload_dir()),
+        )
+        .layer(axum_middleware::from_fn_with_state(
+            app_state.jwt.clone(),
             middleware::request_logging_middleware,
         ))
         .layer(cors)
-        .with_state(app_state);
+        .with_state(app_state)
+}
 
-    // 启动服务器
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
-    info!("后端服务运行在 http://{}", addr);
-    info!("健康检查: http://{}/health", addr);
-    info!("指标监控: http://{}/metrics", addr);
-    info!("Prometheus: http://{}/metrics/prometheus", addr);
+/// 等待 Ctrl-C 或 SIGTERM（仅 Unix）信号
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("无法监听 Ctrl-C 信号");
+    };
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("无法监听 SIGTERM 信号")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("收到停机信号，开始优雅关闭...");
 }
 
 async fn root_handler() -> &'static str {
     "团队运营管理系统后端服务运行中 | API: /api | 健康检查: /health | 指标: /metrics"
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_addr_parses_valid_values_and_falls_back_otherwise() {
+        assert_eq!(
+            parse_bind_addr(" 0.0.0.0:8080 "),
+            "0.0.0.0:8080".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_bind_addr("[::1]:3000"),
+            "[::1]:3000".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(parse_bind_addr("localhost"), DEFAULT_BIND_ADDR);
+        assert_eq!(parse_bind_addr("0.0.0.0:99999"), DEFAULT_BIND_ADDR);
+    }
+}
@@ -1,12 +1,32 @@
 use axum::{
     extract::{Request, State},
+    http::{HeaderName, HeaderValue, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
+    Json,
 };
+use serde_json::json;
 use std::sync::Arc;
 use std::time::Instant;
+use team_operation_system::db::{
+    current_trace_id, record_request_log, request_log_exists, with_request_context, RequestContext,
+    UserService,
+};
+use tracing::Instrument;
 
+use crate::auth::{decode_bearer_claims, track_denied_permission, JwtVerifier};
+use crate::error::ApiError;
 use crate::health::Metrics;
+use crate::state::AppState;
+
+/// 返回请求追踪 ID 的响应头，与请求日志中的 `trace_id` 一致
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// 必须修改密码时返回的错误码，前端据此跳转到修改密码页面
+pub const MUST_CHANGE_PASSWORD_CODE: &str = "MUST_CHANGE_PASSWORD";
+
+/// 必须修改密码的用户仍可访问的接口
+const PASSWORD_CHANGE_ALLOWED_PATHS: [&str; 2] = ["/profile", "/profile/password"];
 
 /// 请求监控中间件
 pub async fn metrics_middleware(
@@ -52,24 +72,354 @@ pub async fn metrics_middleware(
     response
 }
 
-/// 请求日志中间件
-pub async fn request_logging_middleware(request: Request, next: Next) -> Response {
+/// 请求日志中间件：method、path、user、status 等作为结构化字段输出，便于日志平台检索
+pub async fn request_logging_middleware(
+    State(jwt): State<Arc<JwtVerifier>>,
+    request: Request,
+    next: Next,
+) -> Response {
     let path = request.uri().path().to_string();
     let method = request.method().to_string();
+    let user = decode_bearer_claims(request.headers(), &jwt)
+        .map(|claims| claims.sub)
+        .ok();
     let start = Instant::now();
+    let trace_id = uuid::Uuid::new_v4().to_string();
 
-    log::debug!("→ {} {}", method, path);
+    let span = tracing::info_span!(
+        "request",
+        trace_id = %trace_id,
+        method = %method,
+        path = %path,
+        user = user.as_deref().unwrap_or("-"),
+    );
+    tracing::debug!(parent: &span, "请求开始");
 
-    let response = next.run(request).await;
+    // 处理函数内写入的请求日志会带上从这里开始计算的耗时和追踪 ID
+    let context = RequestContext {
+        start,
+        trace_id: trace_id.clone(),
+    };
+    let mut response =
+        with_request_context(context, next.run(request).instrument(span.clone())).await;
     let duration = start.elapsed();
+    if let Ok(value) = HeaderValue::from_str(&trace_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
 
-    log::debug!(
-        "← {} {} - {} ({:?})",
-        method,
-        path,
-        response.status().as_u16(),
-        duration
+    tracing::info!(
+        parent: &span,
+        status = response.status().as_u16(),
+        duration_ms = duration.as_millis() as u64,
+        "请求完成"
     );
 
     response
 }
+
+/// 账号状态中间件：已被禁用的账号即使持有未过期的令牌也一律拒绝；
+/// 被标记为必须修改密码的用户只能访问个人信息和修改密码接口
+pub async fn must_change_password_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let full_path = request.uri().path().to_string();
+    let path = full_path.strip_prefix("/api").unwrap_or(&full_path);
+    if path.starts_with("/auth/") {
+        return next.run(request).await;
+    }
+
+    // 未登录或令牌无效的请求交给各接口自行返回 401
+    let Ok(claims) = decode_bearer_claims(request.headers(), &state.jwt) else {
+        return next.run(request).await;
+    };
+
+    match UserService::access_flags(&state.pool, &claims.sub).await {
+        Ok(Some((false, _))) => {
+            log::warn!("用户 {} 的账号已被禁用，拒绝访问 {}", claims.sub, path);
+            ApiError::forbidden("账号已被禁用").into_response()
+        }
+        Ok(Some((true, true))) if !PASSWORD_CHANGE_ALLOWED_PATHS.contains(&path) => {
+            log::warn!("用户 {} 需要先修改密码，拒绝访问 {}", claims.sub, path);
+            (
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "message": "请先修改密码后再使用系统功能",
+                    "code": MUST_CHANGE_PASSWORD_CODE,
+                })),
+            )
+                .into_response()
+        }
+        Ok(_) => next.run(request).await,
+        Err(e) => {
+            log::error!("查询账号状态失败: {}", e);
+            next.run(request).await
+        }
+    }
+}
+
+/// 权限拒绝日志中间件：接口返回 403 时写入请求日志并注明所需权限，便于发现越权试探。
+/// 处理函数已自行记录过 403 的请求不再重复写入。每次拒绝都会记录，反复试探由 `/logs/denials` 在查询时按用户汇总
+pub async fn permission_denied_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let full_path = request.uri().path().to_string();
+    let user = decode_bearer_claims(request.headers(), &state.jwt)
+        .map(|claims| claims.sub)
+        .ok();
+
+    let (response, denied_permission) = track_denied_permission(next.run(request)).await;
+    if response.status() != StatusCode::FORBIDDEN {
+        return response;
+    }
+    let Some(user) = user else {
+        return response;
+    };
+
+    let status = StatusCode::FORBIDDEN.as_u16() as i32;
+    if let Some(trace_id) = current_trace_id() {
+        match request_log_exists(&state.pool, &trace_id, status).await {
+            Ok(true) => return response,
+            Ok(false) => {}
+            Err(e) => log::error!("查询请求日志失败: {}", e),
+        }
+    }
+
+    let path = full_path.strip_prefix("/api").unwrap_or(&full_path);
+    let note = match &denied_permission {
+        Some(permission) => format!("权限不足: 需要「{}」权限", permission),
+        None => "权限不足".to_string(),
+    };
+    log::warn!("用户 {} 访问 {} {} 被拒绝: {}", user, method, path, note);
+
+    let _ = record_request_log(&state.pool, &method, path, Some(&user), Some(note), status).await;
+
+    response
+}
+
+/// 将请求体超限（413）的响应统一转换为带提示信息的 JSON
+pub async fn payload_too_large_middleware(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE {
+        return response;
+    }
+
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(json!({ "message": "请求内容过大，请精简后重试" })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{add_user, bearer_token, response_json, send, send_json, test_state};
+
+    #[tokio::test]
+    async fn handler_request_log_carries_latency_and_trace_id() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &["审核LP"]);
+
+        let response = send(&state, "GET", "/api/lp/logs/pending", Some(&token)).await;
+        let trace_id = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let (elapsed_ms,) = sqlx::query_as::<_, (Option<i64>,)>(
+            "SELECT elapsed_ms FROM requestlog WHERE trace_id = ?",
+        )
+        .bind(&trace_id)
+        .fetch_one(&state.pool)
+        .await
+        .unwrap();
+        assert!(elapsed_ms.is_some_and(|ms| ms >= 0));
+    }
+
+    #[tokio::test]
+    async fn request_id_header_matches_stored_trace_id() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &["审核LP"]);
+
+        let mut seen = Vec::new();
+        for _ in 0..2 {
+            let response = send(&state, "GET", "/api/lp/logs/pending", Some(&token)).await;
+            let header = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+            assert!(uuid::Uuid::parse_str(header).is_ok());
+            seen.push(header.to_string());
+        }
+        assert_ne!(seen[0], seen[1]);
+
+        let stored = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT trace_id FROM requestlog WHERE path = '/lp/logs/pending' ORDER BY id",
+        )
+        .fetch_all(&state.pool)
+        .await
+        .unwrap();
+        let stored: Vec<String> = stored.into_iter().flatten().collect();
+        assert_eq!(stored, seen);
+    }
+
+    #[tokio::test]
+    async fn every_denial_is_logged_and_summarized_when_read() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let member = bearer_token("10001", &[]);
+
+        // 超过限流器的次数，每一次拒绝都要留下记录
+        for _ in 0..25 {
+            let response = send(&state, "GET", "/api/logs/stats", Some(&member)).await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+
+        let (logged,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM requestlog
+             WHERE user_qq = '10001' AND status = 403 AND path = '/logs/stats'
+               AND body = '权限不足: 需要「查看日志」权限'",
+        )
+        .fetch_one(&state.pool)
+        .await
+        .unwrap();
+        assert_eq!(logged, 25);
+
+        let auditor = bearer_token("10002", &["查看日志"]);
+        let (status, body) =
+            response_json(send(&state, "GET", "/api/logs/denials", Some(&auditor)).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["denials"][0]["user_qq"], "10001");
+        assert_eq!(body["denials"][0]["denials"], 25);
+    }
+
+    #[tokio::test]
+    async fn flagged_user_is_blocked_until_password_changes() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        UserService::set_must_change_password(&state.pool, "10001", true)
+            .await
+            .unwrap();
+        let token = bearer_token("10001", &["用户管理"]);
+
+        let response = send(&state, "GET", "/api/users", Some(&token)).await;
+        let (status, body) = response_json(response).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["code"], MUST_CHANGE_PASSWORD_CODE);
+
+        // 个人信息接口仍可访问，以便完成改密
+        let response = send(&state, "GET", "/api/profile", Some(&token)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        UserService::set_must_change_password(&state.pool, "10001", false)
+            .await
+            .unwrap();
+        let response = send(&state, "GET", "/api/users", Some(&token)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn oversized_body_is_rejected_with_413() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &[]);
+        let body = json!({
+            "upload_user_qq": "10001",
+            "user_qq": "10001",
+            "lp_type": 1,
+            "num": 1,
+            "reason": "a".repeat(300 * 1024),
+        });
+
+        let response = send_json(
+            &state,
+            "POST",
+            "/api/lp/submit",
+            Some(&token),
+            body.to_string(),
+        )
+        .await;
+        let (status, body) = response_json(response).await;
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["message"], "请求内容过大，请精简后重试");
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM lplog")
+            .fetch_one(&state.pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, 0);
+    }
+
+    #[tokio::test]
+    async fn overlong_field_within_body_limit_is_rejected_with_400() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &[]);
+        let body = json!({
+            "upload_user_qq": "10001",
+            "user_qq": "10001",
+            "lp_type": 1,
+            "num": 1,
+            "reason": "a".repeat(501),
+        });
+
+        let response = send_json(
+            &state,
+            "POST",
+            "/api/lp/submit",
+            Some(&token),
+            body.to_string(),
+        )
+        .await;
+        let (status, body) = response_json(response).await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], "申请理由不能超过500个字符");
+    }
+
+    async fn login_status(state: &AppState, qq: &str) -> (StatusCode, serde_json::Value) {
+        let body = json!({ "qq": qq, "password": "password" }).to_string();
+        response_json(send_json(state, "POST", "/api/auth/login", None, body).await).await
+    }
+
+    #[tokio::test]
+    async fn disabled_user_cannot_log_in_until_re_enabled() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        add_user(&state.pool, "10002").await;
+        let admin = bearer_token("10002", &["用户管理"]);
+
+        let response = send(&state, "POST", "/api/users/10001/disable", Some(&admin)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let (status, body) = login_status(&state, "10001").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["message"], "账号已被禁用");
+
+        let response = send(&state, "POST", "/api/users/10001/enable", Some(&admin)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let (status, _) = login_status(&state, "10001").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn disabled_user_with_valid_token_is_rejected() {
+        let state = test_state().await;
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &["用户管理"]);
+        UserService::set_active(&state.pool, "10001", false)
+            .await
+            .unwrap();
+
+        for uri in ["/api/profile", "/api/users"] {
+            let (status, body) = response_json(send(&state, "GET", uri, Some(&token)).await).await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            assert_eq!(body["message"], "账号已被禁用");
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, Message, SmtpTransport,
+    Transport,
+};
+use sqlx::SqlitePool;
+use team_operation_system::db::{NotificationService, UserService};
+
+/// 一条待发送的通知
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// 邮件等外部通知渠道。实现方只负责投递，调用方通过 [`notify_user`] 以后台任务方式使用
+pub trait Notifier: Send + Sync {
+    fn send(&self, notification: &Notification) -> Result<(), String>;
+}
+
+/// 基于 SMTP 的邮件通知，通过 `SMTP_*` 环境变量配置
+pub struct SmtpNotifier {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl SmtpNotifier {
+    /// 读取 `SMTP_HOST`、`SMTP_PORT`、`SMTP_USERNAME`、`SMTP_PASSWORD`、`SMTP_FROM`。
+    /// 未配置 `SMTP_HOST` 时返回 `None`，表示关闭邮件通知
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let Ok(host) = std::env::var("SMTP_HOST") else {
+            return Ok(None);
+        };
+
+        let from = std::env::var("SMTP_FROM")
+            .map_err(|_| "缺少 SMTP_FROM 配置".to_string())?
+            .parse::<Mailbox>()
+            .map_err(|e| format!("SMTP_FROM 格式不正确: {}", e))?;
+
+        let mut builder =
+            SmtpTransport::relay(&host).map_err(|e| format!("SMTP 配置无效: {}", e))?;
+        if let Ok(port) = std::env::var("SMTP_PORT") {
+            let port = port
+                .parse::<u16>()
+                .map_err(|_| "SMTP_PORT 必须是端口号".to_string())?;
+            builder = builder.port(port);
+        }
+        if let (Ok(username), Ok(password)) = (
+            std::env::var("SMTP_USERNAME"),
+            std::env::var("SMTP_PASSWORD"),
+        ) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Some(Self {
+            transport: builder.build(),
+            from,
+        }))
+    }
+}
+
+impl Notifier for SmtpNotifier {
+    fn send(&self, notification: &Notification) -> Result<(), String> {
+        let to = notification
+            .to
+            .parse::<Mailbox>()
+            .map_err(|e| format!("收件地址无效: {}", e))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(notification.subject.clone())
+            .body(notification.body.clone())
+            .map_err(|e| format!("构建邮件失败: {}", e))?;
+
+        self.transport
+            .send(&message)
+            .map(|_| ())
+            .map_err(|e| format!("发送邮件失败: {}", e))
+    }
+}
+
+/// 尽力而为地通知用户：在后台写入站内通知（`kind` 见 `NOTIFICATION_KIND_*`），
+/// 配置了通知渠道且用户填写了邮箱时再发送邮件。不阻塞当前请求，失败只记录日志
+pub fn notify_user(
+    notifier: Option<Arc<dyn Notifier>>,
+    pool: SqlitePool,
+    qq: String,
+    kind: &'static str,
+    subject: String,
+    body: String,
+) {
+    tokio::spawn(async move {
+        if let Err(e) = NotificationService::create(&pool, &qq, kind, &body).await {
+            log::error!("写入用户 {} 的站内通知失败: {}", qq, e);
+        }
+
+        let Some(notifier) = notifier else {
+            return;
+        };
+        let to = match UserService::get_email(&pool, &qq).await {
+            Ok(Some(email)) => email,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("查询用户 {} 邮箱失败: {}", qq, e);
+                return;
+            }
+        };
+
+        let notification = Notification { to, subject, body };
+        match tokio::task::spawn_blocking(move || notifier.send(&notification)).await {
+            Ok(Ok(())) => log::info!("已向用户 {} 发送邮件通知", qq),
+            Ok(Err(e)) => log::warn!("向用户 {} 发送邮件通知失败: {}", qq, e),
+            Err(e) => log::error!("邮件通知任务异常: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{add_user, test_pool};
+    use std::time::Duration;
+    use team_operation_system::db::NOTIFICATION_KIND_LP_PROCESSED;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+    /// 记录发送内容而不真正投递的通知渠道
+    struct RecordingNotifier(UnboundedSender<Notification>);
+
+    impl Notifier for RecordingNotifier {
+        fn send(&self, notification: &Notification) -> Result<(), String> {
+            self.0.send(notification.clone()).map_err(|e| e.to_string())
+        }
+    }
+
+    fn recording_notifier() -> (Arc<dyn Notifier>, UnboundedReceiver<Notification>) {
+        let (tx, rx) = unbounded_channel();
+        (Arc::new(RecordingNotifier(tx)), rx)
+    }
+
+    async fn unread_count(pool: &SqlitePool, qq: &str) -> i64 {
+        // 站内通知在后台任务中写入，稍等片刻再读取
+        for _ in 0..50 {
+            let count = NotificationService::count_unread(pool, qq).await.unwrap();
+            if count > 0 {
+                return count;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        0
+    }
+
+    #[tokio::test]
+    async fn user_with_email_receives_mail_and_site_notification() {
+        let pool = test_pool().await;
+        UserService::register(
+            &pool,
+            "10001",
+            "张三",
+            "password",
+            None,
+            Some("zhangsan@example.com"),
+        )
+        .await
+        .unwrap();
+        let (notifier, mut sent) = recording_notifier();
+
+        notify_user(
+            Some(notifier),
+            pool.clone(),
+            "10001".to_string(),
+            NOTIFICATION_KIND_LP_PROCESSED,
+            "LP申请已通过".to_string(),
+            "你的LP申请已通过".to_string(),
+        );
+
+        let mail = tokio::time::timeout(Duration::from_secs(5), sent.recv())
+            .await
+            .expect("未发送邮件通知")
+            .unwrap();
+        assert_eq!(mail.to, "zhangsan@example.com");
+        assert_eq!(mail.subject, "LP申请已通过");
+        assert_eq!(mail.body, "你的LP申请已通过");
+        assert_eq!(unread_count(&pool, "10001").await, 1);
+    }
+
+    #[tokio::test]
+    async fn user_without_email_only_gets_site_notification() {
+        let pool = test_pool().await;
+        add_user(&pool, "10001").await;
+        let (notifier, mut sent) = recording_notifier();
+
+        notify_user(
+            Some(notifier),
+            pool.clone(),
+            "10001".to_string(),
+            NOTIFICATION_KIND_LP_PROCESSED,
+            "LP申请已通过".to_string(),
+            "你的LP申请已通过".to_string(),
+        );
+
+        assert_eq!(unread_count(&pool, "10001").await, 1);
+        // 通知任务已结束且丢弃了发送端，没有收到任何邮件
+        assert!(tokio::time::timeout(Duration::from_secs(5), sent.recv())
+            .await
+            .expect("通知任务未结束")
+            .is_none());
+    }
+}
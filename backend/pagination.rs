@@ -0,0 +1,50 @@
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, Uri};
+
+/// 列表总条数响应头，与响应体中的 `total` 一致
+pub const TOTAL_COUNT_HEADER: HeaderName = HeaderName::from_static("x-total-count");
+
+/// 为分页列表添加 `X-Total-Count` 头，并在有上一页/下一页时添加 `Link` 头（rel="prev"/"next"），
+/// 链接沿用原请求的路径和其他查询参数，只替换 `limit` 与 `offset`
+pub fn set_pagination_headers(
+    headers: &mut HeaderMap,
+    uri: &Uri,
+    total: i64,
+    limit: i64,
+    offset: i64,
+) {
+    headers.insert(TOTAL_COUNT_HEADER, HeaderValue::from(total));
+
+    let mut links = Vec::new();
+    if offset > 0 && limit > 0 {
+        let prev = (offset - limit).max(0);
+        links.push(format!("<{}>; rel=\"prev\"", page_url(uri, limit, prev)));
+    }
+    if limit > 0 && offset + limit < total {
+        links.push(format!(
+            "<{}>; rel=\"next\"",
+            page_url(uri, limit, offset + limit)
+        ));
+    }
+    if links.is_empty() {
+        return;
+    }
+    if let Ok(value) = HeaderValue::from_str(&links.join(", ")) {
+        headers.insert(header::LINK, value);
+    }
+}
+
+fn page_url(uri: &Uri, limit: i64, offset: i64) -> String {
+    let mut params = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| {
+            let key = pair.split('=').next().unwrap_or_default();
+            !pair.is_empty() && key != "limit" && key != "offset"
+        })
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    params.push(format!("limit={}", limit));
+    params.push(format!("offset={}", offset));
+    format!("{}?{}", uri.path(), params.join("&"))
+}
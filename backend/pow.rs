@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use team_operation_system::pow;
+
+/// 允许配置的最大难度（前导零比特数），过高会让浏览器长时间无法完成注册
+const MAX_DIFFICULTY: u32 = 28;
+/// 挑战的有效期
+const CHALLENGE_TTL: Duration = Duration::from_secs(300);
+/// 同时保留的挑战数量上限，超过时先清理过期挑战，仍超过则拒绝签发
+const MAX_PENDING_CHALLENGES: usize = 10_000;
+
+/// 注册工作量证明：签发一次性挑战并校验解答，保存在 `AppState` 中
+pub struct PowGuard {
+    difficulty: u32,
+    challenges: Mutex<HashMap<String, Instant>>,
+}
+
+impl PowGuard {
+    pub fn new(difficulty: u32) -> Self {
+        Self {
+            difficulty: difficulty.clamp(1, MAX_DIFFICULTY),
+            challenges: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 读取 `REGISTER_POW_DIFFICULTY`，未配置或为 0 时返回 `None`，表示注册不需要工作量证明
+    pub fn from_env() -> Option<Self> {
+        let raw = std::env::var("REGISTER_POW_DIFFICULTY").ok()?;
+        match raw.trim().parse::<u32>() {
+            Ok(0) => None,
+            Ok(difficulty) => Some(Self::new(difficulty)),
+            Err(_) => {
+                log::warn!(
+                    "REGISTER_POW_DIFFICULTY 格式不正确: {}，已关闭注册工作量证明",
+                    raw
+                );
+                None
+            }
+        }
+    }
+
+    pub fn difficulty(&self) -> u32 {
+        self.difficulty
+    }
+
+    /// 签发一个新的挑战，返回随机 nonce
+    pub fn issue(&self) -> Result<String, &'static str> {
+        let now = Instant::now();
+        let mut challenges = self.challenges.lock().unwrap_or_else(|e| e.into_inner());
+
+        if challenges.len() >= MAX_PENDING_CHALLENGES {
+            challenges.retain(|_, issued_at| now.duration_since(*issued_at) < CHALLENGE_TTL);
+            if challenges.len() >= MAX_PENDING_CHALLENGES {
+                return Err("注册请求过多，请稍后再试");
+            }
+        }
+
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        challenges.insert(nonce.clone(), now);
+        Ok(nonce)
+    }
+
+    /// 校验解答。挑战无论成功与否都会被消耗，不能重复使用
+    pub fn verify(&self, nonce: &str, solution: &str) -> Result<(), &'static str> {
+        let issued_at = self
+            .challenges
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(nonce)
+            .ok_or("验证挑战不存在或已被使用，请重新获取")?;
+
+        if issued_at.elapsed() >= CHALLENGE_TTL {
+            return Err("验证挑战已过期，请重新获取");
+        }
+        if !pow::verify(nonce, solution, self.difficulty) {
+            return Err("人机验证未通过");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::error::ApiError;
+
+/// 默认突发容量（令牌桶大小）
+const DEFAULT_BURST: u32 = 20;
+/// 默认每分钟补充的令牌数
+const DEFAULT_PER_MINUTE: u32 = 60;
+/// 桶数量超过该值时清理已经回满的桶，避免长期运行占用内存
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// 按用户、按接口的令牌桶限流器，保存在 `AppState` 中供开销较大的接口复用
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: Mutex<HashMap<(String, &'static str), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, per_minute: u32) -> Self {
+        Self {
+            capacity: burst.max(1) as f64,
+            refill_per_sec: per_minute.max(1) as f64 / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 读取 `RATE_LIMIT_BURST`（突发容量）和 `RATE_LIMIT_PER_MINUTE`（每分钟补充量），
+    /// 未配置或格式错误时使用默认值
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: u32| {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.trim().parse::<u32>().ok())
+                .filter(|value| *value > 0)
+                .unwrap_or(default)
+        };
+        Self::new(
+            read("RATE_LIMIT_BURST", DEFAULT_BURST),
+            read("RATE_LIMIT_PER_MINUTE", DEFAULT_PER_MINUTE),
+        )
+    }
+
+    /// 为用户在指定接口上消耗一个令牌；令牌不足时返回 429，并附带需要等待的秒数
+    pub fn check(&self, user_qq: &str, route: &'static str) -> Result<(), ApiError> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() > MAX_TRACKED_BUCKETS {
+            let (capacity, refill) = (self.capacity, self.refill_per_sec);
+            buckets.retain(|_, bucket| {
+                let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+                bucket.tokens + elapsed * refill < capacity
+            });
+        }
+
+        let bucket = buckets
+            .entry((user_qq.to_string(), route))
+            .or_insert(Bucket {
+                tokens: self.capacity,
+                updated_at: now,
+            });
+
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let retry_after = ((1.0 - bucket.tokens) / self.refill_per_sec).ceil() as u64;
+            log::warn!("用户 {} 请求 {} 过于频繁，已限流", user_qq, route);
+            Err(ApiError::too_many_requests(retry_after.max(1)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AppState;
+    use crate::test_support::{add_user, bearer_token, send, test_state};
+    use axum::http::{header, StatusCode};
+    use std::sync::Arc;
+
+    #[test]
+    fn buckets_are_separate_per_user_and_route() {
+        let limiter = RateLimiter::new(2, 1);
+        assert!(limiter.check("10001", "/a").is_ok());
+        assert!(limiter.check("10001", "/a").is_ok());
+        let error = limiter.check("10001", "/a").unwrap_err();
+        assert_eq!(error.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        assert!(limiter.check("10001", "/b").is_ok());
+        assert!(limiter.check("10002", "/a").is_ok());
+    }
+
+    #[tokio::test]
+    async fn rapid_requests_get_429_with_retry_after() {
+        let state = AppState {
+            rate_limiter: Arc::new(RateLimiter::new(3, 1)),
+            ..test_state().await
+        };
+        add_user(&state.pool, "10001").await;
+        let token = bearer_token("10001", &[]);
+
+        for _ in 0..3 {
+            let response = send(&state, "GET", "/api/shop/items", Some(&token)).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = send(&state, "GET", "/api/shop/items", Some(&token)).await;
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+    }
+}
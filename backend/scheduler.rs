@@ -1,36 +1,128 @@
-use chrono::Local;
+use crate::notifier::{notify_user, Notifier};
 use sqlx::SqlitePool;
+use std::sync::Arc;
 use std::time::Duration;
+use team_operation_system::db::{
+    now_string, previous_month_period, DrawOutcome, LpService, LuckyDrawService, ShopService,
+    NOTIFICATION_KIND_DRAW_WON,
+};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
 use tokio::time::interval;
 
-/// 定时检查并自动开奖
-pub async fn start_lottery_scheduler(pool: SqlitePool) {
+/// 定时检查并自动开奖，收到停机信号后在当前一轮检查结束时退出
+pub async fn start_lottery_scheduler(
+    pool: SqlitePool,
+    notifier: Option<Arc<dyn Notifier>>,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
     tokio::spawn(async move {
         // 每分钟检查一次
         let mut ticker = interval(Duration::from_secs(60));
 
         loop {
-            ticker.tick().await;
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.changed() => break,
+            }
+
+            if *shutdown.borrow() {
+                break;
+            }
 
-            if let Err(e) = check_and_execute_pending_lotteries(&pool).await {
+            if let Err(e) = check_and_execute_pending_lotteries(&pool, &notifier).await {
                 tracing::error!("定时开奖任务执行失败: {}", e);
             }
         }
-    });
+
+        tracing::info!("抽奖定时任务已停止");
+    })
 }
 
-/// 检查并执行到期的抽奖
-async fn check_and_execute_pending_lotteries(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    let current_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+/// 定时生成LP汇总月度快照：每小时检查一次，上个月还没有快照时立即生成，
+/// 因此每月初会记录下上月末的LP汇总
+pub async fn start_snapshot_scheduler(
+    pool: SqlitePool,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(60 * 60));
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.changed() => break,
+            }
+
+            if *shutdown.borrow() {
+                break;
+            }
+
+            if let Err(e) = snapshot_previous_month(&pool).await {
+                tracing::error!("LP汇总快照任务执行失败: {}", e);
+            }
+        }
+
+        tracing::info!("LP汇总快照定时任务已停止");
+    })
+}
+
+/// 定时删除已过下架时间的商品，每 10 分钟检查一次。
+/// 仅在 `SHOP_EXPIRED_ITEMS=delete` 时启动，默认过期商品只是不再展示
+pub async fn start_item_expiry_scheduler(
+    pool: SqlitePool,
+    mut shutdown: watch::Receiver<bool>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(10 * 60));
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.changed() => break,
+            }
+
+            if *shutdown.borrow() {
+                break;
+            }
+
+            match ShopService::delete_expired_items(&pool).await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("已删除 {} 个过期商品", count),
+                Err(e) => tracing::error!("删除过期商品失败: {}", e),
+            }
+        }
 
-    // 查询所有到期但未开奖的抽奖
+        tracing::info!("过期商品清理任务已停止");
+    })
+}
+
+/// 为上个月生成LP汇总快照（已存在时跳过）
+async fn snapshot_previous_month(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let period = previous_month_period();
+    if LpService::snapshot_exists(pool, &period).await? {
+        return Ok(());
+    }
+
+    let rows = LpService::snapshot_summaries(pool, &period).await?;
+    tracing::info!("已生成 {} 的LP汇总快照，共 {} 条", period, rows);
+    Ok(())
+}
+
+/// 检查并执行到期的抽奖
+async fn check_and_execute_pending_lotteries(
+    pool: &SqlitePool,
+    notifier: &Option<Arc<dyn Notifier>>,
+) -> Result<(), sqlx::Error> {
+    // 查询所有到期但未开奖的抽奖。plan_time 在创建时已规范化为服务器时区的 "%Y-%m-%d %H:%M:%S"，
+    // 与 now_string() 时区和格式一致，可直接按字符串比较
     let pending_draws = sqlx::query_as::<_, (i64,)>(
-        "SELECT id FROM luckydrawlog 
-         WHERE status = 0 
-         AND plan_time <= ? 
+        "SELECT id FROM luckydrawlog
+         WHERE status = 0
+         AND plan_time <= ?
          ORDER BY plan_time ASC",
     )
-    .bind(&current_time)
+    .bind(now_string())
     .fetch_all(pool)
     .await?;
 
@@ -41,21 +133,35 @@ async fn check_and_execute_pending_lotteries(pool: &SqlitePool) -> Result<(), sq
     // 逐个执行开奖
     for (draw_id,) in pending_draws {
         match team_operation_system::db::draw_lucky_winner(pool, draw_id).await {
-            Ok(Some(winners)) => {
+            Ok(DrawOutcome::Drawn(winners)) => {
                 tracing::info!(
                     "自动开奖成功: 抽奖ID={}, 中奖者={:?} (共{}人)",
                     draw_id,
                     winners,
                     winners.len()
                 );
+                for winner in winners {
+                    notify_user(
+                        notifier.clone(),
+                        pool.clone(),
+                        winner,
+                        NOTIFICATION_KIND_DRAW_WON,
+                        "抽奖中奖通知".to_string(),
+                        format!("恭喜！你在抽奖活动 #{} 中中奖了。", draw_id),
+                    );
+                }
             }
-            Ok(None) => {
+            Ok(DrawOutcome::NoEligibleUsers) => {
                 tracing::warn!("自动开奖失败: 抽奖ID={}, 没有符合条件的参与者", draw_id);
-                // 标记为已处理，避免重复检查
-                let _ = sqlx::query("UPDATE luckydrawlog SET status = 2 WHERE id = ?")
-                    .bind(draw_id)
-                    .execute(pool)
-                    .await;
+                // 标记为已处理，避免重复检查（手动开奖可能已抢先完成，不能覆盖其结果）
+                let _ =
+                    sqlx::query("UPDATE luckydrawlog SET status = 2 WHERE id = ? AND status = 0")
+                        .bind(draw_id)
+                        .execute(pool)
+                        .await;
+            }
+            Ok(DrawOutcome::AlreadyExecuted) | Ok(DrawOutcome::NotFound) => {
+                tracing::info!("抽奖ID={} 已由其他请求处理，跳过自动开奖", draw_id);
             }
             Err(e) => {
                 tracing::error!("自动开奖出错: 抽奖ID={}, 错误={}", draw_id, e);
@@ -63,5 +169,122 @@ async fn check_and_execute_pending_lotteries(pool: &SqlitePool) -> Result<(), sq
         }
     }
 
+    // 为已结束的重复抽奖创建下一期（含手动开奖的），每期只会生成一次
+    for draw_id in LuckyDrawService::list_recurring_due(pool).await? {
+        if let Err(e) = LuckyDrawService::spawn_next_occurrence(pool, draw_id).await {
+            tracing::error!("创建重复抽奖下一期失败: 抽奖ID={}, 错误={}", draw_id, e);
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{add_user, insert_lp_log, test_pool};
+    use chrono::{Duration as ChronoDuration, NaiveDateTime};
+    use team_operation_system::db::{
+        record_request_log, server_now, CancelDrawOutcome, DrawRecurrence, TIMESTAMP_FORMAT,
+    };
+
+    #[tokio::test]
+    async fn cancelled_draw_is_never_executed() {
+        let pool = test_pool().await;
+        add_user(&pool, "10001").await;
+        insert_lp_log(&pool, "10001", 1, 50, 1).await;
+        let mut draw_ids = Vec::new();
+        for _ in 0..2 {
+            let draw_id = LuckyDrawService::create_draw(
+                &pool,
+                "9999",
+                None,
+                Some("奖品".to_string()),
+                1,
+                0,
+                "2020-01-01 12:00:00",
+                None,
+                DrawRecurrence::None,
+                0,
+            )
+            .await
+            .unwrap();
+            draw_ids.push(draw_id);
+        }
+        let outcome = LuckyDrawService::cancel_draw(&pool, draw_ids[0])
+            .await
+            .unwrap();
+        assert!(matches!(outcome, CancelDrawOutcome::Cancelled));
+
+        check_and_execute_pending_lotteries(&pool, &None)
+            .await
+            .unwrap();
+
+        let cancelled = LuckyDrawService::get_draw(&pool, draw_ids[0])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cancelled.status, 3);
+        assert_eq!(cancelled.winner_qq, None);
+        let executed = LuckyDrawService::get_draw(&pool, draw_ids[1])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(executed.status, 1);
+    }
+
+    #[tokio::test]
+    async fn stored_timestamps_and_due_check_share_the_server_clock() {
+        let pool = test_pool().await;
+        add_user(&pool, "10001").await;
+        insert_lp_log(&pool, "10001", 1, 50, 1).await;
+
+        record_request_log(&pool, "GET", "/time", None, None, 200)
+            .await
+            .unwrap();
+        let (timestamp,) = sqlx::query_as::<_, (String,)>(
+            "SELECT timestamp FROM requestlog ORDER BY id DESC LIMIT 1",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let stored = NaiveDateTime::parse_from_str(&timestamp, TIMESTAMP_FORMAT).unwrap();
+        assert!((server_now() - stored).num_seconds().abs() <= 5);
+
+        // 开奖时间按写入时间戳的同一时钟给出：一分钟前的到期，一小时后的不到期
+        let mut draw_ids = Vec::new();
+        for offset in [ChronoDuration::minutes(-1), ChronoDuration::hours(1)] {
+            let plan_time = (stored + offset).format(TIMESTAMP_FORMAT).to_string();
+            let draw_id = LuckyDrawService::create_draw(
+                &pool,
+                "9999",
+                None,
+                Some("奖品".to_string()),
+                1,
+                0,
+                &plan_time,
+                None,
+                DrawRecurrence::None,
+                0,
+            )
+            .await
+            .unwrap();
+            draw_ids.push(draw_id);
+        }
+
+        check_and_execute_pending_lotteries(&pool, &None)
+            .await
+            .unwrap();
+
+        let due = LuckyDrawService::get_draw(&pool, draw_ids[0])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(due.status, 1);
+        let upcoming = LuckyDrawService::get_draw(&pool, draw_ids[1])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(upcoming.status, 0);
+    }
+}
@@ -2,19 +2,31 @@ use std::sync::Arc;
 
 use axum::extract::FromRef;
 
+use crate::auth::JwtVerifier;
 use crate::health::Metrics;
+use crate::notifier::Notifier;
+use crate::pow::PowGuard;
+use crate::rate_limit::RateLimiter;
 use team_operation_system::db::DbPool;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: DbPool,
+    /// 登录凭证校验，所有请求共用同一份密钥
+    pub jwt: Arc<JwtVerifier>,
     pub metrics: Arc<Metrics>,
-}
-
-impl AppState {
-    pub fn new(pool: DbPool, metrics: Arc<Metrics>) -> Self {
-        Self { pool, metrics }
-    }
+    /// 邮件等附加通知渠道，未配置时为 `None`
+    pub notifier: Option<Arc<dyn Notifier>>,
+    /// 开销较大接口的按用户限流器
+    pub rate_limiter: Arc<RateLimiter>,
+    /// 注册工作量证明，未开启时为 `None`
+    pub register_pow: Option<Arc<PowGuard>>,
+    /// 公开排行榜展示的人数，未开启时为 `None`
+    pub public_leaderboard_size: Option<i64>,
+    /// 开启后没有"审核LP"权限的用户只能为自己提交LP申请
+    pub lp_self_submit_only: bool,
+    /// 开启后拒绝LP申请时必须填写理由
+    pub lp_reject_reason_required: bool,
 }
 
 impl FromRef<AppState> for DbPool {
@@ -28,3 +40,9 @@ impl FromRef<AppState> for Arc<Metrics> {
         state.metrics.clone()
     }
 }
+
+impl FromRef<AppState> for Arc<JwtVerifier> {
+    fn from_ref(state: &AppState) -> Arc<JwtVerifier> {
+        state.jwt.clone()
+    }
+}
@@ -0,0 +1,160 @@
+//! 接口测试共用的应用状态和登录用户
+
+use crate::auth::{AuthenticatedUser, Claims, JwtVerifier, JWT_SECRET};
+use crate::health::Metrics;
+use crate::rate_limit::RateLimiter;
+use crate::state::AppState;
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use axum::response::Response;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use std::str::FromStr;
+use std::sync::Arc;
+use team_operation_system::db::{self, DbPool, UserService};
+use tower::ServiceExt;
+
+/// 执行过全部迁移的内存数据库（单连接，不回收）
+pub async fn test_pool() -> DbPool {
+    // 测试用户的密码用最低强度哈希，避免每个测试都花上数秒
+    std::env::set_var("BCRYPT_COST", "4");
+
+    let options = SqliteConnectOptions::from_str("sqlite::memory:")
+        .expect("测试数据库地址无效")
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(options)
+        .await
+        .expect("无法连接测试数据库");
+    db::init_database(&pool)
+        .await
+        .expect("初始化测试数据库失败");
+    pool
+}
+
+/// 使用内存数据库和默认配置的应用状态，可选功能全部关闭
+pub async fn test_state() -> AppState {
+    AppState {
+        pool: test_pool().await,
+        jwt: Arc::new(JwtVerifier::new(JWT_SECRET)),
+        metrics: Arc::new(Metrics::new()),
+        notifier: None,
+        rate_limiter: Arc::new(RateLimiter::new(20, 60)),
+        register_pow: None,
+        public_leaderboard_size: None,
+        lp_self_submit_only: false,
+        lp_reject_reason_required: false,
+    }
+}
+
+/// 已通过认证、拥有指定权限的用户
+pub fn auth_user(qq: &str, permissions: &[&str]) -> AuthenticatedUser {
+    AuthenticatedUser(Claims {
+        sub: qq.to_string(),
+        nickname: format!("用户{}", qq),
+        exp: usize::MAX,
+        permissions: permissions.iter().map(|p| p.to_string()).collect(),
+    })
+}
+
+/// 注册一个“成员”角色的用户，密码为 `password`
+pub async fn add_user(pool: &DbPool, qq: &str) {
+    UserService::register(pool, qq, &format!("用户{}", qq), "password", None, None)
+        .await
+        .expect("注册测试用户失败");
+}
+
+/// 为用户写入一条指定类型和状态的LP记录（0 待审核，1 通过，2 拒绝），返回记录ID
+pub async fn insert_lp_log(pool: &DbPool, qq: &str, lp_type: i64, num: i32, status: i32) -> i64 {
+    sqlx::query(
+        "INSERT INTO lplog (upload_time, upload_user_qq, user_qq, lp_type, num, reason, status)
+         VALUES (?, ?, ?, ?, ?, '测试', ?)",
+    )
+    .bind(db::now_string())
+    .bind(qq)
+    .bind(qq)
+    .bind(lp_type)
+    .bind(num)
+    .bind(status)
+    .execute(pool)
+    .await
+    .expect("写入测试LP记录失败")
+    .last_insert_rowid()
+}
+
+/// 读取响应体文本
+pub async fn response_text(response: Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("读取响应体失败");
+    String::from_utf8(bytes.to_vec()).expect("响应体不是 UTF-8")
+}
+
+/// 读取响应的状态码和 JSON 响应体
+pub async fn response_json(response: Response) -> (StatusCode, Value) {
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .expect("读取响应体失败");
+    let body = serde_json::from_slice(&bytes).expect("响应体不是 JSON");
+    (status, body)
+}
+
+/// 为指定用户签发带权限的登录凭证，格式与登录接口返回的一致
+pub fn bearer_token(qq: &str, permissions: &[&str]) -> String {
+    let token = encode(
+        &Header::default(),
+        &auth_user(qq, permissions).0,
+        &EncodingKey::from_secret(JWT_SECRET),
+    )
+    .expect("签发测试凭证失败");
+    format!("Bearer {}", token)
+}
+
+/// 以指定用户身份发起不带请求体的请求，经过完整的路由和中间件
+pub async fn send(state: &AppState, method: &str, uri: &str, token: Option<&str>) -> Response {
+    let request = request_builder(method, uri, token)
+        .body(Body::empty())
+        .unwrap();
+    send_request(state, request).await
+}
+
+/// 以指定用户身份发起带 JSON 请求体的请求，请求体原样发送，可以是格式错误的 JSON
+pub async fn send_json(
+    state: &AppState,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: impl Into<String>,
+) -> Response {
+    let request = request_builder(method, uri, token)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.into()))
+        .unwrap();
+    send_request(state, request).await
+}
+
+/// 发送自行构造的请求，用于需要额外请求头的场景
+pub async fn send_request(state: &AppState, request: Request<Body>) -> Response {
+    crate::build_router(state.clone())
+        .oneshot(request)
+        .await
+        .unwrap()
+}
+
+/// 带上请求方法、地址和可选登录凭证的请求构造器
+pub fn request_builder(
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+) -> axum::http::request::Builder {
+    let builder = Request::builder().method(method).uri(uri);
+    match token {
+        Some(token) => builder.header(header::AUTHORIZATION, token),
+        None => builder,
+    }
+}
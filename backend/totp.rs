@@ -0,0 +1,78 @@
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// 身份验证器中显示的签发方名称
+const TOTP_ISSUER: &str = "团队运营系统";
+
+/// 生成新的 Base32 编码密钥
+pub fn generate_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+fn build(secret: &str, account: &str) -> Result<TOTP, String> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| format!("两步验证密钥无效: {:?}", e))?;
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        bytes,
+        Some(TOTP_ISSUER.to_string()),
+        account.to_string(),
+    )
+    .map_err(|e| format!("两步验证密钥无效: {}", e))
+}
+
+/// 生成供身份验证器扫码导入的 otpauth 地址
+pub fn otpauth_url(secret: &str, account: &str) -> Result<String, String> {
+    build(secret, account).map(|totp| totp.get_url())
+}
+
+/// 校验验证码，允许前后各一个 30 秒时间窗口的误差
+pub fn verify_code(secret: &str, account: &str, code: &str) -> Result<bool, String> {
+    let code = code.trim();
+    if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(false);
+    }
+    build(secret, account)?
+        .check_current(code)
+        .map_err(|e| format!("系统时间异常: {}", e))
+}
+
+/// 生成当前时间窗口的验证码，供测试模拟身份验证器
+#[cfg(test)]
+pub fn current_code(secret: &str, account: &str) -> String {
+    build(secret, account)
+        .and_then(|totp| totp.generate_current().map_err(|e| e.to_string()))
+        .expect("生成验证码失败")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_code_is_accepted() {
+        let secret = generate_secret();
+        let code = current_code(&secret, "10001");
+        assert_eq!(verify_code(&secret, "10001", &code), Ok(true));
+        assert_eq!(
+            verify_code(&secret, "10001", &format!(" {} ", code)),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn wrong_or_malformed_codes_are_rejected() {
+        let secret = generate_secret();
+        let code = current_code(&secret, "10001");
+        let wrong = format!(
+            "{:06}",
+            (code.parse::<u32>().unwrap() + 500_000) % 1_000_000
+        );
+        assert_eq!(verify_code(&secret, "10001", &wrong), Ok(false));
+        assert_eq!(verify_code(&secret, "10001", "12345"), Ok(false));
+        assert_eq!(verify_code(&secret, "10001", "12a456"), Ok(false));
+    }
+}
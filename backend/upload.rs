@@ -0,0 +1,39 @@
+//! 图片上传的存储配置与文件类型识别
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 单张图片大小上限（字节）
+pub const UPLOAD_MAX_BYTES: usize = 5 * 1024 * 1024;
+
+/// 上传文件对外访问的路径前缀
+pub const UPLOAD_URL_PREFIX: &str = "/uploads";
+
+/// 上传文件的存储目录，可通过 UPLOAD_DIR 配置，默认为工作目录下的 uploads
+pub fn upload_dir() -> &'static Path {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        std::env::var("UPLOAD_DIR")
+            .ok()
+            .map(|dir| dir.trim().to_string())
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("uploads"))
+    })
+}
+
+/// 根据文件头识别图片格式，返回保存时使用的扩展名。
+/// 不信任客户端声明的 Content-Type，未识别的格式一律拒绝
+pub fn detect_image_ext(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("webp")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,257 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use std::sync::OnceLock;
+use team_operation_system::db::{server_now, TIMESTAMP_FORMAT};
+
+/// LP 申请理由最大长度（字符）
+pub const LP_REASON_MAX_CHARS: usize = 500;
+/// LP 申请图片地址最大长度（字符）
+pub const LP_PICTURE_MAX_CHARS: usize = 2048;
+/// LP 申请角色名最大长度（字符）
+pub const LP_ROLE_MAX_CHARS: usize = 64;
+/// 每位用户最多保存的LP草稿数
+pub const LP_DRAFT_MAX_PER_USER: i64 = 20;
+/// 商品名称最大长度（字符）
+pub const ITEM_NAME_MAX_CHARS: usize = 100;
+/// 商品位置最大长度（字符）
+pub const ITEM_LOCATION_MAX_CHARS: usize = 200;
+/// 商品价格最大长度（字符）
+pub const ITEM_PRICE_MAX_CHARS: usize = 32;
+
+/// 单次CSV批量导入商品的最大行数
+pub const ITEM_IMPORT_MAX_ROWS: usize = 500;
+
+/// 校验商品价格：必须是非负数字
+pub fn validate_item_price(price: &str) -> Result<(), String> {
+    match price.trim().parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => Ok(()),
+        _ => Err(format!("商品价格必须是非负数字: {}", price)),
+    }
+}
+
+/// 批量审批LP默认的单次最大条数
+pub const DEFAULT_BATCH_LP_MAX_IDS: usize = 500;
+
+/// 批量审批LP单次最多处理的条数，读取环境变量 `BATCH_LP_MAX_IDS`（正整数），
+/// 避免一次提交过多ID长时间占用写锁。未配置或取值无效时使用默认值，首次调用后缓存
+pub fn batch_lp_max_ids() -> usize {
+    static MAX: OnceLock<usize> = OnceLock::new();
+    *MAX.get_or_init(|| {
+        let Ok(value) = std::env::var("BATCH_LP_MAX_IDS") else {
+            return DEFAULT_BATCH_LP_MAX_IDS;
+        };
+        match value.trim().parse::<usize>() {
+            Ok(max) if max > 0 => max,
+            _ => {
+                log::warn!(
+                    "BATCH_LP_MAX_IDS={} 无效（应为正整数），使用默认值 {}",
+                    value,
+                    DEFAULT_BATCH_LP_MAX_IDS
+                );
+                DEFAULT_BATCH_LP_MAX_IDS
+            }
+        }
+    })
+}
+
+/// QQ号最少位数
+pub const QQ_MIN_DIGITS: usize = 5;
+/// QQ号最多位数
+pub const QQ_MAX_DIGITS: usize = 12;
+
+/// 校验QQ号格式：仅允许 5–12 位 ASCII 数字
+pub fn validate_qq(qq: &str) -> Result<(), String> {
+    if !qq.chars().all(|c| c.is_ascii_digit()) {
+        return Err("QQ号只能包含数字".to_string());
+    }
+    if !(QQ_MIN_DIGITS..=QQ_MAX_DIGITS).contains(&qq.len()) {
+        return Err(format!("QQ号长度应为{}-{}位", QQ_MIN_DIGITS, QQ_MAX_DIGITS));
+    }
+    Ok(())
+}
+
+/// 抽奖计划时间的存储格式，与其他时间戳一致，可直接按字符串比较
+pub const PLAN_TIME_FORMAT: &str = TIMESTAMP_FORMAT;
+
+/// 解析带或不带秒、空格或 `T` 分隔的本地时间
+fn parse_local_datetime(raw: &str) -> Option<NaiveDateTime> {
+    const ACCEPTED_FORMATS: [&str; 4] = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ];
+
+    let raw = raw.trim();
+    ACCEPTED_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+}
+
+/// 解析并规范化抽奖计划开奖时间：接受带或不带秒、空格或 `T` 分隔的服务器时区时间，
+/// 统一转换为 [`PLAN_TIME_FORMAT`]，早于当前时间的计划时间视为无效
+pub fn normalize_plan_time(raw: &str) -> Result<String, String> {
+    let parsed = parse_local_datetime(raw)
+        .ok_or_else(|| "计划开奖时间格式不正确，应为 YYYY-MM-DD HH:MM:SS".to_string())?;
+
+    if parsed < server_now() {
+        return Err("计划开奖时间不能早于当前时间".to_string());
+    }
+
+    Ok(parsed.format(PLAN_TIME_FORMAT).to_string())
+}
+
+/// 解析并规范化商品下架时间，格式要求与计划开奖时间相同，早于当前时间视为无效
+pub fn normalize_expires_at(raw: &str) -> Result<String, String> {
+    let parsed = parse_local_datetime(raw)
+        .ok_or_else(|| "下架时间格式不正确，应为 YYYY-MM-DD HH:MM:SS".to_string())?;
+
+    if parsed < server_now() {
+        return Err("下架时间不能早于当前时间".to_string());
+    }
+
+    Ok(parsed.format(PLAN_TIME_FORMAT).to_string())
+}
+
+/// 解析统计时间段的起止时间：接受与计划开奖时间相同的格式，或只写日期 `YYYY-MM-DD`
+/// （视为当天 00:00:00），不限制早于当前时间
+pub fn parse_window_bound(label: &str, raw: &str) -> Result<NaiveDateTime, String> {
+    parse_local_datetime(raw)
+        .or_else(|| {
+            NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(|| {
+            format!(
+                "{}格式不正确，应为 YYYY-MM-DD 或 YYYY-MM-DD HH:MM:SS",
+                label
+            )
+        })
+}
+
+/// 生日的存储格式
+pub const BIRTHDAY_FORMAT: &str = "%Y-%m-%d";
+
+/// 校验并规范化生日：必须是真实存在的 `YYYY-MM-DD` 日期，返回补零后的标准格式。
+/// 空字符串视为未填写，原样返回空字符串
+pub fn validate_birthday(birthday: &str) -> Result<String, String> {
+    let birthday = birthday.trim();
+    if birthday.is_empty() {
+        return Ok(String::new());
+    }
+
+    NaiveDate::parse_from_str(birthday, BIRTHDAY_FORMAT)
+        .map(|date| date.format(BIRTHDAY_FORMAT).to_string())
+        .map_err(|_| "生日格式不正确，应为有效的 YYYY-MM-DD 日期".to_string())
+}
+
+/// 邮箱地址最大长度（字符）
+pub const EMAIL_MAX_CHARS: usize = 254;
+
+/// 校验邮箱格式，空字符串视为未填写
+pub fn validate_email(email: &str) -> Result<(), String> {
+    let email = email.trim();
+    if email.is_empty() {
+        return Ok(());
+    }
+    check_max_chars("邮箱", email, EMAIL_MAX_CHARS)?;
+
+    let invalid = || "邮箱格式不正确".to_string();
+    let (local, domain) = email.split_once('@').ok_or_else(invalid)?;
+    if local.is_empty()
+        || domain.contains('@')
+        || !domain.contains('.')
+        || domain.starts_with('.')
+        || domain.ends_with('.')
+        || email.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(invalid());
+    }
+
+    Ok(())
+}
+
+/// 校验文本字段长度（按字符计），超出时返回可直接展示给用户的提示
+pub fn check_max_chars(label: &str, value: &str, max: usize) -> Result<(), String> {
+    if value.chars().count() > max {
+        Err(format!("{}不能超过{}个字符", label, max))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qq_accepts_five_to_twelve_digits() {
+        assert!(validate_qq("12345").is_ok());
+        assert!(validate_qq("123456789012").is_ok());
+    }
+
+    #[test]
+    fn qq_rejects_short_long_and_non_numeric_values() {
+        assert_eq!(validate_qq("1234"), Err("QQ号长度应为5-12位".to_string()));
+        assert!(validate_qq("1234567890123").is_err());
+        assert_eq!(validate_qq("12a45"), Err("QQ号只能包含数字".to_string()));
+        assert!(validate_qq("１２３４５").is_err());
+    }
+
+    #[test]
+    fn plan_time_is_normalized_to_canonical_format() {
+        let future = server_now() + chrono::Duration::days(1);
+        let raw = future.format("%Y-%m-%dT%H:%M").to_string();
+        let expected = future.format("%Y-%m-%d %H:%M:00").to_string();
+        assert_eq!(normalize_plan_time(&raw), Ok(expected));
+    }
+
+    #[test]
+    fn malformed_or_past_plan_time_is_rejected() {
+        for raw in ["", "明天中午", "2030-13-01 12:00:00", "2030/01/01 12:00"] {
+            assert_eq!(
+                normalize_plan_time(raw),
+                Err("计划开奖时间格式不正确，应为 YYYY-MM-DD HH:MM:SS".to_string())
+            );
+        }
+        let past = (server_now() - chrono::Duration::hours(1))
+            .format(PLAN_TIME_FORMAT)
+            .to_string();
+        assert_eq!(
+            normalize_plan_time(&past),
+            Err("计划开奖时间不能早于当前时间".to_string())
+        );
+    }
+
+    #[test]
+    fn birthday_accepts_valid_and_empty_dates() {
+        assert_eq!(
+            validate_birthday("2000-02-29"),
+            Ok("2000-02-29".to_string())
+        );
+        assert_eq!(
+            validate_birthday(" 1999-1-5 "),
+            Ok("1999-01-05".to_string())
+        );
+        assert_eq!(validate_birthday(""), Ok(String::new()));
+        assert_eq!(validate_birthday("   "), Ok(String::new()));
+    }
+
+    #[test]
+    fn birthday_rejects_malformed_and_impossible_dates() {
+        for raw in [
+            "2023-02-30",
+            "2023-13-01",
+            "2023/01/01",
+            "01-01-2000",
+            "明天",
+        ] {
+            assert_eq!(
+                validate_birthday(raw),
+                Err("生日格式不正确，应为有效的 YYYY-MM-DD 日期".to_string()),
+                "{}",
+                raw
+            );
+        }
+    }
+}
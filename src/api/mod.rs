@@ -1,7 +1,14 @@
-use crate::models::UserLpSummary;
+use crate::models::{
+    EffectivePermission, LeaderboardEntry, LpDraft, LpTypeStat, OverviewStats,
+    PermissionDenialSummary, RequestLogStats, RoleMember, SellerStats, ShopSeller, TimelineEvent,
+    UserLpSummary, UserNotification, UserSuggestion,
+};
 use gloo_net::http::Request;
 use serde::{Deserialize, Serialize};
-use web_sys::window;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use web_sys::{window, Blob, BlobPropertyBag, File, FormData, HtmlAnchorElement, Url};
 
 const API_BASE_URL: &str = "http://127.0.0.1:3000/api";
 const TOKEN_KEY: &str = "jwt_token";
@@ -58,17 +65,40 @@ pub struct RegisterRequest {
     pub nickname: String,
     pub password: String,
     pub birthday: Option<String>,
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pow_nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pow_solution: Option<String>,
+}
+
+/// 注册工作量证明挑战，`enabled` 为 false 时不需要求解
+#[derive(Debug, Deserialize)]
+pub struct RegisterChallenge {
+    pub enabled: bool,
+    #[serde(default)]
+    pub nonce: Option<String>,
+    #[serde(default)]
+    pub difficulty: u32,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RegisterResponse {
     pub message: String,
+    /// 新注册用户的信息，旧版后端不返回
+    #[serde(default)]
+    pub user: Option<UserInfo>,
+    /// 注册后直接签发的登录凭证，签发失败时为空，需要手动登录
+    #[serde(default)]
+    pub token: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct LoginRequest {
     pub qq: String,
     pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totp_code: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,10 +112,22 @@ pub struct UserInfo {
     pub qq: String,
     pub nickname: String,
     pub birthday: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
     pub role_name: Option<String>,
     pub permissions: Vec<String>,
     #[serde(default)]
     pub is_default_password: bool, // 是否使用默认密码
+    #[serde(default)]
+    pub must_change_password: bool, // 是否必须先修改密码
+    #[serde(default)]
+    pub totp_enabled: bool, // 是否已开启两步验证
+    #[serde(default)]
+    pub default_lp_type: Option<i64>, // 提交LP时默认选中的类型
+    #[serde(default)]
+    pub is_protected_admin: bool, // 是否是受保护的默认管理员
 }
 
 #[derive(Debug, Deserialize)]
@@ -97,6 +139,10 @@ pub struct ProfileResponse {
 pub struct UpdateProfileRequest {
     pub nickname: Option<String>,
     pub birthday: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email: Option<String>,
+    /// 传入 0 表示清除默认LP类型
+    pub default_lp_type: Option<i64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -114,6 +160,32 @@ pub async fn register(req: RegisterRequest) -> Result<RegisterResponse, String>
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
+    if response.ok() {
+        let register_resp: RegisterResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+
+        if let Some(token) = register_resp.token.as_deref() {
+            set_token(token)?;
+        }
+
+        Ok(register_resp)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "注册失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 获取注册用的工作量证明挑战
+pub async fn get_register_challenge() -> Result<RegisterChallenge, String> {
+    let response = Request::get(&format!("{}/auth/challenge", API_BASE_URL))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
     if response.ok() {
         response
             .json()
@@ -121,7 +193,7 @@ pub async fn register(req: RegisterRequest) -> Result<RegisterResponse, String>
             .map_err(|e| format!("解析响应失败: {}", e))
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "注册失败".to_string(),
+            message: "获取人机验证失败".to_string(),
         });
         Err(error.message)
     }
@@ -234,6 +306,67 @@ pub async fn change_password(old_password: String, new_password: String) -> Resu
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct TotpSetupResponse {
+    pub message: String,
+    pub otpauth_url: String,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyTotpRequest {
+    code: String,
+}
+
+/// 申请开启两步验证，返回待导入身份验证器的密钥
+pub async fn enable_totp() -> Result<TotpSetupResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/profile/2fa/enable", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "开启两步验证失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 提交身份验证器中的验证码，确认开启两步验证
+pub async fn verify_totp(code: String) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/profile/2fa/verify", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .json(&VerifyTotpRequest { code })
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let msg_resp: MessageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(msg_resp.message)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "验证失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
 // ============ 用户管理 ============
 
 #[derive(Debug, Deserialize)]
@@ -247,15 +380,105 @@ pub struct User {
     pub nickname: String,
     pub password: String,
     pub birthday: Option<String>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
     pub main_role_id: Option<i64>,
     pub role_name: Option<String>,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserPermissionsResponse {
+    pub permissions: Vec<EffectivePermission>,
+}
+
+/// 获取用户实际拥有的权限及来源角色
+pub async fn get_user_permissions(qq: &str) -> Result<Vec<EffectivePermission>, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/users/{}/permissions", API_BASE_URL, qq))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let perms_resp: UserPermissionsResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(perms_resp.permissions)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取用户权限失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserTimelineResponse {
+    pub events: Vec<TimelineEvent>,
+    pub total: i64,
+}
+
+/// 获取用户的合并时间线（LP记录、商店交易、请求日志），按时间倒序分页
+pub async fn get_user_timeline(
+    qq: &str,
+    limit: i32,
+    offset: i32,
+) -> Result<UserTimelineResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!(
+        "{}/users/{}/timeline?limit={}&offset={}",
+        API_BASE_URL, qq, limit, offset
+    ))
+    .header("Authorization", &format!("Bearer {}", token))
+    .send()
+    .await
+    .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取用户时间线失败".to_string(),
+        });
+        Err(error.message)
+    }
 }
 
 /// 获取用户列表
 pub async fn get_users() -> Result<Vec<User>, String> {
+    get_users_filtered(None, "").await
+}
+
+/// 按主角色和关键字（QQ号或昵称）筛选用户，条件为空时返回全部用户
+pub async fn get_users_filtered(role_id: Option<i64>, search: &str) -> Result<Vec<User>, String> {
     let token = get_token().ok_or("未登录")?;
 
+    let mut params = Vec::new();
+    if let Some(role_id) = role_id {
+        params.push(("role_id", role_id.to_string()));
+    }
+    let search = search.trim();
+    if !search.is_empty() {
+        params.push(("search", search.to_string()));
+    }
+
     let response = Request::get(&format!("{}/users", API_BASE_URL))
+        .query(params.iter().map(|(key, value)| (*key, value.as_str())))
         .header("Authorization", &format!("Bearer {}", token))
         .send()
         .await
@@ -295,6 +518,31 @@ pub async fn delete_user(qq: &str) -> Result<(), String> {
     }
 }
 
+/// 禁用或启用用户账号
+pub async fn set_user_active(qq: &str, active: bool) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+    let action = if active { "enable" } else { "disable" };
+
+    let response = Request::post(&format!("{}/users/{}/{}", API_BASE_URL, qq, action))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: MessageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.message)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "更新账号状态失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
 // ============ 权限管理 ============
 
 #[derive(Debug, Deserialize)]
@@ -357,9 +605,23 @@ pub struct CreateRoleRequest {
 }
 
 #[derive(Debug, Serialize)]
-pub struct AssignRoleRequest {
-    pub user_qq: String,
+pub struct BulkAssignRoleRequest {
     pub role_id: i64,
+    pub user_qqs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulkAssignLine {
+    pub user_qq: String,
+    /// assigned / skipped_protected / not_found
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulkAssignResponse {
+    pub message: String,
+    pub skipped: usize,
+    pub results: Vec<BulkAssignLine>,
 }
 
 #[derive(Debug, Serialize)]
@@ -373,6 +635,41 @@ pub struct RolePermissionsResponse {
     pub permissions: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UserSuggestionsResponse {
+    pub users: Vec<UserSuggestion>,
+}
+
+/// 按 QQ 或昵称前缀查找用户，用于输入框自动补全
+pub async fn search_users(prefix: &str) -> Result<Vec<UserSuggestion>, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/users/search", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .query([("q", prefix)])
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: UserSuggestionsResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.users)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "搜索用户失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleUsersResponse {
+    pub users: Vec<RoleMember>,
+}
+
 /// 获取所有角色
 pub async fn get_roles() -> Result<Vec<Role>, String> {
     let token = get_token().ok_or("未登录")?;
@@ -426,26 +723,36 @@ pub async fn create_role(name: String, description: Option<String>) -> Result<St
 }
 
 /// 删除角色
-pub async fn delete_role(role_id: i64) -> Result<String, String> {
-    let token = get_token().ok_or("未登录")?;
+pub async fn delete_role(role_id: i64, force: bool) -> Result<String, DeleteRoleError> {
+    let token = get_token().ok_or(DeleteRoleError::Failed("未登录".to_string()))?;
 
-    let response = Request::delete(&format!("{}/roles/{}", API_BASE_URL, role_id))
-        .header("Authorization", &format!("Bearer {}", token))
+    let mut request = Request::delete(&format!("{}/roles/{}", API_BASE_URL, role_id))
+        .header("Authorization", &format!("Bearer {}", token));
+    if force {
+        request = request.query([("force", "true")]);
+    }
+
+    let response = request
         .send()
         .await
-        .map_err(|e| format!("请求失败: {}", e))?;
+        .map_err(|e| DeleteRoleError::Failed(format!("请求失败: {}", e)))?;
 
     if response.ok() {
         let msg_resp: MessageResponse = response
             .json()
             .await
-            .map_err(|e| format!("解析响应失败: {}", e))?;
+            .map_err(|e| DeleteRoleError::Failed(format!("解析响应失败: {}", e)))?;
         Ok(msg_resp.message)
     } else {
+        let in_use = response.status() == 409;
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
             message: "删除角色失败".to_string(),
         });
-        Err(error.message)
+        if in_use {
+            Err(DeleteRoleError::InUse(error.message))
+        } else {
+            Err(DeleteRoleError::Failed(error.message))
+        }
     }
 }
 
@@ -517,6 +824,14 @@ pub async fn revoke_permission_from_role(
     }
 }
 
+/// 删除角色失败的原因
+#[derive(Debug, Clone)]
+pub enum DeleteRoleError {
+    /// 仍有用户使用该角色，可选择强制删除
+    InUse(String),
+    Failed(String),
+}
+
 /// 获取角色的所有权限
 pub async fn get_role_permissions(role_id: i64) -> Result<Vec<String>, String> {
     let token = get_token().ok_or("未登录")?;
@@ -541,13 +856,73 @@ pub async fn get_role_permissions(role_id: i64) -> Result<Vec<String>, String> {
     }
 }
 
-/// 给用户分配角色
-pub async fn assign_role_to_user(user_qq: String, role_id: i64) -> Result<String, String> {
+#[derive(Debug, Deserialize)]
+struct PermissionRolesResponse {
+    roles: Vec<Role>,
+}
+
+/// 获取拥有指定权限的所有角色
+pub async fn get_permission_roles(permission_name: &str) -> Result<Vec<Role>, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!(
+        "{}/permissions/{}/roles",
+        API_BASE_URL,
+        js_sys::encode_uri_component(permission_name)
+    ))
+    .header("Authorization", &format!("Bearer {}", token))
+    .send()
+    .await
+    .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: PermissionRolesResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.roles)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取权限对应角色失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 获取拥有该角色的用户
+pub async fn get_role_users(role_id: i64) -> Result<Vec<RoleMember>, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/roles/{}/users", API_BASE_URL, role_id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let users_resp: RoleUsersResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(users_resp.users)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取角色成员失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 批量给用户分配角色，受保护的管理员和不存在的用户会被跳过
+pub async fn assign_role_bulk(
+    role_id: i64,
+    user_qqs: Vec<String>,
+) -> Result<BulkAssignResponse, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let req = AssignRoleRequest { user_qq, role_id };
+    let req = BulkAssignRoleRequest { role_id, user_qqs };
 
-    let response = Request::post(&format!("{}/roles/assign", API_BASE_URL))
+    let response = Request::post(&format!("{}/roles/assign-bulk", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
         .json(&req)
         .map_err(|e| format!("序列化请求失败: {}", e))?
@@ -556,14 +931,13 @@ pub async fn assign_role_to_user(user_qq: String, role_id: i64) -> Result<String
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        let msg_resp: MessageResponse = response
+        response
             .json()
             .await
-            .map_err(|e| format!("解析响应失败: {}", e))?;
-        Ok(msg_resp.message)
+            .map_err(|e| format!("解析响应失败: {}", e))
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "分配角色失败".to_string(),
+            message: "批量分配角色失败".to_string(),
         });
         Err(error.message)
     }
@@ -593,6 +967,23 @@ pub struct SubmitLpRequest {
     pub role: Option<String>,
 }
 
+/// 保存LP草稿的请求，`id` 为空时新建草稿
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SaveLpDraftRequest {
+    pub id: Option<i64>,
+    pub user_qq: Option<String>,
+    pub lp_type: Option<i64>,
+    pub num: Option<i32>,
+    pub reason: String,
+    pub picture: Option<String>,
+    pub role: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LpDraftsResponse {
+    drafts: Vec<LpDraft>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SubmitLpResponse {
     pub message: String,
@@ -615,6 +1006,11 @@ pub struct LpSummariesResponse {
     pub summaries: Vec<UserLpSummary>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LpTypeStatsResponse {
+    pub stats: Vec<LpTypeStat>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct LpLog {
     pub id: Option<i64>,
@@ -629,12 +1025,123 @@ pub struct LpLog {
     pub status: i32,
     pub picture: Option<String>,
     pub process_time: Option<String>,
+    #[serde(default)]
+    pub reject_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LpDetailResponse {
+    pub log: LpLog,
+    pub type_name: Option<String>,
+}
+
+/// 按ID获取单条LP申请及其类型名称
+pub async fn get_lp_detail(id: i64) -> Result<LpDetailResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/lp/{}", API_BASE_URL, id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取LP申请失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    url: String,
+}
+
+/// 上传LP凭证图片，返回可直接填入图片地址的完整URL
+/// （上传文件挂在服务根路径的 `/uploads` 下，不在 `/api` 下）
+pub async fn upload_image(file: &File) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let form = FormData::new().map_err(|_| "无法创建表单数据")?;
+    form.append_with_blob_and_filename("file", file, &file.name())
+        .map_err(|_| "无法读取所选文件")?;
+
+    let response = Request::post(&format!("{}/uploads", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .body(form)
+        .map_err(|e| format!("构建请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let uploaded: UploadResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        let base_url = API_BASE_URL.trim_end_matches("/api");
+        Ok(format!("{}{}", base_url, uploaded.url))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "上传图片失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// CSV 导入中单行的处理结果
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportItemLine {
+    pub line: usize,
+    pub name: String,
+    pub id: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportItemsResponse {
+    pub message: String,
+    pub success: bool,
+    pub results: Vec<ImportItemLine>,
+}
+
+/// 以当前用户为卖家从 CSV 文件（name,price,count,location）批量上架商品
+pub async fn import_shop_items(file: &File) -> Result<ImportItemsResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/shop/items/import", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .header("Content-Type", "text/csv")
+        .body(file.clone())
+        .map_err(|e| format!("构建请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "导入商品失败".to_string(),
+        });
+        Err(error.message)
+    }
 }
 
 #[derive(Debug, Serialize)]
 pub struct ProcessLpRequest {
     pub id: i64,
     pub status: i32,
+    pub reason: Option<String>,
 }
 
 /// 获取LP类型
@@ -671,6 +1178,7 @@ pub async fn submit_lp(
     reason: String,
     picture: Option<String>,
     role: Option<String>,
+    idempotency_key: &str,
 ) -> Result<SubmitLpResponse, String> {
     let token = get_token().ok_or("未登录")?;
 
@@ -686,6 +1194,7 @@ pub async fn submit_lp(
 
     let response = Request::post(&format!("{}/lp/submit", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
+        .header("Idempotency-Key", idempotency_key)
         .json(&req)
         .map_err(|e| format!("序列化请求失败: {}", e))?
         .send()
@@ -705,110 +1214,85 @@ pub async fn submit_lp(
     }
 }
 
-/// 获取LP日志
-pub async fn get_lp_logs() -> Result<Vec<LpLog>, String> {
+/// 保存LP草稿，返回草稿ID
+pub async fn save_lp_draft(req: SaveLpDraftRequest) -> Result<i64, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let response = Request::get(&format!("{}/lp/logs", API_BASE_URL))
+    let response = Request::post(&format!("{}/lp/draft", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
+        .json(&req)
+        .map_err(|e| format!("序列化请求失败: {}", e))?
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        let logs_resp: LpLogsResponse = response
+        let resp: SubmitLpResponse = response
             .json()
             .await
             .map_err(|e| format!("解析响应失败: {}", e))?;
-        Ok(logs_resp.logs)
+        Ok(resp.id)
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "获取LP日志失败".to_string(),
+            message: "保存草稿失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-/// 处理LP审批
-pub async fn process_lp(id: i64, status: i32) -> Result<String, String> {
+/// 获取当前用户自己的LP草稿
+pub async fn get_lp_drafts() -> Result<Vec<LpDraft>, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let req = ProcessLpRequest { id, status };
-
-    let response = Request::post(&format!("{}/lp/process", API_BASE_URL))
+    let response = Request::get(&format!("{}/lp/drafts", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
-        .json(&req)
-        .map_err(|e| format!("序列化请求失败: {}", e))?
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        let msg_resp: MessageResponse = response
+        let resp: LpDraftsResponse = response
             .json()
             .await
             .map_err(|e| format!("解析响应失败: {}", e))?;
-        Ok(msg_resp.message)
+        Ok(resp.drafts)
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "处理LP失败".to_string(),
+            message: "获取草稿失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-#[derive(Serialize)]
-struct BatchProcessLpRequest {
-    ids: Vec<i64>,
-    status: i32,
-}
-
-#[derive(Deserialize)]
-pub struct BatchProcessLpResponse {
-    pub message: String,
-    pub approved_count: u64,
-    pub requested_count: usize,
-}
-
-/// 批量处理LP审批
-pub async fn batch_process_lp(
-    ids: Vec<i64>,
-    status: i32,
-) -> Result<BatchProcessLpResponse, String> {
+/// 删除自己的LP草稿
+pub async fn delete_lp_draft(id: i64) -> Result<String, String> {
     let token = get_token().ok_or("未登录")?;
 
-    if ids.is_empty() {
-        return Err("请至少选择一条申请".to_string());
-    }
-
-    let req = BatchProcessLpRequest { ids, status };
-
-    let response = Request::post(&format!("{}/lp/batch-process", API_BASE_URL))
+    let response = Request::delete(&format!("{}/lp/drafts/{}", API_BASE_URL, id))
         .header("Authorization", &format!("Bearer {}", token))
-        .json(&req)
-        .map_err(|e| format!("序列化请求失败: {}", e))?
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        response
+        let msg_resp: MessageResponse = response
             .json()
             .await
-            .map_err(|e| format!("解析响应失败: {}", e))
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(msg_resp.message)
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "批量处理LP失败".to_string(),
+            message: "删除草稿失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-/// 查询指定用户的LP详情
-pub async fn get_user_lp_detail(user_qq: &str) -> Result<LpUserDetailResponse, String> {
+/// 把草稿提交为待审批的LP申请
+pub async fn submit_lp_draft(id: i64) -> Result<SubmitLpResponse, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let response = Request::get(&format!("{}/lp/user/{}", API_BASE_URL, user_qq))
+    let response = Request::post(&format!("{}/lp/drafts/{}/submit", API_BASE_URL, id))
         .header("Authorization", &format!("Bearer {}", token))
         .send()
         .await
@@ -821,136 +1305,129 @@ pub async fn get_user_lp_detail(user_qq: &str) -> Result<LpUserDetailResponse, S
             .map_err(|e| format!("解析响应失败: {}", e))
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "查询用户LP失败".to_string(),
+            message: "提交草稿失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-/// 获取所有用户LP汇总
-pub async fn get_lp_summaries() -> Result<Vec<UserLpSummary>, String> {
+/// 获取LP日志
+pub async fn get_lp_logs() -> Result<Vec<LpLog>, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let response = Request::get(&format!("{}/lp/summaries", API_BASE_URL))
+    let response = Request::get(&format!("{}/lp/logs", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        let resp: LpSummariesResponse = response
+        let logs_resp: LpLogsResponse = response
             .json()
             .await
             .map_err(|e| format!("解析响应失败: {}", e))?;
-        Ok(resp.summaries)
+        Ok(logs_resp.logs)
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "获取LP汇总失败".to_string(),
+            message: "获取LP日志失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-// ============ 抽奖管理 ============
-
 #[derive(Debug, Deserialize)]
-pub struct LuckyDrawsResponse {
-    pub draws: Vec<LuckyDraw>,
-}
-
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct LuckyDraw {
-    pub id: Option<i64>,
-    pub create_time: String,
-    pub create_qq: String,
-    pub item_id: Option<i64>,
-    pub fitting: Option<String>,
-    pub num: i32,
-    pub min_lp_require: i32,
-    pub plan_time: String,
-    pub status: i32,
-    pub winner_qq: Option<String>,
-    pub description: Option<String>,
+pub struct LpLogsPageResponse {
+    pub logs: Vec<LpLog>,
+    pub total: i64,
 }
 
-#[derive(Debug, Serialize)]
-pub struct CreateDrawPayload {
-    pub create_qq: String,
-    pub item_id: Option<i64>,
-    pub fitting: Option<String>,
-    pub num: i32,
-    pub min_lp_require: i32,
-    pub plan_time: String,
-    pub description: Option<String>,
-}
+/// 分页获取当前审批人处理过的LP申请
+pub async fn get_lp_processed_by_me(limit: i32, offset: i32) -> Result<LpLogsPageResponse, String> {
+    let token = get_token().ok_or("未登录")?;
 
-#[derive(Debug, Deserialize)]
-pub struct CreateDrawResponse {
-    pub message: String,
-    pub id: i64,
-}
+    let response = Request::get(&format!("{}/lp/processed-by-me", API_BASE_URL))
+        .query([("limit", limit.to_string()), ("offset", offset.to_string())])
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
 
-#[derive(Debug, Deserialize)]
-pub struct ExecuteDrawResponse {
-    pub message: String,
-    #[serde(default)]
-    pub winner: Option<String>,
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取审批记录失败".to_string(),
+        });
+        Err(error.message)
+    }
 }
 
-/// 获取抽奖列表
-pub async fn get_lucky_draws() -> Result<Vec<LuckyDraw>, String> {
+/// 获取待处理的LP申请
+pub async fn get_pending_lp_logs() -> Result<Vec<LpLog>, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let response = Request::get(&format!("{}/lucky-draw", API_BASE_URL))
+    let response = Request::get(&format!("{}/lp/logs/pending", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        let draws_resp: LuckyDrawsResponse = response
+        let logs_resp: LpLogsResponse = response
             .json()
             .await
             .map_err(|e| format!("解析响应失败: {}", e))?;
-        Ok(draws_resp.draws)
+        Ok(logs_resp.logs)
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "获取抽奖列表失败".to_string(),
+            message: "获取待处理LP失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-/// 创建抽奖
-pub async fn create_lucky_draw(payload: CreateDrawPayload) -> Result<CreateDrawResponse, String> {
+/// 处理LP审批
+pub async fn process_lp(id: i64, status: i32, reason: Option<String>) -> Result<String, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let response = Request::post(&format!("{}/lucky-draw/create", API_BASE_URL))
+    let req = ProcessLpRequest { id, status, reason };
+
+    let response = Request::post(&format!("{}/lp/process", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
-        .json(&payload)
+        .json(&req)
         .map_err(|e| format!("序列化请求失败: {}", e))?
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        response
+        let msg_resp: MessageResponse = response
             .json()
             .await
-            .map_err(|e| format!("解析响应失败: {}", e))
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(msg_resp.message)
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "创建抽奖失败".to_string(),
+            message: "处理LP失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-/// 执行抽奖
-pub async fn execute_lucky_draw(draw_id: i64) -> Result<ExecuteDrawResponse, String> {
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct LpReviewPolicy {
+    /// 拒绝LP申请时是否必须填写理由
+    pub reject_reason_required: bool,
+}
+
+/// 获取LP审批规则
+pub async fn get_lp_review_policy() -> Result<LpReviewPolicy, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let response = Request::post(&format!("{}/lucky-draw/execute/{}", API_BASE_URL, draw_id))
+    let response = Request::get(&format!("{}/lp/review-policy", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
         .send()
         .await
@@ -963,113 +1440,151 @@ pub async fn execute_lucky_draw(draw_id: i64) -> Result<ExecuteDrawResponse, Str
             .map_err(|e| format!("解析响应失败: {}", e))
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "执行抽奖失败".to_string(),
+            message: "获取LP审批规则失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-/// 删除抽奖
-pub async fn delete_lucky_draw(draw_id: i64) -> Result<String, String> {
+#[derive(Serialize)]
+struct CloneLpRequest<'a> {
+    user_qq: &'a str,
+}
+
+/// 以已有LP申请为模板，为另一位用户创建新的待审批申请
+pub async fn clone_lp(id: i64, user_qq: &str) -> Result<SubmitLpResponse, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let response = Request::delete(&format!("{}/lucky-draw/{}", API_BASE_URL, draw_id))
+    let response = Request::post(&format!("{}/lp/{}/clone", API_BASE_URL, id))
         .header("Authorization", &format!("Bearer {}", token))
+        .json(&CloneLpRequest { user_qq })
+        .map_err(|e| format!("序列化请求失败: {}", e))?
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        let msg_resp: MessageResponse = response
+        response
             .json()
             .await
-            .map_err(|e| format!("解析响应失败: {}", e))?;
-        Ok(msg_resp.message)
+            .map_err(|e| format!("解析响应失败: {}", e))
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "删除抽奖失败".to_string(),
+            message: "克隆LP申请失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-// ============ 商店管理 ============
-
-#[derive(Debug, Deserialize)]
-pub struct ShopItemsResponse {
-    pub items: Vec<ShopItem>,
-}
-
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct ShopItem {
-    pub id: Option<i64>,
-    pub count: i32,
-    pub price: String,
-    pub name: String,
-    pub seller: String,
-    pub location: String,
+#[derive(Serialize)]
+struct TransferLpRequest<'a> {
+    from_qq: &'a str,
+    to_qq: &'a str,
+    num: i32,
+    reason: &'a str,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct CreateItemResponse {
+#[derive(Deserialize)]
+pub struct TransferLpResponse {
     pub message: String,
-    pub id: i64,
+    pub debit_id: i64,
+    pub credit_id: i64,
 }
 
-#[derive(Debug, Serialize)]
-pub struct CreateItemPayload {
-    pub count: i32,
-    pub price: String,
-    pub name: String,
-    pub seller: String,
-    pub location: String,
-}
+/// 在两个用户之间转移LP，后端生成一对已通过的转出/转入记录
+pub async fn transfer_lp(
+    from_qq: &str,
+    to_qq: &str,
+    num: i32,
+    reason: &str,
+) -> Result<TransferLpResponse, String> {
+    let token = get_token().ok_or("未登录")?;
 
-#[derive(Debug, Serialize)]
-pub struct PurchasePayload {
-    pub buyer: String,
-    pub item_id: i64,
-    pub count: i32,
+    let response = Request::post(&format!("{}/lp/transfer", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .json(&TransferLpRequest {
+            from_qq,
+            to_qq,
+            num,
+            reason,
+        })
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "LP转移失败".to_string(),
+        });
+        Err(error.message)
+    }
 }
 
-/// 获取商店物品列表
-pub async fn get_shop_items(seller_qq: Option<&str>) -> Result<Vec<ShopItem>, String> {
+#[derive(Serialize)]
+struct BatchProcessLpRequest {
+    ids: Vec<i64>,
+    status: i32,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BatchProcessLpResponse {
+    pub message: String,
+    pub approved_count: u64,
+    pub requested_count: usize,
+}
+
+/// 批量处理LP审批
+pub async fn batch_process_lp(
+    ids: Vec<i64>,
+    status: i32,
+    reason: Option<String>,
+) -> Result<BatchProcessLpResponse, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let url = if let Some(qq) = seller_qq {
-        format!("{}/shop/items/my?seller={}", API_BASE_URL, qq)
-    } else {
-        format!("{}/shop/items", API_BASE_URL)
+    if ids.is_empty() {
+        return Err("请至少选择一条申请".to_string());
+    }
+
+    let req = BatchProcessLpRequest {
+        ids,
+        status,
+        reason,
     };
 
-    let response = Request::get(&url)
+    let response = Request::post(&format!("{}/lp/batch-process", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
+        .json(&req)
+        .map_err(|e| format!("序列化请求失败: {}", e))?
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        let items_resp: ShopItemsResponse = response
+        response
             .json()
             .await
-            .map_err(|e| format!("解析响应失败: {}", e))?;
-        Ok(items_resp.items)
+            .map_err(|e| format!("解析响应失败: {}", e))
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "获取商店物品失败".to_string(),
+            message: "批量处理LP失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-/// 创建商店物品
-pub async fn create_shop_item(payload: CreateItemPayload) -> Result<CreateItemResponse, String> {
+/// 查询指定用户的LP详情
+pub async fn get_user_lp_detail(user_qq: &str) -> Result<LpUserDetailResponse, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let response = Request::post(&format!("{}/shop/items/create", API_BASE_URL))
+    let response = Request::get(&format!("{}/lp/user/{}", API_BASE_URL, user_qq))
         .header("Authorization", &format!("Bearer {}", token))
-        .json(&payload)
-        .map_err(|e| format!("序列化请求失败: {}", e))?
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
@@ -1081,127 +1596,280 @@ pub async fn create_shop_item(payload: CreateItemPayload) -> Result<CreateItemRe
             .map_err(|e| format!("解析响应失败: {}", e))
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "创建商店物品失败".to_string(),
+            message: "查询用户LP失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-/// 购买商店物品
-pub async fn purchase_shop_item(buyer: String, item_id: i64, count: i32) -> Result<String, String> {
+/// 获取所有用户LP汇总
+pub async fn get_lp_summaries() -> Result<Vec<UserLpSummary>, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let payload = PurchasePayload {
-        buyer,
-        item_id,
-        count,
-    };
-
-    let response = Request::post(&format!("{}/shop/purchase", API_BASE_URL))
+    let response = Request::get(&format!("{}/lp/summaries", API_BASE_URL))
         .header("Authorization", &format!("Bearer {}", token))
-        .json(&payload)
-        .map_err(|e| format!("序列化请求失败: {}", e))?
         .send()
         .await
         .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        let msg_resp: MessageResponse = response
+        let resp: LpSummariesResponse = response
             .json()
             .await
             .map_err(|e| format!("解析响应失败: {}", e))?;
-        Ok(msg_resp.message)
+        Ok(resp.summaries)
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "购买失败".to_string(),
+            message: "获取LP汇总失败".to_string(),
         });
         Err(error.message)
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct ShopLog {
-    pub id: Option<i64>,
-    pub buyer: String,
-    pub count: i32,
-    pub price: String,
-    pub name: String,
-    pub time: String,
-    pub seller: String,
-    pub location: String,
+/// 把下载到的文件内容交给浏览器保存
+fn save_file(filename: &str, bytes: &[u8], mime: &str) -> Result<(), String> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let options = BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+        .map_err(|_| "创建文件失败")?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(|_| "创建下载链接失败")?;
+
+    let document = window()
+        .and_then(|w| w.document())
+        .ok_or("无法获取document对象")?;
+    let anchor: HtmlAnchorElement = document
+        .create_element("a")
+        .map_err(|_| "创建下载链接失败")?
+        .dyn_into()
+        .map_err(|_| "创建下载链接失败")?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+
+    let _ = Url::revoke_object_url(&url);
+    Ok(())
+}
+
+/// 下载需要登录的 CSV 导出文件并保存到本地
+async fn download_csv(path: &str, filename: &str) -> Result<(), String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}{}", API_BASE_URL, path))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        // 按字节读取，保留服务端写入的 BOM
+        let bytes = response
+            .binary()
+            .await
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+        save_file(filename, &bytes, "text/csv;charset=utf-8")
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "导出失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 导出所有用户的LP汇总
+pub async fn export_lp_summaries() -> Result<(), String> {
+    download_csv("/lp/summaries/export", "LP汇总.csv").await
+}
+
+/// 导出指定用户的LP历史记录
+pub async fn export_user_lp_history(qq: &str) -> Result<(), String> {
+    download_csv(
+        &format!("/lp/user/{}/export", qq),
+        &format!("LP记录_{}.csv", qq),
+    )
+    .await
 }
 
 #[derive(Debug, Deserialize)]
-pub struct UserTransactionsResponse {
-    pub purchases: Vec<ShopLog>,
-    pub sales: Vec<ShopLog>,
+struct CountResponse {
+    count: i64,
 }
 
-/// 获取用户的交易记录（购买和销售）
-pub async fn get_shop_transactions(user_qq: &str) -> Result<UserTransactionsResponse, String> {
+/// 获取待审核的LP申请数量
+pub async fn get_lp_pending_count() -> Result<i64, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let response = Request::get(&format!(
-        "{}/shop/transactions?user_qq={}",
-        API_BASE_URL, user_qq
-    ))
-    .header("Authorization", &format!("Bearer {}", token))
-    .send()
-    .await
-    .map_err(|e| format!("请求失败: {}", e))?;
+    let response = Request::get(&format!("{}/lp/pending-count", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
 
     if response.ok() {
-        response
+        let resp: CountResponse = response
             .json()
             .await
-            .map_err(|e| format!("解析响应失败: {}", e))
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.count)
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "获取交易记录失败".to_string(),
+            message: "获取待审核数量失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 获取按LP类型汇总的统计
+pub async fn get_lp_type_stats() -> Result<Vec<LpTypeStat>, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/lp/stats/by-type", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: LpTypeStatsResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.stats)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取LP类型统计失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LeaderboardResponse {
+    entries: Vec<LeaderboardEntry>,
+}
+
+/// 获取公开LP排行榜，无需登录；后端未开启时返回错误
+pub async fn get_public_leaderboard() -> Result<Vec<LeaderboardEntry>, String> {
+    let response = Request::get(&format!("{}/public/leaderboard", API_BASE_URL))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: LeaderboardResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.entries)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取排行榜失败".to_string(),
         });
         Err(error.message)
     }
 }
 
+// ============ 抽奖管理 ============
+
+#[derive(Debug, Deserialize)]
+pub struct LuckyDrawsResponse {
+    pub draws: Vec<LuckyDraw>,
+    #[serde(default)]
+    pub total: i64,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct RequestLog {
-    pub id: i64,
-    pub method: String,
-    pub path: String,
-    pub user_qq: Option<String>,
-    pub body: Option<String>,
+pub struct LuckyDraw {
+    pub id: Option<i64>,
+    pub create_time: String,
+    pub create_qq: String,
+    pub item_id: Option<i64>,
+    pub fitting: Option<String>,
+    pub num: i32,
+    pub min_lp_require: i32,
+    pub plan_time: String,
     pub status: i32,
-    pub timestamp: String,
+    pub winner_qq: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub recurrence: String,
+    #[serde(default)]
+    pub recurrence_remaining: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateDrawPayload {
+    pub create_qq: String,
+    pub item_id: Option<i64>,
+    pub fitting: Option<String>,
+    pub num: i32,
+    pub min_lp_require: i32,
+    pub plan_time: String,
+    pub description: Option<String>,
+    /// none/daily/weekly
+    pub recurrence: String,
+    /// 重复抽奖的总期数（含本期）
+    pub occurrences: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct LogListResponse {
-    pub logs: Vec<RequestLog>,
-    pub total: i64,
+pub struct CreateDrawResponse {
+    pub message: String,
+    pub id: i64,
 }
 
-/// 获取请求日志
-pub async fn get_request_logs(
+/// 开奖结果类型，已开奖和抽奖不存在通过 409/404 错误返回
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawOutcome {
+    Drawn,
+    NoEligibleUsers,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExecuteDrawResponse {
+    pub message: String,
+    pub outcome: DrawOutcome,
+    #[serde(default)]
+    pub winners: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RetryDrawPayload {
+    pub min_lp_require: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateDrawPayload {
+    pub plan_time: String,
+}
+
+/// 获取抽奖列表
+pub async fn get_lucky_draws(
+    status: Option<i32>,
+    create_qq: Option<String>,
     limit: Option<i32>,
     offset: Option<i32>,
-    user_qq: Option<String>,
-) -> Result<LogListResponse, String> {
+) -> Result<LuckyDrawsResponse, String> {
     let token = get_token().ok_or("未登录")?;
 
-    let mut url = format!("{}/logs", API_BASE_URL);
+    let mut url = format!("{}/lucky-draw", API_BASE_URL);
     let mut params = Vec::new();
 
+    if let Some(s) = status {
+        params.push(format!("status={}", s));
+    }
+    if let Some(qq) = create_qq {
+        if !qq.is_empty() {
+            params.push(format!("create_qq={}", qq));
+        }
+    }
     if let Some(l) = limit {
         params.push(format!("limit={}", l));
     }
     if let Some(o) = offset {
         params.push(format!("offset={}", o));
     }
-    if let Some(qq) = user_qq {
-        if !qq.is_empty() {
-            params.push(format!("user_qq={}", qq));
-        }
-    }
 
     if !params.is_empty() {
         url.push('?');
@@ -1221,7 +1889,1096 @@ pub async fn get_request_logs(
             .map_err(|e| format!("解析响应失败: {}", e))
     } else {
         let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
-            message: "获取日志失败".to_string(),
+            message: "获取抽奖列表失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 创建抽奖
+pub async fn create_lucky_draw(payload: CreateDrawPayload) -> Result<CreateDrawResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/lucky-draw/create", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .json(&payload)
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "创建抽奖失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 执行抽奖
+pub async fn execute_lucky_draw(draw_id: i64) -> Result<ExecuteDrawResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/lucky-draw/execute/{}", API_BASE_URL, draw_id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "执行抽奖失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 重新开奖，可降低最低LP要求
+pub async fn retry_lucky_draw(
+    draw_id: i64,
+    min_lp_require: Option<i32>,
+) -> Result<ExecuteDrawResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/lucky-draw/{}/retry", API_BASE_URL, draw_id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .json(&RetryDrawPayload { min_lp_require })
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "重新开奖失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 以已有抽奖为模板复制一个新的未开奖抽奖
+pub async fn duplicate_draw(draw_id: i64, plan_time: String) -> Result<CreateDrawResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!(
+        "{}/lucky-draw/{}/duplicate",
+        API_BASE_URL, draw_id
+    ))
+    .header("Authorization", &format!("Bearer {}", token))
+    .json(&DuplicateDrawPayload { plan_time })
+    .map_err(|e| format!("序列化请求失败: {}", e))?
+    .send()
+    .await
+    .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "复制抽奖失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EligibleUsersResponse {
+    pub count: i64,
+}
+
+/// 查询抽奖当前符合条件的参与人数
+pub async fn get_lucky_draw_eligible(draw_id: i64) -> Result<EligibleUsersResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/lucky-draw/{}/eligible", API_BASE_URL, draw_id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "查询参与人数失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateDrawPayload {
+    pub num: i32,
+    pub min_lp_require: i32,
+    pub plan_time: String,
+    pub description: String,
+}
+
+/// 修改未开奖的抽奖
+pub async fn update_lucky_draw(draw_id: i64, payload: UpdateDrawPayload) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::patch(&format!("{}/lucky-draw/{}", API_BASE_URL, draw_id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .json(&payload)
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let msg_resp: MessageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(msg_resp.message)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "修改抽奖失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 删除抽奖
+pub async fn delete_lucky_draw(draw_id: i64) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::delete(&format!("{}/lucky-draw/{}", API_BASE_URL, draw_id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let msg_resp: MessageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(msg_resp.message)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "删除抽奖失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 取消未开奖的抽奖（保留记录，恢复库存）
+pub async fn cancel_lucky_draw(draw_id: i64) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/lucky-draw/{}/cancel", API_BASE_URL, draw_id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let msg_resp: MessageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(msg_resp.message)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "取消抽奖失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BatchDeleteDrawsPayload<'a> {
+    ids: &'a [i64],
+    include_pending: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchDeleteDrawsResponse {
+    pub message: String,
+    pub skipped_pending: Vec<i64>,
+    pub not_found: Vec<i64>,
+}
+
+/// 批量删除抽奖，`include_pending` 为 false 时跳过未开奖的抽奖
+pub async fn batch_delete_lucky_draws(
+    ids: &[i64],
+    include_pending: bool,
+) -> Result<BatchDeleteDrawsResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::delete(&format!("{}/lucky-draw/batch", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .json(&BatchDeleteDrawsPayload {
+            ids,
+            include_pending,
+        })
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "批量删除抽奖失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+// ============ 商店管理 ============
+
+#[derive(Debug, Deserialize)]
+pub struct ShopItemsResponse {
+    pub items: Vec<ShopItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShopItemsPageResponse {
+    pub items: Vec<ShopItem>,
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ShopItem {
+    pub id: Option<i64>,
+    pub count: i32,
+    pub price: String,
+    pub name: String,
+    pub seller: String,
+    pub location: String,
+    #[serde(default)]
+    pub low_stock_threshold: Option<i32>,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateItemResponse {
+    pub message: String,
+    pub id: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateItemPayload {
+    pub count: i32,
+    pub price: String,
+    pub name: String,
+    pub seller: String,
+    pub location: String,
+    pub low_stock_threshold: Option<i32>,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurchasePayload {
+    pub buyer: String,
+    pub item_id: i64,
+    pub count: i32,
+}
+
+/// 获取商店物品列表
+pub async fn get_shop_items(seller_qq: Option<&str>) -> Result<Vec<ShopItem>, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let url = if let Some(qq) = seller_qq {
+        format!("{}/shop/items/my?seller={}", API_BASE_URL, qq)
+    } else {
+        format!("{}/shop/items", API_BASE_URL)
+    };
+
+    let response = Request::get(&url)
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let items_resp: ShopItemsResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(items_resp.items)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取商店物品失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+thread_local! {
+    /// 商品分页缓存：查询参数 -> (ETag, 响应)
+    static SHOP_ITEMS_CACHE: RefCell<HashMap<String, (String, ShopItemsPageResponse)>> =
+        RefCell::new(HashMap::new());
+}
+
+/// 分页获取在售商品（关键字在服务端匹配名称、地点、卖家）
+pub async fn get_shop_items_page(
+    keyword: Option<String>,
+    seller: Option<String>,
+    price_range: (Option<f64>, Option<f64>),
+    limit: i32,
+    offset: i32,
+) -> Result<ShopItemsPageResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let mut params = vec![("limit", limit.to_string()), ("offset", offset.to_string())];
+    if let Some(kw) = keyword {
+        if !kw.is_empty() {
+            params.push(("keyword", kw));
+        }
+    }
+    if let Some(seller) = seller {
+        params.push(("seller", seller));
+    }
+    if let Some(min_price) = price_range.0 {
+        params.push(("min_price", min_price.to_string()));
+    }
+    if let Some(max_price) = price_range.1 {
+        params.push(("max_price", max_price.to_string()));
+    }
+
+    // 以查询参数作为缓存键，带上次的 ETag 发起条件请求，未变化时服务端返回 304
+    let cache_key = params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&");
+    let cached = SHOP_ITEMS_CACHE.with(|cache| cache.borrow().get(&cache_key).cloned());
+
+    let mut request = Request::get(&format!("{}/shop/items", API_BASE_URL))
+        .query(params.iter().map(|(key, value)| (*key, value.as_str())))
+        .header("Authorization", &format!("Bearer {}", token));
+    if let Some((etag, _)) = cached.as_ref() {
+        request = request.header("If-None-Match", etag);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.status() == 304 {
+        if let Some((_, page)) = cached {
+            return Ok(page);
+        }
+    }
+
+    if response.ok() {
+        let etag = response.headers().get("etag");
+        let page: ShopItemsPageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        if let Some(etag) = etag {
+            SHOP_ITEMS_CACHE.with(|cache| {
+                cache.borrow_mut().insert(cache_key, (etag, page.clone()));
+            });
+        }
+        Ok(page)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取商店物品失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 创建商店物品
+pub async fn create_shop_item(payload: CreateItemPayload) -> Result<CreateItemResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/shop/items/create", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .json(&payload)
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "创建商店物品失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransferItemRequest {
+    pub new_seller: String,
+}
+
+/// 将商品转让给其他卖家
+pub async fn transfer_shop_item(item_id: i64, new_seller: String) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/shop/items/{}/transfer", API_BASE_URL, item_id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .json(&TransferItemRequest { new_seller })
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: MessageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.message)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "转让商品失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RestockItemRequest {
+    delta: i32,
+}
+
+#[derive(Deserialize)]
+pub struct RestockItemResponse {
+    pub message: String,
+    pub count: i32,
+}
+
+/// 为商品补货（`delta` 为负数时减少库存），返回调整后的库存
+pub async fn restock_shop_item(item_id: i64, delta: i32) -> Result<RestockItemResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/shop/items/{}/restock", API_BASE_URL, item_id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .json(&RestockItemRequest { delta })
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "商品补货失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 购买商店物品（同一个幂等键重复提交只会购买一次）
+pub async fn purchase_shop_item(
+    buyer: String,
+    item_id: i64,
+    count: i32,
+    idempotency_key: &str,
+) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let payload = PurchasePayload {
+        buyer,
+        item_id,
+        count,
+    };
+
+    let response = Request::post(&format!("{}/shop/purchase", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .header("Idempotency-Key", idempotency_key)
+        .json(&payload)
+        .map_err(|e| format!("序列化请求失败: {}", e))?
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let msg_resp: MessageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(msg_resp.message)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "购买失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ShopLog {
+    pub id: Option<i64>,
+    pub buyer: String,
+    pub count: i32,
+    pub price: String,
+    pub name: String,
+    pub time: String,
+    pub seller: String,
+    pub location: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserTransactionsResponse {
+    pub purchases: Vec<ShopLog>,
+    pub sales: Vec<ShopLog>,
+    #[serde(default)]
+    pub purchases_total: i64,
+    #[serde(default)]
+    pub sales_total: i64,
+}
+
+/// 获取卖家累计销售统计
+pub async fn get_seller_stats(seller_qq: &str) -> Result<SellerStats, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!(
+        "{}/shop/sellers/{}/stats",
+        API_BASE_URL, seller_qq
+    ))
+    .header("Authorization", &format!("Bearer {}", token))
+    .send()
+    .await
+    .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取销售统计失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Deserialize)]
+struct ShopSellersResponse {
+    sellers: Vec<ShopSeller>,
+}
+
+/// 获取有在售商品的卖家列表
+pub async fn get_shop_sellers() -> Result<Vec<ShopSeller>, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/shop/sellers", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: ShopSellersResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.sellers)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取卖家列表失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 获取用户的交易记录（购买和销售），购买和销售各自按 `limit`/`offset` 分页
+pub async fn get_shop_transactions(
+    user_qq: &str,
+    limit: i32,
+    offset: i32,
+) -> Result<UserTransactionsResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!(
+        "{}/shop/transactions?user_qq={}&limit={}&offset={}",
+        API_BASE_URL, user_qq, limit, offset
+    ))
+    .header("Authorization", &format!("Bearer {}", token))
+    .send()
+    .await
+    .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取交易记录失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionResponse {
+    pub transaction: ShopLog,
+}
+
+/// 按ID获取单条交易记录
+pub async fn get_transaction(id: i64) -> Result<ShopLog, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/shop/transactions/{}", API_BASE_URL, id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: TransactionResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.transaction)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取交易记录失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RequestLog {
+    pub id: i64,
+    pub method: String,
+    pub path: String,
+    pub user_qq: Option<String>,
+    pub body: Option<String>,
+    pub status: i32,
+    pub timestamp: String,
+    #[serde(default)]
+    pub elapsed_ms: Option<i64>,
+    #[serde(default)]
+    pub trace_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogListResponse {
+    pub logs: Vec<RequestLog>,
+    pub total: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PermissionDenialsResponse {
+    denials: Vec<PermissionDenialSummary>,
+}
+
+/// 获取最近 `days` 天内按用户汇总的拒绝访问（403）记录
+pub async fn get_permission_denials(days: i64) -> Result<Vec<PermissionDenialSummary>, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/logs/denials", API_BASE_URL))
+        .query([("days", days.to_string())])
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: PermissionDenialsResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.denials)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取拒绝访问记录失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestLogStatsResponse {
+    stats: RequestLogStats,
+}
+
+/// 按状态码类别和请求方法统计一段时间内的请求，起止时间为空时统计最近 24 小时
+pub async fn get_request_log_stats(
+    start: Option<String>,
+    end: Option<String>,
+) -> Result<RequestLogStats, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let mut params = Vec::new();
+    if let Some(start) = start {
+        params.push(("start", start));
+    }
+    if let Some(end) = end {
+        params.push(("end", end));
+    }
+    let response = Request::get(&format!("{}/logs/stats", API_BASE_URL))
+        .query(params)
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: RequestLogStatsResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.stats)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取日志统计失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 获取请求日志
+pub async fn get_request_logs(
+    limit: Option<i32>,
+    offset: Option<i32>,
+    user_qq: Option<String>,
+) -> Result<LogListResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let mut url = format!("{}/logs", API_BASE_URL);
+    let mut params = Vec::new();
+
+    if let Some(l) = limit {
+        params.push(format!("limit={}", l));
+    }
+    if let Some(o) = offset {
+        params.push(format!("offset={}", o));
+    }
+    if let Some(qq) = user_qq {
+        if !qq.is_empty() {
+            params.push(format!("user_qq={}", qq));
+        }
+    }
+
+    if !params.is_empty() {
+        url.push('?');
+        url.push_str(&params.join("&"));
+    }
+
+    let response = Request::get(&url)
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取日志失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 增量获取 ID 大于 `since_id` 的新日志（最新的在前）
+pub async fn get_request_logs_since(
+    since_id: i64,
+    user_qq: Option<String>,
+    limit: i32,
+) -> Result<LogListResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let since_id = since_id.to_string();
+    let limit = limit.to_string();
+    let mut params = vec![("since_id", since_id.as_str()), ("limit", limit.as_str())];
+    if let Some(qq) = user_qq.as_deref().filter(|qq| !qq.is_empty()) {
+        params.push(("user_qq", qq));
+    }
+
+    let response = Request::get(&format!("{}/logs", API_BASE_URL))
+        .query(params)
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取日志失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 获取当前用户自己的操作记录
+pub async fn get_my_activity(limit: i32, offset: i32) -> Result<LogListResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!(
+        "{}/profile/activity?limit={}&offset={}",
+        API_BASE_URL, limit, offset
+    ))
+    .header("Authorization", &format!("Bearer {}", token))
+    .send()
+    .await
+    .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取操作记录失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 按用户QQ和/或请求路径批量删除日志
+pub async fn delete_request_logs(
+    user_qq: Option<String>,
+    path: Option<String>,
+) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let mut params = Vec::new();
+    if let Some(qq) = user_qq.as_deref().filter(|qq| !qq.is_empty()) {
+        params.push(("user_qq", qq));
+    }
+    if let Some(path) = path.as_deref().filter(|path| !path.is_empty()) {
+        params.push(("path", path));
+    }
+
+    let response = Request::delete(&format!("{}/logs", API_BASE_URL))
+        .query(params)
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let msg_resp: MessageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(msg_resp.message)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "删除日志失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MetricsReport {
+    pub timestamp: String,
+    pub uptime_seconds: u64,
+    pub uptime_human: String,
+    pub requests: RequestMetrics,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RequestMetrics {
+    pub total: u64,
+    pub errors: u64,
+    pub success: u64,
+    pub error_rate_percent: f64,
+}
+
+/// 获取服务运行指标（`/metrics` 挂在服务根路径，不在 `/api` 下）
+pub async fn get_metrics() -> Result<MetricsReport, String> {
+    let base_url = API_BASE_URL.trim_end_matches("/api");
+
+    let response = Request::get(&format!("{}/metrics", base_url))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        Err(format!("获取运行指标失败: HTTP {}", response.status()))
+    }
+}
+
+// ============ 统计相关 ============
+
+/// 获取首页概览统计
+pub async fn get_overview() -> Result<OverviewStats, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/stats/overview", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取概览统计失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+// ============ 服务器时间 ============
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerTime {
+    /// 服务器时区下的当前时间，格式 YYYY-MM-DD HH:MM:SS
+    pub now: String,
+    /// 时区偏移，如 +08:00
+    pub timezone: String,
+    pub utc_offset_seconds: i32,
+}
+
+/// 获取服务器当前时间和时区
+pub async fn get_server_time() -> Result<ServerTime, String> {
+    let response = Request::get(&format!("{}/time", API_BASE_URL))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取服务器时间失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+// ============ 站内通知 ============
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationsResponse {
+    pub notifications: Vec<UserNotification>,
+    pub total: i64,
+    pub unread: i64,
+}
+
+/// 分页获取当前用户的站内通知，同时返回总数和未读数量
+pub async fn get_notifications(limit: i32, offset: i32) -> Result<NotificationsResponse, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/notifications", API_BASE_URL))
+        .query([("limit", limit.to_string()), ("offset", offset.to_string())])
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取通知失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 获取当前用户的未读通知数量
+pub async fn get_unread_notification_count() -> Result<i64, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::get(&format!("{}/notifications/unread-count", API_BASE_URL))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let resp: CountResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(resp.count)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "获取未读通知数量失败".to_string(),
+        });
+        Err(error.message)
+    }
+}
+
+/// 把自己的一条通知标记为已读
+pub async fn mark_notification_read(id: i64) -> Result<String, String> {
+    let token = get_token().ok_or("未登录")?;
+
+    let response = Request::post(&format!("{}/notifications/{}/read", API_BASE_URL, id))
+        .header("Authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .map_err(|e| format!("请求失败: {}", e))?;
+
+    if response.ok() {
+        let msg_resp: MessageResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("解析响应失败: {}", e))?;
+        Ok(msg_resp.message)
+    } else {
+        let error: ErrorResponse = response.json().await.unwrap_or(ErrorResponse {
+            message: "标记已读失败".to_string(),
         });
         Err(error.message)
     }
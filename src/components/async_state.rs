@@ -0,0 +1,94 @@
+use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+
+/// 加载提示的延迟显示时间（毫秒），请求很快完成时不闪烁
+const LOADING_DELAY_MS: u32 = 180;
+
+/// 页面异步操作的公共状态：加载中、延迟显示的加载提示、错误和成功消息
+#[derive(Clone, Copy, PartialEq)]
+pub struct AsyncState {
+    loading: Signal<bool>,
+    loading_visible: Signal<bool>,
+    error: Signal<Option<String>>,
+    success: Signal<Option<String>>,
+}
+
+impl AsyncState {
+    pub fn is_loading(&self) -> bool {
+        *self.loading.read()
+    }
+
+    pub fn is_loading_visible(&self) -> bool {
+        *self.loading_visible.read()
+    }
+
+    pub fn start(&mut self) {
+        self.loading.set(true);
+    }
+
+    pub fn finish(&mut self) {
+        self.loading.set(false);
+    }
+
+    pub fn set_error(&mut self, message: impl Into<String>) {
+        self.error.set(Some(message.into()));
+    }
+
+    pub fn clear_error(&mut self) {
+        self.error.set(None);
+    }
+
+    /// 设置成功消息，同时清除之前的错误
+    pub fn set_success(&mut self, message: impl Into<String>) {
+        self.success.set(Some(message.into()));
+        self.error.set(None);
+    }
+}
+
+/// 创建页面异步状态，加载超过 180ms 才显示加载提示
+pub fn use_async_state() -> AsyncState {
+    let loading = use_signal(|| false);
+    let loading_visible = use_signal(|| false);
+    let error = use_signal(|| None::<String>);
+    let success = use_signal(|| None::<String>);
+
+    use_effect(move || {
+        if *loading.read() {
+            let mut loading_visible = loading_visible;
+            spawn(async move {
+                TimeoutFuture::new(LOADING_DELAY_MS).await;
+                if *loading.peek() {
+                    loading_visible.set(true);
+                }
+            });
+        } else {
+            let mut loading_visible = loading_visible;
+            loading_visible.set(false);
+        }
+    });
+
+    AsyncState {
+        loading,
+        loading_visible,
+        error,
+        success,
+    }
+}
+
+/// 显示加载提示、错误消息和成功消息
+#[component]
+pub fn AsyncStatus(state: AsyncState, loading_text: &'static str) -> Element {
+    rsx! {
+        if state.is_loading_visible() {
+            div { class: "loading-message", "{loading_text}" }
+        }
+
+        if let Some(err) = state.error.read().as_ref() {
+            div { class: "error-message", "{err}" }
+        }
+
+        if let Some(succ) = state.success.read().as_ref() {
+            div { class: "success-message", "{succ}" }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use dioxus::prelude::*;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+/// 对话框的回答，由 `ConfirmDialog` 写入，等待方在自己的任务里读取
+#[derive(Default)]
+struct AnswerSlot {
+    answer: Option<bool>,
+    waker: Option<Waker>,
+}
+
+/// 等待用户在确认对话框中做出选择，确定为 `true`，取消或被新的确认替换为 `false`
+pub struct ConfirmAnswer {
+    slot: Rc<RefCell<AnswerSlot>>,
+}
+
+impl Future for ConfirmAnswer {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        let mut slot = self.slot.borrow_mut();
+        match slot.answer {
+            Some(answer) => Poll::Ready(answer),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+struct ConfirmRequest {
+    message: String,
+    slot: Rc<RefCell<AnswerSlot>>,
+}
+
+impl ConfirmRequest {
+    fn resolve(self, answer: bool) {
+        let mut slot = self.slot.borrow_mut();
+        slot.answer = Some(answer);
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// 全局确认对话框，通过 `use_confirm()` 在任意页面中使用，代替浏览器原生的 `confirm`。
+/// 等待结果的任务属于调用方页面，页面卸载时随之取消
+#[derive(Clone, Copy)]
+pub struct Confirmer {
+    pending: Signal<Option<ConfirmRequest>>,
+}
+
+impl Confirmer {
+    /// 弹出确认对话框并等待用户选择；已有对话框时先按取消处理
+    pub fn confirm(&mut self, message: impl Into<String>) -> ConfirmAnswer {
+        let slot = Rc::new(RefCell::new(AnswerSlot::default()));
+        let previous = self.pending.write().replace(ConfirmRequest {
+            message: message.into(),
+            slot: slot.clone(),
+        });
+        if let Some(previous) = previous {
+            previous.resolve(false);
+        }
+        ConfirmAnswer { slot }
+    }
+
+    fn answer(&mut self, answer: bool) {
+        let request = self.pending.write().take();
+        if let Some(request) = request {
+            request.resolve(answer);
+        }
+    }
+}
+
+/// 在根组件中提供确认对话框
+pub fn use_confirm_provider() -> Confirmer {
+    use_context_provider(|| Confirmer {
+        pending: Signal::new(None),
+    })
+}
+
+pub fn use_confirm() -> Confirmer {
+    use_context::<Confirmer>()
+}
+
+/// 确认对话框：有待确认的操作时显示，放在布局中即可。
+/// 打开时焦点落在“取消”上，Tab 只在两个按钮间切换，Esc 或点击遮罩等同于取消
+#[component]
+pub fn ConfirmDialog() -> Element {
+    let mut confirmer = use_confirm();
+    let mut cancel_button = use_signal(|| None::<Rc<MountedData>>);
+    let mut confirm_button = use_signal(|| None::<Rc<MountedData>>);
+    let mut confirm_focused = use_signal(|| false);
+
+    let message = confirmer
+        .pending
+        .read()
+        .as_ref()
+        .map(|request| request.message.clone());
+    let Some(message) = message else {
+        return rsx! {};
+    };
+
+    let on_keydown = move |evt: KeyboardEvent| match evt.key() {
+        Key::Escape => {
+            evt.prevent_default();
+            confirmer.answer(false);
+        }
+        Key::Tab => {
+            evt.prevent_default();
+            let next = if *confirm_focused.peek() {
+                cancel_button.peek().clone()
+            } else {
+                confirm_button.peek().clone()
+            };
+            if let Some(next) = next {
+                spawn(async move {
+                    let _ = next.set_focus(true).await;
+                });
+            }
+        }
+        _ => {}
+    };
+
+    rsx! {
+        div {
+            class: "confirm-overlay",
+            onclick: move |_| confirmer.answer(false),
+            onkeydown: on_keydown,
+            div {
+                class: "confirm-dialog",
+                role: "alertdialog",
+                aria_modal: "true",
+                onclick: move |evt| evt.stop_propagation(),
+                p { class: "confirm-message", "{message}" }
+                div { class: "confirm-actions",
+                    button {
+                        class: "btn-secondary",
+                        onmounted: move |evt: MountedEvent| async move {
+                            let element = evt.data();
+                            cancel_button.set(Some(element.clone()));
+                            let _ = element.set_focus(true).await;
+                        },
+                        onfocus: move |_| confirm_focused.set(false),
+                        onclick: move |_| confirmer.answer(false),
+                        "取消"
+                    }
+                    button {
+                        class: "btn-danger",
+                        onmounted: move |evt: MountedEvent| confirm_button.set(Some(evt.data())),
+                        onfocus: move |_| confirm_focused.set(true),
+                        onclick: move |_| confirmer.answer(true),
+                        "确定"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use chrono::{Local, NaiveDateTime};
+use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+
+/// 商品下架时间的格式，与后端存储一致
+const EXPIRES_AT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 倒计时刷新间隔（毫秒）
+const REFRESH_INTERVAL_MS: u32 = 60_000;
+
+/// 显示商品距下架的剩余时间，过期后显示“已过期”，每分钟刷新一次
+#[component]
+pub fn ExpiryBadge(expires_at: String) -> Element {
+    let mut now = use_signal(|| Local::now().naive_local());
+
+    use_future(move || async move {
+        loop {
+            TimeoutFuture::new(REFRESH_INTERVAL_MS).await;
+            now.set(Local::now().naive_local());
+        }
+    });
+
+    let Ok(deadline) = NaiveDateTime::parse_from_str(&expires_at, EXPIRES_AT_FORMAT) else {
+        return rsx! {};
+    };
+
+    let remaining = deadline - *now.read();
+    if remaining.num_seconds() <= 0 {
+        return rsx! {
+            span { class: "badge badge-danger", title: "下架时间: {expires_at}", "已过期" }
+        };
+    }
+
+    let label = if remaining.num_days() > 0 {
+        format!(
+            "剩余 {} 天 {} 小时",
+            remaining.num_days(),
+            remaining.num_hours() % 24
+        )
+    } else if remaining.num_hours() > 0 {
+        format!(
+            "剩余 {} 小时 {} 分钟",
+            remaining.num_hours(),
+            remaining.num_minutes() % 60
+        )
+    } else {
+        format!("剩余 {} 分钟", remaining.num_minutes().max(1))
+    };
+
+    rsx! {
+        span { class: "badge badge-info", title: "下架时间: {expires_at}", "{label}" }
+    }
+}
@@ -0,0 +1,64 @@
+use dioxus::prelude::*;
+use pulldown_cmark::{html, Options, Parser};
+
+/// 将 Markdown 渲染为 HTML，并用 ammonia 过滤脚本、事件属性等危险内容。
+/// 只有经过这里处理的内容才能交给 `dangerous_inner_html`
+pub fn render_markdown(text: &str) -> String {
+    let parser = Parser::new_ext(text, Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES);
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+    ammonia::clean(&unsafe_html)
+}
+
+/// 显示 Markdown 文本，可切换查看原文
+#[component]
+pub fn MarkdownText(text: String) -> Element {
+    let mut show_raw = use_signal(|| false);
+    let rendered = render_markdown(&text);
+
+    rsx! {
+        div { class: "markdown-text",
+            if *show_raw.read() {
+                pre { class: "markdown-raw", "{text}" }
+            } else {
+                div { class: "markdown-body", dangerous_inner_html: "{rendered}" }
+            }
+            button {
+                class: "markdown-toggle",
+                r#type: "button",
+                onclick: move |_| {
+                    let raw = *show_raw.read();
+                    show_raw.set(!raw);
+                },
+                if *show_raw.read() { "格式化" } else { "原文" }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_tags_and_event_handlers_are_stripped() {
+        let html = render_markdown(
+            "正文<script>alert(1)</script><img src=\"a.png\" onerror=\"alert(2)\">",
+        );
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("alert(1)"));
+        assert!(!html.contains("onerror"));
+        assert!(html.contains("正文"));
+    }
+
+    #[test]
+    fn javascript_links_are_removed_but_formatting_is_kept() {
+        let html = render_markdown(
+            "**加粗**\n\n- [链接](https://example.com)\n- [坏链接](javascript:alert(1))",
+        );
+        assert!(html.contains("<strong>加粗</strong>"));
+        assert!(html.contains("<li>"));
+        assert!(html.contains("href=\"https://example.com\""));
+        assert!(!html.contains("javascript:"));
+    }
+}
@@ -1,5 +1,21 @@
 mod app_state;
+mod async_state;
+mod confirm_dialog;
+mod expiry_badge;
+mod markdown;
+mod pending_lp;
+mod toast;
+mod unread_notifications;
+mod user_suggestions;
 
 pub use app_state::{use_app_context, use_current_user, AppContext};
+pub use async_state::{use_async_state, AsyncStatus};
+pub use confirm_dialog::{use_confirm, use_confirm_provider, ConfirmDialog};
+pub use expiry_badge::ExpiryBadge;
+pub use markdown::MarkdownText;
+pub use pending_lp::use_pending_lp_count;
+pub use toast::{use_toast, use_toast_provider, ToastContainer};
+pub use unread_notifications::{use_unread_notifications, use_unread_notifications_provider};
+pub use user_suggestions::UserSuggestions;
 
 // 未来可继续扩展可复用组件
@@ -0,0 +1,50 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+
+use crate::api;
+use crate::components::use_current_user;
+
+/// 待审核LP数量的轮询间隔（毫秒）
+const POLL_INTERVAL_MS: u32 = 60_000;
+
+/// 当前用户有审核LP权限时，定时获取待审核LP数量；无权限或未登录时为 0
+pub fn use_pending_lp_count() -> Signal<i64> {
+    let current_user = use_current_user();
+    let mut count = use_signal(|| 0i64);
+
+    let can_review = move || {
+        current_user
+            .peek()
+            .as_ref()
+            .is_some_and(|user| user.permissions.iter().any(|p| p == "审核LP"))
+    };
+
+    let mut refresh = move || {
+        if !can_review() {
+            count.set(0);
+            return;
+        }
+        spawn(async move {
+            match api::get_lp_pending_count().await {
+                Ok(pending) => count.set(pending),
+                Err(e) => warn!("获取待审核LP数量失败: {}", e),
+            }
+        });
+    };
+
+    // 登录用户变化时立即刷新一次
+    use_effect(move || {
+        let _ = current_user.read();
+        refresh();
+    });
+
+    use_future(move || async move {
+        loop {
+            TimeoutFuture::new(POLL_INTERVAL_MS).await;
+            refresh();
+        }
+    });
+
+    count
+}
@@ -0,0 +1,128 @@
+use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+
+/// 成功提示默认显示时长（毫秒）
+const SUCCESS_DURATION_MS: u32 = 3000;
+/// 错误提示默认显示时长（毫秒）
+const ERROR_DURATION_MS: u32 = 4000;
+/// 普通提示默认显示时长（毫秒）
+const INFO_DURATION_MS: u32 = 3000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ToastKind {
+    Success,
+    Error,
+    Info,
+}
+
+impl ToastKind {
+    fn class(self) -> &'static str {
+        match self {
+            ToastKind::Success => "toast toast-success",
+            ToastKind::Error => "toast toast-error",
+            ToastKind::Info => "toast toast-info",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToastMessage {
+    pub id: u64,
+    pub kind: ToastKind,
+    pub text: String,
+    pub duration_ms: u32,
+}
+
+/// 全局提示队列，通过 `use_toast()` 在任意页面中使用
+#[derive(Clone, Copy)]
+pub struct Toaster {
+    toasts: Signal<Vec<ToastMessage>>,
+    next_id: Signal<u64>,
+}
+
+impl Toaster {
+    pub fn success(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Success, text, SUCCESS_DURATION_MS);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Error, text, ERROR_DURATION_MS);
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(ToastKind::Info, text, INFO_DURATION_MS);
+    }
+
+    /// 加入一条提示，`duration_ms` 为 0 时不自动消失，只能手动关闭
+    pub fn push(&mut self, kind: ToastKind, text: impl Into<String>, duration_ms: u32) {
+        let id = *self.next_id.peek();
+        self.next_id.set(id + 1);
+        self.toasts.write().push(ToastMessage {
+            id,
+            kind,
+            text: text.into(),
+            duration_ms,
+        });
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.toasts.write().retain(|toast| toast.id != id);
+    }
+}
+
+/// 在根组件中提供提示队列
+pub fn use_toast_provider() -> Toaster {
+    use_context_provider(|| Toaster {
+        toasts: Signal::new(Vec::new()),
+        next_id: Signal::new(0),
+    })
+}
+
+pub fn use_toast() -> Toaster {
+    use_context::<Toaster>()
+}
+
+/// 提示容器：堆叠显示所有提示，放在布局中即可
+#[component]
+pub fn ToastContainer() -> Element {
+    let toaster = use_toast();
+    let toasts = toaster.toasts.read().clone();
+
+    rsx! {
+        div { class: "toast-container",
+            for toast in toasts {
+                ToastItem { key: "{toast.id}", toast }
+            }
+        }
+    }
+}
+
+#[component]
+fn ToastItem(toast: ToastMessage) -> Element {
+    let mut toaster = use_toast();
+    let id = toast.id;
+    let duration_ms = toast.duration_ms;
+
+    // 计时任务挂在提示自身的作用域上，切换页面不会打断自动消失
+    use_hook(move || {
+        if duration_ms > 0 {
+            let mut toaster = toaster;
+            spawn(async move {
+                TimeoutFuture::new(duration_ms).await;
+                toaster.dismiss(id);
+            });
+        }
+    });
+
+    rsx! {
+        div { class: toast.kind.class(),
+            span { class: "toast-text", "{toast.text}" }
+            button {
+                class: "toast-close",
+                title: "关闭",
+                onclick: move |_| toaster.dismiss(id),
+                "×"
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use dioxus::logger::tracing::warn;
+use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+
+use crate::api;
+use crate::components::use_current_user;
+
+/// 未读通知数量的轮询间隔（毫秒）
+const POLL_INTERVAL_MS: u32 = 60_000;
+
+/// 导航栏铃铛显示的未读通知数量，通知页标记已读后直接更新，不必等下一次轮询
+#[derive(Clone, Copy)]
+pub struct UnreadNotifications(pub Signal<i64>);
+
+/// 在布局组件中提供未读通知数量：登录后定时获取，未登录时为 0
+pub fn use_unread_notifications_provider() -> Signal<i64> {
+    let current_user = use_current_user();
+    let mut count = use_signal(|| 0i64);
+    use_context_provider(|| UnreadNotifications(count));
+
+    let mut refresh = move || {
+        if current_user.peek().is_none() {
+            count.set(0);
+            return;
+        }
+        spawn(async move {
+            match api::get_unread_notification_count().await {
+                Ok(unread) => count.set(unread),
+                Err(e) => warn!("获取未读通知数量失败: {}", e),
+            }
+        });
+    };
+
+    // 登录用户变化时立即刷新一次
+    use_effect(move || {
+        let _ = current_user.read();
+        refresh();
+    });
+
+    use_future(move || async move {
+        loop {
+            TimeoutFuture::new(POLL_INTERVAL_MS).await;
+            refresh();
+        }
+    });
+
+    count
+}
+
+pub fn use_unread_notifications() -> Signal<i64> {
+    use_context::<UnreadNotifications>().0
+}
@@ -0,0 +1,32 @@
+use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+
+use crate::api;
+
+/// 输入停止多久后再请求补全（毫秒），避免每个按键都发请求
+const SUGGEST_DEBOUNCE_MS: u32 = 250;
+
+/// 根据输入框内容提供用户 QQ 的自动补全候选。
+/// 输入框通过 `list` 属性引用同一个 `id` 即可使用
+#[component]
+pub fn UserSuggestions(id: String, query: Signal<String>) -> Element {
+    let suggestions = use_resource(move || async move {
+        let prefix = query.read().trim().to_string();
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+        // 输入变化时资源会重新执行，未完成的旧请求随之取消
+        TimeoutFuture::new(SUGGEST_DEBOUNCE_MS).await;
+        api::search_users(&prefix).await.unwrap_or_default()
+    });
+
+    let users = suggestions.read().clone().unwrap_or_default();
+
+    rsx! {
+        datalist { id: "{id}",
+            for user in users {
+                option { key: "{user.qq}", value: "{user.qq}", "{user.nickname}" }
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use crate::db::{now_string, DbResult};
+use serde_json::{Map, Value};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Column, Row, SqliteConnection, SqlitePool, TypeInfo, ValueRef};
+
+/// 备份文件格式版本，格式不兼容时递增
+const BACKUP_FORMAT_VERSION: i64 = 1;
+
+/// 参与备份的表，按外键依赖排序，恢复时依次写入
+const BACKUP_TABLES: &[&str] = &[
+    "role",
+    "permission",
+    "rolepermissionlink",
+    "lptype",
+    "user",
+    "lplog",
+    "lp_draft",
+    "lp_summary_snapshot",
+    "shopitems",
+    "shoplog",
+    "luckydrawlog",
+    "notification",
+];
+
+/// 有数据即视为非空数据库的业务表，恢复前必须为空
+const BUSINESS_TABLES: &[&str] = &["lplog", "shopitems", "shoplog", "luckydrawlog"];
+
+pub struct BackupService;
+
+/// 恢复备份的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreOutcome {
+    /// 恢复成功，包含每张表写入的行数
+    Restored(Vec<(String, u64)>),
+    /// 数据库已有业务数据或除默认管理员外的用户，只能恢复到空数据库
+    NotEmpty,
+    /// 备份文件格式不正确
+    Invalid(String),
+}
+
+impl BackupService {
+    /// 将全部业务表导出为一个 JSON 文档（用户密码保持哈希形式）
+    pub async fn export(pool: &SqlitePool) -> DbResult<Value> {
+        let mut tables = Map::new();
+        for table in BACKUP_TABLES {
+            let rows = sqlx::query(&format!("SELECT * FROM {}", table))
+                .fetch_all(pool)
+                .await?;
+            let rows = rows.iter().map(row_to_json).collect::<DbResult<Vec<_>>>()?;
+            tables.insert(table.to_string(), Value::Array(rows));
+        }
+
+        let mut document = Map::new();
+        document.insert("version".to_string(), Value::from(BACKUP_FORMAT_VERSION));
+        document.insert("created_at".to_string(), Value::from(now_string()));
+        document.insert("tables".to_string(), Value::Object(tables));
+        Ok(Value::Object(document))
+    }
+
+    /// 在一个事务中把备份文档写入空数据库。
+    /// 初始化时写入的默认角色、权限和默认管理员会被备份中的同名记录覆盖，外键在提交时统一检查；
+    /// 已有其他用户时拒绝恢复，避免覆盖现有账号（包括其密码哈希）
+    pub async fn restore(pool: &SqlitePool, document: &Value) -> DbResult<RestoreOutcome> {
+        let tables = match validate_document(document) {
+            Ok(tables) => tables,
+            Err(message) => return Ok(RestoreOutcome::Invalid(message)),
+        };
+
+        let mut tx = pool.begin().await?;
+
+        for table in BUSINESS_TABLES {
+            let (count,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {}", table))
+                .fetch_one(&mut *tx)
+                .await?;
+            if count > 0 {
+                return Ok(RestoreOutcome::NotEmpty);
+            }
+        }
+        // 第一个用户是初始化时创建的默认管理员，允许被备份覆盖
+        let (other_users,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM user WHERE rowid != (SELECT MIN(rowid) FROM user)",
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if other_users > 0 {
+            return Ok(RestoreOutcome::NotEmpty);
+        }
+
+        // 外键推迟到提交时检查，覆盖默认角色时不会因引用关系中途失败；该设置在事务结束后自动失效
+        sqlx::query("PRAGMA defer_foreign_keys = ON")
+            .execute(&mut *tx)
+            .await?;
+        // 默认的角色权限关系以备份为准
+        sqlx::query("DELETE FROM rolepermissionlink")
+            .execute(&mut *tx)
+            .await?;
+
+        let mut restored = Vec::new();
+        for table in BACKUP_TABLES {
+            let rows = tables
+                .get(*table)
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            match restore_table(&mut tx, table, rows).await? {
+                Ok(count) => restored.push((table.to_string(), count)),
+                Err(message) => return Ok(RestoreOutcome::Invalid(message)),
+            }
+        }
+
+        tx.commit().await?;
+        Ok(RestoreOutcome::Restored(restored))
+    }
+}
+
+/// 检查备份文档的版本和结构，返回其中的表数据
+fn validate_document(document: &Value) -> Result<&Map<String, Value>, String> {
+    match document.get("version").and_then(Value::as_i64) {
+        Some(BACKUP_FORMAT_VERSION) => {}
+        Some(version) => return Err(format!("不支持的备份版本: {}", version)),
+        None => return Err("缺少备份版本号".to_string()),
+    }
+    let tables = document
+        .get("tables")
+        .and_then(Value::as_object)
+        .ok_or_else(|| "缺少 tables 字段".to_string())?;
+    for (table, rows) in tables {
+        if !BACKUP_TABLES.contains(&table.as_str()) {
+            return Err(format!("未知的表: {}", table));
+        }
+        if !rows.is_array() {
+            return Err(format!("表 {} 的数据应为数组", table));
+        }
+    }
+    Ok(tables)
+}
+
+/// 写入一张表的备份数据，列名必须是表中已有的列；格式错误时返回 `Ok(Err(..))`
+async fn restore_table(
+    conn: &mut SqliteConnection,
+    table: &str,
+    rows: &[Value],
+) -> DbResult<Result<u64, String>> {
+    let columns: Vec<String> =
+        sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .fetch_all(&mut *conn)
+            .await?;
+
+    let mut count = 0;
+    for row in rows {
+        let Some(row) = row.as_object().filter(|row| !row.is_empty()) else {
+            return Ok(Err(format!("表 {} 中存在无效的记录", table)));
+        };
+        if let Some(unknown) = row.keys().find(|key| !columns.contains(key)) {
+            return Ok(Err(format!("表 {} 不存在列 {}", table, unknown)));
+        }
+
+        let names = row.keys().cloned().collect::<Vec<_>>().join(", ");
+        let placeholders = vec!["?"; row.len()].join(", ");
+        let sql = format!(
+            "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+            table, names, placeholders
+        );
+        let mut query = sqlx::query(&sql);
+        for value in row.values() {
+            query = match value {
+                Value::Null => query.bind(None::<String>),
+                Value::Bool(flag) => query.bind(i64::from(*flag)),
+                Value::Number(number) => match number.as_i64() {
+                    Some(integer) => query.bind(integer),
+                    None => query.bind(number.as_f64()),
+                },
+                Value::String(text) => query.bind(text.clone()),
+                Value::Array(_) | Value::Object(_) => {
+                    return Ok(Err(format!("表 {} 中存在无法识别的字段值", table)));
+                }
+            };
+        }
+        query.execute(&mut *conn).await?;
+        count += 1;
+    }
+
+    Ok(Ok(count))
+}
+
+/// 按 SQLite 实际存储类型把一行转换为 JSON 对象
+fn row_to_json(row: &SqliteRow) -> DbResult<Value> {
+    let mut object = Map::new();
+    for column in row.columns() {
+        let index = column.ordinal();
+        let raw = row.try_get_raw(index)?;
+        let value = if raw.is_null() {
+            Value::Null
+        } else {
+            let type_name = raw.type_info().name().to_string();
+            match type_name.as_str() {
+                "INTEGER" => Value::from(row.try_get::<i64, _>(index)?),
+                "REAL" => Value::from(row.try_get::<f64, _>(index)?),
+                "BLOB" => Value::from(
+                    String::from_utf8_lossy(&row.try_get::<Vec<u8>, _>(index)?).into_owned(),
+                ),
+                _ => Value::from(row.try_get::<String, _>(index)?),
+            }
+        };
+        object.insert(column.name().to_string(), value);
+    }
+    Ok(Value::Object(object))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, insert_lp_log, memory_pool};
+    use crate::db::ShopService;
+
+    #[tokio::test]
+    async fn backup_round_trips_into_an_empty_database() {
+        let source = memory_pool().await;
+        add_user(&source, "10001").await;
+        insert_lp_log(&source, "10001", 1, 5, 1).await;
+        insert_lp_log(&source, "10001", 2, -2, 0).await;
+        let item_id = ShopService::add_item(&source, 3, "10", "咖啡", "10001", "A区", None, None)
+            .await
+            .unwrap();
+        ShopService::purchase_item(&source, "10001", item_id, 1)
+            .await
+            .unwrap();
+        let document = BackupService::export(&source).await.unwrap();
+
+        let target = memory_pool().await;
+        let outcome = BackupService::restore(&target, &document).await.unwrap();
+        let RestoreOutcome::Restored(counts) = outcome else {
+            panic!("恢复应当成功: {:?}", outcome);
+        };
+        assert!(counts.contains(&("lplog".to_string(), 2)));
+        assert!(counts.contains(&("shoplog".to_string(), 1)));
+
+        let restored = BackupService::export(&target).await.unwrap();
+        assert_eq!(restored["tables"], document["tables"]);
+
+        // 已有业务数据的数据库不允许恢复
+        assert_eq!(
+            BackupService::restore(&target, &document).await.unwrap(),
+            RestoreOutcome::NotEmpty
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_backup_version_is_invalid() {
+        let pool = memory_pool().await;
+        let document = serde_json::json!({ "version": 99, "tables": {} });
+
+        let outcome = BackupService::restore(&pool, &document).await.unwrap();
+
+        assert_eq!(
+            outcome,
+            RestoreOutcome::Invalid("不支持的备份版本: 99".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn registered_users_block_restore() {
+        let source = memory_pool().await;
+        add_user(&source, "10001").await;
+        let document = BackupService::export(&source).await.unwrap();
+
+        // 只有用户、没有业务数据的数据库同样不允许恢复，现有账号保持不变
+        let target = memory_pool().await;
+        add_user(&target, "10001").await;
+        sqlx::query("UPDATE user SET nickname = '原账号' WHERE qq = '10001'")
+            .execute(&target)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            BackupService::restore(&target, &document).await.unwrap(),
+            RestoreOutcome::NotEmpty
+        );
+        let (nickname,): (String,) = sqlx::query_as("SELECT nickname FROM user WHERE qq = '10001'")
+            .fetch_one(&target)
+            .await
+            .unwrap();
+        assert_eq!(nickname, "原账号");
+    }
+}
@@ -0,0 +1,42 @@
+use chrono::{FixedOffset, Local, NaiveDateTime, Offset, Utc};
+use std::sync::OnceLock;
+
+/// 数据库中时间戳的存储格式，同一时区下按字符串比较即为按时间比较
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+static SERVER_OFFSET: OnceLock<FixedOffset> = OnceLock::new();
+
+/// 设置服务器时区（启动时调用一次）。写入的时间戳、下架/开奖时间的比较都按该时区计算，
+/// 不再依赖操作系统或 SQLite `localtime` 的时区设置
+pub fn set_server_offset(offset: FixedOffset) {
+    if SERVER_OFFSET.set(offset).is_err() {
+        tracing::warn!("服务器时区已设置，忽略重复设置");
+    }
+}
+
+/// 当前使用的时区偏移，未设置 `SERVER_TZ` 时使用系统本地时区
+pub fn server_offset() -> FixedOffset {
+    SERVER_OFFSET
+        .get()
+        .copied()
+        .unwrap_or_else(|| Local::now().offset().fix())
+}
+
+/// 服务器时区下的当前时间
+pub fn server_now() -> NaiveDateTime {
+    Utc::now().with_timezone(&server_offset()).naive_local()
+}
+
+/// 服务器时区下的当前时间，格式为 [`TIMESTAMP_FORMAT`]，所有写入数据库的时间戳都应使用它
+pub fn now_string() -> String {
+    server_now().format(TIMESTAMP_FORMAT).to_string()
+}
+
+/// 解析时区配置：`UTC`/`Z`，或 `+08:00`、`-05:30` 形式的固定偏移
+pub fn parse_utc_offset(raw: &str) -> Option<FixedOffset> {
+    let raw = raw.trim();
+    if raw.eq_ignore_ascii_case("utc") || raw.eq_ignore_ascii_case("z") {
+        return FixedOffset::east_opt(0);
+    }
+    raw.parse::<FixedOffset>().ok()
+}
@@ -0,0 +1,158 @@
+use crate::db::DbResult;
+use sqlx::SqlitePool;
+
+pub struct IdempotencyService;
+
+/// 已登记的幂等键记录：`status` 为空表示请求仍在处理中
+#[derive(Debug, Clone)]
+pub struct IdempotencyRecord {
+    pub status: Option<i32>,
+    pub response: Option<String>,
+}
+
+impl IdempotencyService {
+    // 清理早于 cutoff 的幂等键
+    pub async fn purge_expired(pool: &SqlitePool, cutoff: &str) -> DbResult<u64> {
+        let result = sqlx::query("DELETE FROM idempotencykey WHERE created_at < ?")
+            .bind(cutoff)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    // 占用幂等键，返回 false 表示该键已被占用
+    pub async fn reserve(
+        pool: &SqlitePool,
+        user_qq: &str,
+        scope: &str,
+        key: &str,
+        created_at: &str,
+    ) -> DbResult<bool> {
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO idempotencykey (user_qq, scope, idem_key, created_at)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(user_qq)
+        .bind(scope)
+        .bind(key)
+        .bind(created_at)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    // 查询幂等键对应的处理结果
+    pub async fn get_record(
+        pool: &SqlitePool,
+        user_qq: &str,
+        scope: &str,
+        key: &str,
+    ) -> DbResult<Option<IdempotencyRecord>> {
+        let row = sqlx::query_as::<_, (Option<i32>, Option<String>)>(
+            "SELECT status, response FROM idempotencykey
+             WHERE user_qq = ? AND scope = ? AND idem_key = ?",
+        )
+        .bind(user_qq)
+        .bind(scope)
+        .bind(key)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.map(|(status, response)| IdempotencyRecord { status, response }))
+    }
+
+    // 保存处理结果，之后相同的键直接返回该结果
+    pub async fn store_response(
+        pool: &SqlitePool,
+        user_qq: &str,
+        scope: &str,
+        key: &str,
+        status: i32,
+        response: &str,
+    ) -> DbResult<()> {
+        sqlx::query(
+            "UPDATE idempotencykey SET status = ?, response = ?
+             WHERE user_qq = ? AND scope = ? AND idem_key = ?",
+        )
+        .bind(status)
+        .bind(response)
+        .bind(user_qq)
+        .bind(scope)
+        .bind(key)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    // 释放幂等键（处理失败时调用，允许客户端用同一个键重试）
+    pub async fn release(pool: &SqlitePool, user_qq: &str, scope: &str, key: &str) -> DbResult<()> {
+        sqlx::query("DELETE FROM idempotencykey WHERE user_qq = ? AND scope = ? AND idem_key = ?")
+            .bind(user_qq)
+            .bind(scope)
+            .bind(key)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, memory_pool};
+
+    #[tokio::test]
+    async fn reserved_key_is_replayed_until_released() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        add_user(&pool, "10002").await;
+        let now = "2026-01-01 00:00:00";
+
+        assert!(
+            IdempotencyService::reserve(&pool, "10001", "/shop/purchase", "k", now)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !IdempotencyService::reserve(&pool, "10001", "/shop/purchase", "k", now)
+                .await
+                .unwrap()
+        );
+        // 同一个键在其他接口或其他用户下互不影响
+        assert!(
+            IdempotencyService::reserve(&pool, "10001", "/lp/submit", "k", now)
+                .await
+                .unwrap()
+        );
+        assert!(
+            IdempotencyService::reserve(&pool, "10002", "/shop/purchase", "k", now)
+                .await
+                .unwrap()
+        );
+
+        let pending = IdempotencyService::get_record(&pool, "10001", "/shop/purchase", "k")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pending.status, None);
+
+        IdempotencyService::store_response(&pool, "10001", "/shop/purchase", "k", 200, "{}")
+            .await
+            .unwrap();
+        let stored = IdempotencyService::get_record(&pool, "10001", "/shop/purchase", "k")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, Some(200));
+        assert_eq!(stored.response.as_deref(), Some("{}"));
+
+        IdempotencyService::release(&pool, "10001", "/shop/purchase", "k")
+            .await
+            .unwrap();
+        assert!(
+            IdempotencyService::reserve(&pool, "10001", "/shop/purchase", "k", now)
+                .await
+                .unwrap()
+        );
+    }
+}
@@ -1,5 +1,7 @@
-use crate::db::DbResult;
-use crate::models::RequestLog;
+use crate::db::{now_string, DbResult};
+use crate::models::{
+    PermissionDenialSummary, RequestLog, RequestLogCount, RequestLogStats, TimelineEvent,
+};
 use sqlx::SqlitePool;
 
 pub async fn list_request_logs(
@@ -8,7 +10,7 @@ pub async fn list_request_logs(
     offset: i64,
 ) -> DbResult<Vec<RequestLog>> {
     let logs = sqlx::query_as::<_, RequestLog>(
-        "SELECT id, method, path, user_qq, body, status, timestamp
+        "SELECT id, method, path, user_qq, body, status, timestamp, elapsed_ms, trace_id
          FROM requestlog
          ORDER BY timestamp DESC
          LIMIT ? OFFSET ?",
@@ -21,6 +23,96 @@ pub async fn list_request_logs(
     Ok(logs)
 }
 
+/// 判断指定追踪 ID 的请求是否已经写入过某个状态码的日志
+pub async fn request_log_exists(pool: &SqlitePool, trace_id: &str, status: i32) -> DbResult<bool> {
+    let exists: Option<i64> =
+        sqlx::query_scalar("SELECT 1 FROM requestlog WHERE trace_id = ? AND status = ? LIMIT 1")
+            .bind(trace_id)
+            .bind(status)
+            .fetch_optional(pool)
+            .await?;
+    Ok(exists.is_some())
+}
+
+/// 按用户汇总最近 `days` 天内被拒绝访问（403）的请求，拒绝次数多的排在前面
+pub async fn list_permission_denials(
+    pool: &SqlitePool,
+    days: i64,
+    limit: i64,
+) -> DbResult<Vec<PermissionDenialSummary>> {
+    let denials = sqlx::query_as::<_, PermissionDenialSummary>(
+        "SELECT user_qq, COUNT(*) AS denials, COUNT(DISTINCT path) AS paths,
+                MAX(timestamp) AS last_denied
+         FROM requestlog
+         WHERE status = 403 AND user_qq IS NOT NULL
+           AND timestamp >= datetime(?, '-' || ? || ' days')
+         GROUP BY user_qq
+         ORDER BY denials DESC, last_denied DESC
+         LIMIT ?",
+    )
+    .bind(now_string())
+    .bind(days)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(denials)
+}
+
+/// 统计 `[start, end)` 时间段内的请求，分别按状态码类别（2xx/3xx/4xx/5xx）和请求方法汇总
+pub async fn request_log_stats(
+    pool: &SqlitePool,
+    start: &str,
+    end: &str,
+) -> DbResult<RequestLogStats> {
+    let status_counts = sqlx::query_as::<_, RequestLogCount>(
+        "SELECT (status / 100) || 'xx' AS key, COUNT(*) AS count
+         FROM requestlog
+         WHERE timestamp >= ? AND timestamp < ?
+         GROUP BY status / 100",
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?;
+
+    let by_method = sqlx::query_as::<_, RequestLogCount>(
+        "SELECT method AS key, COUNT(*) AS count
+         FROM requestlog
+         WHERE timestamp >= ? AND timestamp < ?
+         GROUP BY method
+         ORDER BY count DESC, method",
+    )
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?;
+
+    let total = status_counts.iter().map(|bucket| bucket.count).sum();
+    // 常见类别没有请求时补 0，其他类别（如后台任务记录的 0 状态）排在后面
+    let mut by_status = ["2xx", "3xx", "4xx", "5xx"]
+        .iter()
+        .map(|key| RequestLogCount {
+            key: key.to_string(),
+            count: 0,
+        })
+        .collect::<Vec<_>>();
+    for bucket in status_counts {
+        match by_status.iter_mut().find(|known| known.key == bucket.key) {
+            Some(known) => known.count = bucket.count,
+            None => by_status.push(bucket),
+        }
+    }
+
+    Ok(RequestLogStats {
+        start: start.to_string(),
+        end: end.to_string(),
+        total,
+        by_status,
+        by_method,
+    })
+}
+
 pub async fn count_request_logs(pool: &SqlitePool) -> DbResult<i64> {
     let (count,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM requestlog")
         .fetch_one(pool)
@@ -43,7 +135,7 @@ pub async fn list_request_logs_by_user(
     offset: i64,
 ) -> DbResult<Vec<RequestLog>> {
     let logs = sqlx::query_as::<_, RequestLog>(
-        "SELECT id, method, path, user_qq, body, status, timestamp
+        "SELECT id, method, path, user_qq, body, status, timestamp, elapsed_ms, trace_id
          FROM requestlog
          WHERE user_qq = ?
          ORDER BY timestamp DESC
@@ -57,3 +149,307 @@ pub async fn list_request_logs_by_user(
 
     Ok(logs)
 }
+
+/// 增量查询：返回 ID 大于 `since_id` 的日志（最新的在前），可按用户筛选
+pub async fn list_request_logs_since(
+    pool: &SqlitePool,
+    since_id: i64,
+    user_qq: Option<&str>,
+    limit: i64,
+) -> DbResult<Vec<RequestLog>> {
+    let logs = sqlx::query_as::<_, RequestLog>(
+        "SELECT id, method, path, user_qq, body, status, timestamp, elapsed_ms, trace_id
+         FROM requestlog
+         WHERE id > ? AND (? IS NULL OR user_qq = ?)
+         ORDER BY id DESC
+         LIMIT ?",
+    )
+    .bind(since_id)
+    .bind(user_qq)
+    .bind(user_qq)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(logs)
+}
+
+/// 按用户和/或路径删除请求日志，返回删除的条数。两个条件都为空时不删除任何记录
+pub async fn delete_request_logs(
+    pool: &SqlitePool,
+    user_qq: Option<&str>,
+    path: Option<&str>,
+) -> DbResult<u64> {
+    if user_qq.is_none() && path.is_none() {
+        return Ok(0);
+    }
+
+    let result = sqlx::query(
+        "DELETE FROM requestlog
+         WHERE (? IS NULL OR user_qq = ?)
+           AND (? IS NULL OR path = ?)",
+    )
+    .bind(user_qq)
+    .bind(user_qq)
+    .bind(path)
+    .bind(path)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// 用户时间线的数据来源：作为LP对象的申请、购买、销售和该用户发出的请求。
+/// 各表时间都是 "YYYY-MM-DD HH:MM:SS" 格式，可以直接按字符串排序
+const USER_TIMELINE_SOURCES: &str = "
+    SELECT upload_time AS timestamp, 'lp' AS kind,
+           'LP ' || CASE WHEN num > 0 THEN '+' ELSE '' END || num || '（'
+               || CASE status WHEN 0 THEN '待审核' WHEN 1 THEN '已通过' ELSE '已拒绝' END
+               || '）：' || reason AS detail
+    FROM lplog WHERE user_qq = ?
+    UNION ALL
+    SELECT time, 'purchase', '购买 ' || name || ' x' || count || '，卖家 ' || seller
+    FROM shoplog WHERE buyer = ?
+    UNION ALL
+    SELECT time, 'sale', '出售 ' || name || ' x' || count || '，买家 ' || buyer
+    FROM shoplog WHERE seller = ?
+    UNION ALL
+    SELECT timestamp, 'request', method || ' ' || path || ' → ' || status
+    FROM requestlog WHERE user_qq = ?";
+
+/// 按时间倒序分页查询用户时间线
+pub async fn list_user_timeline(
+    pool: &SqlitePool,
+    user_qq: &str,
+    limit: i64,
+    offset: i64,
+) -> DbResult<Vec<TimelineEvent>> {
+    let sql = format!(
+        "SELECT timestamp, kind, detail FROM ({})
+         ORDER BY timestamp DESC
+         LIMIT ? OFFSET ?",
+        USER_TIMELINE_SOURCES
+    );
+    let events = sqlx::query_as::<_, TimelineEvent>(&sql)
+        .bind(user_qq)
+        .bind(user_qq)
+        .bind(user_qq)
+        .bind(user_qq)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(events)
+}
+
+pub async fn count_user_timeline(pool: &SqlitePool, user_qq: &str) -> DbResult<i64> {
+    let sql = format!("SELECT COUNT(*) FROM ({})", USER_TIMELINE_SOURCES);
+    let (count,) = sqlx::query_as::<_, (i64,)>(&sql)
+        .bind(user_qq)
+        .bind(user_qq)
+        .bind(user_qq)
+        .bind(user_qq)
+        .fetch_one(pool)
+        .await?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, insert_lp_log, memory_pool};
+    use crate::db::{record_request_log, with_request_context, RequestContext};
+    use std::time::{Duration, Instant};
+
+    async fn remaining(pool: &SqlitePool) -> Vec<(Option<String>, String)> {
+        sqlx::query_as("SELECT user_qq, path FROM requestlog ORDER BY id")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn delete_removes_only_matching_logs() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        add_user(&pool, "10002").await;
+        for (user, path) in [
+            ("10001", "/shop/items"),
+            ("10001", "/lp/submit"),
+            ("10002", "/shop/items"),
+            ("10002", "/lp/submit"),
+        ] {
+            record_request_log(&pool, "GET", path, Some(user), None, 200)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(delete_request_logs(&pool, None, None).await.unwrap(), 0);
+        assert_eq!(
+            delete_request_logs(&pool, Some("10001"), None)
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            delete_request_logs(&pool, Some("10002"), Some("/lp/submit"))
+                .await
+                .unwrap(),
+            1
+        );
+
+        assert_eq!(
+            remaining(&pool).await,
+            vec![(Some("10002".to_string()), "/shop/items".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn since_id_returns_only_newer_rows_newest_first() {
+        let pool = memory_pool().await;
+        for path in ["/a", "/b", "/c", "/d"] {
+            record_request_log(&pool, "GET", path, None, None, 200)
+                .await
+                .unwrap();
+        }
+        let second: i64 = sqlx::query_scalar("SELECT id FROM requestlog WHERE path = '/b'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        let logs = list_request_logs_since(&pool, second, None, 50)
+            .await
+            .unwrap();
+
+        let paths: Vec<&str> = logs.iter().map(|log| log.path.as_str()).collect();
+        assert_eq!(paths, vec!["/d", "/c"]);
+    }
+
+    #[tokio::test]
+    async fn timeline_interleaves_all_sources_by_time() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        insert_lp_log(&pool, "10001", 1, 5, 1).await;
+        sqlx::query("UPDATE lplog SET upload_time = '2026-01-02 09:00:00'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        for (buyer, seller, time) in [
+            ("10001", "9999", "2026-01-03 09:00:00"),
+            ("9999", "10001", "2026-01-01 09:00:00"),
+        ] {
+            sqlx::query(
+                "INSERT INTO shoplog (buyer, count, price, name, time, seller, location)
+                 VALUES (?, 1, '10', '咖啡', ?, ?, 'A区')",
+            )
+            .bind(buyer)
+            .bind(time)
+            .bind(seller)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        record_request_log(&pool, "GET", "/shop/items", Some("10001"), None, 200)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE requestlog SET timestamp = '2026-01-02 18:00:00'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let events = list_user_timeline(&pool, "10001", 10, 0).await.unwrap();
+
+        let kinds: Vec<&str> = events.iter().map(|event| event.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["purchase", "request", "lp", "sale"]);
+        assert_eq!(events[2].detail, "LP +5（已通过）：测试");
+        assert_eq!(count_user_timeline(&pool, "10001").await.unwrap(), 4);
+        let second_page = list_user_timeline(&pool, "10001", 2, 2).await.unwrap();
+        assert_eq!(second_page[0].kind, "lp");
+    }
+
+    #[tokio::test]
+    async fn request_log_records_elapsed_time_inside_request_context() {
+        let pool = memory_pool().await;
+        let context = RequestContext {
+            start: Instant::now() - Duration::from_millis(25),
+            trace_id: "trace-1".to_string(),
+        };
+
+        with_request_context(context, async {
+            record_request_log(&pool, "GET", "/lp/logs", None, None, 200)
+                .await
+                .unwrap();
+        })
+        .await;
+        // 定时任务等不在请求中的调用没有耗时
+        record_request_log(&pool, "POST", "/scheduler", None, None, 200)
+            .await
+            .unwrap();
+
+        let rows = sqlx::query_as::<_, (String, Option<i64>, Option<String>)>(
+            "SELECT path, elapsed_ms, trace_id FROM requestlog ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(rows[0].0, "/lp/logs");
+        assert!(rows[0].1.unwrap() >= 25);
+        assert_eq!(rows[0].2.as_deref(), Some("trace-1"));
+        assert_eq!(rows[1], ("/scheduler".to_string(), None, None));
+    }
+
+    #[tokio::test]
+    async fn stats_bucket_requests_by_status_class_and_method() {
+        let pool = memory_pool().await;
+        for (method, status, timestamp) in [
+            ("GET", 200, "2024-05-01 09:00:00"),
+            ("GET", 201, "2024-05-01 10:00:00"),
+            ("POST", 403, "2024-05-01 11:00:00"),
+            ("GET", 404, "2024-05-01 12:00:00"),
+            ("DELETE", 500, "2024-05-01 13:00:00"),
+            // 结束时间不含在内，窗口之外的请求不计入
+            ("POST", 200, "2024-05-02 00:00:00"),
+            ("POST", 500, "2024-04-30 23:59:59"),
+        ] {
+            sqlx::query(
+                "INSERT INTO requestlog (method, path, body, status, timestamp) VALUES (?, '/test', '', ?, ?)",
+            )
+            .bind(method)
+            .bind(status)
+            .bind(timestamp)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let stats = request_log_stats(&pool, "2024-05-01 00:00:00", "2024-05-02 00:00:00")
+            .await
+            .unwrap();
+
+        let counts = |buckets: &[RequestLogCount]| {
+            buckets
+                .iter()
+                .map(|bucket| (bucket.key.clone(), bucket.count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(stats.total, 5);
+        assert_eq!(
+            counts(&stats.by_status),
+            [
+                ("2xx".to_string(), 2),
+                ("3xx".to_string(), 0),
+                ("4xx".to_string(), 2),
+                ("5xx".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            counts(&stats.by_method),
+            [
+                ("GET".to_string(), 3),
+                ("DELETE".to_string(), 1),
+                ("POST".to_string(), 1),
+            ]
+        );
+    }
+}
@@ -1,13 +1,28 @@
-use crate::db::DbResult;
+use crate::db::{now_string, server_now, DbResult};
 use crate::models::*;
-use chrono::Local;
+use chrono::Datelike;
 use log::{info, warn};
 use sqlx::SqlitePool;
 
 pub struct LpService;
 
+/// LP转移使用的LP类型名称
+const LP_TRANSFER_TYPE: &str = "调整";
+
+/// LP转移的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LpTransferOutcome {
+    /// 转移成功，包含转出和转入两条记录的ID
+    Transferred { debit_id: i64, credit_id: i64 },
+    /// 转出用户的LP余额不足
+    InsufficientLp { available: i64 },
+    /// 转出或转入用户不存在
+    UserNotFound(String),
+}
+
 impl LpService {
     // 提交LP申请
+    #[allow(clippy::too_many_arguments)]
     pub async fn submit_lp_request(
         pool: &SqlitePool,
         upload_user_qq: &str,
@@ -18,7 +33,7 @@ impl LpService {
         picture: Option<String>,
         role: Option<String>,
     ) -> DbResult<i64> {
-        let upload_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let upload_time = now_string();
 
         let result = sqlx::query(
             "INSERT INTO lplog (upload_time, upload_user_qq, user_qq, lp_type, num, reason, status, picture, role)
@@ -43,20 +58,150 @@ impl LpService {
         Ok(result.last_insert_rowid())
     }
 
+    // 以已有LP申请为模板为其他用户创建新的待审批申请，复制类型、数量、理由和角色；
+    // 原申请不存在时返回 None
+    pub async fn clone_lp_request(
+        pool: &SqlitePool,
+        source_id: i64,
+        upload_user_qq: &str,
+        user_qq: &str,
+    ) -> DbResult<Option<i64>> {
+        let upload_time = now_string();
+
+        let result = sqlx::query(
+            "INSERT INTO lplog (upload_time, upload_user_qq, user_qq, lp_type, num, reason, status, role)
+             SELECT ?, ?, ?, lp_type, num, reason, 0, role FROM lplog WHERE id = ?",
+        )
+        .bind(upload_time)
+        .bind(upload_user_qq)
+        .bind(user_qq)
+        .bind(source_id)
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let id = result.last_insert_rowid();
+        info!(
+            "LP申请克隆: 原申请={}, 新申请={}, 上传者={}, 关联用户={}, 状态=待审批",
+            source_id, id, upload_user_qq, user_qq
+        );
+
+        Ok(Some(id))
+    }
+
+    // 在两个用户之间转移LP：同一事务内为转出方写入一条 -num、为转入方写入一条 +num 的
+    // 已通过记录，两条记录的理由互相引用对方的ID；转出后余额不能为负
+    pub async fn transfer_lp(
+        pool: &SqlitePool,
+        operator_qq: &str,
+        from_qq: &str,
+        to_qq: &str,
+        num: i32,
+        reason: &str,
+    ) -> DbResult<LpTransferOutcome> {
+        let now = now_string();
+        let mut tx = pool.begin().await?;
+
+        let available: Option<i64> =
+            sqlx::query_scalar("SELECT total_lp FROM user_lp_summary WHERE qq = ?")
+                .bind(from_qq)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some(available) = available else {
+            return Ok(LpTransferOutcome::UserNotFound(from_qq.to_string()));
+        };
+        let target_exists: Option<i64> = sqlx::query_scalar("SELECT 1 FROM user WHERE qq = ?")
+            .bind(to_qq)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if target_exists.is_none() {
+            return Ok(LpTransferOutcome::UserNotFound(to_qq.to_string()));
+        }
+        if available < i64::from(num) {
+            return Ok(LpTransferOutcome::InsufficientLp { available });
+        }
+
+        sqlx::query("INSERT OR IGNORE INTO lptype (name) VALUES (?)")
+            .bind(LP_TRANSFER_TYPE)
+            .execute(&mut *tx)
+            .await?;
+        let lp_type: i64 = sqlx::query_scalar("SELECT id FROM lptype WHERE name = ?")
+            .bind(LP_TRANSFER_TYPE)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let insert = "INSERT INTO lplog (upload_time, upload_user_qq, user_qq, process_user_qq,
+                                         lp_type, num, reason, status, process_time)
+                      VALUES (?, ?, ?, ?, ?, ?, ?, 1, ?)";
+        let debit_id = sqlx::query(insert)
+            .bind(&now)
+            .bind(operator_qq)
+            .bind(from_qq)
+            .bind(operator_qq)
+            .bind(lp_type)
+            .bind(-num)
+            .bind(format!("LP转移至 {}：{}", to_qq, reason))
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+        let credit_id = sqlx::query(insert)
+            .bind(&now)
+            .bind(operator_qq)
+            .bind(to_qq)
+            .bind(operator_qq)
+            .bind(lp_type)
+            .bind(num)
+            .bind(format!(
+                "LP转移自 {}（转出记录 #{}）：{}",
+                from_qq, debit_id, reason
+            ))
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+        sqlx::query("UPDATE lplog SET reason = ? WHERE id = ?")
+            .bind(format!(
+                "LP转移至 {}（转入记录 #{}）：{}",
+                to_qq, credit_id, reason
+            ))
+            .bind(debit_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        info!(
+            "LP转移: 操作人={}, 转出={}, 转入={}, 数量={}, 记录={}/{}",
+            operator_qq, from_qq, to_qq, num, debit_id, credit_id
+        );
+
+        Ok(LpTransferOutcome::Transferred {
+            debit_id,
+            credit_id,
+        })
+    }
+
     // 审批LP申请
     pub async fn process_lp_request(
         pool: &SqlitePool,
         id: i64,
         process_user_qq: &str,
         status: i32,
+        reject_reason: Option<&str>,
     ) -> DbResult<()> {
-        let process_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let process_time = now_string();
         let rows = sqlx::query(
-            "UPDATE lplog SET status = ?, process_user_qq = ?, process_time = ? WHERE id = ?",
+            "UPDATE lplog SET status = ?, process_user_qq = ?, process_time = ?, reject_reason = ?
+             WHERE id = ?",
         )
         .bind(status)
         .bind(process_user_qq)
         .bind(process_time)
+        .bind(reject_reason.filter(|_| status == 2))
         .bind(id)
         .execute(pool)
         .await?
@@ -74,11 +219,59 @@ impl LpService {
         Ok(())
     }
 
+    // 获取单条LP申请
+    pub async fn get_lp_log(pool: &SqlitePool, id: i64) -> DbResult<Option<LpLog>> {
+        let log = sqlx::query_as::<_, LpLog>(
+            "SELECT id, upload_time, upload_user_qq, user_qq, process_user_qq, role,
+                    lp_type, num, reason, status, picture, process_time, reject_reason
+             FROM lplog WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(log)
+    }
+
+    // 分页获取某位审批人处理过的LP申请，按处理时间倒序
+    pub async fn get_processed_by(
+        pool: &SqlitePool,
+        process_user_qq: &str,
+        limit: i64,
+        offset: i64,
+    ) -> DbResult<Vec<LpLog>> {
+        let logs = sqlx::query_as::<_, LpLog>(
+            "SELECT id, upload_time, upload_user_qq, user_qq, process_user_qq, role,
+                    lp_type, num, reason, status, picture, process_time, reject_reason
+             FROM lplog WHERE process_user_qq = ?
+             ORDER BY process_time DESC, id DESC
+             LIMIT ? OFFSET ?",
+        )
+        .bind(process_user_qq)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(logs)
+    }
+
+    // 统计某位审批人处理过的LP申请数
+    pub async fn count_processed_by(pool: &SqlitePool, process_user_qq: &str) -> DbResult<i64> {
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM lplog WHERE process_user_qq = ?")
+                .bind(process_user_qq)
+                .fetch_one(pool)
+                .await?;
+
+        Ok(count)
+    }
+
     // 获取所有LP申请
     pub async fn get_all_lp_logs(pool: &SqlitePool) -> DbResult<Vec<LpLog>> {
         let logs = sqlx::query_as::<_, LpLog>(
             "SELECT id, upload_time, upload_user_qq, user_qq, process_user_qq, role,
-                    lp_type, num, reason, status, picture, process_time
+                    lp_type, num, reason, status, picture, process_time, reject_reason
              FROM lplog ORDER BY upload_time DESC",
         )
         .fetch_all(pool)
@@ -87,12 +280,12 @@ impl LpService {
         Ok(logs)
     }
 
-    // 获取待处理的LP申请
+    // 获取待处理的LP申请，最新提交的在前（走 idx_lplog_status_time 索引）
     pub async fn get_pending_lp_logs(pool: &SqlitePool) -> DbResult<Vec<LpLog>> {
         let logs = sqlx::query_as::<_, LpLog>(
             "SELECT id, upload_time, upload_user_qq, user_qq, process_user_qq, role,
-                    lp_type, num, reason, status, picture, process_time
-             FROM lplog WHERE status = 0 ORDER BY upload_time ASC",
+                    lp_type, num, reason, status, picture, process_time, reject_reason
+             FROM lplog WHERE status = 0 ORDER BY upload_time DESC",
         )
         .fetch_all(pool)
         .await?;
@@ -100,11 +293,19 @@ impl LpService {
         Ok(logs)
     }
 
+    // 统计待处理的LP申请数量（走 idx_lplog_status 索引）
+    pub async fn count_pending(pool: &SqlitePool) -> DbResult<i64> {
+        let (count,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM lplog WHERE status = 0")
+            .fetch_one(pool)
+            .await?;
+        Ok(count)
+    }
+
     // 获取用户的LP历史
     pub async fn get_user_lp_history(pool: &SqlitePool, user_qq: &str) -> DbResult<Vec<LpLog>> {
         let logs = sqlx::query_as::<_, LpLog>(
             "SELECT id, upload_time, upload_user_qq, user_qq, process_user_qq, role,
-                    lp_type, num, reason, status, picture, process_time
+                    lp_type, num, reason, status, picture, process_time, reject_reason
              FROM lplog WHERE user_qq = ? ORDER BY upload_time DESC",
         )
         .bind(user_qq)
@@ -114,15 +315,24 @@ impl LpService {
         Ok(logs)
     }
 
-    // 获取用户LP总数（使用视图）
+    // 获取用户LP总数（使用视图），并附带本月通过审核的LP变化量
     pub async fn get_user_lp_summary(
         pool: &SqlitePool,
         user_qq: &str,
     ) -> DbResult<Option<UserLpSummary>> {
+        let (month_start, next_month_start) = current_month_range();
         let summary = sqlx::query_as::<_, UserLpSummary>(
-            "SELECT qq, nickname, total_lp, pending_count, approved_count, rejected_count
-             FROM user_lp_summary WHERE qq = ?",
+            "SELECT s.qq, s.nickname, s.total_lp, s.pending_count, s.approved_count,
+                    s.rejected_count,
+                    COALESCE((
+                        SELECT SUM(l.num) FROM lplog l
+                        WHERE l.user_qq = s.qq AND l.status = 1
+                          AND l.process_time >= ? AND l.process_time < ?
+                    ), 0) AS monthly_delta
+             FROM user_lp_summary s WHERE s.qq = ?",
         )
+        .bind(month_start)
+        .bind(next_month_start)
         .bind(user_qq)
         .fetch_optional(pool)
         .await?;
@@ -142,6 +352,92 @@ impl LpService {
         Ok(summaries)
     }
 
+    // 获取LP总数前 limit 名的昵称和LP，用于公开排行榜
+    pub async fn get_leaderboard(pool: &SqlitePool, limit: i64) -> DbResult<Vec<LeaderboardEntry>> {
+        let entries = sqlx::query_as::<_, LeaderboardEntry>(
+            "SELECT nickname, total_lp FROM user_lp_summary
+             ORDER BY total_lp DESC, nickname LIMIT ?",
+        )
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    // 把当前LP汇总保存为指定月份的快照，已存在的月份不会被覆盖，返回新写入的行数
+    pub async fn snapshot_summaries(pool: &SqlitePool, period: &str) -> DbResult<u64> {
+        let created_at = now_string();
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO lp_summary_snapshot
+                 (period, qq, nickname, total_lp, approved_count, created_at)
+             SELECT ?, qq, nickname, total_lp, approved_count, ? FROM user_lp_summary",
+        )
+        .bind(period)
+        .bind(&created_at)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // 指定月份是否已有快照
+    pub async fn snapshot_exists(pool: &SqlitePool, period: &str) -> DbResult<bool> {
+        let exists = sqlx::query_scalar::<_, i64>(
+            "SELECT EXISTS(SELECT 1 FROM lp_summary_snapshot WHERE period = ?)",
+        )
+        .bind(period)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(exists != 0)
+    }
+
+    // 已有快照的月份，最近的在前
+    pub async fn list_snapshot_periods(pool: &SqlitePool) -> DbResult<Vec<String>> {
+        let periods = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT period FROM lp_summary_snapshot ORDER BY period DESC",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(periods)
+    }
+
+    // 获取指定月份的快照，按LP总数降序
+    pub async fn get_snapshots(
+        pool: &SqlitePool,
+        period: &str,
+    ) -> DbResult<Vec<LpSummarySnapshot>> {
+        let snapshots = sqlx::query_as::<_, LpSummarySnapshot>(
+            "SELECT period, qq, nickname, total_lp, approved_count, created_at
+             FROM lp_summary_snapshot WHERE period = ?
+             ORDER BY total_lp DESC, qq",
+        )
+        .bind(period)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(snapshots)
+    }
+
+    // 按LP类型统计申请数量及已通过的LP总和（没有申请的类型也会列出）
+    pub async fn get_stats_by_type(pool: &SqlitePool) -> DbResult<Vec<LpTypeStat>> {
+        let stats = sqlx::query_as::<_, LpTypeStat>(
+            "SELECT t.id AS lp_type_id, t.name,
+                    COUNT(l.id) AS log_count,
+                    COALESCE(SUM(CASE WHEN l.status = 1 THEN l.num ELSE 0 END), 0) AS approved_num
+             FROM lptype t
+             LEFT JOIN lplog l ON l.lp_type = t.id
+             GROUP BY t.id, t.name
+             ORDER BY t.id",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(stats)
+    }
+
     // 获取所有LP类型
     pub async fn get_all_lp_types(pool: &SqlitePool) -> DbResult<Vec<LpType>> {
         let types = sqlx::query_as::<_, LpType>("SELECT id, name FROM lptype")
@@ -150,4 +446,425 @@ impl LpService {
 
         Ok(types)
     }
+
+    // 获取LP类型名称
+    pub async fn get_lp_type_name(pool: &SqlitePool, id: i64) -> DbResult<Option<String>> {
+        let name: Option<String> = sqlx::query_scalar("SELECT name FROM lptype WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(name)
+    }
+
+    // 检查LP类型是否存在
+    pub async fn lp_type_exists(pool: &SqlitePool, id: i64) -> DbResult<bool> {
+        let found: Option<i64> = sqlx::query_scalar("SELECT id FROM lptype WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(found.is_some())
+    }
+
+    // 获取用户自己的LP草稿，最近修改的在前
+    pub async fn list_drafts(pool: &SqlitePool, owner_qq: &str) -> DbResult<Vec<LpDraft>> {
+        let drafts = sqlx::query_as::<_, LpDraft>(
+            "SELECT id, owner_qq, user_qq, lp_type, num, reason, picture, role, updated_at
+             FROM lp_draft WHERE owner_qq = ? ORDER BY updated_at DESC, id DESC",
+        )
+        .bind(owner_qq)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(drafts)
+    }
+
+    // 获取单个草稿，不属于该用户时视为不存在
+    pub async fn get_draft(
+        pool: &SqlitePool,
+        owner_qq: &str,
+        id: i64,
+    ) -> DbResult<Option<LpDraft>> {
+        let draft = sqlx::query_as::<_, LpDraft>(
+            "SELECT id, owner_qq, user_qq, lp_type, num, reason, picture, role, updated_at
+             FROM lp_draft WHERE id = ? AND owner_qq = ?",
+        )
+        .bind(id)
+        .bind(owner_qq)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(draft)
+    }
+
+    // 统计用户的草稿数
+    pub async fn count_drafts(pool: &SqlitePool, owner_qq: &str) -> DbResult<i64> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM lp_draft WHERE owner_qq = ?")
+            .bind(owner_qq)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    // 保存草稿：`draft.id` 为 0 时新建，否则更新该用户自己的草稿；
+    // 草稿不存在或不属于该用户时返回 None
+    pub async fn save_draft(pool: &SqlitePool, draft: &LpDraft) -> DbResult<Option<i64>> {
+        let updated_at = now_string();
+
+        if draft.id == 0 {
+            let result = sqlx::query(
+                "INSERT INTO lp_draft (owner_qq, user_qq, lp_type, num, reason, picture, role, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&draft.owner_qq)
+            .bind(&draft.user_qq)
+            .bind(draft.lp_type)
+            .bind(draft.num)
+            .bind(&draft.reason)
+            .bind(&draft.picture)
+            .bind(&draft.role)
+            .bind(updated_at)
+            .execute(pool)
+            .await?;
+
+            return Ok(Some(result.last_insert_rowid()));
+        }
+
+        let result = sqlx::query(
+            "UPDATE lp_draft SET user_qq = ?, lp_type = ?, num = ?, reason = ?, picture = ?, role = ?,
+                                 updated_at = ?
+             WHERE id = ? AND owner_qq = ?",
+        )
+        .bind(&draft.user_qq)
+        .bind(draft.lp_type)
+        .bind(draft.num)
+        .bind(&draft.reason)
+        .bind(&draft.picture)
+        .bind(&draft.role)
+        .bind(updated_at)
+        .bind(draft.id)
+        .bind(&draft.owner_qq)
+        .execute(pool)
+        .await?;
+
+        Ok((result.rows_affected() > 0).then_some(draft.id))
+    }
+
+    // 删除用户自己的草稿，草稿不存在或不属于该用户时返回 false
+    pub async fn delete_draft(pool: &SqlitePool, owner_qq: &str, id: i64) -> DbResult<bool> {
+        let result = sqlx::query("DELETE FROM lp_draft WHERE id = ? AND owner_qq = ?")
+            .bind(id)
+            .bind(owner_qq)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // 把已校验的草稿转为待审批的LP申请：同一事务中删除草稿并写入 lplog，
+    // 草稿已被删除（或已提交过）时返回 None，保证同一草稿只会提交一次
+    pub async fn promote_draft(
+        pool: &SqlitePool,
+        draft: &LpDraft,
+        user_qq: &str,
+        lp_type: i64,
+        num: i32,
+    ) -> DbResult<Option<i64>> {
+        let mut tx = pool.begin().await?;
+
+        let removed = sqlx::query("DELETE FROM lp_draft WHERE id = ? AND owner_qq = ?")
+            .bind(draft.id)
+            .bind(&draft.owner_qq)
+            .execute(&mut *tx)
+            .await?;
+        if removed.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let result = sqlx::query(
+            "INSERT INTO lplog (upload_time, upload_user_qq, user_qq, lp_type, num, reason, status, picture, role)
+             VALUES (?, ?, ?, ?, ?, ?, 0, ?, ?)",
+        )
+        .bind(now_string())
+        .bind(&draft.owner_qq)
+        .bind(user_qq)
+        .bind(lp_type)
+        .bind(num)
+        .bind(&draft.reason)
+        .bind(&draft.picture)
+        .bind(&draft.role)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let id = result.last_insert_rowid();
+        info!(
+            "LP草稿提交: 草稿={}, 新申请={}, 上传者={}, 关联用户={}, 状态=待审批",
+            draft.id, id, draft.owner_qq, user_qq
+        );
+
+        Ok(Some(id))
+    }
+}
+
+/// 上一个自然月的标识（YYYY-MM），月初生成的快照记录的是上月末的汇总
+pub fn previous_month_period() -> String {
+    let today = server_now().date();
+    let (year, month) = if today.month() == 1 {
+        (today.year() - 1, 12)
+    } else {
+        (today.year(), today.month() - 1)
+    };
+    format!("{:04}-{:02}", year, month)
+}
+
+/// 本自然月的时间范围 [本月1日 00:00:00, 下月1日 00:00:00)，格式与 `process_time` 一致
+fn current_month_range() -> (String, String) {
+    let today = server_now().date();
+    let (year, month) = (today.year(), today.month());
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    (
+        format!("{:04}-{:02}-01 00:00:00", year, month),
+        format!("{:04}-{:02}-01 00:00:00", next_year, next_month),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, insert_lp_log, memory_pool};
+
+    #[tokio::test]
+    async fn stats_by_type_count_all_logs_and_sum_approved() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        // 奖励：两条通过、一条待审核；惩罚：一条通过、一条拒绝；兑换和调整没有记录
+        insert_lp_log(&pool, "10001", 1, 5, 1).await;
+        insert_lp_log(&pool, "10001", 1, 3, 1).await;
+        insert_lp_log(&pool, "10001", 1, 100, 0).await;
+        insert_lp_log(&pool, "10001", 2, -2, 1).await;
+        insert_lp_log(&pool, "10001", 2, -50, 2).await;
+
+        let stats = LpService::get_stats_by_type(&pool).await.unwrap();
+
+        let summary: Vec<(&str, i64, i64)> = stats
+            .iter()
+            .map(|s| (s.name.as_str(), s.log_count, s.approved_num))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("奖励", 3, 8),
+                ("惩罚", 2, -2),
+                ("兑换", 0, 0),
+                ("调整", 0, 0)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pending_count_tracks_unprocessed_logs() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        assert_eq!(LpService::count_pending(&pool).await.unwrap(), 0);
+
+        insert_lp_log(&pool, "10001", 1, 5, 0).await;
+        insert_lp_log(&pool, "10001", 1, 5, 0).await;
+        insert_lp_log(&pool, "10001", 1, 5, 1).await;
+        insert_lp_log(&pool, "10001", 1, 5, 2).await;
+
+        assert_eq!(LpService::count_pending(&pool).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn monthly_delta_counts_only_approved_logs_this_month() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let (month_start, _) = current_month_range();
+        let last_month = format!("{}-28 12:00:00", previous_month_period());
+        // 本月通过 +5、-2，上月通过 +7，本月待审核 +3
+        for (num, status, process_time) in [
+            (5, 1, month_start.clone()),
+            (-2, 1, now_string()),
+            (7, 1, last_month),
+            (3, 0, now_string()),
+        ] {
+            let id = insert_lp_log(&pool, "10001", 1, num, status).await;
+            sqlx::query("UPDATE lplog SET process_time = ? WHERE id = ?")
+                .bind(process_time)
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let summary = LpService::get_user_lp_summary(&pool, "10001")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(summary.monthly_delta, 3);
+        assert_eq!(summary.total_lp, 10);
+    }
+
+    #[tokio::test]
+    async fn pending_logs_exclude_processed_rows_newest_first() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let mut pending = Vec::new();
+        for (status, upload_time) in [
+            (0, "2026-01-01 10:00:00"),
+            (1, "2026-01-02 10:00:00"),
+            (0, "2026-01-03 10:00:00"),
+            (2, "2026-01-04 10:00:00"),
+        ] {
+            let id = insert_lp_log(&pool, "10001", 1, 1, status).await;
+            sqlx::query("UPDATE lplog SET upload_time = ? WHERE id = ?")
+                .bind(upload_time)
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+            if status == 0 {
+                pending.push(id);
+            }
+        }
+
+        let logs = LpService::get_pending_lp_logs(&pool).await.unwrap();
+
+        let ids: Vec<i64> = logs.iter().filter_map(|log| log.id).collect();
+        pending.reverse();
+        assert_eq!(ids, pending);
+    }
+
+    #[tokio::test]
+    async fn snapshots_for_different_periods_are_kept_apart() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        insert_lp_log(&pool, "10001", 1, 5, 1).await;
+        LpService::snapshot_summaries(&pool, "2026-01")
+            .await
+            .unwrap();
+        insert_lp_log(&pool, "10001", 1, 3, 1).await;
+        LpService::snapshot_summaries(&pool, "2026-02")
+            .await
+            .unwrap();
+        // 已有快照的月份再次执行不会覆盖
+        assert_eq!(
+            LpService::snapshot_summaries(&pool, "2026-01")
+                .await
+                .unwrap(),
+            0
+        );
+
+        let total_of = |snapshots: Vec<LpSummarySnapshot>| {
+            snapshots
+                .into_iter()
+                .find(|snapshot| snapshot.qq == "10001")
+                .map(|snapshot| snapshot.total_lp)
+        };
+        let january = LpService::get_snapshots(&pool, "2026-01").await.unwrap();
+        let february = LpService::get_snapshots(&pool, "2026-02").await.unwrap();
+        assert_eq!(total_of(january), Some(5));
+        assert_eq!(total_of(february), Some(8));
+        assert_eq!(
+            LpService::list_snapshot_periods(&pool).await.unwrap(),
+            vec!["2026-02".to_string(), "2026-01".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn cloned_request_is_pending_with_copied_fields() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        add_user(&pool, "10002").await;
+        let source = insert_lp_log(&pool, "10001", 1, 5, 1).await;
+        sqlx::query("UPDATE lplog SET role = '主持' WHERE id = ?")
+            .bind(source)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let id = LpService::clone_lp_request(&pool, source, "9999", "10002")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let clone = LpService::get_lp_log(&pool, id).await.unwrap().unwrap();
+        assert_eq!(clone.status, 0);
+        assert_eq!(clone.upload_user_qq, "9999");
+        assert_eq!(clone.user_qq, "10002");
+        assert_eq!(
+            (clone.lp_type, clone.num, clone.reason.as_str()),
+            (1, 5, "测试")
+        );
+        assert_eq!(clone.role.as_deref(), Some("主持"));
+        assert_eq!(clone.process_user_qq, None);
+        assert_eq!(
+            LpService::clone_lp_request(&pool, 999, "9999", "10002")
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn transfer_writes_linked_entries_and_nets_out() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        add_user(&pool, "10002").await;
+        insert_lp_log(&pool, "10001", 1, 10, 1).await;
+        let total = |qq: &'static str| {
+            let pool = pool.clone();
+            async move {
+                LpService::get_user_lp_summary(&pool, qq)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .total_lp
+            }
+        };
+
+        let outcome = LpService::transfer_lp(&pool, "9999", "10001", "10002", 4, "调岗")
+            .await
+            .unwrap();
+        let LpTransferOutcome::Transferred {
+            debit_id,
+            credit_id,
+        } = outcome
+        else {
+            panic!("转移应当成功: {:?}", outcome);
+        };
+        let debit = LpService::get_lp_log(&pool, debit_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let credit = LpService::get_lp_log(&pool, credit_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (debit.user_qq.as_str(), debit.num, debit.status),
+            ("10001", -4, 1)
+        );
+        assert_eq!(
+            (credit.user_qq.as_str(), credit.num, credit.status),
+            ("10002", 4, 1)
+        );
+        assert_eq!(total("10001").await, 6);
+        assert_eq!(total("10002").await, 4);
+
+        let outcome = LpService::transfer_lp(&pool, "9999", "10001", "10002", 7, "超额")
+            .await
+            .unwrap();
+        assert_eq!(outcome, LpTransferOutcome::InsufficientLp { available: 6 });
+        assert_eq!(total("10001").await + total("10002").await, 10);
+    }
 }
@@ -1,12 +1,116 @@
-use crate::db::{draw_lucky_winner, DbResult};
+use crate::db::{
+    draw_lucky_winner, draw_lucky_winner_with_threshold, list_eligible_users, now_string,
+    server_now, DbResult, ServiceError, ServiceResult, TIMESTAMP_FORMAT,
+};
 use crate::models::*;
-use chrono::Local;
-use sqlx::SqlitePool;
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+use sqlx::{SqliteConnection, SqlitePool};
 
 pub struct LuckyDrawService;
 
+/// 重复抽奖最多的总期数，防止无限创建
+pub const MAX_DRAW_OCCURRENCES: i32 = 52;
+
+/// 抽奖的重复周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DrawRecurrence {
+    #[default]
+    None,
+    Daily,
+    Weekly,
+}
+
+impl DrawRecurrence {
+    /// 解析数据库和请求中使用的 none/daily/weekly
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(Self::None),
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    /// 相邻两期的间隔，不重复时为 None
+    pub fn interval(self) -> Option<Duration> {
+        match self {
+            Self::None => None,
+            Self::Daily => Some(Duration::days(1)),
+            Self::Weekly => Some(Duration::days(7)),
+        }
+    }
+}
+
+/// 开奖的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DrawOutcome {
+    /// 开奖成功，包含全部中奖者
+    Drawn(Vec<String>),
+    /// 没有达到最低LP要求的用户，抽奖保持未开奖
+    NoEligibleUsers,
+    /// 抽奖已经开过奖（或被并发的请求抢先开奖）
+    AlreadyExecuted,
+    /// 抽奖不存在
+    NotFound,
+}
+
+/// 批量删除抽奖的结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchDeleteDraws {
+    pub deleted: Vec<i64>,
+    /// 未开奖且未要求一并删除，受保护未删除
+    pub skipped_pending: Vec<i64>,
+    pub not_found: Vec<i64>,
+}
+
+/// 取消抽奖的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelDrawOutcome {
+    Cancelled,
+    /// 已开奖、已关闭或已取消，不能再取消
+    NotPending,
+    NotFound,
+}
+
+/// 抽奖列表筛选条件
+#[derive(Debug, Clone, Default)]
+pub struct DrawFilter {
+    /// 0: 未开奖, 1: 已开奖, 2: 无人中奖已关闭, 3: 已取消
+    pub status: Option<i32>,
+    pub create_qq: Option<String>,
+}
+
+impl DrawFilter {
+    /// 只拼接实际指定的条件，使 status/create_qq 上的索引可以被使用
+    fn where_clause(&self) -> String {
+        let mut conditions = Vec::new();
+        if self.status.is_some() {
+            conditions.push("status = ?");
+        }
+        if self.create_qq.is_some() {
+            conditions.push("create_qq = ?");
+        }
+        if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        }
+    }
+}
+
 impl LuckyDrawService {
-    // 创建抽奖活动
+    // 创建抽奖活动。`recurrence_remaining` 为开奖后还要自动创建的期数，不重复时为 0。
+    // 关联的商品不存在时返回 `NotFound`，不属于发起人时返回 `Forbidden`，库存不足时返回 `Validation`
+    #[allow(clippy::too_many_arguments)]
     pub async fn create_draw(
         pool: &SqlitePool,
         create_qq: &str,
@@ -16,31 +120,32 @@ impl LuckyDrawService {
         min_lp_require: i32,
         plan_time: &str,
         description: Option<String>,
-    ) -> DbResult<i64> {
+        recurrence: DrawRecurrence,
+        recurrence_remaining: i32,
+    ) -> ServiceResult<i64> {
         // 如果指定了商品，需要先检查库存并扣除
         if let Some(item_id) = item_id {
             // 查询商品库存和所有者
-            let item: (i64, String) =
+            let (stock, seller): (i64, String) =
                 sqlx::query_as("SELECT count, seller FROM shopitems WHERE id = ?")
                     .bind(item_id)
-                    .fetch_one(pool)
-                    .await
-                    .map_err(|_| sqlx::Error::RowNotFound)?;
-
-            let (stock, seller) = item;
+                    .fetch_optional(pool)
+                    .await?
+                    .ok_or_else(|| ServiceError::NotFound("商品不存在".to_string()))?;
 
-            // 检查库存是否足够
-            if stock < num as i64 {
-                return Err(sqlx::Error::Decode(
-                    format!("库存不足: 需要 {}, 实际 {}", num, stock).into(),
+            // 检查是否是商品所有者，防止构造请求使用他人的商品
+            if seller != create_qq {
+                return Err(ServiceError::Forbidden(
+                    "只能抽取您自己发布的商品".to_string(),
                 ));
             }
 
-            // 检查是否是商品所有者
-            if seller != create_qq {
-                return Err(sqlx::Error::Decode(
-                    format!("只能使用自己的商品创建抽奖").into(),
-                ));
+            // 检查库存是否足够
+            if stock < num as i64 {
+                return Err(ServiceError::Validation(format!(
+                    "库存不足: 需要 {}, 实际 {}",
+                    num, stock
+                )));
             }
 
             // 开启事务
@@ -57,15 +162,16 @@ impl LuckyDrawService {
 
             // 检查是否成功更新（防止并发问题）
             if update_result.rows_affected() == 0 {
-                return Err(sqlx::Error::Decode("库存不足或商品不存在".into()));
+                return Err(ServiceError::Validation("库存不足或商品不存在".to_string()));
             }
 
             // 创建抽奖记录
-            let create_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            let create_time = now_string();
             let result = sqlx::query(
                 "INSERT INTO luckydrawlog (create_time, create_qq, item_id, fitting, num, min_lp_require,
-                                           plan_time, status, description)
-                 VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?)",
+                                           plan_time, status, description, recurrence,
+                                           recurrence_remaining)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?)",
             )
             .bind(create_time)
             .bind(create_qq)
@@ -75,6 +181,8 @@ impl LuckyDrawService {
             .bind(min_lp_require)
             .bind(plan_time)
             .bind(description)
+            .bind(recurrence.as_str())
+            .bind(recurrence_remaining)
             .execute(&mut *tx)
             .await?;
 
@@ -92,11 +200,12 @@ impl LuckyDrawService {
             Ok(draw_id)
         } else {
             // 没有指定商品，直接创建抽奖
-            let create_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            let create_time = now_string();
             let result = sqlx::query(
                 "INSERT INTO luckydrawlog (create_time, create_qq, item_id, fitting, num, min_lp_require,
-                                           plan_time, status, description)
-                 VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?)",
+                                           plan_time, status, description, recurrence,
+                                           recurrence_remaining)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?)",
             )
             .bind(create_time)
             .bind(create_qq)
@@ -106,6 +215,8 @@ impl LuckyDrawService {
             .bind(min_lp_require)
             .bind(plan_time)
             .bind(description)
+            .bind(recurrence.as_str())
+            .bind(recurrence_remaining)
             .execute(pool)
             .await?;
 
@@ -113,11 +224,39 @@ impl LuckyDrawService {
         }
     }
 
+    // 预览抽奖当前符合条件的参与者（只读，不修改抽奖状态），抽奖不存在时返回 None
+    pub async fn get_eligible_users(
+        pool: &SqlitePool,
+        draw_id: i64,
+    ) -> DbResult<Option<(i64, Vec<String>)>> {
+        let min_lp: Option<i64> =
+            sqlx::query_scalar("SELECT min_lp_require FROM luckydrawlog WHERE id = ?")
+                .bind(draw_id)
+                .fetch_optional(pool)
+                .await?;
+
+        let Some(min_lp) = min_lp else {
+            return Ok(None);
+        };
+
+        let users = list_eligible_users(pool, min_lp).await?;
+        Ok(Some((min_lp, users)))
+    }
+
     // 执行抽奖（调用存储过程）
-    pub async fn execute_draw(pool: &SqlitePool, draw_id: i64) -> DbResult<Option<Vec<String>>> {
+    pub async fn execute_draw(pool: &SqlitePool, draw_id: i64) -> DbResult<DrawOutcome> {
         draw_lucky_winner(pool, draw_id).await
     }
 
+    // 重新开奖，可临时指定更低的最低LP要求；成功开奖时新的要求会保存到抽奖记录
+    pub async fn retry_draw(
+        pool: &SqlitePool,
+        draw_id: i64,
+        min_lp_require: Option<i32>,
+    ) -> DbResult<DrawOutcome> {
+        draw_lucky_winner_with_threshold(pool, draw_id, min_lp_require.map(i64::from)).await
+    }
+
     // 手动设置中奖者
     pub async fn set_winner(pool: &SqlitePool, draw_id: i64, winner_qq: &str) -> DbResult<()> {
         sqlx::query("UPDATE luckydrawlog SET status = 1, winner_qq = ? WHERE id = ?")
@@ -132,7 +271,7 @@ impl LuckyDrawService {
     pub async fn get_all_draws(pool: &SqlitePool) -> DbResult<Vec<LuckyDrawLog>> {
         let draws = sqlx::query_as::<_, LuckyDrawLog>(
             "SELECT id, create_time, create_qq, item_id, fitting, num, min_lp_require,
-                    plan_time, status, winner_qq, description
+                    plan_time, status, winner_qq, description, recurrence, recurrence_remaining
              FROM luckydrawlog ORDER BY create_time DESC",
         )
         .fetch_all(pool)
@@ -141,11 +280,56 @@ impl LuckyDrawService {
         Ok(draws)
     }
 
+    // 按条件分页查询抽奖活动，最新创建的在前
+    pub async fn list_draws(
+        pool: &SqlitePool,
+        filter: &DrawFilter,
+        limit: i64,
+        offset: i64,
+    ) -> DbResult<Vec<LuckyDrawLog>> {
+        let sql = format!(
+            "SELECT id, create_time, create_qq, item_id, fitting, num, min_lp_require,
+                    plan_time, status, winner_qq, description, recurrence, recurrence_remaining
+             FROM luckydrawlog {}
+             ORDER BY create_time DESC
+             LIMIT ? OFFSET ?",
+            filter.where_clause()
+        );
+        let mut query = sqlx::query_as::<_, LuckyDrawLog>(&sql);
+        if let Some(status) = filter.status {
+            query = query.bind(status);
+        }
+        if let Some(create_qq) = &filter.create_qq {
+            query = query.bind(create_qq);
+        }
+        let draws = query.bind(limit).bind(offset).fetch_all(pool).await?;
+
+        Ok(draws)
+    }
+
+    // 统计符合条件的抽奖数量
+    pub async fn count_draws(pool: &SqlitePool, filter: &DrawFilter) -> DbResult<i64> {
+        let sql = format!(
+            "SELECT COUNT(*) FROM luckydrawlog {}",
+            filter.where_clause()
+        );
+        let mut query = sqlx::query_as::<_, (i64,)>(&sql);
+        if let Some(status) = filter.status {
+            query = query.bind(status);
+        }
+        if let Some(create_qq) = &filter.create_qq {
+            query = query.bind(create_qq);
+        }
+        let (count,) = query.fetch_one(pool).await?;
+
+        Ok(count)
+    }
+
     // 获取待开奖的活动
     pub async fn get_pending_draws(pool: &SqlitePool) -> DbResult<Vec<LuckyDrawLog>> {
         let draws = sqlx::query_as::<_, LuckyDrawLog>(
             "SELECT id, create_time, create_qq, item_id, fitting, num, min_lp_require,
-                    plan_time, status, winner_qq, description
+                    plan_time, status, winner_qq, description, recurrence, recurrence_remaining
              FROM luckydrawlog WHERE status = 0 ORDER BY plan_time ASC",
         )
         .fetch_all(pool)
@@ -158,7 +342,7 @@ impl LuckyDrawService {
     pub async fn get_user_wins(pool: &SqlitePool, user_qq: &str) -> DbResult<Vec<LuckyDrawLog>> {
         let draws = sqlx::query_as::<_, LuckyDrawLog>(
             "SELECT id, create_time, create_qq, item_id, fitting, num, min_lp_require,
-                    plan_time, status, winner_qq, description
+                    plan_time, status, winner_qq, description, recurrence, recurrence_remaining
              FROM luckydrawlog WHERE winner_qq = ? ORDER BY create_time DESC",
         )
         .bind(user_qq)
@@ -168,6 +352,225 @@ impl LuckyDrawService {
         Ok(draws)
     }
 
+    // 获取单个抽奖活动
+    pub async fn get_draw(pool: &SqlitePool, draw_id: i64) -> DbResult<Option<LuckyDrawLog>> {
+        let draw = sqlx::query_as::<_, LuckyDrawLog>(
+            "SELECT id, create_time, create_qq, item_id, fitting, num, min_lp_require,
+                    plan_time, status, winner_qq, description, recurrence, recurrence_remaining
+             FROM luckydrawlog WHERE id = ?",
+        )
+        .bind(draw_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(draw)
+    }
+
+    // 以已有抽奖为模板创建新的未开奖抽奖，复制奖品、数量、LP要求和描述（不复制重复设置）。
+    // 关联商品时与新建抽奖一样扣除库存。源抽奖不存在时返回 None
+    pub async fn duplicate_draw(
+        pool: &SqlitePool,
+        source_id: i64,
+        create_qq: &str,
+        plan_time: &str,
+    ) -> ServiceResult<Option<i64>> {
+        let Some(source) = Self::get_draw(pool, source_id).await? else {
+            return Ok(None);
+        };
+
+        let id = Self::create_draw(
+            pool,
+            create_qq,
+            source.item_id,
+            source.fitting,
+            source.num,
+            source.min_lp_require,
+            plan_time,
+            source.description,
+            DrawRecurrence::None,
+            0,
+        )
+        .await?;
+
+        Ok(Some(id))
+    }
+
+    // 修改未开奖的抽奖活动。关联了商品时按数量差额扣除或归还库存，库存不足时返回 `Validation`。
+    // 抽奖已开奖或不存在时返回 false
+    pub async fn update_draw(
+        pool: &SqlitePool,
+        draw_id: i64,
+        num: i32,
+        min_lp_require: i32,
+        plan_time: &str,
+        description: Option<String>,
+    ) -> ServiceResult<bool> {
+        let mut tx = pool.begin().await?;
+
+        let draw_info: Option<(i32, Option<i64>, i32)> =
+            sqlx::query_as("SELECT status, item_id, num FROM luckydrawlog WHERE id = ?")
+                .bind(draw_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+        let Some((0, item_id, old_num)) = draw_info else {
+            return Ok(false);
+        };
+
+        let delta = num - old_num;
+        if let (Some(item_id), true) = (item_id, delta != 0) {
+            let update_result =
+                sqlx::query("UPDATE shopitems SET count = count - ? WHERE id = ? AND count >= ?")
+                    .bind(delta)
+                    .bind(item_id)
+                    .bind(delta.max(0))
+                    .execute(&mut *tx)
+                    .await?;
+
+            if update_result.rows_affected() == 0 {
+                return Err(ServiceError::Validation(
+                    "商品库存不足，无法增加奖品数量".to_string(),
+                ));
+            }
+
+            log::info!(
+                "修改抽奖 ID={}, 商品 ID={} 库存变化 {}",
+                draw_id,
+                item_id,
+                -delta
+            );
+        }
+
+        let result = sqlx::query(
+            "UPDATE luckydrawlog SET num = ?, min_lp_require = ?, plan_time = ?, description = ?
+             WHERE id = ? AND status = 0",
+        )
+        .bind(num)
+        .bind(min_lp_require)
+        .bind(plan_time)
+        .bind(description)
+        .bind(draw_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// 为已结束（开奖或无人中奖关闭）且还有剩余期数的重复抽奖创建下一期，返回新抽奖ID。
+    /// 下一期复制奖品、数量、LP要求和描述，计划时间按周期顺延到当前时间之后；
+    /// 创建后把本期的剩余期数清零，保证每期只生成一次。关联商品库存不足时停止重复
+    pub async fn spawn_next_occurrence(pool: &SqlitePool, draw_id: i64) -> DbResult<Option<i64>> {
+        let mut tx = pool.begin().await?;
+
+        let Some(draw) = sqlx::query_as::<_, LuckyDrawLog>(
+            "SELECT id, create_time, create_qq, item_id, fitting, num, min_lp_require,
+                    plan_time, status, winner_qq, description, recurrence, recurrence_remaining
+             FROM luckydrawlog WHERE id = ?",
+        )
+        .bind(draw_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let interval = DrawRecurrence::parse(&draw.recurrence).and_then(DrawRecurrence::interval);
+        let (Some(interval), true, true) = (
+            interval,
+            draw.recurrence_remaining > 0,
+            matches!(draw.status, 1 | 2),
+        ) else {
+            return Ok(None);
+        };
+
+        // 条件更新防止并发的检查重复创建下一期
+        let claimed = sqlx::query(
+            "UPDATE luckydrawlog SET recurrence_remaining = 0
+             WHERE id = ? AND recurrence_remaining > 0",
+        )
+        .bind(draw_id)
+        .execute(&mut *tx)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let Ok(plan_time) = NaiveDateTime::parse_from_str(&draw.plan_time, TIMESTAMP_FORMAT) else {
+            log::warn!("重复抽奖 ID={} 的计划时间无法解析，停止重复", draw_id);
+            tx.commit().await?;
+            return Ok(None);
+        };
+        let next_plan_time = next_plan_time(plan_time, interval, server_now());
+
+        if let Some(item_id) = draw.item_id {
+            let reserved =
+                sqlx::query("UPDATE shopitems SET count = count - ? WHERE id = ? AND count >= ?")
+                    .bind(draw.num)
+                    .bind(item_id)
+                    .bind(draw.num)
+                    .execute(&mut *tx)
+                    .await?;
+            if reserved.rows_affected() == 0 {
+                log::warn!(
+                    "重复抽奖 ID={} 关联的商品 ID={} 库存不足，停止重复",
+                    draw_id,
+                    item_id
+                );
+                tx.commit().await?;
+                return Ok(None);
+            }
+        }
+
+        let create_time = now_string();
+        let result = sqlx::query(
+            "INSERT INTO luckydrawlog (create_time, create_qq, item_id, fitting, num, min_lp_require,
+                                       plan_time, status, description, recurrence,
+                                       recurrence_remaining)
+             VALUES (?, ?, ?, ?, ?, ?, ?, 0, ?, ?, ?)",
+        )
+        .bind(create_time)
+        .bind(&draw.create_qq)
+        .bind(draw.item_id)
+        .bind(&draw.fitting)
+        .bind(draw.num)
+        .bind(draw.min_lp_require)
+        .bind(next_plan_time.format(TIMESTAMP_FORMAT).to_string())
+        .bind(&draw.description)
+        .bind(&draw.recurrence)
+        .bind(draw.recurrence_remaining - 1)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let next_id = result.last_insert_rowid();
+        log::info!(
+            "重复抽奖 ID={} 已创建下一期 ID={}, 计划开奖时间 {}",
+            draw_id,
+            next_id,
+            next_plan_time
+        );
+        Ok(Some(next_id))
+    }
+
+    /// 已结束但还没有创建下一期的重复抽奖ID
+    pub async fn list_recurring_due(pool: &SqlitePool) -> DbResult<Vec<i64>> {
+        let ids = sqlx::query_scalar(
+            "SELECT id FROM luckydrawlog
+             WHERE status IN (1, 2) AND recurrence != 'none' AND recurrence_remaining > 0
+             ORDER BY plan_time ASC",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(ids)
+    }
+
     // 删除抽奖活动
     pub async fn delete_draw(pool: &SqlitePool, draw_id: i64) -> DbResult<()> {
         // 开启事务
@@ -180,34 +583,638 @@ impl LuckyDrawService {
                 .fetch_one(&mut *tx)
                 .await?;
 
-        let (status, item_id, num) = draw_info;
+        remove_draw(&mut tx, draw_id, draw_info).await?;
 
-        // 如果未开奖且关联了商品，需要恢复库存
-        if status == 0 && item_id.is_some() {
-            let item_id = item_id.unwrap();
-            sqlx::query("UPDATE shopitems SET count = count + ? WHERE id = ?")
-                .bind(num)
-                .bind(item_id)
-                .execute(&mut *tx)
-                .await?;
+        // 提交事务
+        tx.commit().await?;
 
-            log::info!(
-                "删除抽奖 ID={}, 恢复商品 ID={} 库存 {} 个",
-                draw_id,
-                item_id,
-                num
-            );
+        Ok(())
+    }
+
+    /// 取消未开奖的抽奖：保留记录并标记为已取消（status = 3），恢复关联商品的库存。
+    /// 定时任务只处理 status = 0 的抽奖，取消后不会再被开奖
+    pub async fn cancel_draw(pool: &SqlitePool, draw_id: i64) -> DbResult<CancelDrawOutcome> {
+        let mut tx = pool.begin().await?;
+
+        let draw_info: Option<(i32, Option<i64>, i32)> =
+            sqlx::query_as("SELECT status, item_id, num FROM luckydrawlog WHERE id = ?")
+                .bind(draw_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some((status, item_id, num)) = draw_info else {
+            return Ok(CancelDrawOutcome::NotFound);
+        };
+        if status != 0 {
+            return Ok(CancelDrawOutcome::NotPending);
         }
 
-        // 删除抽奖记录
-        sqlx::query("DELETE FROM luckydrawlog WHERE id = ?")
+        // 条件更新防止与开奖并发时重复恢复库存
+        let updated = sqlx::query("UPDATE luckydrawlog SET status = 3 WHERE id = ? AND status = 0")
             .bind(draw_id)
             .execute(&mut *tx)
             .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(CancelDrawOutcome::NotPending);
+        }
+        if let Some(item_id) = item_id {
+            restore_draw_stock(&mut tx, draw_id, item_id, num).await?;
+        }
 
-        // 提交事务
         tx.commit().await?;
 
-        Ok(())
+        Ok(CancelDrawOutcome::Cancelled)
+    }
+
+    /// 在同一事务中批量删除抽奖。默认只删除已开奖（含无人中奖关闭）的抽奖，
+    /// `include_pending` 为 true 时未开奖的也一并删除并恢复库存
+    pub async fn delete_draws(
+        pool: &SqlitePool,
+        ids: &[i64],
+        include_pending: bool,
+    ) -> DbResult<BatchDeleteDraws> {
+        let mut result = BatchDeleteDraws::default();
+        let mut tx = pool.begin().await?;
+
+        for &draw_id in ids {
+            let draw_info: Option<(i32, Option<i64>, i32)> =
+                sqlx::query_as("SELECT status, item_id, num FROM luckydrawlog WHERE id = ?")
+                    .bind(draw_id)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+
+            match draw_info {
+                None => result.not_found.push(draw_id),
+                Some((0, _, _)) if !include_pending => result.skipped_pending.push(draw_id),
+                Some(info) => {
+                    remove_draw(&mut tx, draw_id, info).await?;
+                    result.deleted.push(draw_id);
+                }
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(result)
+    }
+}
+
+/// 按周期顺延计划时间，直到晚于 `now`，避免停机后补建的期数立即开奖
+fn next_plan_time(
+    plan_time: NaiveDateTime,
+    interval: Duration,
+    now: NaiveDateTime,
+) -> NaiveDateTime {
+    let mut next = plan_time + interval;
+    while next <= now {
+        next += interval;
+    }
+    next
+}
+
+/// 删除抽奖记录；未开奖且关联了商品的抽奖先恢复库存
+async fn remove_draw(
+    conn: &mut SqliteConnection,
+    draw_id: i64,
+    (status, item_id, num): (i32, Option<i64>, i32),
+) -> DbResult<()> {
+    if let (0, Some(item_id)) = (status, item_id) {
+        restore_draw_stock(conn, draw_id, item_id, num).await?;
+    }
+
+    sqlx::query("DELETE FROM luckydrawlog WHERE id = ?")
+        .bind(draw_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// 归还未开奖抽奖预留的商品库存
+async fn restore_draw_stock(
+    conn: &mut SqliteConnection,
+    draw_id: i64,
+    item_id: i64,
+    num: i32,
+) -> DbResult<()> {
+    sqlx::query("UPDATE shopitems SET count = count + ? WHERE id = ?")
+        .bind(num)
+        .bind(item_id)
+        .execute(&mut *conn)
+        .await?;
+
+    log::info!(
+        "抽奖 ID={} 未开奖即移除，恢复商品 ID={} 库存 {} 个",
+        draw_id,
+        item_id,
+        num
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, grant_lp, memory_pool, TempDb};
+
+    /// 创建一个不关联商品的抽奖
+    async fn create_plain_draw(pool: &SqlitePool, num: i32, min_lp: i32) -> i64 {
+        LuckyDrawService::create_draw(
+            pool,
+            "9999",
+            None,
+            Some("奖品".to_string()),
+            num,
+            min_lp,
+            "2030-01-01 12:00:00",
+            None,
+            DrawRecurrence::None,
+            0,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn eligible_preview_counts_only_users_at_threshold() {
+        let pool = memory_pool().await;
+        for (qq, lp) in [("10001", 50), ("10002", 100), ("10003", 150)] {
+            add_user(&pool, qq).await;
+            grant_lp(&pool, qq, lp).await;
+        }
+        let draw_id = create_plain_draw(&pool, 1, 100).await;
+
+        let (min_lp, mut users) = LuckyDrawService::get_eligible_users(&pool, draw_id)
+            .await
+            .unwrap()
+            .unwrap();
+        users.sort();
+
+        assert_eq!(min_lp, 100);
+        assert_eq!(users, vec!["10002", "10003"]);
+        // 预览不改变抽奖状态
+        let draw = LuckyDrawService::get_draw(&pool, draw_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(draw.status, 0);
+        assert!(LuckyDrawService::get_eligible_users(&pool, draw_id + 1)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn winners_respect_num_and_eligibility() {
+        let pool = memory_pool().await;
+        for (qq, lp) in [
+            ("10001", 10),
+            ("10002", 100),
+            ("10003", 100),
+            ("10004", 100),
+        ] {
+            add_user(&pool, qq).await;
+            grant_lp(&pool, qq, lp).await;
+        }
+
+        let draw_id = create_plain_draw(&pool, 2, 100).await;
+        let DrawOutcome::Drawn(mut winners) = LuckyDrawService::execute_draw(&pool, draw_id)
+            .await
+            .unwrap()
+        else {
+            panic!("应当开奖成功");
+        };
+        winners.sort();
+        winners.dedup();
+        assert_eq!(winners.len(), 2);
+        assert!(winners.iter().all(|qq| qq != "10001" && qq != "9999"));
+
+        // 中奖人数超过符合条件的人数时，全部符合条件的用户中奖
+        let draw_id = create_plain_draw(&pool, 5, 100).await;
+        let DrawOutcome::Drawn(mut winners) = LuckyDrawService::execute_draw(&pool, draw_id)
+            .await
+            .unwrap()
+        else {
+            panic!("应当开奖成功");
+        };
+        winners.sort();
+        assert_eq!(winners, vec!["10002", "10003", "10004"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn concurrent_scheduler_and_manual_execute_draw_once() {
+        let db = TempDb::new().await;
+        let pool = &db.pool;
+        for qq in ["10001", "10002", "10003"] {
+            add_user(pool, qq).await;
+            grant_lp(pool, qq, 100).await;
+        }
+
+        for _ in 0..10 {
+            let draw_id = create_plain_draw(pool, 1, 100).await;
+            // 定时任务直接调用 draw_lucky_winner，手动开奖经由 LuckyDrawService
+            let scheduler = tokio::spawn({
+                let pool = pool.clone();
+                async move { draw_lucky_winner(&pool, draw_id).await }
+            });
+            let manual = tokio::spawn({
+                let pool = pool.clone();
+                async move { LuckyDrawService::execute_draw(&pool, draw_id).await }
+            });
+            let outcomes = [
+                scheduler.await.unwrap().unwrap(),
+                manual.await.unwrap().unwrap(),
+            ];
+
+            let drawn: Vec<_> = outcomes
+                .iter()
+                .filter_map(|outcome| match outcome {
+                    DrawOutcome::Drawn(winners) => Some(winners.join(", ")),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(drawn.len(), 1, "只能有一次开奖生效: {:?}", outcomes);
+            assert!(outcomes.contains(&DrawOutcome::AlreadyExecuted));
+
+            let draw = LuckyDrawService::get_draw(pool, draw_id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(draw.status, 1);
+            assert_eq!(draw.winner_qq.as_deref(), Some(drawn[0].as_str()));
+        }
+    }
+
+    async fn item_stock(pool: &SqlitePool, item_id: i64) -> i32 {
+        sqlx::query_scalar("SELECT count FROM shopitems WHERE id = ?")
+            .bind(item_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn pending_draw_edit_adjusts_reserved_stock() {
+        let pool = memory_pool().await;
+        let item_id =
+            crate::db::ShopService::add_item(&pool, 5, "10", "咖啡", "9999", "A区", None, None)
+                .await
+                .unwrap();
+        let draw_id = LuckyDrawService::create_draw(
+            &pool,
+            "9999",
+            Some(item_id),
+            None,
+            2,
+            0,
+            "2030-01-01 12:00:00",
+            None,
+            DrawRecurrence::None,
+            0,
+        )
+        .await
+        .unwrap();
+        assert_eq!(item_stock(&pool, item_id).await, 3);
+
+        let updated = LuckyDrawService::update_draw(
+            &pool,
+            draw_id,
+            4,
+            10,
+            "2030-02-01 12:00:00",
+            Some("新描述".to_string()),
+        )
+        .await
+        .unwrap();
+        assert!(updated);
+        assert_eq!(item_stock(&pool, item_id).await, 1);
+        let draw = LuckyDrawService::get_draw(&pool, draw_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (draw.num, draw.min_lp_require, draw.plan_time.as_str()),
+            (4, 10, "2030-02-01 12:00:00")
+        );
+        assert_eq!(draw.description.as_deref(), Some("新描述"));
+
+        // 库存不够增加到 10 份时整体不修改
+        let result =
+            LuckyDrawService::update_draw(&pool, draw_id, 10, 10, "2030-02-01 12:00:00", None)
+                .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        assert_eq!(item_stock(&pool, item_id).await, 1);
+    }
+
+    #[tokio::test]
+    async fn executed_draw_cannot_be_edited() {
+        let pool = memory_pool().await;
+        let draw_id = create_plain_draw(&pool, 1, 0).await;
+        sqlx::query("UPDATE luckydrawlog SET status = 1 WHERE id = ?")
+            .bind(draw_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let updated =
+            LuckyDrawService::update_draw(&pool, draw_id, 3, 0, "2030-02-01 12:00:00", None)
+                .await
+                .unwrap();
+
+        assert!(!updated);
+        let draw = LuckyDrawService::get_draw(&pool, draw_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(draw.num, 1);
+    }
+
+    #[tokio::test]
+    async fn retry_with_lowered_threshold_draws_and_saves_it() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        grant_lp(&pool, "10001", 10).await;
+        let draw_id = create_plain_draw(&pool, 1, 100).await;
+
+        assert_eq!(
+            LuckyDrawService::execute_draw(&pool, draw_id)
+                .await
+                .unwrap(),
+            DrawOutcome::NoEligibleUsers
+        );
+        let outcome = LuckyDrawService::retry_draw(&pool, draw_id, Some(10))
+            .await
+            .unwrap();
+
+        assert_eq!(outcome, DrawOutcome::Drawn(vec!["10001".to_string()]));
+        let draw = LuckyDrawService::get_draw(&pool, draw_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((draw.status, draw.min_lp_require), (1, 10));
+    }
+
+    #[tokio::test]
+    async fn execute_draw_reports_each_outcome() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        grant_lp(&pool, "10001", 50).await;
+
+        assert_eq!(
+            LuckyDrawService::execute_draw(&pool, 404).await.unwrap(),
+            DrawOutcome::NotFound
+        );
+
+        let draw_id = create_plain_draw(&pool, 1, 100).await;
+        assert_eq!(
+            LuckyDrawService::execute_draw(&pool, draw_id)
+                .await
+                .unwrap(),
+            DrawOutcome::NoEligibleUsers
+        );
+
+        let draw_id = create_plain_draw(&pool, 1, 50).await;
+        assert_eq!(
+            LuckyDrawService::execute_draw(&pool, draw_id)
+                .await
+                .unwrap(),
+            DrawOutcome::Drawn(vec!["10001".to_string()])
+        );
+        assert_eq!(
+            LuckyDrawService::execute_draw(&pool, draw_id)
+                .await
+                .unwrap(),
+            DrawOutcome::AlreadyExecuted
+        );
+    }
+
+    #[tokio::test]
+    async fn draws_filter_by_status_and_creator() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        grant_lp(&pool, "10001", 10).await;
+        let executed = create_plain_draw(&pool, 1, 0).await;
+        LuckyDrawService::execute_draw(&pool, executed)
+            .await
+            .unwrap();
+        let pending = [
+            create_plain_draw(&pool, 1, 0).await,
+            create_plain_draw(&pool, 1, 0).await,
+        ];
+
+        let ids = |draws: Vec<LuckyDrawLog>| {
+            let mut ids: Vec<i64> = draws.into_iter().filter_map(|d| d.id).collect();
+            ids.sort();
+            ids
+        };
+        let pending_filter = DrawFilter {
+            status: Some(0),
+            create_qq: None,
+        };
+        let executed_filter = DrawFilter {
+            status: Some(1),
+            create_qq: Some("9999".to_string()),
+        };
+
+        assert_eq!(
+            ids(LuckyDrawService::list_draws(&pool, &pending_filter, 20, 0)
+                .await
+                .unwrap()),
+            pending.to_vec()
+        );
+        assert_eq!(
+            LuckyDrawService::count_draws(&pool, &pending_filter)
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            ids(LuckyDrawService::list_draws(&pool, &executed_filter, 20, 0)
+                .await
+                .unwrap()),
+            vec![executed]
+        );
+        let other_creator = DrawFilter {
+            status: None,
+            create_qq: Some("10001".to_string()),
+        };
+        assert_eq!(
+            LuckyDrawService::count_draws(&pool, &other_creator)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn duplicate_is_pending_and_copies_fields() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        grant_lp(&pool, "10001", 10).await;
+        let source = LuckyDrawService::create_draw(
+            &pool,
+            "9999",
+            None,
+            Some("耳机".to_string()),
+            2,
+            5,
+            "2030-01-01 12:00:00",
+            Some("年会抽奖".to_string()),
+            DrawRecurrence::None,
+            0,
+        )
+        .await
+        .unwrap();
+        LuckyDrawService::execute_draw(&pool, source).await.unwrap();
+
+        let copy_id =
+            LuckyDrawService::duplicate_draw(&pool, source, "10001", "2031-01-01 12:00:00")
+                .await
+                .unwrap()
+                .unwrap();
+
+        let copy = LuckyDrawService::get_draw(&pool, copy_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.status, 0);
+        assert_eq!(copy.create_qq, "10001");
+        assert_eq!(copy.plan_time, "2031-01-01 12:00:00");
+        assert_eq!(
+            (copy.fitting.as_deref(), copy.num, copy.min_lp_require),
+            (Some("耳机"), 2, 5)
+        );
+        assert_eq!(copy.description.as_deref(), Some("年会抽奖"));
+        assert!(copy.winner_qq.is_none());
+        assert!(
+            LuckyDrawService::duplicate_draw(&pool, 404, "10001", "2031-01-01 12:00:00")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_delete_skips_pending_unless_included_and_restores_stock() {
+        let pool = memory_pool().await;
+        let item_id =
+            crate::db::ShopService::add_item(&pool, 5, "10", "咖啡", "9999", "A区", None, None)
+                .await
+                .unwrap();
+        let pending = LuckyDrawService::create_draw(
+            &pool,
+            "9999",
+            Some(item_id),
+            None,
+            2,
+            0,
+            "2030-01-01 12:00:00",
+            None,
+            DrawRecurrence::None,
+            0,
+        )
+        .await
+        .unwrap();
+        let executed = create_plain_draw(&pool, 1, 0).await;
+        sqlx::query("UPDATE luckydrawlog SET status = 1 WHERE id = ?")
+            .bind(executed)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = LuckyDrawService::delete_draws(&pool, &[pending, executed, 999], false)
+            .await
+            .unwrap();
+        assert_eq!(result.deleted, vec![executed]);
+        assert_eq!(result.skipped_pending, vec![pending]);
+        assert_eq!(result.not_found, vec![999]);
+        assert_eq!(item_stock(&pool, item_id).await, 3);
+
+        let result = LuckyDrawService::delete_draws(&pool, &[pending], true)
+            .await
+            .unwrap();
+        assert_eq!(result.deleted, vec![pending]);
+        assert_eq!(item_stock(&pool, item_id).await, 5);
+    }
+
+    #[tokio::test]
+    async fn draw_with_missing_or_foreign_item_is_a_typed_error() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let item_id =
+            crate::db::ShopService::add_item(&pool, 5, "10", "咖啡", "10001", "A区", None, None)
+                .await
+                .unwrap();
+        let create = |item_id| {
+            LuckyDrawService::create_draw(
+                &pool,
+                "9999",
+                Some(item_id),
+                None,
+                1,
+                0,
+                "2030-01-01 12:00:00",
+                None,
+                DrawRecurrence::None,
+                0,
+            )
+        };
+
+        assert!(matches!(
+            create(item_id).await,
+            Err(ServiceError::Forbidden(_))
+        ));
+        assert!(matches!(
+            create(item_id + 1).await,
+            Err(ServiceError::NotFound(_))
+        ));
+        assert_eq!(item_stock(&pool, item_id).await, 5);
+    }
+
+    #[tokio::test]
+    async fn executed_weekly_draw_spawns_next_occurrence_a_week_later() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        grant_lp(&pool, "10001", 100).await;
+        let draw_id = LuckyDrawService::create_draw(
+            &pool,
+            "9999",
+            None,
+            Some("奖品".to_string()),
+            1,
+            0,
+            "2099-01-02 20:00:00",
+            Some("周五抽奖".to_string()),
+            DrawRecurrence::Weekly,
+            1,
+        )
+        .await
+        .unwrap();
+        let outcome = LuckyDrawService::execute_draw(&pool, draw_id)
+            .await
+            .unwrap();
+        assert!(matches!(outcome, DrawOutcome::Drawn(_)));
+
+        let next_id = LuckyDrawService::spawn_next_occurrence(&pool, draw_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let next = LuckyDrawService::get_draw(&pool, next_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(next.status, 0);
+        assert_eq!(next.plan_time, "2099-01-09 20:00:00");
+        assert_eq!(next.recurrence, "weekly");
+        assert_eq!(next.recurrence_remaining, 0);
+        assert_eq!(next.description.as_deref(), Some("周五抽奖"));
+        // 每期只生成一次下一期
+        assert_eq!(
+            LuckyDrawService::spawn_next_occurrence(&pool, draw_id)
+                .await
+                .unwrap(),
+            None
+        );
     }
 }
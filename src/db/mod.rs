@@ -1,25 +1,104 @@
+mod backup_service;
+mod clock;
+mod idempotency_service;
 mod log_service;
 mod lp_service;
 mod lucky_draw_service;
+mod notification_service;
 mod permission_service;
 mod role_service;
+mod service_error;
 mod shop_service;
+mod stats_service;
+#[cfg(test)]
+pub(crate) mod test_support;
 mod user_service;
 
+pub use backup_service::{BackupService, RestoreOutcome};
+pub use clock::{
+    now_string, parse_utc_offset, server_now, server_offset, set_server_offset, TIMESTAMP_FORMAT,
+};
+pub use idempotency_service::{IdempotencyRecord, IdempotencyService};
 pub use log_service::*;
-pub use lp_service::LpService;
-pub use lucky_draw_service::LuckyDrawService;
+pub use lp_service::{previous_month_period, LpService, LpTransferOutcome};
+pub use lucky_draw_service::{
+    BatchDeleteDraws, CancelDrawOutcome, DrawFilter, DrawOutcome, DrawRecurrence, LuckyDrawService,
+    MAX_DRAW_OCCURRENCES,
+};
+pub use notification_service::{
+    NotificationService, NOTIFICATION_KIND_DRAW_WON, NOTIFICATION_KIND_LOW_STOCK,
+    NOTIFICATION_KIND_LP_PROCESSED,
+};
 pub use permission_service::PermissionService;
-pub use role_service::RoleService;
-pub use shop_service::ShopService;
-pub use user_service::UserService;
-
-use chrono::Local;
-use sqlx::{Executor, SqlitePool};
+pub use role_service::{BulkAssignLine, BulkAssignStatus, DeleteRoleOutcome, RoleService};
+pub use service_error::{ServiceError, ServiceResult};
+pub use shop_service::{
+    BatchPurchaseLine, BatchPurchaseStatus, NewShopItem, ShopItemFilter, ShopService, StockAlert,
+};
+pub use stats_service::StatsService;
+pub use user_service::{UserFilter, UserService};
+
+use sqlx::{Executor, Sqlite, SqlitePool};
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Instant;
 
 pub type DbPool = SqlitePool;
 pub type DbResult<T> = Result<T, sqlx::Error>;
 
+/// 判断错误是否由唯一约束冲突引起（如重复的角色名），便于调用方返回 409 而不是 500
+pub fn is_unique_violation(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .is_some_and(|db_error| db_error.is_unique_violation())
+}
+
+/// `BCRYPT_COST` 允许的取值范围
+const BCRYPT_COST_RANGE: std::ops::RangeInclusive<u32> = 4..=15;
+
+/// 密码哈希使用的 bcrypt 强度，读取环境变量 `BCRYPT_COST`（4–15），
+/// 未配置或取值无效时使用 `bcrypt::DEFAULT_COST`。首次调用后缓存
+pub fn bcrypt_cost() -> u32 {
+    static COST: OnceLock<u32> = OnceLock::new();
+    *COST.get_or_init(|| {
+        let Ok(value) = std::env::var("BCRYPT_COST") else {
+            return bcrypt::DEFAULT_COST;
+        };
+        match value.trim().parse::<u32>() {
+            Ok(cost) if BCRYPT_COST_RANGE.contains(&cost) => cost,
+            _ => {
+                tracing::warn!(
+                    "BCRYPT_COST={} 无效（应为 4-15 的整数），使用默认值 {}",
+                    value,
+                    bcrypt::DEFAULT_COST
+                );
+                bcrypt::DEFAULT_COST
+            }
+        }
+    })
+}
+
+/// 为旧数据库补充新增列（`CREATE TABLE IF NOT EXISTS` 不会修改已存在的表）
+async fn ensure_column(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> DbResult<()> {
+    let columns: Vec<String> =
+        sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .fetch_all(pool)
+            .await?;
+
+    if !columns.iter().any(|c| c == column) {
+        let sql = format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition);
+        pool.execute(sql.as_str()).await?;
+        tracing::info!("已为表 {} 添加列 {}", table, column);
+    }
+
+    Ok(())
+}
+
 pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
     pool.execute("PRAGMA foreign_keys = ON").await?;
     tracing::info!("已启用外键约束");
@@ -40,10 +119,30 @@ pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
             nickname VARCHAR NOT NULL,
             password VARCHAR NOT NULL,
             birthday VARCHAR,
+            avatar_url VARCHAR,
+            email VARCHAR,
+            must_change_password INTEGER NOT NULL DEFAULT 0,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            totp_secret VARCHAR,
+            totp_enabled INTEGER NOT NULL DEFAULT 0,
+            default_lp_type INTEGER,
             FOREIGN KEY(main_role_id) REFERENCES role(role_id)
         )",
     )
     .await?;
+    ensure_column(pool, "user", "avatar_url", "VARCHAR").await?;
+    ensure_column(pool, "user", "email", "VARCHAR").await?;
+    ensure_column(pool, "user", "is_active", "INTEGER NOT NULL DEFAULT 1").await?;
+    ensure_column(pool, "user", "totp_secret", "VARCHAR").await?;
+    ensure_column(pool, "user", "totp_enabled", "INTEGER NOT NULL DEFAULT 0").await?;
+    ensure_column(pool, "user", "default_lp_type", "INTEGER").await?;
+    ensure_column(
+        pool,
+        "user",
+        "must_change_password",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
 
     pool.execute(
         "CREATE TABLE IF NOT EXISTS permission (
@@ -92,6 +191,7 @@ pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
         )",
     )
     .await?;
+    ensure_column(pool, "lplog", "reject_reason", "VARCHAR").await?;
 
     pool.execute(
         "CREATE TABLE IF NOT EXISTS luckydrawlog (
@@ -111,6 +211,20 @@ pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
         )",
     )
     .await?;
+    ensure_column(
+        pool,
+        "luckydrawlog",
+        "recurrence",
+        "VARCHAR NOT NULL DEFAULT 'none'",
+    )
+    .await?;
+    ensure_column(
+        pool,
+        "luckydrawlog",
+        "recurrence_remaining",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
 
     pool.execute(
         "CREATE TABLE IF NOT EXISTS shopitems (
@@ -120,10 +234,14 @@ pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
             name VARCHAR NOT NULL,
             seller VARCHAR NOT NULL,
             location VARCHAR NOT NULL,
+            low_stock_threshold INTEGER,
+            expires_at VARCHAR,
             FOREIGN KEY(seller) REFERENCES user(qq)
         )",
     )
     .await?;
+    ensure_column(pool, "shopitems", "low_stock_threshold", "INTEGER").await?;
+    ensure_column(pool, "shopitems", "expires_at", "VARCHAR").await?;
 
     pool.execute(
         "CREATE TABLE IF NOT EXISTS shoplog (
@@ -150,10 +268,108 @@ pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
             body TEXT,
             status INTEGER NOT NULL,
             timestamp VARCHAR NOT NULL,
+            elapsed_ms INTEGER,
+            trace_id VARCHAR,
             FOREIGN KEY(user_qq) REFERENCES user(qq)
         )",
     )
     .await?;
+    ensure_column(pool, "requestlog", "elapsed_ms", "INTEGER").await?;
+    ensure_column(pool, "requestlog", "trace_id", "VARCHAR").await?;
+
+    // 管理员修改他人资料的审计记录（不设外键，用户删除后仍保留记录）
+    pool.execute(
+        "CREATE TABLE IF NOT EXISTS useraudit (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            operator_qq VARCHAR NOT NULL,
+            target_qq VARCHAR NOT NULL,
+            field VARCHAR NOT NULL,
+            old_value VARCHAR,
+            new_value VARCHAR,
+            time VARCHAR NOT NULL
+        )",
+    )
+    .await?;
+
+    // 商品库存降到提醒阈值时的记录（不设外键，商品售罄删除后仍保留记录）
+    pool.execute(
+        "CREATE TABLE IF NOT EXISTS stockalert (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            item_id INTEGER NOT NULL,
+            name VARCHAR NOT NULL,
+            seller VARCHAR NOT NULL,
+            remaining INTEGER NOT NULL,
+            threshold INTEGER NOT NULL,
+            time VARCHAR NOT NULL
+        )",
+    )
+    .await?;
+
+    pool.execute(
+        "CREATE TABLE IF NOT EXISTS idempotencykey (
+            user_qq VARCHAR NOT NULL,
+            scope VARCHAR NOT NULL,
+            idem_key VARCHAR NOT NULL,
+            status INTEGER,
+            response TEXT,
+            created_at VARCHAR NOT NULL,
+            PRIMARY KEY(user_qq, scope, idem_key),
+            FOREIGN KEY(user_qq) REFERENCES user(qq) ON DELETE CASCADE
+        )",
+    )
+    .await?;
+
+    // LP申请草稿，只属于创建者本人，提交后删除并写入 lplog
+    pool.execute(
+        "CREATE TABLE IF NOT EXISTS lp_draft (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            owner_qq VARCHAR NOT NULL,
+            user_qq VARCHAR,
+            lp_type INTEGER,
+            num INTEGER,
+            reason TEXT NOT NULL DEFAULT '',
+            picture VARCHAR,
+            role VARCHAR,
+            updated_at VARCHAR NOT NULL,
+            FOREIGN KEY(owner_qq) REFERENCES user(qq) ON DELETE CASCADE
+        )",
+    )
+    .await?;
+    pool.execute("CREATE INDEX IF NOT EXISTS idx_lp_draft_owner ON lp_draft(owner_qq)")
+        .await?;
+
+    // 站内通知，LP审批、抽奖中奖和库存提醒时写入，只有接收者本人可见
+    pool.execute(
+        "CREATE TABLE IF NOT EXISTS notification (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_qq VARCHAR NOT NULL,
+            kind VARCHAR NOT NULL,
+            message TEXT NOT NULL,
+            read INTEGER NOT NULL DEFAULT 0,
+            created_at VARCHAR NOT NULL,
+            FOREIGN KEY(user_qq) REFERENCES user(qq) ON DELETE CASCADE
+        )",
+    )
+    .await?;
+    pool.execute(
+        "CREATE INDEX IF NOT EXISTS idx_notification_user_read ON notification(user_qq, read)",
+    )
+    .await?;
+
+    // 每月LP汇总快照，period 为快照对应的月份（YYYY-MM），同一月份每个用户只保留一条
+    pool.execute(
+        "CREATE TABLE IF NOT EXISTS lp_summary_snapshot (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            period VARCHAR NOT NULL,
+            qq VARCHAR NOT NULL,
+            nickname VARCHAR NOT NULL,
+            total_lp INTEGER NOT NULL,
+            approved_count INTEGER NOT NULL,
+            created_at VARCHAR NOT NULL,
+            UNIQUE(period, qq)
+        )",
+    )
+    .await?;
 
     pool.execute(
         "CREATE VIEW IF NOT EXISTS user_lp_summary AS
@@ -181,18 +397,10 @@ pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
     )
     .await?;
 
-    pool.execute(
-        "CREATE TRIGGER IF NOT EXISTS update_shop_inventory
-        AFTER INSERT ON shoplog
-        FOR EACH ROW
-        BEGIN
-            UPDATE shopitems 
-            SET count = count - NEW.count 
-            WHERE id = (SELECT id FROM shopitems WHERE name = NEW.name AND seller = NEW.seller LIMIT 1)
-            AND count >= NEW.count;
-        END",
-    )
-    .await?;
+    // 旧版本用触发器按“名称 + 卖家”扣减库存，卖家有同名商品时可能扣错商品。
+    // 现在由购买逻辑在事务中按商品 ID 扣减，删除已存在的旧触发器
+    pool.execute("DROP TRIGGER IF EXISTS update_shop_inventory")
+        .await?;
 
     // 索引，优化查询性能
 
@@ -261,44 +469,19 @@ pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
     pool.execute("CREATE INDEX IF NOT EXISTS idx_shopitems_name ON shopitems(name)")
         .await?;
 
-    let default_permissions = vec![
-        "审核LP",
-        "发起抽奖",
-        "管理商品",
-        "用户管理",
-        "管理角色",
-        "查看日志",
-    ];
-
-    for perm in default_permissions {
-        sqlx::query("INSERT OR IGNORE INTO permission (name) VALUES (?)")
-            .bind(perm)
-            .execute(pool)
-            .await?;
-    }
-
-    let default_roles = vec![
-        ("管理员", "系统全面管理权限"),
-        ("审核员", "负责LP审批与抽奖管理"),
-        ("成员", "日常使用权限"),
-    ];
-
-    for (name, desc) in default_roles {
-        sqlx::query("INSERT OR IGNORE INTO role (name, description) VALUES (?, ?)")
-            .bind(name)
-            .bind(desc)
-            .execute(pool)
-            .await?;
-    }
+    // useraudit 表索引 - 按被修改用户查询
+    pool.execute(
+        "CREATE INDEX IF NOT EXISTS idx_useraudit_target_time ON useraudit(target_qq, time DESC)",
+    )
+    .await?;
 
-    let default_lp_types = vec!["奖励", "惩罚", "兑换", "调整"];
+    // idempotencykey 表索引 - 过期键清理
+    pool.execute(
+        "CREATE INDEX IF NOT EXISTS idx_idempotencykey_created ON idempotencykey(created_at)",
+    )
+    .await?;
 
-    for lp_type in default_lp_types {
-        sqlx::query("INSERT OR IGNORE INTO lptype (name) VALUES (?)")
-            .bind(lp_type)
-            .execute(pool)
-            .await?;
-    }
+    seed_defaults(pool).await?;
 
     // 创建默认管理员用户
     let admin_role_id: Option<i64> = sqlx::query_scalar("SELECT role_id FROM role WHERE name = ?")
@@ -320,11 +503,12 @@ pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
 
         if admin_exists.is_none() {
             // 使用 bcrypt 加密默认密码
-            use bcrypt::{hash, DEFAULT_COST};
-            let hashed_password = hash(default_admin_password, DEFAULT_COST).expect("密码加密失败");
+            let hashed_password =
+                bcrypt::hash(default_admin_password, bcrypt_cost()).expect("密码加密失败");
 
+            // 默认管理员首次登录后必须修改密码
             sqlx::query(
-                "INSERT INTO user (qq, main_role_id, nickname, password, birthday) VALUES (?, ?, ?, ?, NULL)",
+                "INSERT INTO user (qq, main_role_id, nickname, password, birthday, must_change_password) VALUES (?, ?, ?, ?, NULL, 1)",
             )
             .bind(default_admin_qq)
             .bind(role_id)
@@ -358,30 +542,136 @@ pub async fn init_database(pool: &SqlitePool) -> DbResult<()> {
     Ok(())
 }
 
+/// 补齐默认权限、角色和LP类型时实际新增的条目
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeededDefaults {
+    pub permissions: Vec<String>,
+    pub roles: Vec<String>,
+    pub lp_types: Vec<String>,
+}
+
+impl SeededDefaults {
+    pub fn is_empty(&self) -> bool {
+        self.permissions.is_empty() && self.roles.is_empty() && self.lp_types.is_empty()
+    }
+}
+
+/// 以 `INSERT OR IGNORE` 写入内置的权限、角色和LP类型，已存在的条目保持不变，不涉及用户数据。
+/// 新增的权限会授予管理员角色；管理员角色本身被重建时授予全部权限。
+/// 启动时由 `init_database` 调用，误删内置条目后也可通过管理接口再次调用恢复
+pub async fn seed_defaults(pool: &SqlitePool) -> DbResult<SeededDefaults> {
+    let mut seeded = SeededDefaults::default();
+
+    let default_permissions = vec![
+        "审核LP",
+        "发起抽奖",
+        "管理商品",
+        "用户管理",
+        "管理角色",
+        "查看日志",
+        "系统管理",
+    ];
+
+    // 记录本次新增的权限，稍后授予管理员角色，使旧数据库升级后管理员也能使用新功能
+    for perm in default_permissions {
+        let inserted = sqlx::query("INSERT OR IGNORE INTO permission (name) VALUES (?)")
+            .bind(perm)
+            .execute(pool)
+            .await?
+            .rows_affected();
+        if inserted > 0 {
+            seeded.permissions.push(perm.to_string());
+        }
+    }
+
+    let default_roles = vec![
+        ("管理员", "系统全面管理权限"),
+        ("审核员", "负责LP审批与抽奖管理"),
+        ("成员", "日常使用权限"),
+    ];
+
+    for (name, desc) in default_roles {
+        let inserted = sqlx::query("INSERT OR IGNORE INTO role (name, description) VALUES (?, ?)")
+            .bind(name)
+            .bind(desc)
+            .execute(pool)
+            .await?
+            .rows_affected();
+        if inserted > 0 {
+            seeded.roles.push(name.to_string());
+        }
+    }
+
+    let default_lp_types = vec!["奖励", "惩罚", "兑换", "调整"];
+
+    for lp_type in default_lp_types {
+        let inserted = sqlx::query("INSERT OR IGNORE INTO lptype (name) VALUES (?)")
+            .bind(lp_type)
+            .execute(pool)
+            .await?
+            .rows_affected();
+        if inserted > 0 {
+            seeded.lp_types.push(lp_type.to_string());
+        }
+    }
+
+    let admin_role_id: Option<i64> = sqlx::query_scalar("SELECT role_id FROM role WHERE name = ?")
+        .bind("管理员")
+        .fetch_optional(pool)
+        .await?;
+
+    if let Some(role_id) = admin_role_id {
+        let grants: Vec<String> = if seeded.roles.iter().any(|role| role == "管理员") {
+            sqlx::query_scalar("SELECT name FROM permission")
+                .fetch_all(pool)
+                .await?
+        } else {
+            seeded.permissions.clone()
+        };
+        for perm in grants {
+            sqlx::query(
+                "INSERT OR IGNORE INTO rolepermissionlink (role_id, permission_name) VALUES (?, ?)",
+            )
+            .bind(role_id)
+            .bind(&perm)
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    Ok(seeded)
+}
+
+/// 批量审批待审核的LP申请，返回本次实际被更新的申请ID。
+/// 已处理过的申请（包括被并发的另一次审批抢先处理的）不会出现在结果中
 pub async fn batch_approve_lp(
     pool: &SqlitePool,
     ids: &[i64],
     process_qq: &str,
     status: i32,
-) -> DbResult<u64> {
-    let process_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-    let mut approved: u64 = 0;
+    reject_reason: Option<&str>,
+) -> DbResult<Vec<i64>> {
+    let process_time = now_string();
+    let mut approved = Vec::new();
 
     let mut tx = pool.begin().await?;
 
     for id in ids {
         let result = sqlx::query(
-            "UPDATE lplog SET status = ?, process_user_qq = ?, process_time = ? \
+            "UPDATE lplog SET status = ?, process_user_qq = ?, process_time = ?, reject_reason = ? \
              WHERE id = ? AND status = 0",
         )
         .bind(status)
         .bind(process_qq)
         .bind(&process_time)
+        .bind(reject_reason.filter(|_| status == 2))
         .bind(id)
         .execute(&mut *tx)
         .await?;
 
-        approved += result.rows_affected();
+        if result.rows_affected() > 0 {
+            approved.push(*id);
+        }
     }
 
     tx.commit().await?;
@@ -389,67 +679,124 @@ pub async fn batch_approve_lp(
     Ok(approved)
 }
 
-pub async fn draw_lucky_winner(pool: &SqlitePool, draw_id: i64) -> DbResult<Option<Vec<String>>> {
+/// 查询 LP 总数达到抽奖门槛的用户（开奖和参与预览共用同一条件）
+pub(crate) async fn list_eligible_users<'e, E>(executor: E, min_lp: i64) -> DbResult<Vec<String>>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let users = sqlx::query_scalar::<_, String>(
+        "SELECT qq FROM user_lp_summary WHERE total_lp >= ? ORDER BY qq",
+    )
+    .bind(min_lp)
+    .fetch_all(executor)
+    .await?;
+    Ok(users)
+}
+
+/// 执行开奖。定时任务和手动开奖可能同时触发同一个抽奖，
+/// 因此状态检查与开奖结果写入放在同一事务中，并通过 `status = 0` 条件更新保证只有一次开奖生效。
+/// 事务以 `BEGIN IMMEDIATE` 开始，后到的一方等待先到的一方提交后再读取状态，得到“已开奖”而不是写锁冲突
+pub async fn draw_lucky_winner(pool: &SqlitePool, draw_id: i64) -> DbResult<DrawOutcome> {
+    draw_lucky_winner_with_threshold(pool, draw_id, None).await
+}
+
+/// 按指定的最低LP要求开奖，`min_lp_override` 为 `None` 时使用抽奖自身的要求。
+/// 只有成功开奖时才把新的要求写回抽奖记录，没有中奖者时抽奖保持原样
+pub async fn draw_lucky_winner_with_threshold(
+    pool: &SqlitePool,
+    draw_id: i64,
+    min_lp_override: Option<i64>,
+) -> DbResult<DrawOutcome> {
+    // 立即获取写锁：延迟事务中两个开奖请求都先读到未开奖，再争抢写锁时失败的一方会直接报 SQLITE_BUSY
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
     // 查询抽奖信息，包括关联的商品ID和数量
     let record = sqlx::query_as::<_, (i64, i32, Option<i64>, i32)>(
         "SELECT min_lp_require, status, item_id, num FROM luckydrawlog WHERE id = ?",
     )
     .bind(draw_id)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *tx)
     .await?;
 
-    let Some((min_lp, status, item_id, num)) = record else {
-        return Ok(None);
+    let Some((min_lp, status, _item_id, num)) = record else {
+        return Ok(DrawOutcome::NotFound);
     };
 
     if status != 0 {
-        return Ok(None);
+        return Ok(DrawOutcome::AlreadyExecuted);
     }
 
+    let min_lp = min_lp_override.unwrap_or(min_lp);
+
     // 获取所有符合条件的用户
-    let eligible_users =
-        sqlx::query_scalar::<_, String>("SELECT qq FROM user_lp_summary WHERE total_lp >= ?")
-            .bind(min_lp)
-            .fetch_all(pool)
-            .await?;
+    let eligible_users = list_eligible_users(&mut *tx, min_lp).await?;
 
-    // 如果没有符合条件的用户，返回 None
     if eligible_users.is_empty() {
-        return Ok(None);
+        return Ok(DrawOutcome::NoEligibleUsers);
     }
 
-    // 使用时间戳和抽奖ID作为随机种子选择中奖者
+    // 使用操作系统提供的安全随机源选择中奖者。
+    // 不能用时间戳等可推测的值做种子：开奖时间和抽奖ID都是公开的，
+    // 知道大致开奖时间的人可以枚举种子、预测结果，甚至挑选对自己有利的时机手动开奖。
     use rand::seq::SliceRandom;
-    use rand::SeedableRng;
-    let seed = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_nanos() as u64
-        ^ draw_id as u64;
-    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut rng = rand::rngs::OsRng;
 
     // 根据数量选择多个中奖者（不重复）
     let winner_count = std::cmp::min(num as usize, eligible_users.len());
-    let mut shuffled = eligible_users.clone();
+    let mut shuffled = eligible_users;
     shuffled.shuffle(&mut rng);
     let winners: Vec<String> = shuffled.into_iter().take(winner_count).collect();
 
-    if !winners.is_empty() {
-        // 将多个中奖者用逗号连接存储
-        let winners_str = winners.join(", ");
+    if winners.is_empty() {
+        return Ok(DrawOutcome::NoEligibleUsers);
+    }
 
-        // 更新抽奖状态为已开奖
-        sqlx::query("UPDATE luckydrawlog SET status = 1, winner_qq = ? WHERE id = ?")
-            .bind(&winners_str)
-            .bind(draw_id)
-            .execute(pool)
-            .await?;
+    // 将多个中奖者用逗号连接存储
+    let winners_str = winners.join(", ");
+
+    // 仅当抽奖仍未开奖时才写入结果，另一方已抢先开奖则放弃本次结果
+    let result = sqlx::query(
+        "UPDATE luckydrawlog SET status = 1, winner_qq = ?, min_lp_require = ?
+         WHERE id = ? AND status = 0",
+    )
+    .bind(&winners_str)
+    .bind(min_lp)
+    .bind(draw_id)
+    .execute(&mut *tx)
+    .await?;
 
-        tracing::info!("抽奖 {} 开奖成功，中奖者: {}", draw_id, winners_str);
-        Ok(Some(winners))
-    } else {
-        Ok(None)
+    if result.rows_affected() == 0 {
+        tracing::warn!("抽奖 {} 已被其他请求开奖，放弃本次结果", draw_id);
+        return Ok(DrawOutcome::AlreadyExecuted);
     }
+
+    tx.commit().await?;
+
+    tracing::info!("抽奖 {} 开奖成功，中奖者: {}", draw_id, winners_str);
+    Ok(DrawOutcome::Drawn(winners))
+}
+
+/// 当前请求的上下文，由请求日志中间件设置
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    /// 请求开始时间
+    pub start: Instant,
+    /// 请求追踪 ID，同时出现在应用日志和 `X-Request-Id` 响应头中
+    pub trace_id: String,
+}
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: RequestContext;
+}
+
+/// 在请求上下文中执行 `future`，其中调用的 [`record_request_log`] 会记录请求耗时和追踪 ID
+pub async fn with_request_context<F: Future>(context: RequestContext, future: F) -> F::Output {
+    REQUEST_CONTEXT.scope(context, future).await
+}
+
+/// 当前请求的追踪 ID，不在请求上下文中时返回 `None`
+pub fn current_trace_id() -> Option<String> {
+    REQUEST_CONTEXT.try_with(|ctx| ctx.trace_id.clone()).ok()
 }
 
 pub async fn record_request_log(
@@ -460,12 +807,21 @@ pub async fn record_request_log(
     body: Option<String>,
     status: i32,
 ) -> DbResult<()> {
-    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let timestamp = now_string();
     let user_value = user_qq.unwrap_or("");
     let body_value = body.unwrap_or_default();
+    // 不在请求中调用（如定时任务）时没有请求上下文，耗时和追踪 ID 留空
+    let (elapsed_ms, trace_id) = REQUEST_CONTEXT
+        .try_with(|ctx| {
+            (
+                Some(ctx.start.elapsed().as_millis() as i64),
+                Some(ctx.trace_id.clone()),
+            )
+        })
+        .unwrap_or((None, None));
 
     sqlx::query(
-        "INSERT INTO requestlog (method, path, user_qq, body, status, timestamp) VALUES (?, ?, NULLIF(?, ''), ?, ?, ?)",
+        "INSERT INTO requestlog (method, path, user_qq, body, status, timestamp, elapsed_ms, trace_id) VALUES (?, ?, NULLIF(?, ''), ?, ?, ?, ?, ?)",
     )
     .bind(method)
     .bind(path)
@@ -473,6 +829,8 @@ pub async fn record_request_log(
     .bind(body_value)
     .bind(status)
     .bind(timestamp)
+    .bind(elapsed_ms)
+    .bind(trace_id)
     .execute(pool)
     .await?;
 
@@ -0,0 +1,87 @@
+use crate::db::{now_string, DbResult};
+use crate::models::UserNotification;
+use sqlx::SqlitePool;
+
+/// 站内通知类型：LP申请被审批
+pub const NOTIFICATION_KIND_LP_PROCESSED: &str = "lp_processed";
+/// 站内通知类型：抽奖中奖
+pub const NOTIFICATION_KIND_DRAW_WON: &str = "draw_won";
+/// 站内通知类型：商品库存偏低
+pub const NOTIFICATION_KIND_LOW_STOCK: &str = "low_stock";
+
+pub struct NotificationService;
+
+impl NotificationService {
+    // 写入一条站内通知
+    pub async fn create(
+        pool: &SqlitePool,
+        user_qq: &str,
+        kind: &str,
+        message: &str,
+    ) -> DbResult<i64> {
+        let result = sqlx::query(
+            "INSERT INTO notification (user_qq, kind, message, read, created_at) VALUES (?, ?, ?, 0, ?)",
+        )
+        .bind(user_qq)
+        .bind(kind)
+        .bind(message)
+        .bind(now_string())
+        .execute(pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    // 分页获取用户自己的通知，最新的在前
+    pub async fn list_for_user(
+        pool: &SqlitePool,
+        user_qq: &str,
+        limit: i64,
+        offset: i64,
+    ) -> DbResult<Vec<UserNotification>> {
+        let notifications = sqlx::query_as::<_, UserNotification>(
+            "SELECT id, user_qq, kind, message, read, created_at FROM notification
+             WHERE user_qq = ? ORDER BY id DESC LIMIT ? OFFSET ?",
+        )
+        .bind(user_qq)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(notifications)
+    }
+
+    // 统计用户的通知总数
+    pub async fn count_for_user(pool: &SqlitePool, user_qq: &str) -> DbResult<i64> {
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM notification WHERE user_qq = ?")
+                .bind(user_qq)
+                .fetch_one(pool)
+                .await?;
+
+        Ok(count)
+    }
+
+    // 统计用户的未读通知数
+    pub async fn count_unread(pool: &SqlitePool, user_qq: &str) -> DbResult<i64> {
+        let (count,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM notification WHERE user_qq = ? AND read = 0")
+                .bind(user_qq)
+                .fetch_one(pool)
+                .await?;
+
+        Ok(count)
+    }
+
+    // 把用户自己的通知标记为已读，通知不存在或不属于该用户时返回 false
+    pub async fn mark_read(pool: &SqlitePool, user_qq: &str, id: i64) -> DbResult<bool> {
+        let result = sqlx::query("UPDATE notification SET read = 1 WHERE id = ? AND user_qq = ?")
+            .bind(id)
+            .bind(user_qq)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
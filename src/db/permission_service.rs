@@ -21,6 +21,26 @@ impl PermissionService {
         Ok(permissions)
     }
 
+    /// 获取用户的所有权限及授予该权限的角色
+    pub async fn get_user_permission_sources(
+        pool: &SqlitePool,
+        user_qq: &str,
+    ) -> DbResult<Vec<EffectivePermission>> {
+        let permissions = sqlx::query_as::<_, EffectivePermission>(
+            "SELECT rpl.permission_name AS permission, r.name AS via_role
+             FROM user u
+             JOIN role r ON u.main_role_id = r.role_id
+             JOIN rolepermissionlink rpl ON r.role_id = rpl.role_id
+             WHERE u.qq = ?
+             ORDER BY rpl.permission_name",
+        )
+        .bind(user_qq)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(permissions)
+    }
+
     /// 获取所有权限
     pub async fn get_all_permissions(pool: &SqlitePool) -> DbResult<Vec<Permission>> {
         let permissions = sqlx::query_as::<_, Permission>("SELECT name FROM permission")
@@ -30,3 +50,35 @@ impl PermissionService {
         Ok(permissions)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, memory_pool};
+    use crate::db::RoleService;
+
+    #[tokio::test]
+    async fn permission_sources_name_the_granting_role() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let role_id = RoleService::create_role(&pool, "仓管", None).await.unwrap();
+        for permission in ["管理商品", "查看日志"] {
+            RoleService::grant_permission_to_role(&pool, role_id, permission)
+                .await
+                .unwrap();
+        }
+        RoleService::assign_main_role(&pool, "10001", role_id, "9999")
+            .await
+            .unwrap();
+
+        let sources = PermissionService::get_user_permission_sources(&pool, "10001")
+            .await
+            .unwrap();
+
+        let pairs: Vec<(&str, &str)> = sources
+            .iter()
+            .map(|p| (p.permission.as_str(), p.via_role.as_str()))
+            .collect();
+        assert_eq!(pairs, vec![("查看日志", "仓管"), ("管理商品", "仓管")]);
+    }
+}
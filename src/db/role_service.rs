@@ -1,12 +1,42 @@
-use crate::db::DbResult;
-use crate::models::Role;
-use sqlx::SqlitePool;
+use crate::db::{ServiceError, ServiceResult, UserService};
+use crate::models::{Role, RoleMember};
+use serde::Serialize;
+use sqlx::{SqliteConnection, SqlitePool};
 
 pub struct RoleService;
 
+/// 管理角色所需的权限，移除或改派时必须保证仍有账号拥有它
+const ROLE_ADMIN_PERMISSION: &str = "管理角色";
+
+/// 删除角色的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteRoleOutcome {
+    /// 已删除，`reassigned` 为被改回"成员"角色的用户数
+    Deleted { reassigned: i64 },
+    /// 仍有用户使用该角色，未删除
+    InUse(i64),
+}
+
+/// 批量分配角色时单个用户的处理状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkAssignStatus {
+    Assigned,
+    /// 第一个默认管理员受保护，不修改其角色
+    SkippedProtected,
+    NotFound,
+}
+
+/// 批量分配角色时单个用户的处理结果
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkAssignLine {
+    pub user_qq: String,
+    pub status: BulkAssignStatus,
+}
+
 impl RoleService {
     /// 获取所有角色
-    pub async fn get_all_roles(pool: &SqlitePool) -> DbResult<Vec<Role>> {
+    pub async fn get_all_roles(pool: &SqlitePool) -> ServiceResult<Vec<Role>> {
         let roles = sqlx::query_as::<_, Role>(
             "SELECT role_id, name, description FROM role ORDER BY role_id",
         )
@@ -21,7 +51,7 @@ impl RoleService {
         pool: &SqlitePool,
         name: &str,
         description: Option<&str>,
-    ) -> DbResult<i64> {
+    ) -> ServiceResult<i64> {
         let result = sqlx::query("INSERT INTO role (name, description) VALUES (?, ?)")
             .bind(name)
             .bind(description)
@@ -32,7 +62,7 @@ impl RoleService {
     }
 
     /// 根据名称获取角色
-    pub async fn get_role_by_name(pool: &SqlitePool, name: &str) -> DbResult<Option<Role>> {
+    pub async fn get_role_by_name(pool: &SqlitePool, name: &str) -> ServiceResult<Option<Role>> {
         let role =
             sqlx::query_as::<_, Role>("SELECT role_id, name, description FROM role WHERE name = ?")
                 .bind(name)
@@ -43,7 +73,7 @@ impl RoleService {
     }
 
     /// 获取用户主角色
-    pub async fn get_user_role(pool: &SqlitePool, user_qq: &str) -> DbResult<Option<Role>> {
+    pub async fn get_user_role(pool: &SqlitePool, user_qq: &str) -> ServiceResult<Option<Role>> {
         let role = sqlx::query_as::<_, Role>(
             "SELECT r.role_id, r.name, r.description
              FROM user u
@@ -57,22 +87,157 @@ impl RoleService {
         Ok(role)
     }
 
-    /// 设置用户主角色
-    pub async fn assign_main_role(pool: &SqlitePool, user_qq: &str, role_id: i64) -> DbResult<()> {
+    /// 设置用户的主角色，角色或用户不存在时返回 `NotFound`。新角色没有“管理角色”权限时，
+    /// 不允许操作人把自己改过去，也不允许改掉最后一个拥有该权限的启用账号，此时返回 `Conflict`
+    pub async fn assign_main_role(
+        pool: &SqlitePool,
+        user_qq: &str,
+        role_id: i64,
+        actor_qq: &str,
+    ) -> ServiceResult<()> {
+        ensure_role_exists(pool, role_id).await?;
+
+        // 先读后写，立即获取写锁，避免并发时延迟事务升级写锁失败报 SQLITE_BUSY
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        let current_role: Option<Option<i64>> =
+            sqlx::query_scalar("SELECT main_role_id FROM user WHERE qq = ?")
+                .bind(user_qq)
+                .fetch_optional(&mut *tx)
+                .await?;
+        let Some(current_role) = current_role else {
+            return Err(ServiceError::NotFound("用户不存在".to_string()));
+        };
+
+        let loses_role_admin = match current_role {
+            Some(current_role) if current_role != role_id => {
+                role_has_admin_permission(&mut tx, current_role).await?
+                    && !role_has_admin_permission(&mut tx, role_id).await?
+            }
+            _ => false,
+        };
+        if loses_role_admin {
+            if user_qq == actor_qq {
+                return Err(ServiceError::Conflict(
+                    "不能把自己改为没有“管理角色”权限的角色，否则将无法再管理角色".to_string(),
+                ));
+            }
+            let remaining = count_role_admins(&mut tx, None, Some(user_qq)).await?;
+            if remaining == 0 {
+                return Err(ServiceError::Conflict(
+                    "该用户是最后一个拥有“管理角色”权限的账号，不能改为没有该权限的角色"
+                        .to_string(),
+                ));
+            }
+        }
+
         sqlx::query("UPDATE user SET main_role_id = ? WHERE qq = ?")
             .bind(role_id)
             .bind(user_qq)
-            .execute(pool)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
         Ok(())
     }
 
-    /// 给角色分配权限
+    /// 在同一事务中为多个用户设置主角色，重复的QQ只处理一次。
+    /// 第一个默认管理员和不存在的用户会被跳过；角色不存在时返回 `None`。
+    /// 新角色没有“管理角色”权限时，与 [`Self::assign_main_role`] 一样不允许操作人改掉自己，
+    /// 也不允许改完后没有任何启用账号拥有该权限，此时返回 `Conflict`，整批都不生效
+    pub async fn assign_main_role_bulk(
+        pool: &SqlitePool,
+        role_id: i64,
+        user_qqs: &[String],
+        actor_qq: &str,
+    ) -> ServiceResult<Option<Vec<BulkAssignLine>>> {
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        let role_exists: Option<i64> =
+            sqlx::query_scalar("SELECT role_id FROM role WHERE role_id = ?")
+                .bind(role_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if role_exists.is_none() {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+
+        let first_user_qq = UserService::protected_admin_qq(&mut *tx).await?;
+
+        let revokes_role_admin = !role_has_admin_permission(&mut tx, role_id).await?;
+        if revokes_role_admin
+            && Some(actor_qq) != first_user_qq.as_deref()
+            && user_qqs.iter().any(|qq| qq == actor_qq)
+        {
+            let actor_role: Option<Option<i64>> =
+                sqlx::query_scalar("SELECT main_role_id FROM user WHERE qq = ?")
+                    .bind(actor_qq)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+            if let Some(actor_role) = actor_role.flatten() {
+                if actor_role != role_id && role_has_admin_permission(&mut tx, actor_role).await? {
+                    return Err(ServiceError::Conflict(
+                        "不能把自己改为没有“管理角色”权限的角色，否则将无法再管理角色".to_string(),
+                    ));
+                }
+            }
+        }
+
+        let mut lines: Vec<BulkAssignLine> = Vec::with_capacity(user_qqs.len());
+        for user_qq in user_qqs {
+            if lines.iter().any(|line| &line.user_qq == user_qq) {
+                continue;
+            }
+
+            let status = if Some(user_qq.as_str()) == first_user_qq.as_deref() {
+                BulkAssignStatus::SkippedProtected
+            } else {
+                let result = sqlx::query("UPDATE user SET main_role_id = ? WHERE qq = ?")
+                    .bind(role_id)
+                    .bind(user_qq)
+                    .execute(&mut *tx)
+                    .await?;
+                if result.rows_affected() > 0 {
+                    BulkAssignStatus::Assigned
+                } else {
+                    BulkAssignStatus::NotFound
+                }
+            };
+            lines.push(BulkAssignLine {
+                user_qq: user_qq.clone(),
+                status,
+            });
+        }
+
+        // 未提交的事务在返回时回滚
+        if revokes_role_admin && count_role_admins(&mut tx, None, None).await? == 0 {
+            return Err(ServiceError::Conflict(
+                "分配后将没有任何账号拥有“管理角色”权限，请保留至少一个拥有该权限的账号"
+                    .to_string(),
+            ));
+        }
+
+        tx.commit().await?;
+        Ok(Some(lines))
+    }
+
+    /// 给角色分配权限，角色或权限不存在时返回 `NotFound`
     pub async fn grant_permission_to_role(
         pool: &SqlitePool,
         role_id: i64,
         permission_name: &str,
-    ) -> DbResult<()> {
+    ) -> ServiceResult<()> {
+        ensure_role_exists(pool, role_id).await?;
+
+        let permission: Option<String> =
+            sqlx::query_scalar("SELECT name FROM permission WHERE name = ?")
+                .bind(permission_name)
+                .fetch_optional(pool)
+                .await?;
+        if permission.is_none() {
+            return Err(ServiceError::NotFound("权限不存在".to_string()));
+        }
+
         sqlx::query(
             "INSERT OR IGNORE INTO rolepermissionlink (role_id, permission_name) VALUES (?, ?)",
         )
@@ -83,22 +248,50 @@ impl RoleService {
         Ok(())
     }
 
-    /// 从角色移除权限
+    /// 从角色移除权限。移除“管理角色”时，不允许移除操作人自己所在角色的该权限，
+    /// 也不允许移除后没有任何启用账号拥有该权限，此时返回 `Conflict`
     pub async fn revoke_permission_from_role(
         pool: &SqlitePool,
         role_id: i64,
         permission_name: &str,
-    ) -> DbResult<()> {
+        actor_qq: &str,
+    ) -> ServiceResult<()> {
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        if permission_name == ROLE_ADMIN_PERMISSION {
+            let actor_role: Option<Option<i64>> =
+                sqlx::query_scalar("SELECT main_role_id FROM user WHERE qq = ?")
+                    .bind(actor_qq)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+            if actor_role.flatten() == Some(role_id) {
+                return Err(ServiceError::Conflict(
+                    "不能移除自己所在角色的“管理角色”权限，否则将无法再管理角色".to_string(),
+                ));
+            }
+            let remaining = count_role_admins(&mut tx, Some(role_id), None).await?;
+            if remaining == 0 {
+                return Err(ServiceError::Conflict(
+                    "移除后将没有任何账号拥有“管理角色”权限，请先为其他角色或账号授予该权限"
+                        .to_string(),
+                ));
+            }
+        }
+
         sqlx::query("DELETE FROM rolepermissionlink WHERE role_id = ? AND permission_name = ?")
             .bind(role_id)
             .bind(permission_name)
-            .execute(pool)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
         Ok(())
     }
 
     /// 获取角色的所有权限
-    pub async fn get_role_permissions(pool: &SqlitePool, role_id: i64) -> DbResult<Vec<String>> {
+    pub async fn get_role_permissions(
+        pool: &SqlitePool,
+        role_id: i64,
+    ) -> ServiceResult<Vec<String>> {
         let permissions = sqlx::query_scalar::<_, String>(
             "SELECT permission_name FROM rolepermissionlink WHERE role_id = ? ORDER BY permission_name",
         )
@@ -109,8 +302,48 @@ impl RoleService {
         Ok(permissions)
     }
 
-    /// 删除角色
-    pub async fn delete_role(pool: &SqlitePool, role_id: i64) -> DbResult<()> {
+    /// 获取拥有指定权限的所有角色
+    pub async fn get_roles_with_permission(
+        pool: &SqlitePool,
+        permission_name: &str,
+    ) -> ServiceResult<Vec<Role>> {
+        let roles = sqlx::query_as::<_, Role>(
+            "SELECT r.role_id, r.name, r.description FROM role r
+             JOIN rolepermissionlink l ON l.role_id = r.role_id
+             WHERE l.permission_name = ?
+             ORDER BY r.role_id",
+        )
+        .bind(permission_name)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(roles)
+    }
+
+    /// 获取以该角色为主角色的所有用户
+    pub async fn get_users_by_role(
+        pool: &SqlitePool,
+        role_id: i64,
+    ) -> ServiceResult<Vec<RoleMember>> {
+        let users = sqlx::query_as::<_, RoleMember>(
+            "SELECT qq, nickname FROM user WHERE main_role_id = ? ORDER BY nickname",
+        )
+        .bind(role_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    /// 删除角色。仍有用户使用该角色时，除非 `force` 为真，否则不删除并返回使用人数；
+    /// 强制删除会在同一事务中先将这些用户改为"成员"角色。角色拥有“管理角色”权限时，
+    /// 不允许强制删除操作人自己所在的角色，也不允许删除后没有任何启用账号拥有该权限，此时返回 `Conflict`
+    pub async fn delete_role(
+        pool: &SqlitePool,
+        role_id: i64,
+        force: bool,
+        actor_qq: &str,
+    ) -> ServiceResult<DeleteRoleOutcome> {
         // 检查是否是系统核心角色（管理员或成员）
         let role_name: Option<String> =
             sqlx::query_scalar("SELECT name FROM role WHERE role_id = ?")
@@ -118,37 +351,424 @@ impl RoleService {
                 .fetch_optional(pool)
                 .await?;
 
-        if let Some(name) = role_name {
-            if name == "管理员" || name == "成员" {
-                return Err(sqlx::Error::Decode(
-                    format!("不能删除系统核心角色: {}", name).into(),
+        let Some(name) = role_name else {
+            return Err(ServiceError::NotFound("角色不存在".to_string()));
+        };
+        if name == "管理员" || name == "成员" {
+            return Err(ServiceError::Validation(format!(
+                "不能删除系统核心角色: {}",
+                name
+            )));
+        }
+
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        let user_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM user WHERE main_role_id = ?")
+                .bind(role_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        if user_count > 0 && !force {
+            tx.rollback().await?;
+            return Ok(DeleteRoleOutcome::InUse(user_count));
+        }
+
+        if user_count > 0 && role_has_admin_permission(&mut tx, role_id).await? {
+            let actor_role: Option<Option<i64>> =
+                sqlx::query_scalar("SELECT main_role_id FROM user WHERE qq = ?")
+                    .bind(actor_qq)
+                    .fetch_optional(&mut *tx)
+                    .await?;
+            if actor_role.flatten() == Some(role_id) {
+                return Err(ServiceError::Conflict(
+                    "不能删除自己所在的拥有“管理角色”权限的角色，否则将无法再管理角色".to_string(),
+                ));
+            }
+            if count_role_admins(&mut tx, Some(role_id), None).await? == 0 {
+                return Err(ServiceError::Conflict(
+                    "删除后将没有任何账号拥有“管理角色”权限，请先为其他角色或账号授予该权限"
+                        .to_string(),
                 ));
             }
         }
 
-        // 查询"成员"角色的ID
-        let member_role_id: Option<i64> =
-            sqlx::query_scalar("SELECT role_id FROM role WHERE name = ?")
-                .bind("成员")
-                .fetch_optional(pool)
-                .await?;
+        if user_count > 0 {
+            // 查询"成员"角色的ID
+            let member_role_id: Option<i64> =
+                sqlx::query_scalar("SELECT role_id FROM role WHERE name = ?")
+                    .bind("成员")
+                    .fetch_optional(&mut *tx)
+                    .await?;
 
-        let member_role_id =
-            member_role_id.ok_or_else(|| sqlx::Error::Decode("未找到'成员'角色".into()))?;
+            let member_role_id = member_role_id
+                .ok_or_else(|| ServiceError::NotFound("未找到'成员'角色".to_string()))?;
 
-        // 将使用此角色的用户的main_role_id设为"成员"角色
-        sqlx::query("UPDATE user SET main_role_id = ? WHERE main_role_id = ?")
-            .bind(member_role_id)
-            .bind(role_id)
-            .execute(pool)
-            .await?;
+            // 将使用此角色的用户的main_role_id设为"成员"角色
+            sqlx::query("UPDATE user SET main_role_id = ? WHERE main_role_id = ?")
+                .bind(member_role_id)
+                .bind(role_id)
+                .execute(&mut *tx)
+                .await?;
+        }
 
         // 删除角色（级联删除会自动删除rolepermissionlink中的记录）
         sqlx::query("DELETE FROM role WHERE role_id = ?")
             .bind(role_id)
-            .execute(pool)
+            .execute(&mut *tx)
             .await?;
 
-        Ok(())
+        tx.commit().await?;
+
+        Ok(DeleteRoleOutcome::Deleted {
+            reassigned: user_count,
+        })
+    }
+}
+
+/// 角色是否拥有“管理角色”权限
+async fn role_has_admin_permission(
+    conn: &mut SqliteConnection,
+    role_id: i64,
+) -> ServiceResult<bool> {
+    let found: Option<i64> = sqlx::query_scalar(
+        "SELECT 1 FROM rolepermissionlink WHERE role_id = ? AND permission_name = ?",
+    )
+    .bind(role_id)
+    .bind(ROLE_ADMIN_PERMISSION)
+    .fetch_optional(conn)
+    .await?;
+    Ok(found.is_some())
+}
+
+/// 统计拥有“管理角色”权限的启用账号数，可排除某个角色（将被移除该权限）或某个用户（将被改派）
+async fn count_role_admins(
+    conn: &mut SqliteConnection,
+    excluded_role: Option<i64>,
+    excluded_user: Option<&str>,
+) -> ServiceResult<i64> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM user u
+         JOIN rolepermissionlink l ON l.role_id = u.main_role_id
+         WHERE l.permission_name = ? AND u.is_active = 1
+           AND (? IS NULL OR u.main_role_id != ?)
+           AND (? IS NULL OR u.qq != ?)",
+    )
+    .bind(ROLE_ADMIN_PERMISSION)
+    .bind(excluded_role)
+    .bind(excluded_role)
+    .bind(excluded_user)
+    .bind(excluded_user)
+    .fetch_one(conn)
+    .await?;
+    Ok(count)
+}
+
+/// 角色不存在时返回 `NotFound("角色不存在")`，避免依赖外键约束报出数据库错误
+async fn ensure_role_exists(pool: &SqlitePool, role_id: i64) -> ServiceResult<()> {
+    let role: Option<i64> = sqlx::query_scalar("SELECT role_id FROM role WHERE role_id = ?")
+        .bind(role_id)
+        .fetch_optional(pool)
+        .await?;
+    match role {
+        Some(_) => Ok(()),
+        None => Err(ServiceError::NotFound("角色不存在".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, memory_pool};
+
+    #[tokio::test]
+    async fn users_by_role_lists_every_holder() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        add_user(&pool, "10002").await;
+        add_user(&pool, "10003").await;
+        let role_id = RoleService::create_role(&pool, "仓管", None).await.unwrap();
+        for qq in ["10001", "10002"] {
+            RoleService::assign_main_role(&pool, qq, role_id, "9999")
+                .await
+                .unwrap();
+        }
+
+        let mut members: Vec<String> = RoleService::get_users_by_role(&pool, role_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|member| member.qq)
+            .collect();
+        members.sort();
+
+        assert_eq!(members, vec!["10001", "10002"]);
+    }
+
+    async fn role_of(pool: &SqlitePool, qq: &str) -> Option<String> {
+        RoleService::get_user_role(pool, qq)
+            .await
+            .unwrap()
+            .map(|role| role.name)
+    }
+
+    #[tokio::test]
+    async fn deleting_role_in_use_is_blocked_without_force() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let role_id = RoleService::create_role(&pool, "仓管", None).await.unwrap();
+        RoleService::assign_main_role(&pool, "10001", role_id, "9999")
+            .await
+            .unwrap();
+
+        let outcome = RoleService::delete_role(&pool, role_id, false, "9999")
+            .await
+            .unwrap();
+
+        assert_eq!(outcome, DeleteRoleOutcome::InUse(1));
+        assert!(RoleService::get_role_by_name(&pool, "仓管")
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(role_of(&pool, "10001").await.as_deref(), Some("仓管"));
+    }
+
+    #[tokio::test]
+    async fn forced_delete_moves_holders_back_to_member() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let role_id = RoleService::create_role(&pool, "仓管", None).await.unwrap();
+        RoleService::assign_main_role(&pool, "10001", role_id, "9999")
+            .await
+            .unwrap();
+
+        let outcome = RoleService::delete_role(&pool, role_id, true, "9999")
+            .await
+            .unwrap();
+
+        assert_eq!(outcome, DeleteRoleOutcome::Deleted { reassigned: 1 });
+        assert!(RoleService::get_role_by_name(&pool, "仓管")
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(role_of(&pool, "10001").await.as_deref(), Some("成员"));
+    }
+
+    #[tokio::test]
+    async fn bulk_assign_skips_protected_admin_and_unknown_users() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        add_user(&pool, "10002").await;
+        let role_id = RoleService::create_role(&pool, "仓管", None).await.unwrap();
+        let qqs: Vec<String> = ["9999", "10001", "10002", "10001", "40404"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        let lines = RoleService::assign_main_role_bulk(&pool, role_id, &qqs, "9999")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let statuses: Vec<(&str, BulkAssignStatus)> = lines
+            .iter()
+            .map(|line| (line.user_qq.as_str(), line.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("9999", BulkAssignStatus::SkippedProtected),
+                ("10001", BulkAssignStatus::Assigned),
+                ("10002", BulkAssignStatus::Assigned),
+                ("40404", BulkAssignStatus::NotFound),
+            ]
+        );
+        assert_eq!(role_of(&pool, "9999").await.as_deref(), Some("管理员"));
+        assert_eq!(role_of(&pool, "10001").await.as_deref(), Some("仓管"));
+        assert!(RoleService::assign_main_role_bulk(&pool, 404, &qqs, "9999")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn roles_with_permission_lists_every_granting_role() {
+        let pool = memory_pool().await;
+        let events = RoleService::create_role(&pool, "活动组", None)
+            .await
+            .unwrap();
+        let publicity = RoleService::create_role(&pool, "宣传组", None)
+            .await
+            .unwrap();
+        let other = RoleService::create_role(&pool, "后勤组", None)
+            .await
+            .unwrap();
+        for role_id in [events, publicity] {
+            RoleService::grant_permission_to_role(&pool, role_id, "发起抽奖")
+                .await
+                .unwrap();
+        }
+
+        let roles = RoleService::get_roles_with_permission(&pool, "发起抽奖")
+            .await
+            .unwrap();
+
+        let ids: Vec<i64> = roles.iter().map(|role| role.role_id).collect();
+        assert!(ids.contains(&events));
+        assert!(ids.contains(&publicity));
+        assert!(!ids.contains(&other));
+    }
+
+    #[tokio::test]
+    async fn bogus_role_or_permission_is_not_found() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let role_id = RoleService::create_role(&pool, "活动组", None)
+            .await
+            .unwrap();
+        let message = |result: ServiceResult<()>| match result {
+            Err(ServiceError::NotFound(message)) => message,
+            other => panic!("应当返回 NotFound: {:?}", other),
+        };
+
+        assert_eq!(
+            message(RoleService::assign_main_role(&pool, "10001", 999, "9999").await),
+            "角色不存在"
+        );
+        assert_eq!(
+            message(RoleService::grant_permission_to_role(&pool, 999, "发起抽奖").await),
+            "角色不存在"
+        );
+        assert_eq!(
+            message(RoleService::grant_permission_to_role(&pool, role_id, "不存在的权限").await),
+            "权限不存在"
+        );
+        assert!(RoleService::get_role_permissions(&pool, role_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn last_role_admin_grant_cannot_be_revoked() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let admin = RoleService::get_role_by_name(&pool, "管理员")
+            .await
+            .unwrap()
+            .unwrap()
+            .role_id;
+        let reviewer = RoleService::get_role_by_name(&pool, "审核员")
+            .await
+            .unwrap()
+            .unwrap()
+            .role_id;
+
+        // 只有默认管理员账号拥有“管理角色”，移除后将无人能管理角色
+        match RoleService::revoke_permission_from_role(&pool, admin, ROLE_ADMIN_PERMISSION, "10001")
+            .await
+        {
+            Err(ServiceError::Conflict(message)) => assert_eq!(
+                message,
+                "移除后将没有任何账号拥有“管理角色”权限，请先为其他角色或账号授予该权限"
+            ),
+            other => panic!("应当返回 Conflict: {:?}", other),
+        }
+        assert!(RoleService::get_role_permissions(&pool, admin)
+            .await
+            .unwrap()
+            .iter()
+            .any(|name| name == ROLE_ADMIN_PERMISSION));
+
+        // 另有启用账号拥有该权限后即可移除
+        RoleService::grant_permission_to_role(&pool, reviewer, ROLE_ADMIN_PERMISSION)
+            .await
+            .unwrap();
+        RoleService::assign_main_role(&pool, "10001", reviewer, "9999")
+            .await
+            .unwrap();
+        RoleService::revoke_permission_from_role(&pool, admin, ROLE_ADMIN_PERMISSION, "10001")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn bulk_assign_and_forced_delete_keep_a_role_admin() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        add_user(&pool, "10002").await;
+        let keeper = RoleService::create_role(&pool, "仓管", None).await.unwrap();
+        let member = RoleService::get_role_by_name(&pool, "成员")
+            .await
+            .unwrap()
+            .unwrap()
+            .role_id;
+        RoleService::grant_permission_to_role(&pool, keeper, ROLE_ADMIN_PERMISSION)
+            .await
+            .unwrap();
+        RoleService::assign_main_role(&pool, "10001", keeper, "9999")
+            .await
+            .unwrap();
+        let conflict = |result: ServiceResult<()>| match result {
+            Err(ServiceError::Conflict(message)) => message,
+            other => panic!("应当返回 Conflict: {:?}", other),
+        };
+        let qqs = |list: &[&str]| list.iter().map(|qq| qq.to_string()).collect::<Vec<_>>();
+
+        // 不能通过批量分配或强制删除把自己移出“管理角色”
+        assert_eq!(
+            conflict(
+                RoleService::assign_main_role_bulk(&pool, member, &qqs(&["10001"]), "10001")
+                    .await
+                    .map(|_| ())
+            ),
+            "不能把自己改为没有“管理角色”权限的角色，否则将无法再管理角色"
+        );
+        assert_eq!(
+            conflict(
+                RoleService::delete_role(&pool, keeper, true, "10001")
+                    .await
+                    .map(|_| ())
+            ),
+            "不能删除自己所在的拥有“管理角色”权限的角色，否则将无法再管理角色"
+        );
+
+        // 默认管理员被禁用后 10001 是唯一的角色管理者，其他人也不能把他移走
+        sqlx::query("UPDATE user SET is_active = 0 WHERE qq = '9999'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            conflict(
+                RoleService::assign_main_role_bulk(
+                    &pool,
+                    member,
+                    &qqs(&["10001", "10002"]),
+                    "10002"
+                )
+                .await
+                .map(|_| ())
+            ),
+            "分配后将没有任何账号拥有“管理角色”权限，请保留至少一个拥有该权限的账号"
+        );
+        assert_eq!(
+            conflict(
+                RoleService::delete_role(&pool, keeper, true, "10002")
+                    .await
+                    .map(|_| ())
+            ),
+            "删除后将没有任何账号拥有“管理角色”权限，请先为其他角色或账号授予该权限"
+        );
+        assert_eq!(role_of(&pool, "10001").await.as_deref(), Some("仓管"));
+        assert_eq!(role_of(&pool, "10002").await.as_deref(), Some("成员"));
+
+        sqlx::query("UPDATE user SET is_active = 1 WHERE qq = '9999'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let outcome = RoleService::delete_role(&pool, keeper, true, "10002")
+            .await
+            .unwrap();
+        assert_eq!(outcome, DeleteRoleOutcome::Deleted { reassigned: 1 });
     }
 }
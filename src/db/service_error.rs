@@ -0,0 +1,85 @@
+use std::fmt;
+
+/// 服务层错误：区分“不存在”“冲突”“参数无效”“无权操作”和数据库故障，
+/// 处理函数据此返回 404/409/400/403/500，而不是一律返回 500
+#[derive(Debug)]
+pub enum ServiceError {
+    NotFound(String),
+    Conflict(String),
+    Validation(String),
+    Forbidden(String),
+    Db(sqlx::Error),
+}
+
+pub type ServiceResult<T> = Result<T, ServiceError>;
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::NotFound(message)
+            | ServiceError::Conflict(message)
+            | ServiceError::Validation(message)
+            | ServiceError::Forbidden(message) => f.write_str(message),
+            ServiceError::Db(e) => write!(f, "数据库错误: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ServiceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ServiceError::Db(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// 唯一约束冲突转换为 `Conflict`，`fetch_one` 查不到记录转换为 `NotFound`，其余视为数据库故障
+impl From<sqlx::Error> for ServiceError {
+    fn from(error: sqlx::Error) -> Self {
+        if super::is_unique_violation(&error) {
+            return ServiceError::Conflict("数据已存在".to_string());
+        }
+        match error {
+            sqlx::Error::RowNotFound => ServiceError::NotFound("记录不存在".to_string()),
+            error => ServiceError::Db(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, memory_pool};
+    use crate::db::RoleService;
+
+    #[tokio::test]
+    async fn unique_violation_maps_to_conflict() {
+        let pool = memory_pool().await;
+        RoleService::create_role(&pool, "活动组", None)
+            .await
+            .unwrap();
+
+        let error = RoleService::create_role(&pool, "活动组", None)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ServiceError::Conflict(_)));
+    }
+
+    #[tokio::test]
+    async fn missing_rows_map_to_not_found() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+
+        let error = RoleService::assign_main_role(&pool, "10001", 999, "9999")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ServiceError::NotFound(_)));
+
+        assert!(matches!(
+            ServiceError::from(sqlx::Error::RowNotFound),
+            ServiceError::NotFound(_)
+        ));
+    }
+}
@@ -1,61 +1,171 @@
-use crate::db::DbResult;
+use crate::db::{now_string, DbResult, ServiceError, ServiceResult};
 use crate::models::*;
-use chrono::Local;
-use sqlx::SqlitePool;
+use serde::Serialize;
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
 
 pub struct ShopService;
 
+/// 批量购买中单个商品的处理状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchPurchaseStatus {
+    Purchased,
+    InsufficientStock,
+    NotFound,
+    /// 本商品可购买，但批次中其他商品失败导致整体回滚
+    RolledBack,
+}
+
+/// 批量购买中单个商品的处理结果
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchPurchaseLine {
+    pub item_id: i64,
+    pub count: i32,
+    pub name: Option<String>,
+    pub price: Option<String>,
+    pub status: BatchPurchaseStatus,
+}
+
+/// 购买后库存降到提醒阈值及以下时产生的提醒
+#[derive(Debug, Clone, Serialize)]
+pub struct StockAlert {
+    pub item_id: i64,
+    pub name: String,
+    pub seller: String,
+    pub remaining: i32,
+    pub threshold: i32,
+}
+
+/// 批量导入时待上架的商品
+#[derive(Debug, Clone)]
+pub struct NewShopItem {
+    pub name: String,
+    pub price: String,
+    pub count: i32,
+    pub location: String,
+}
+
+/// 商品列表筛选条件
+#[derive(Debug, Clone, Default)]
+pub struct ShopItemFilter {
+    pub keyword: Option<String>,
+    pub in_stock_only: bool,
+    /// 只显示指定卖家的商品
+    pub seller: Option<String>,
+    /// 价格区间（含边界），价格按数值比较
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+}
+
+impl ShopItemFilter {
+    fn keyword_pattern(&self) -> Option<String> {
+        self.keyword
+            .as_deref()
+            .map(str::trim)
+            .filter(|kw| !kw.is_empty())
+            .map(|kw| format!("%{}%", kw))
+    }
+}
+
 impl ShopService {
     // 上架商品
-    pub async fn add_item(
-        pool: &SqlitePool,
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add_item<'e, E: SqliteExecutor<'e>>(
+        executor: E,
         count: i32,
         price: &str,
         name: &str,
         seller: &str,
         location: &str,
+        low_stock_threshold: Option<i32>,
+        expires_at: Option<&str>,
     ) -> DbResult<i64> {
         let result = sqlx::query(
-            "INSERT INTO shopitems (count, price, name, seller, location) VALUES (?, ?, ?, ?, ?)",
+            "INSERT INTO shopitems (count, price, name, seller, location, low_stock_threshold, expires_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
         )
         .bind(count)
         .bind(price)
         .bind(name)
         .bind(seller)
         .bind(location)
-        .execute(pool)
+        .bind(low_stock_threshold)
+        .bind(expires_at)
+        .execute(executor)
         .await?;
 
         Ok(result.last_insert_rowid())
     }
 
+    // 批量上架商品，全部在同一事务中插入，返回新商品ID（与传入顺序一致）
+    pub async fn import_items(
+        pool: &SqlitePool,
+        seller: &str,
+        items: &[NewShopItem],
+    ) -> DbResult<Vec<i64>> {
+        let mut tx = pool.begin().await?;
+        let mut ids = Vec::with_capacity(items.len());
+        for item in items {
+            let id = Self::add_item(
+                &mut *tx,
+                item.count,
+                &item.price,
+                &item.name,
+                seller,
+                &item.location,
+                None,
+                None,
+            )
+            .await?;
+            ids.push(id);
+        }
+        tx.commit().await?;
+
+        Ok(ids)
+    }
+
     // 购买商品
     pub async fn purchase_item(
         pool: &SqlitePool,
         buyer: &str,
         item_id: i64,
         count: i32,
-    ) -> DbResult<bool> {
+    ) -> DbResult<(bool, Option<StockAlert>)> {
         let mut tx = pool.begin().await?;
 
         let item = sqlx::query_as::<_, ShopItem>(
-            "SELECT id, count, price, name, seller, location FROM shopitems WHERE id = ?",
+            "SELECT id, count, price, name, seller, location, low_stock_threshold, expires_at
+             FROM shopitems WHERE id = ? AND (expires_at IS NULL OR expires_at > ?)",
         )
         .bind(item_id)
+        .bind(now_string())
         .fetch_optional(&mut *tx)
         .await?;
 
         let Some(item) = item else {
             tx.rollback().await?;
-            return Ok(false);
+            return Ok((false, None));
         };
 
         if item.count < count {
             tx.rollback().await?;
-            return Ok(false);
+            return Ok((false, None));
         }
 
-        let time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        // 按商品 ID 扣减库存，条件更新防止并发购买导致超卖
+        let updated =
+            sqlx::query("UPDATE shopitems SET count = count - ? WHERE id = ? AND count >= ?")
+                .bind(count)
+                .bind(item_id)
+                .bind(count)
+                .execute(&mut *tx)
+                .await?;
+        if updated.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok((false, None));
+        }
+
+        let time = now_string();
 
         sqlx::query(
             "INSERT INTO shoplog (buyer, count, price, name, time, seller, location)
@@ -74,9 +184,12 @@ impl ShopService {
         let remaining = sqlx::query_scalar::<_, i32>("SELECT count FROM shopitems WHERE id = ?")
             .bind(item_id)
             .fetch_optional(&mut *tx)
-            .await?;
+            .await?
+            .unwrap_or(0);
+
+        let alert = record_low_stock_alert(&mut tx, &item, remaining, &time).await?;
 
-        if remaining.unwrap_or(0) <= 0 {
+        if remaining <= 0 {
             sqlx::query("DELETE FROM shopitems WHERE id = ?")
                 .bind(item_id)
                 .execute(&mut *tx)
@@ -85,24 +198,240 @@ impl ShopService {
 
         tx.commit().await?;
 
-        Ok(true)
+        Ok((true, alert))
     }
 
-    // 获取所有在售商品
+    // 批量购买：所有商品在同一事务中处理，任一商品失败则全部回滚。
+    // 同一商品出现多次时合并数量，合并后溢出则整单拒绝。返回是否全部成功以及逐项结果。
+    pub async fn purchase_items(
+        pool: &SqlitePool,
+        buyer: &str,
+        items: &[(i64, i32)],
+    ) -> ServiceResult<(bool, Vec<BatchPurchaseLine>, Vec<StockAlert>)> {
+        let mut merged: Vec<(i64, i32)> = Vec::new();
+        for &(item_id, count) in items {
+            match merged.iter_mut().find(|(id, _)| *id == item_id) {
+                Some((_, total)) => {
+                    *total = total
+                        .checked_add(count)
+                        .ok_or_else(|| ServiceError::Validation("购买数量过大".to_string()))?;
+                }
+                None => merged.push((item_id, count)),
+            }
+        }
+
+        let mut tx = pool.begin().await?;
+
+        // 先检查全部商品，确认都能购买后再写入
+        let mut found = Vec::with_capacity(merged.len());
+        let mut lines = Vec::with_capacity(merged.len());
+        for (item_id, count) in merged {
+            let item = sqlx::query_as::<_, ShopItem>(
+                "SELECT id, count, price, name, seller, location, low_stock_threshold, expires_at
+             FROM shopitems WHERE id = ? AND (expires_at IS NULL OR expires_at > ?)",
+            )
+            .bind(item_id)
+            .bind(now_string())
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            let status = match &item {
+                None => BatchPurchaseStatus::NotFound,
+                Some(item) if item.count < count => BatchPurchaseStatus::InsufficientStock,
+                Some(_) => BatchPurchaseStatus::Purchased,
+            };
+            lines.push(BatchPurchaseLine {
+                item_id,
+                count,
+                name: item.as_ref().map(|i| i.name.clone()),
+                price: item.as_ref().map(|i| i.price.clone()),
+                status,
+            });
+            if let Some(item) = item {
+                found.push((item, count));
+            }
+        }
+
+        if lines
+            .iter()
+            .any(|line| line.status != BatchPurchaseStatus::Purchased)
+        {
+            tx.rollback().await?;
+            for line in lines.iter_mut() {
+                if line.status == BatchPurchaseStatus::Purchased {
+                    line.status = BatchPurchaseStatus::RolledBack;
+                }
+            }
+            return Ok((false, lines, Vec::new()));
+        }
+
+        let time = now_string();
+        let mut alerts = Vec::new();
+        for (item, count) in found {
+            let updated =
+                sqlx::query("UPDATE shopitems SET count = count - ? WHERE id = ? AND count >= ?")
+                    .bind(count)
+                    .bind(item.id)
+                    .bind(count)
+                    .execute(&mut *tx)
+                    .await?;
+            if updated.rows_affected() == 0 {
+                // 检查之后库存被并发购买占用，整单回滚
+                tx.rollback().await?;
+                for line in lines.iter_mut() {
+                    line.status = if Some(line.item_id) == item.id {
+                        BatchPurchaseStatus::InsufficientStock
+                    } else {
+                        BatchPurchaseStatus::RolledBack
+                    };
+                }
+                return Ok((false, lines, Vec::new()));
+            }
+
+            sqlx::query(
+                "INSERT INTO shoplog (buyer, count, price, name, time, seller, location)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(buyer)
+            .bind(count)
+            .bind(&item.price)
+            .bind(&item.name)
+            .bind(&time)
+            .bind(&item.seller)
+            .bind(&item.location)
+            .execute(&mut *tx)
+            .await?;
+
+            let remaining =
+                sqlx::query_scalar::<_, i32>("SELECT count FROM shopitems WHERE id = ?")
+                    .bind(item.id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .unwrap_or(0);
+
+            alerts.extend(record_low_stock_alert(&mut tx, &item, remaining, &time).await?);
+
+            if remaining <= 0 {
+                sqlx::query("DELETE FROM shopitems WHERE id = ?")
+                    .bind(item.id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok((true, lines, alerts))
+    }
+
+    // 获取所有在售商品（已过下架时间的商品不再展示）
     pub async fn get_all_items(pool: &SqlitePool) -> DbResult<Vec<ShopItem>> {
         let items = sqlx::query_as::<_, ShopItem>(
-            "SELECT id, count, price, name, seller, location FROM shopitems WHERE count > 0",
+            "SELECT id, count, price, name, seller, location, low_stock_threshold, expires_at
+             FROM shopitems
+             WHERE count > 0 AND (expires_at IS NULL OR expires_at > ?)",
+        )
+        .bind(now_string())
+        .fetch_all(pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    // 分页查询商品（关键字匹配名称/地点/卖家，可按价格区间筛选），已过下架时间的商品不再展示
+    pub async fn list_items(
+        pool: &SqlitePool,
+        filter: &ShopItemFilter,
+        limit: i64,
+        offset: i64,
+    ) -> DbResult<Vec<ShopItem>> {
+        let pattern = filter.keyword_pattern();
+        let items = sqlx::query_as::<_, ShopItem>(
+            "SELECT id, count, price, name, seller, location, low_stock_threshold, expires_at
+             FROM shopitems
+             WHERE (? = 0 OR count > 0)
+               AND (expires_at IS NULL OR expires_at > ?)
+               AND (? IS NULL OR name LIKE ? OR location LIKE ? OR seller LIKE ?)
+               AND (? IS NULL OR seller = ?)
+               AND (? IS NULL OR CAST(price AS REAL) >= ?)
+               AND (? IS NULL OR CAST(price AS REAL) <= ?)
+             ORDER BY id
+             LIMIT ? OFFSET ?",
         )
+        .bind(filter.in_stock_only)
+        .bind(now_string())
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&filter.seller)
+        .bind(&filter.seller)
+        .bind(filter.min_price)
+        .bind(filter.min_price)
+        .bind(filter.max_price)
+        .bind(filter.max_price)
+        .bind(limit)
+        .bind(offset)
         .fetch_all(pool)
         .await?;
 
         Ok(items)
     }
 
+    // 统计符合条件的商品数量
+    pub async fn count_items(pool: &SqlitePool, filter: &ShopItemFilter) -> DbResult<i64> {
+        let pattern = filter.keyword_pattern();
+        let (count,) = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM shopitems
+             WHERE (? = 0 OR count > 0)
+               AND (expires_at IS NULL OR expires_at > ?)
+               AND (? IS NULL OR name LIKE ? OR location LIKE ? OR seller LIKE ?)
+               AND (? IS NULL OR seller = ?)
+               AND (? IS NULL OR CAST(price AS REAL) >= ?)
+               AND (? IS NULL OR CAST(price AS REAL) <= ?)",
+        )
+        .bind(filter.in_stock_only)
+        .bind(now_string())
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(&filter.seller)
+        .bind(&filter.seller)
+        .bind(filter.min_price)
+        .bind(filter.min_price)
+        .bind(filter.max_price)
+        .bind(filter.max_price)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    // 列出有在售商品（有库存且未过期）的卖家及其商品数，按卖家分组可使用 idx_shopitems_seller
+    pub async fn list_sellers(pool: &SqlitePool) -> DbResult<Vec<ShopSeller>> {
+        let sellers = sqlx::query_as::<_, ShopSeller>(
+            "SELECT s.seller AS qq, COALESCE(u.nickname, s.seller) AS nickname, s.item_count
+             FROM (
+                 SELECT seller, COUNT(*) AS item_count FROM shopitems
+                 WHERE count > 0 AND (expires_at IS NULL OR expires_at > ?)
+                 GROUP BY seller
+             ) s
+             LEFT JOIN user u ON u.qq = s.seller
+             ORDER BY nickname, qq",
+        )
+        .bind(now_string())
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sellers)
+    }
+
     // 获取用户的商品
     pub async fn get_user_items(pool: &SqlitePool, seller: &str) -> DbResult<Vec<ShopItem>> {
         let items = sqlx::query_as::<_, ShopItem>(
-            "SELECT id, count, price, name, seller, location FROM shopitems WHERE seller = ?",
+            "SELECT id, count, price, name, seller, location, low_stock_threshold, expires_at
+             FROM shopitems WHERE seller = ?",
         )
         .bind(seller)
         .fetch_all(pool)
@@ -111,6 +440,48 @@ impl ShopService {
         Ok(items)
     }
 
+    // 原子地调整商品库存（补货为正数，也可用负数修正），调整后库存为负时不修改并返回 None，
+    // 成功时返回调整后的库存
+    pub async fn restock_item(
+        pool: &SqlitePool,
+        item_id: i64,
+        delta: i32,
+    ) -> DbResult<Option<i32>> {
+        let count = sqlx::query_scalar::<_, i32>(
+            "UPDATE shopitems SET count = count + ? WHERE id = ? AND count + ? >= 0 RETURNING count",
+        )
+        .bind(delta)
+        .bind(item_id)
+        .bind(delta)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    // 获取单个商品
+    pub async fn get_item(pool: &SqlitePool, item_id: i64) -> DbResult<Option<ShopItem>> {
+        let item = sqlx::query_as::<_, ShopItem>(
+            "SELECT id, count, price, name, seller, location, low_stock_threshold, expires_at
+             FROM shopitems WHERE id = ?",
+        )
+        .bind(item_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(item)
+    }
+
+    // 转让商品给其他卖家（历史交易记录保留原卖家）
+    pub async fn transfer_item(pool: &SqlitePool, item_id: i64, new_seller: &str) -> DbResult<()> {
+        sqlx::query("UPDATE shopitems SET seller = ? WHERE id = ?")
+            .bind(new_seller)
+            .bind(item_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
     // 修改商品信息
     pub async fn update_item(
         pool: &SqlitePool,
@@ -118,6 +489,8 @@ impl ShopService {
         count: Option<i32>,
         price: Option<String>,
         location: Option<String>,
+        low_stock_threshold: Option<Option<i32>>,
+        expires_at: Option<Option<String>>,
     ) -> DbResult<()> {
         if let Some(c) = count {
             sqlx::query("UPDATE shopitems SET count = ? WHERE id = ?")
@@ -143,9 +516,40 @@ impl ShopService {
                 .await?;
         }
 
+        // 外层 None 表示不修改，Some(None) 表示关闭库存提醒
+        if let Some(threshold) = low_stock_threshold {
+            sqlx::query("UPDATE shopitems SET low_stock_threshold = ? WHERE id = ?")
+                .bind(threshold)
+                .bind(item_id)
+                .execute(pool)
+                .await?;
+        }
+
+        // 外层 None 表示不修改，Some(None) 表示取消下架时间
+        if let Some(expires_at) = expires_at {
+            sqlx::query("UPDATE shopitems SET expires_at = ? WHERE id = ?")
+                .bind(expires_at)
+                .bind(item_id)
+                .execute(pool)
+                .await?;
+        }
+
         Ok(())
     }
 
+    // 删除所有已过下架时间的商品，返回删除数量
+    pub async fn delete_expired_items(pool: &SqlitePool) -> DbResult<u64> {
+        let result = sqlx::query(
+            "DELETE FROM shopitems
+             WHERE expires_at IS NOT NULL AND expires_at <= ?",
+        )
+        .bind(now_string())
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
     // 删除商品
     pub async fn delete_item(pool: &SqlitePool, item_id: i64) -> DbResult<()> {
         sqlx::query("DELETE FROM shopitems WHERE id = ?")
@@ -167,43 +571,472 @@ impl ShopService {
         Ok(logs)
     }
 
-    // 获取用户购买记录
-    pub async fn get_user_purchases(pool: &SqlitePool, buyer: &str) -> DbResult<Vec<ShopLog>> {
+    // 按ID获取单条交易记录
+    pub async fn get_transaction(pool: &SqlitePool, id: i64) -> DbResult<Option<ShopLog>> {
+        let log = sqlx::query_as::<_, ShopLog>(
+            "SELECT id, buyer, count, price, name, time, seller, location
+             FROM shoplog WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(log)
+    }
+
+    // 分页获取用户购买记录，最新的在前
+    pub async fn get_user_purchases(
+        pool: &SqlitePool,
+        buyer: &str,
+        limit: i64,
+        offset: i64,
+    ) -> DbResult<Vec<ShopLog>> {
         let logs = sqlx::query_as::<_, ShopLog>(
             "SELECT id, buyer, count, price, name, time, seller, location
-             FROM shoplog WHERE buyer = ? ORDER BY time DESC",
+             FROM shoplog WHERE buyer = ? ORDER BY time DESC
+             LIMIT ? OFFSET ?",
         )
         .bind(buyer)
+        .bind(limit)
+        .bind(offset)
         .fetch_all(pool)
         .await?;
 
         Ok(logs)
     }
 
-    // 获取用户销售记录
-    pub async fn get_user_sales(pool: &SqlitePool, seller: &str) -> DbResult<Vec<ShopLog>> {
+    // 统计用户购买记录总数
+    pub async fn count_user_purchases(pool: &SqlitePool, buyer: &str) -> DbResult<i64> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM shoplog WHERE buyer = ?")
+            .bind(buyer)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    // 分页获取用户销售记录，最新的在前
+    pub async fn get_user_sales(
+        pool: &SqlitePool,
+        seller: &str,
+        limit: i64,
+        offset: i64,
+    ) -> DbResult<Vec<ShopLog>> {
         let logs = sqlx::query_as::<_, ShopLog>(
             "SELECT id, buyer, count, price, name, time, seller, location
-             FROM shoplog WHERE seller = ? ORDER BY time DESC",
+             FROM shoplog WHERE seller = ? ORDER BY time DESC
+             LIMIT ? OFFSET ?",
         )
         .bind(seller)
+        .bind(limit)
+        .bind(offset)
         .fetch_all(pool)
         .await?;
 
         Ok(logs)
     }
 
+    // 统计用户销售记录总数
+    pub async fn count_user_sales(pool: &SqlitePool, seller: &str) -> DbResult<i64> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM shoplog WHERE seller = ?")
+            .bind(seller)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    // 统计卖家累计售出件数、销售总额和不同买家数
+    pub async fn get_seller_stats(pool: &SqlitePool, seller: &str) -> DbResult<SellerStats> {
+        let stats = sqlx::query_as::<_, SellerStats>(
+            "SELECT COALESCE(SUM(count), 0) AS items_sold,
+                    COALESCE(SUM(CAST(price AS REAL) * count), 0.0) AS revenue,
+                    COUNT(DISTINCT buyer) AS distinct_buyers
+             FROM shoplog WHERE seller = ?",
+        )
+        .bind(seller)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(stats)
+    }
+
     // 搜索商品
     pub async fn search_items(pool: &SqlitePool, keyword: &str) -> DbResult<Vec<ShopItem>> {
         let pattern = format!("%{}%", keyword);
         let items = sqlx::query_as::<_, ShopItem>(
-            "SELECT id, count, price, name, seller, location
-             FROM shopitems WHERE name LIKE ? AND count > 0",
+            "SELECT id, count, price, name, seller, location, low_stock_threshold, expires_at
+             FROM shopitems
+             WHERE name LIKE ? AND count > 0 AND (expires_at IS NULL OR expires_at > ?)",
         )
         .bind(pattern)
+        .bind(now_string())
         .fetch_all(pool)
         .await?;
 
         Ok(items)
     }
 }
+
+/// 购买后剩余库存从阈值以上降到阈值及以下时记录一条库存提醒，
+/// 已经低于阈值的商品继续售出不会重复提醒
+async fn record_low_stock_alert(
+    conn: &mut SqliteConnection,
+    item: &ShopItem,
+    remaining: i32,
+    time: &str,
+) -> DbResult<Option<StockAlert>> {
+    let (Some(item_id), Some(threshold)) = (item.id, item.low_stock_threshold) else {
+        return Ok(None);
+    };
+    if item.count <= threshold || remaining > threshold {
+        return Ok(None);
+    }
+
+    sqlx::query(
+        "INSERT INTO stockalert (item_id, name, seller, remaining, threshold, time)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(item_id)
+    .bind(&item.name)
+    .bind(&item.seller)
+    .bind(remaining)
+    .bind(threshold)
+    .bind(time)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(Some(StockAlert {
+        item_id,
+        name: item.name.clone(),
+        seller: item.seller.clone(),
+        remaining,
+        threshold,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, grant_lp, memory_pool, TempDb};
+
+    async fn stock_of(pool: &SqlitePool, item_id: i64) -> i32 {
+        sqlx::query_scalar("SELECT count FROM shopitems WHERE id = ?")
+            .bind(item_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn batch_with_insufficient_stock_rolls_back_entirely() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let coffee = ShopService::add_item(&pool, 5, "10", "咖啡", "9999", "A区", None, None)
+            .await
+            .unwrap();
+        let tea = ShopService::add_item(&pool, 1, "8", "茶", "9999", "A区", None, None)
+            .await
+            .unwrap();
+
+        let (success, lines, alerts) =
+            ShopService::purchase_items(&pool, "10001", &[(coffee, 2), (tea, 3)])
+                .await
+                .unwrap();
+
+        assert!(!success);
+        assert!(alerts.is_empty());
+        let statuses: Vec<_> = lines.iter().map(|line| line.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                BatchPurchaseStatus::RolledBack,
+                BatchPurchaseStatus::InsufficientStock
+            ]
+        );
+        assert_eq!(stock_of(&pool, coffee).await, 5);
+        assert_eq!(stock_of(&pool, tea).await, 1);
+        let logs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM shoplog")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(logs, 0);
+    }
+
+    #[tokio::test]
+    async fn overflowing_merged_count_is_rejected() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let coffee = ShopService::add_item(&pool, 5, "10", "咖啡", "9999", "A区", None, None)
+            .await
+            .unwrap();
+
+        let result =
+            ShopService::purchase_items(&pool, "10001", &[(coffee, i32::MAX), (coffee, 1)]).await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        assert_eq!(stock_of(&pool, coffee).await, 5);
+    }
+
+    #[tokio::test]
+    async fn buying_down_to_threshold_records_one_alert() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        let item_id = ShopService::add_item(&pool, 5, "10", "咖啡", "9999", "A区", Some(2), None)
+            .await
+            .unwrap();
+
+        let (_, alert) = ShopService::purchase_item(&pool, "10001", item_id, 1)
+            .await
+            .unwrap();
+        assert!(alert.is_none());
+
+        let (success, alert) = ShopService::purchase_item(&pool, "10001", item_id, 2)
+            .await
+            .unwrap();
+        assert!(success);
+        let alert = alert.expect("库存降到阈值时应当提醒");
+        assert_eq!(
+            (
+                alert.item_id,
+                alert.seller.as_str(),
+                alert.remaining,
+                alert.threshold
+            ),
+            (item_id, "9999", 2, 2)
+        );
+
+        // 已经低于阈值后继续购买不再重复提醒
+        let (_, alert) = ShopService::purchase_item(&pool, "10001", item_id, 1)
+            .await
+            .unwrap();
+        assert!(alert.is_none());
+        let alerts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM stockalert")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(alerts, 1);
+    }
+
+    async fn insert_sale(pool: &SqlitePool, buyer: &str, seller: &str, name: &str, time: &str) {
+        sqlx::query(
+            "INSERT INTO shoplog (buyer, count, price, name, time, seller, location)
+             VALUES (?, 1, '10', ?, ?, ?, 'A区')",
+        )
+        .bind(buyer)
+        .bind(name)
+        .bind(time)
+        .bind(seller)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn transactions_are_paged_newest_first_with_separate_totals() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        for day in 1..=5 {
+            let time = format!("2026-01-0{} 12:00:00", day);
+            insert_sale(&pool, "10001", "9999", &format!("商品{}", day), &time).await;
+        }
+        insert_sale(&pool, "9999", "10001", "旧书", "2026-01-03 08:00:00").await;
+
+        let page = ShopService::get_user_purchases(&pool, "10001", 2, 1)
+            .await
+            .unwrap();
+        let names: Vec<&str> = page.iter().map(|log| log.name.as_str()).collect();
+        assert_eq!(names, vec!["商品4", "商品3"]);
+
+        assert_eq!(
+            ShopService::count_user_purchases(&pool, "10001")
+                .await
+                .unwrap(),
+            5
+        );
+        assert_eq!(
+            ShopService::count_user_sales(&pool, "10001").await.unwrap(),
+            1
+        );
+        let sales = ShopService::get_user_sales(&pool, "10001", 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(sales.len(), 1);
+        assert_eq!(sales[0].buyer, "9999");
+    }
+
+    #[tokio::test]
+    async fn seller_stats_sum_items_revenue_and_distinct_buyers() {
+        let pool = memory_pool().await;
+        for qq in ["10001", "10002", "10003", "10004"] {
+            add_user(&pool, qq).await;
+        }
+        for (buyer, seller, count, price) in [
+            ("10001", "10003", 2, "10"),
+            ("10001", "10003", 1, "2.5"),
+            ("10002", "10003", 3, "4"),
+            ("10001", "10004", 5, "100"),
+        ] {
+            sqlx::query(
+                "INSERT INTO shoplog (buyer, count, price, name, time, seller, location)
+                 VALUES (?, ?, ?, '咖啡', '2026-01-01 12:00:00', ?, 'A区')",
+            )
+            .bind(buyer)
+            .bind(count)
+            .bind(price)
+            .bind(seller)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let stats = ShopService::get_seller_stats(&pool, "10003").await.unwrap();
+        assert_eq!(stats.items_sold, 6);
+        assert_eq!(stats.revenue, 34.5);
+        assert_eq!(stats.distinct_buyers, 2);
+
+        let empty = ShopService::get_seller_stats(&pool, "9999").await.unwrap();
+        assert_eq!((empty.items_sold, empty.distinct_buyers), (0, 0));
+        assert_eq!(empty.revenue, 0.0);
+    }
+
+    #[tokio::test]
+    async fn purchase_only_changes_the_bought_listing() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        add_user(&pool, "10002").await;
+        grant_lp(&pool, "10002", 100).await;
+        let first = ShopService::add_item(&pool, 5, "10", "咖啡", "10001", "A区", None, None)
+            .await
+            .unwrap();
+        let second = ShopService::add_item(&pool, 5, "10", "咖啡", "10001", "B区", None, None)
+            .await
+            .unwrap();
+
+        let (purchased, _) = ShopService::purchase_item(&pool, "10002", second, 2)
+            .await
+            .unwrap();
+
+        assert!(purchased);
+        for (item_id, expected) in [(first, 5), (second, 3)] {
+            let item = ShopService::get_item(&pool, item_id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(item.count, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn sellers_list_only_those_with_items_in_stock() {
+        let pool = memory_pool().await;
+        for qq in ["10001", "10002", "10003"] {
+            add_user(&pool, qq).await;
+        }
+        let mut sold_out = Vec::new();
+        for (seller, in_stock) in [
+            ("10001", true),
+            ("10001", true),
+            ("10001", false),
+            ("10002", false),
+            ("10003", true),
+        ] {
+            let id = ShopService::add_item(&pool, 5, "10", "咖啡", seller, "A区", None, None)
+                .await
+                .unwrap();
+            if !in_stock {
+                sold_out.push(id);
+            }
+        }
+        for id in sold_out {
+            sqlx::query("UPDATE shopitems SET count = 0 WHERE id = ?")
+                .bind(id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        // 已下架的商品同样不计入
+        sqlx::query(
+            "UPDATE shopitems SET expires_at = '2020-01-01 00:00:00' WHERE seller = '10003'",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let sellers = ShopService::list_sellers(&pool).await.unwrap();
+
+        assert_eq!(sellers.len(), 1);
+        assert_eq!(sellers[0].qq, "10001");
+        assert_eq!(sellers[0].nickname, "用户10001");
+        assert_eq!(sellers[0].item_count, 2);
+    }
+
+    #[tokio::test]
+    async fn price_range_excludes_items_outside_bounds() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        for (price, name) in [
+            ("9.5", "咖啡"),
+            ("10", "咖啡"),
+            ("100", "咖啡"),
+            ("50", "茶"),
+        ] {
+            ShopService::add_item(&pool, 5, price, name, "10001", "A区", None, None)
+                .await
+                .unwrap();
+        }
+
+        // 按数值比较：字符串比较下 "100" 会落在 "10" 与 "50" 之间
+        let filter = ShopItemFilter {
+            min_price: Some(10.0),
+            max_price: Some(50.0),
+            ..Default::default()
+        };
+        let items = ShopService::list_items(&pool, &filter, 10, 0)
+            .await
+            .unwrap();
+        let prices: Vec<&str> = items.iter().map(|item| item.price.as_str()).collect();
+        assert_eq!(prices, ["10", "50"]);
+        assert_eq!(ShopService::count_items(&pool, &filter).await.unwrap(), 2);
+
+        let filter = ShopItemFilter {
+            keyword: Some("咖啡".to_string()),
+            ..filter
+        };
+        let items = ShopService::list_items(&pool, &filter, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].price, "10");
+        assert_eq!(ShopService::count_items(&pool, &filter).await.unwrap(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_restocks_all_apply() {
+        let db = TempDb::new().await;
+        let pool = &db.pool;
+        let item_id = ShopService::add_item(pool, 5, "10", "咖啡", "9999", "A区", None, None)
+            .await
+            .unwrap();
+
+        let restocks: Vec<_> = (1..=20)
+            .map(|delta| {
+                let pool = pool.clone();
+                tokio::spawn(async move { ShopService::restock_item(&pool, item_id, delta).await })
+            })
+            .collect();
+        for restock in restocks {
+            assert!(restock.await.unwrap().unwrap().is_some());
+        }
+
+        let item = ShopService::get_item(pool, item_id).await.unwrap().unwrap();
+        assert_eq!(item.count, 5 + (1..=20).sum::<i32>());
+        // 减到负数的调整被拒绝，库存不变
+        assert_eq!(
+            ShopService::restock_item(pool, item_id, -1000)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
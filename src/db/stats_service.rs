@@ -0,0 +1,48 @@
+use crate::db::{now_string, server_now, DbResult};
+use crate::models::OverviewStats;
+use sqlx::SqlitePool;
+
+pub struct StatsService;
+
+impl StatsService {
+    // 获取首页概览统计（均为可走索引的 COUNT 查询）
+    pub async fn get_overview(pool: &SqlitePool) -> DbResult<OverviewStats> {
+        let total_users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM user")
+            .fetch_one(pool)
+            .await?;
+
+        let pending_lp: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM lplog WHERE status = 0")
+            .fetch_one(pool)
+            .await?;
+
+        let active_draws: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM luckydrawlog WHERE status = 0")
+                .fetch_one(pool)
+                .await?;
+
+        let in_stock_items: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM shopitems
+                 WHERE count > 0
+                   AND (expires_at IS NULL OR expires_at > ?)",
+        )
+        .bind(now_string())
+        .fetch_one(pool)
+        .await?;
+
+        // 时间戳为 "%Y-%m-%d %H:%M:%S" 格式，按字符串比较即可命中时间索引
+        let today_start = server_now().format("%Y-%m-%d 00:00:00").to_string();
+        let today_requests: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM requestlog WHERE timestamp >= ?")
+                .bind(today_start)
+                .fetch_one(pool)
+                .await?;
+
+        Ok(OverviewStats {
+            total_users,
+            pending_lp,
+            active_draws,
+            in_stock_items,
+            today_requests,
+        })
+    }
+}
@@ -0,0 +1,96 @@
+//! 单元测试共用的数据库工具
+
+use crate::db::{init_database, UserService};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+async fn connect(url: &str, max_connections: u32) -> SqlitePool {
+    // 默认管理员和测试用户的密码用最低强度哈希，避免每个测试都花上数秒
+    std::env::set_var("BCRYPT_COST", "4");
+
+    let options = SqliteConnectOptions::from_str(url)
+        .expect("测试数据库地址无效")
+        .create_if_missing(true)
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(max_connections)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(options)
+        .await
+        .expect("无法连接测试数据库");
+    init_database(&pool).await.expect("初始化测试数据库失败");
+    pool
+}
+
+/// 执行过全部建表和补列迁移的内存数据库。
+/// 内存数据库只存在于单个连接中，所以连接池只保留一个连接且不回收
+pub async fn memory_pool() -> SqlitePool {
+    connect("sqlite::memory:", 1).await
+}
+
+/// 需要多个连接并发访问时使用的临时文件数据库，离开作用域时删除文件
+pub struct TempDb {
+    pub pool: SqlitePool,
+    path: PathBuf,
+}
+
+impl TempDb {
+    pub async fn new() -> Self {
+        static SEQ: AtomicU32 = AtomicU32::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "team-operation-test-{}-{}.db",
+            std::process::id(),
+            SEQ.fetch_add(1, Ordering::Relaxed)
+        ));
+        let pool = connect(&format!("sqlite:{}", path.display()), 4).await;
+        Self { pool, path }
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm", "-journal"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+        }
+    }
+}
+
+/// 注册一个“成员”角色的用户，密码为 `password`
+pub async fn add_user(pool: &SqlitePool, qq: &str) {
+    UserService::register(pool, qq, &format!("用户{}", qq), "password", None, None)
+        .await
+        .expect("注册测试用户失败");
+}
+
+/// 为用户写入一条已通过的LP记录
+pub async fn grant_lp(pool: &SqlitePool, qq: &str, num: i32) {
+    insert_lp_log(pool, qq, 1, num, 1).await;
+}
+
+/// 为用户写入一条指定类型和状态的LP记录（0 待审核，1 通过，2 拒绝），返回记录ID
+pub async fn insert_lp_log(
+    pool: &SqlitePool,
+    qq: &str,
+    lp_type: i64,
+    num: i32,
+    status: i32,
+) -> i64 {
+    sqlx::query(
+        "INSERT INTO lplog (upload_time, upload_user_qq, user_qq, lp_type, num, reason, status)
+         VALUES (?, ?, ?, ?, ?, '测试', ?)",
+    )
+    .bind(crate::db::now_string())
+    .bind(qq)
+    .bind(qq)
+    .bind(lp_type)
+    .bind(num)
+    .bind(status)
+    .execute(pool)
+    .await
+    .expect("写入测试LP记录失败")
+    .last_insert_rowid()
+}
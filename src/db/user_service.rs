@@ -1,10 +1,47 @@
-use crate::db::DbResult;
+use crate::db::{bcrypt_cost, now_string, ServiceResult};
 use crate::models::*;
-use bcrypt::{hash, verify, DEFAULT_COST};
-use sqlx::SqlitePool;
+use bcrypt::{hash, verify};
+use sqlx::{SqliteExecutor, SqlitePool};
 
 pub struct UserService;
 
+/// 用户列表筛选条件
+#[derive(Debug, Clone, Default)]
+pub struct UserFilter {
+    pub role_id: Option<i64>,
+    /// 按 QQ 或昵称模糊匹配
+    pub search: Option<String>,
+}
+
+impl UserFilter {
+    /// 只拼接实际指定的条件
+    fn where_clause(&self) -> String {
+        let mut conditions = Vec::new();
+        if self.role_id.is_some() {
+            conditions.push("u.main_role_id = ?");
+        }
+        if self.search.is_some() {
+            conditions.push("(u.qq LIKE ? ESCAPE '\\' OR u.nickname LIKE ? ESCAPE '\\')");
+        }
+        if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        }
+    }
+
+    /// 搜索关键字转义 LIKE 通配符后的匹配模式
+    fn search_pattern(&self) -> Option<String> {
+        self.search.as_ref().map(|keyword| {
+            let escaped = keyword
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        })
+    }
+}
+
 impl UserService {
     // 用户注册
     pub async fn register(
@@ -13,8 +50,9 @@ impl UserService {
         nickname: &str,
         password: &str,
         birthday: Option<&str>,
-    ) -> DbResult<()> {
-        let hashed_password = hash(password, DEFAULT_COST).expect("密码加密失败");
+        email: Option<&str>,
+    ) -> ServiceResult<()> {
+        let hashed_password = hash(password, bcrypt_cost()).expect("密码加密失败");
 
         let default_role_id: Option<i64> =
             sqlx::query_scalar("SELECT role_id FROM role WHERE name = ?")
@@ -23,13 +61,14 @@ impl UserService {
                 .await?;
 
         sqlx::query(
-            "INSERT INTO user (qq, main_role_id, nickname, password, birthday) VALUES (?, ?, ?, ?, ?)",
+            "INSERT INTO user (qq, main_role_id, nickname, password, birthday, email) VALUES (?, ?, ?, ?, ?, ?)",
         )
         .bind(qq)
         .bind(default_role_id)
         .bind(nickname)
         .bind(hashed_password)
         .bind(birthday)
+        .bind(email)
         .execute(pool)
         .await?;
 
@@ -37,9 +76,9 @@ impl UserService {
     }
 
     // 用户登录
-    pub async fn login(pool: &SqlitePool, qq: &str, password: &str) -> DbResult<Option<User>> {
+    pub async fn login(pool: &SqlitePool, qq: &str, password: &str) -> ServiceResult<Option<User>> {
         let user = sqlx::query_as::<_, User>(
-            "SELECT qq, main_role_id, nickname, password, birthday FROM user WHERE qq = ?",
+            "SELECT qq, main_role_id, nickname, password, birthday, avatar_url, email, is_active FROM user WHERE qq = ?",
         )
         .bind(qq)
         .fetch_optional(pool)
@@ -54,10 +93,27 @@ impl UserService {
         Ok(None)
     }
 
+    // 是否是受保护的默认管理员（数据库中第一个创建的用户），不能被删除、禁用或修改角色
+    pub async fn is_protected_admin(pool: &SqlitePool, qq: &str) -> ServiceResult<bool> {
+        let protected_qq = Self::protected_admin_qq(pool).await?;
+        Ok(Some(qq) == protected_qq.as_deref())
+    }
+
+    // 受保护的默认管理员的QQ，可在事务中调用
+    pub async fn protected_admin_qq<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+    ) -> ServiceResult<Option<String>> {
+        let first_user_qq: Option<String> =
+            sqlx::query_scalar("SELECT qq FROM user ORDER BY rowid LIMIT 1")
+                .fetch_optional(executor)
+                .await?;
+        Ok(first_user_qq)
+    }
+
     // 获取单个用户
-    pub async fn get_user(pool: &SqlitePool, qq: &str) -> DbResult<Option<User>> {
+    pub async fn get_user(pool: &SqlitePool, qq: &str) -> ServiceResult<Option<User>> {
         let user = sqlx::query_as::<_, User>(
-            "SELECT qq, main_role_id, nickname, password, birthday FROM user WHERE qq = ?",
+            "SELECT qq, main_role_id, nickname, password, birthday, avatar_url, email, is_active FROM user WHERE qq = ?",
         )
         .bind(qq)
         .fetch_optional(pool)
@@ -67,9 +123,9 @@ impl UserService {
     }
 
     // 获取所有用户
-    pub async fn get_all_users(pool: &SqlitePool) -> DbResult<Vec<UserWithRole>> {
+    pub async fn get_all_users(pool: &SqlitePool) -> ServiceResult<Vec<UserWithRole>> {
         let users = sqlx::query_as::<_, UserWithRole>(
-            "SELECT u.qq, u.main_role_id, u.nickname, u.password, u.birthday, r.name as role_name 
+            "SELECT u.qq, u.main_role_id, u.nickname, u.password, u.birthday, u.avatar_url, u.email, u.is_active, r.name as role_name 
              FROM user u 
              LEFT JOIN role r ON u.main_role_id = r.role_id",
         )
@@ -79,13 +135,39 @@ impl UserService {
         Ok(users)
     }
 
+    // 按角色和关键字筛选用户（含角色名称）
+    pub async fn list_users(
+        pool: &SqlitePool,
+        filter: &UserFilter,
+    ) -> ServiceResult<Vec<UserWithRole>> {
+        let sql = format!(
+            "SELECT u.qq, u.main_role_id, u.nickname, u.password, u.birthday, u.avatar_url, u.email, u.is_active, r.name as role_name
+             FROM user u
+             LEFT JOIN role r ON u.main_role_id = r.role_id
+             {}",
+            filter.where_clause()
+        );
+        let mut query = sqlx::query_as::<_, UserWithRole>(&sql);
+        if let Some(role_id) = filter.role_id {
+            query = query.bind(role_id);
+        }
+        if let Some(pattern) = filter.search_pattern() {
+            query = query.bind(pattern.clone()).bind(pattern);
+        }
+        let users = query.fetch_all(pool).await?;
+
+        Ok(users)
+    }
+
     // 更新用户信息
     pub async fn update_user(
         pool: &SqlitePool,
         qq: &str,
         nickname: Option<String>,
         birthday: Option<String>,
-    ) -> DbResult<()> {
+        avatar_url: Option<String>,
+        email: Option<String>,
+    ) -> ServiceResult<()> {
         if let Some(nick) = nickname {
             sqlx::query("UPDATE user SET nickname = ? WHERE qq = ?")
                 .bind(nick)
@@ -102,16 +184,59 @@ impl UserService {
                 .await?;
         }
 
+        // 传入空字符串表示清除头像
+        if let Some(avatar) = avatar_url {
+            let avatar = Some(avatar.trim().to_string()).filter(|url| !url.is_empty());
+            sqlx::query("UPDATE user SET avatar_url = ? WHERE qq = ?")
+                .bind(avatar)
+                .bind(qq)
+                .execute(pool)
+                .await?;
+        }
+
+        // 传入空字符串表示清除邮箱
+        if let Some(email) = email {
+            let email = Some(email.trim().to_string()).filter(|addr| !addr.is_empty());
+            sqlx::query("UPDATE user SET email = ? WHERE qq = ?")
+                .bind(email)
+                .bind(qq)
+                .execute(pool)
+                .await?;
+        }
+
         Ok(())
     }
 
+    // 获取用户邮箱（未设置时返回 None）
+    pub async fn get_email(pool: &SqlitePool, qq: &str) -> ServiceResult<Option<String>> {
+        let email: Option<Option<String>> =
+            sqlx::query_scalar("SELECT email FROM user WHERE qq = ?")
+                .bind(qq)
+                .fetch_optional(pool)
+                .await?;
+
+        Ok(email.flatten())
+    }
+
+    // 启用或禁用账号，返回是否找到该用户
+    pub async fn set_active(pool: &SqlitePool, qq: &str, active: bool) -> ServiceResult<bool> {
+        let rows = sqlx::query("UPDATE user SET is_active = ? WHERE qq = ?")
+            .bind(active)
+            .bind(qq)
+            .execute(pool)
+            .await?
+            .rows_affected();
+
+        Ok(rows > 0)
+    }
+
     // 修改密码
     pub async fn change_password(
         pool: &SqlitePool,
         qq: &str,
         old_password: &str,
         new_password: &str,
-    ) -> DbResult<bool> {
+    ) -> ServiceResult<bool> {
         let current_password: Option<String> =
             sqlx::query_scalar("SELECT password FROM user WHERE qq = ?")
                 .bind(qq)
@@ -126,8 +251,8 @@ impl UserService {
             return Ok(false);
         }
 
-        let hashed_new_password = hash(new_password, DEFAULT_COST).expect("密码加密失败");
-        sqlx::query("UPDATE user SET password = ? WHERE qq = ?")
+        let hashed_new_password = hash(new_password, bcrypt_cost()).expect("密码加密失败");
+        sqlx::query("UPDATE user SET password = ?, must_change_password = 0 WHERE qq = ?")
             .bind(hashed_new_password)
             .bind(qq)
             .execute(pool)
@@ -136,8 +261,145 @@ impl UserService {
         Ok(true)
     }
 
+    // 记录管理员修改他人资料的字段变化，返回写入的审计条数
+    pub async fn record_user_audit(
+        pool: &SqlitePool,
+        operator_qq: &str,
+        before: &User,
+        after: &User,
+    ) -> ServiceResult<usize> {
+        let changes = [
+            (
+                "nickname",
+                Some(before.nickname.clone()),
+                Some(after.nickname.clone()),
+            ),
+            ("birthday", before.birthday.clone(), after.birthday.clone()),
+            (
+                "avatar_url",
+                before.avatar_url.clone(),
+                after.avatar_url.clone(),
+            ),
+            ("email", before.email.clone(), after.email.clone()),
+        ];
+
+        let time = now_string();
+        let mut tx = pool.begin().await?;
+        let mut recorded = 0;
+        for (field, old_value, new_value) in changes {
+            if old_value == new_value {
+                continue;
+            }
+
+            sqlx::query(
+                "INSERT INTO useraudit (operator_qq, target_qq, field, old_value, new_value, time)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(operator_qq)
+            .bind(&after.qq)
+            .bind(field)
+            .bind(old_value)
+            .bind(new_value)
+            .bind(&time)
+            .execute(&mut *tx)
+            .await?;
+            recorded += 1;
+        }
+        tx.commit().await?;
+
+        Ok(recorded)
+    }
+
+    // 查询用户是否必须先修改密码
+    pub async fn must_change_password(pool: &SqlitePool, qq: &str) -> ServiceResult<bool> {
+        let flag: Option<bool> =
+            sqlx::query_scalar("SELECT must_change_password FROM user WHERE qq = ?")
+                .bind(qq)
+                .fetch_optional(pool)
+                .await?;
+        Ok(flag.unwrap_or(false))
+    }
+
+    // 查询账号是否启用以及是否必须先修改密码，用户不存在时返回 None
+    pub async fn access_flags(pool: &SqlitePool, qq: &str) -> ServiceResult<Option<(bool, bool)>> {
+        let flags = sqlx::query_as::<_, (bool, bool)>(
+            "SELECT is_active, must_change_password FROM user WHERE qq = ?",
+        )
+        .bind(qq)
+        .fetch_optional(pool)
+        .await?;
+        Ok(flags)
+    }
+
+    // 标记用户下次登录时必须修改密码
+    pub async fn set_must_change_password(
+        pool: &SqlitePool,
+        qq: &str,
+        flag: bool,
+    ) -> ServiceResult<()> {
+        sqlx::query("UPDATE user SET must_change_password = ? WHERE qq = ?")
+            .bind(flag)
+            .bind(qq)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    // 查询用户提交LP时默认选中的类型
+    pub async fn get_default_lp_type(pool: &SqlitePool, qq: &str) -> ServiceResult<Option<i64>> {
+        let lp_type: Option<Option<i64>> =
+            sqlx::query_scalar("SELECT default_lp_type FROM user WHERE qq = ?")
+                .bind(qq)
+                .fetch_optional(pool)
+                .await?;
+        Ok(lp_type.flatten())
+    }
+
+    // 设置或清除用户默认的LP类型
+    pub async fn set_default_lp_type(
+        pool: &SqlitePool,
+        qq: &str,
+        lp_type: Option<i64>,
+    ) -> ServiceResult<()> {
+        sqlx::query("UPDATE user SET default_lp_type = ? WHERE qq = ?")
+            .bind(lp_type)
+            .bind(qq)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    // 查询用户的两步验证密钥，返回 (密钥, 是否已启用)
+    pub async fn get_totp(pool: &SqlitePool, qq: &str) -> ServiceResult<(Option<String>, bool)> {
+        let row: Option<(Option<String>, bool)> =
+            sqlx::query_as("SELECT totp_secret, totp_enabled FROM user WHERE qq = ?")
+                .bind(qq)
+                .fetch_optional(pool)
+                .await?;
+        Ok(row.unwrap_or((None, false)))
+    }
+
+    // 保存待确认的两步验证密钥，验证通过前不会在登录时生效
+    pub async fn set_pending_totp(pool: &SqlitePool, qq: &str, secret: &str) -> ServiceResult<()> {
+        sqlx::query("UPDATE user SET totp_secret = ?, totp_enabled = 0 WHERE qq = ?")
+            .bind(secret)
+            .bind(qq)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    // 确认并启用两步验证
+    pub async fn enable_totp(pool: &SqlitePool, qq: &str) -> ServiceResult<()> {
+        sqlx::query("UPDATE user SET totp_enabled = 1 WHERE qq = ? AND totp_secret IS NOT NULL")
+            .bind(qq)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
     // 删除用户
-    pub async fn delete_user(pool: &SqlitePool, qq: &str) -> DbResult<()> {
+    pub async fn delete_user(pool: &SqlitePool, qq: &str) -> ServiceResult<()> {
         let mut tx = pool.begin().await?;
 
         sqlx::query("DELETE FROM requestlog WHERE user_qq = ?")
@@ -180,11 +442,38 @@ impl UserService {
         Ok(())
     }
 
+    // 按 QQ 或昵称前缀查找用户，用于输入框自动补全
+    pub async fn suggest_users(
+        pool: &SqlitePool,
+        prefix: &str,
+        limit: i64,
+    ) -> ServiceResult<Vec<UserSuggestion>> {
+        // 转义 LIKE 通配符，保证只做前缀匹配
+        let escaped = prefix
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let pattern = format!("{}%", escaped);
+        let users = sqlx::query_as::<_, UserSuggestion>(
+            "SELECT qq, nickname FROM user
+             WHERE qq LIKE ? ESCAPE '\\' OR nickname LIKE ? ESCAPE '\\'
+             ORDER BY qq
+             LIMIT ?",
+        )
+        .bind(&pattern)
+        .bind(&pattern)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(users)
+    }
+
     // 搜索用户
-    pub async fn search_users(pool: &SqlitePool, keyword: &str) -> DbResult<Vec<User>> {
+    pub async fn search_users(pool: &SqlitePool, keyword: &str) -> ServiceResult<Vec<User>> {
         let pattern = format!("%{}%", keyword);
         let users = sqlx::query_as::<_, User>(
-            "SELECT qq, main_role_id, nickname, password, birthday FROM user
+            "SELECT qq, main_role_id, nickname, password, birthday, avatar_url, email, is_active FROM user
              WHERE qq LIKE ? OR nickname LIKE ?",
         )
         .bind(&pattern)
@@ -195,3 +484,136 @@ impl UserService {
         Ok(users)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{add_user, memory_pool};
+    use crate::db::RoleService;
+
+    async fn stored_hash(pool: &SqlitePool, qq: &str) -> String {
+        sqlx::query_scalar("SELECT password FROM user WHERE qq = ?")
+            .bind(qq)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn passwords_hashed_at_configured_cost_verify() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+
+        // 测试环境把 BCRYPT_COST 设为 4
+        assert!(stored_hash(&pool, "10001").await.starts_with("$2b$04$"));
+        assert!(UserService::login(&pool, "10001", "password")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(UserService::login(&pool, "10001", "wrong")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn hashes_from_a_different_cost_still_verify() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        // 调整 BCRYPT_COST 之前创建的密码哈希仍然可以登录
+        sqlx::query("UPDATE user SET password = ? WHERE qq = ?")
+            .bind(hash("old-password", 5).unwrap())
+            .bind("10001")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(UserService::login(&pool, "10001", "old-password")
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn suggestions_match_qq_or_nickname_prefix() {
+        let pool = memory_pool().await;
+        add_user(&pool, "10001").await;
+        add_user(&pool, "10002").await;
+        UserService::register(&pool, "20001", "张三", "password", None, None)
+            .await
+            .unwrap();
+
+        let qqs = |users: Vec<UserSuggestion>| -> Vec<String> {
+            users.into_iter().map(|user| user.qq).collect()
+        };
+        assert_eq!(
+            qqs(UserService::suggest_users(&pool, "100", 20).await.unwrap()),
+            vec!["10001", "10002"]
+        );
+        assert_eq!(
+            qqs(UserService::suggest_users(&pool, "张", 20).await.unwrap()),
+            vec!["20001"]
+        );
+        assert_eq!(
+            qqs(UserService::suggest_users(&pool, "100", 1).await.unwrap()),
+            vec!["10001"]
+        );
+        // 只做前缀匹配，通配符按普通字符处理
+        assert!(UserService::suggest_users(&pool, "0001", 20)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(UserService::suggest_users(&pool, "%", 20)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn list_users_filters_by_role_and_search() {
+        let pool = memory_pool().await;
+        for qq in ["10001", "10002", "10003"] {
+            add_user(&pool, qq).await;
+        }
+        let reviewer = RoleService::get_role_by_name(&pool, "审核员")
+            .await
+            .unwrap()
+            .unwrap()
+            .role_id;
+        for qq in ["10001", "10002"] {
+            RoleService::assign_main_role(&pool, qq, reviewer, "9999")
+                .await
+                .unwrap();
+        }
+
+        let qqs = |users: Vec<UserWithRole>| -> Vec<String> {
+            users.into_iter().map(|user| user.qq).collect()
+        };
+        let users = UserService::list_users(
+            &pool,
+            &UserFilter {
+                role_id: Some(reviewer),
+                search: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(users
+            .iter()
+            .all(|user| user.role_name.as_deref() == Some("审核员")));
+        let mut found = qqs(users);
+        found.sort();
+        assert_eq!(found, vec!["10001", "10002"]);
+
+        let users = UserService::list_users(
+            &pool,
+            &UserFilter {
+                role_id: Some(reviewer),
+                search: Some("10002".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(qqs(users), vec!["10002"]);
+    }
+}
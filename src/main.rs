@@ -3,14 +3,14 @@
 mod components;
 mod models;
 mod pages;
+mod pow;
 
 #[cfg(feature = "frontend")]
 mod api;
 
-#[cfg(feature = "backend")]
-mod db;
-
-use components::AppContext;
+use components::{
+    use_confirm_provider, use_toast_provider, AppContext, ConfirmDialog, ToastContainer,
+};
 use dioxus::logger::tracing::{info, warn, Level};
 use dioxus::prelude::*;
 use dioxus_router::hooks::use_route;
@@ -22,13 +22,6 @@ fn main() {
     dioxus_logger::init(Level::INFO).expect("日志初始化失败");
     info!("团队运营管理系统启动中...");
 
-    #[cfg(feature = "backend")]
-    {
-        // 后端模式:初始化数据库
-        let _ = &*db::DB;
-        info!("数据库初始化完成");
-    }
-
     #[cfg(feature = "frontend")]
     {
         // 前端模式:直接启动Web应用
@@ -46,6 +39,8 @@ fn App() -> Element {
         current_user,
         is_loading,
     });
+    use_toast_provider();
+    use_confirm_provider();
 
     #[cfg(feature = "frontend")]
     {
@@ -66,9 +61,13 @@ fn App() -> Element {
                                 qq: user.qq.clone(),
                                 nickname: user.nickname.clone(),
                                 birthday: user.birthday.clone(),
+                                avatar_url: user.avatar_url.clone(),
+                                email: user.email.clone(),
                                 main_role_id: None,
                                 role_name: user.role_name.clone(),
                                 permissions: user.permissions.clone(),
+                                must_change_password: user.must_change_password,
+                                is_protected_admin: user.is_protected_admin,
                             }));
                             is_loading_signal.set(false);
                         }
@@ -114,6 +113,8 @@ enum Route {
         LpManagement {},
         #[route("/lp/submit")]
         LpSubmit {},
+        #[route("/lp/:id")]
+        LpDetail { id: i64 },
         #[route("/luckydraw")]
         LuckyDraw {},
         #[route("/shop")]
@@ -126,6 +127,10 @@ enum Route {
         Profile {},
         #[route("/logs")]
         Logs {},
+        #[route("/notifications")]
+        Notifications {},
+        #[route("/leaderboard")]
+        PublicLeaderboard {},
 }
 
 #[component]
@@ -135,8 +140,13 @@ fn Layout() -> Element {
     let is_loading = app_ctx.is_loading;
     let nav = use_navigator();
     let current_route: Route = use_route();
-    let is_public_route = matches!(current_route, Route::Login {} | Route::Register {});
+    let is_public_route = matches!(
+        current_route,
+        Route::Login {} | Route::Register {} | Route::PublicLeaderboard {}
+    );
     let user_state = current_user.read().clone();
+    let pending_lp_count = components::use_pending_lp_count();
+    let unread_notifications = components::use_unread_notifications_provider();
 
     // 如果正在加载，显示加载界面
     if *is_loading.read() {
@@ -162,6 +172,20 @@ fn Layout() -> Element {
         };
     }
 
+    // 必须修改密码的用户只能停留在个人中心
+    let must_change_password = user_state
+        .as_ref()
+        .is_some_and(|user| user.must_change_password);
+    if must_change_password && !matches!(current_route, Route::Profile {}) {
+        nav.replace(Route::Profile {});
+        return rsx! {
+            div { class: "page-container",
+                h1 { "正在跳转" }
+                p { "请先修改密码后再使用系统功能。" }
+            }
+        };
+    }
+
     rsx! {
         div { class: "app-container",
             style { {include_str!("../assets/main.css")} }
@@ -171,18 +195,34 @@ fn Layout() -> Element {
                     "团队运营管理系统"
                 }
                 div { class: "nav-links",
-                    if user_state.is_some() {
+                    if user_state.is_some() && !must_change_password {
                         Link { to: Route::Home {}, "首页" }
                         Link { to: Route::Users {}, "用户管理" }
                         Link { to: Route::Roles {}, "角色管理" }
-                        Link { to: Route::LpManagement {}, "LP管理" }
+                        Link { to: Route::LpManagement {},
+                            "LP管理"
+                            if *pending_lp_count.read() > 0 {
+                                span { class: "nav-badge", title: "待审核LP", "{pending_lp_count}" }
+                            }
+                        }
                         Link { to: Route::LuckyDraw {}, "抽奖活动" }
                         Link { to: Route::Shop {}, "虚拟商店" }
                         Link { to: Route::Logs {}, "系统日志" }
                     }
 
                     if let Some(user) = user_state.as_ref() {
+                        if let Some(avatar) = user.avatar_url.as_ref() {
+                            img { class: "nav-avatar", src: "{avatar}", alt: "头像" }
+                        }
                         span { class: "nav-user", "欢迎, {user.nickname}" }
+                        if !must_change_password {
+                            Link { to: Route::Notifications {}, class: "nav-bell", title: "我的通知",
+                                "🔔"
+                                if *unread_notifications.read() > 0 {
+                                    span { class: "nav-badge", "{unread_notifications}" }
+                                }
+                            }
+                        }
                         Link { to: Route::Profile {}, "个人中心" }
                         button {
                             class: "btn-small btn-secondary",
@@ -207,6 +247,9 @@ fn Layout() -> Element {
                 Outlet::<Route> {}
             }
 
+            ToastContainer {}
+            ConfirmDialog {}
+
             footer { class: "footer",
                 "© 2025 团队运营管理系统"
             }
@@ -216,6 +259,19 @@ fn Layout() -> Element {
 
 #[component]
 fn Home() -> Element {
+    let mut overview = use_signal(|| None::<models::OverviewStats>);
+
+    use_effect(move || {
+        spawn(async move {
+            match crate::api::get_overview().await {
+                Ok(stats) => overview.set(Some(stats)),
+                Err(e) => warn!("获取概览统计失败: {}", e),
+            }
+        });
+    });
+
+    let stats = overview.read().clone();
+
     rsx! {
         div { class: "page-container",
             h1 { "欢迎使用团队运营管理系统" }
@@ -223,6 +279,12 @@ fn Home() -> Element {
             div { class: "feature-grid",
                 div { class: "feature-card",
                     h3 { "用户管理" }
+                    if let Some(stats) = stats.as_ref() {
+                        div { class: "feature-stat",
+                            span { class: "feature-stat-value", "{stats.total_users}" }
+                            span { class: "feature-stat-label", "用户总数" }
+                        }
+                    }
                     p { "注册、登录、用户信息管理" }
                     Link { to: Route::Users {},
                         button { class: "btn-primary", "进入" }
@@ -239,6 +301,12 @@ fn Home() -> Element {
 
                 div { class: "feature-card",
                     h3 { "LP管理" }
+                    if let Some(stats) = stats.as_ref() {
+                        div { class: "feature-stat",
+                            span { class: "feature-stat-value", "{stats.pending_lp}" }
+                            span { class: "feature-stat-label", "待审核LP" }
+                        }
+                    }
                     p { "LP申请、审批、查询" }
                     Link { to: Route::LpManagement {},
                         button { class: "btn-primary", "进入" }
@@ -247,6 +315,12 @@ fn Home() -> Element {
 
                 div { class: "feature-card",
                     h3 { "抽奖活动" }
+                    if let Some(stats) = stats.as_ref() {
+                        div { class: "feature-stat",
+                            span { class: "feature-stat-value", "{stats.active_draws}" }
+                            span { class: "feature-stat-label", "进行中的抽奖" }
+                        }
+                    }
                     p { "创建抽奖、开奖、中奖记录" }
                     Link { to: Route::LuckyDraw {},
                         button { class: "btn-primary", "进入" }
@@ -255,6 +329,12 @@ fn Home() -> Element {
 
                 div { class: "feature-card",
                     h3 { "虚拟商店" }
+                    if let Some(stats) = stats.as_ref() {
+                        div { class: "feature-stat",
+                            span { class: "feature-stat-value", "{stats.in_stock_items}" }
+                            span { class: "feature-stat-label", "在售商品" }
+                        }
+                    }
                     p { "商品上架、购买、交易记录" }
                     Link { to: Route::Shop {},
                         button { class: "btn-primary", "进入" }
@@ -263,6 +343,12 @@ fn Home() -> Element {
 
                 div { class: "feature-card",
                     h3 { "系统日志" }
+                    if let Some(stats) = stats.as_ref() {
+                        div { class: "feature-stat",
+                            span { class: "feature-stat-value", "{stats.today_requests}" }
+                            span { class: "feature-stat-label", "今日请求数" }
+                        }
+                    }
                     p { "查看系统请求日志和操作记录" }
                     Link { to: Route::Logs {},
                         button { class: "btn-primary", "进入" }
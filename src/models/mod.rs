@@ -11,6 +11,9 @@ pub struct User {
     pub nickname: String,
     pub password: String,
     pub birthday: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email: Option<String>,
+    pub is_active: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +24,9 @@ pub struct UserWithRole {
     pub nickname: String,
     pub password: String,
     pub birthday: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email: Option<String>,
+    pub is_active: bool,
     pub role_name: Option<String>,
 }
 
@@ -32,14 +38,46 @@ pub struct Role {
     pub description: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct RoleMember {
+    pub qq: String,
+    pub nickname: String,
+}
+
+/// 输入框自动补全使用的用户简要信息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct UserSuggestion {
+    pub qq: String,
+    pub nickname: String,
+}
+
+/// 用户时间线中的一条事件，合并自LP记录、商店交易和请求日志。
+/// `kind` 取值：lp、purchase、sale、request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct TimelineEvent {
+    pub timestamp: String,
+    pub kind: String,
+    pub detail: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionUser {
     pub qq: String,
     pub nickname: String,
     pub birthday: Option<String>,
+    pub avatar_url: Option<String>,
+    pub email: Option<String>,
     pub main_role_id: Option<i64>,
     pub role_name: Option<String>,
     pub permissions: Vec<String>,
+    #[serde(default)]
+    pub must_change_password: bool,
+    /// 是否是受保护的默认管理员，不能被删除、禁用或修改角色
+    #[serde(default)]
+    pub is_protected_admin: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +86,14 @@ pub struct Permission {
     pub name: String,
 }
 
+/// 用户实际拥有的权限及其来源角色
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct EffectivePermission {
+    pub permission: String,
+    pub via_role: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "backend", derive(FromRow))]
 pub struct PermissionLog {
@@ -81,6 +127,8 @@ pub struct LpLog {
     pub status: i32, // 0: 待处理, 1: 已通过, 2: 已拒绝
     pub picture: Option<String>,
     pub process_time: Option<String>,
+    /// 审核员拒绝时填写的理由
+    pub reject_reason: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,9 +142,12 @@ pub struct LuckyDrawLog {
     pub num: i32,
     pub min_lp_require: i32,
     pub plan_time: String,
-    pub status: i32, // 0: 未开奖, 1: 已开奖
+    pub status: i32, // 0: 未开奖, 1: 已开奖, 2: 无人中奖已关闭, 3: 已取消
     pub winner_qq: Option<String>,
     pub description: Option<String>,
+    pub recurrence: String, // none: 不重复, daily: 每天, weekly: 每周
+    /// 本期开奖后还会自动创建的期数，为 0 时不再创建下一期
+    pub recurrence_remaining: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +159,12 @@ pub struct ShopItem {
     pub name: String,
     pub seller: String,
     pub location: String,
+    /// 库存降到该数量及以下时提醒卖家，未设置时不提醒
+    #[serde(default)]
+    pub low_stock_threshold: Option<i32>,
+    /// 下架时间（`%Y-%m-%d %H:%M:%S`），过期后不再公开展示和出售，未设置时长期有效
+    #[serde(default)]
+    pub expires_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -123,6 +180,54 @@ pub struct ShopLog {
     pub location: String,
 }
 
+/// 站内通知，`kind` 为 lp_processed / draw_won / low_stock
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct UserNotification {
+    pub id: i64,
+    pub user_qq: String,
+    pub kind: String,
+    pub message: String,
+    pub read: bool,
+    pub created_at: String,
+}
+
+/// LP申请草稿，只有创建者本人可见，提交后转为待审批的LP申请
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct LpDraft {
+    pub id: i64,
+    pub owner_qq: String,
+    pub user_qq: Option<String>,
+    pub lp_type: Option<i64>,
+    pub num: Option<i32>,
+    pub reason: String,
+    pub picture: Option<String>,
+    pub role: Option<String>,
+    pub updated_at: String,
+}
+
+/// 有在售商品的卖家，用于商店按卖家筛选
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct ShopSeller {
+    pub qq: String,
+    pub nickname: String,
+    /// 有库存且未过期的商品数
+    pub item_count: i64,
+}
+
+/// 卖家累计销售统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct SellerStats {
+    /// 售出的商品件数
+    pub items_sold: i64,
+    /// 销售总额（单价 × 数量）
+    pub revenue: f64,
+    pub distinct_buyers: i64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "backend", derive(FromRow))]
 pub struct UserLpSummary {
@@ -132,6 +237,71 @@ pub struct UserLpSummary {
     pub pending_count: i64,
     pub approved_count: i64,
     pub rejected_count: i64,
+    /// 本自然月内通过审核的LP变化量，只在查询单个用户汇总时计算
+    #[serde(default)]
+    #[cfg_attr(feature = "backend", sqlx(default))]
+    pub monthly_delta: i64,
+}
+
+/// 某个月份末的用户LP汇总快照，只在后端查询和导出，前端不使用
+#[cfg(feature = "backend")]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LpSummarySnapshot {
+    pub period: String,
+    pub qq: String,
+    pub nickname: String,
+    pub total_lp: i64,
+    pub approved_count: i64,
+    pub created_at: String,
+}
+
+/// 公开排行榜条目，只包含昵称，不暴露QQ号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct LeaderboardEntry {
+    pub nickname: String,
+    pub total_lp: i64,
+}
+
+/// 按LP类型汇总的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct LpTypeStat {
+    pub lp_type_id: i64,
+    pub name: String,
+    pub log_count: i64,
+    pub approved_num: i64,
+}
+
+/// 某个用户一段时间内被拒绝访问（403）的汇总，用于发现越权试探
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct PermissionDenialSummary {
+    pub user_qq: String,
+    pub denials: i64,
+    /// 被拒绝的不同接口数
+    pub paths: i64,
+    pub last_denied: String,
+}
+
+/// 请求日志统计中的一个分组：状态码类别（如 `2xx`）或请求方法
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "backend", derive(FromRow))]
+pub struct RequestLogCount {
+    pub key: String,
+    pub count: i64,
+}
+
+/// 一段时间 `[start, end)` 内的请求日志统计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestLogStats {
+    pub start: String,
+    pub end: String,
+    pub total: i64,
+    /// 按状态码类别汇总，2xx–5xx 始终都有，没有请求时为 0
+    pub by_status: Vec<RequestLogCount>,
+    /// 按请求方法汇总，请求数多的在前
+    pub by_method: Vec<RequestLogCount>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,4 +314,20 @@ pub struct RequestLog {
     pub body: Option<String>,
     pub status: i32,
     pub timestamp: String,
+    /// 从收到请求到记录日志所用的毫秒数，旧记录和后台任务产生的记录为空
+    #[serde(default)]
+    pub elapsed_ms: Option<i64>,
+    /// 请求追踪 ID，与响应头 `X-Request-Id` 一致，旧记录和后台任务产生的记录为空
+    #[serde(default)]
+    pub trace_id: Option<String>,
+}
+
+/// 首页概览统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OverviewStats {
+    pub total_users: i64,
+    pub pending_lp: i64,
+    pub active_draws: i64,
+    pub in_stock_items: i64,
+    pub today_requests: i64,
 }
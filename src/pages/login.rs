@@ -8,6 +8,7 @@ use dioxus::prelude::*;
 pub fn Login() -> Element {
     let mut qq = use_signal(String::new);
     let mut password = use_signal(String::new);
+    let mut totp_code = use_signal(String::new);
     let mut error = use_signal(|| None::<String>);
     let mut success = use_signal(|| false);
     let mut warning = use_signal(|| None::<String>);
@@ -20,6 +21,7 @@ pub fn Login() -> Element {
 
         let qq_val = qq.read().clone();
         let pwd_val = password.read().clone();
+        let totp_val = totp_code.read().trim().to_string();
 
         if qq_val.is_empty() || pwd_val.is_empty() {
             error.set(Some("请输入QQ号和密码".to_string()));
@@ -31,6 +33,11 @@ pub fn Login() -> Element {
             let req = api::LoginRequest {
                 qq: qq_val.clone(),
                 password: pwd_val,
+                totp_code: if totp_val.is_empty() {
+                    None
+                } else {
+                    Some(totp_val)
+                },
             };
 
             match api::login(req).await {
@@ -40,9 +47,13 @@ pub fn Login() -> Element {
                         qq: login_resp.user.qq.clone(),
                         nickname: login_resp.user.nickname.clone(),
                         birthday: login_resp.user.birthday.clone(),
+                        avatar_url: login_resp.user.avatar_url.clone(),
+                        email: login_resp.user.email.clone(),
                         main_role_id: None, // API没有返回role_id
                         role_name: login_resp.user.role_name.clone(),
                         permissions: login_resp.user.permissions.clone(),
+                        must_change_password: login_resp.user.must_change_password,
+                        is_protected_admin: login_resp.user.is_protected_admin,
                     };
 
                     current_user.set(Some(session_user));
@@ -51,9 +62,12 @@ pub fn Login() -> Element {
                     info!("用户 {} 登录成功", login_resp.user.nickname);
                     qq.set(String::new());
                     password.set(String::new());
+                    totp_code.set(String::new());
 
-                    // 检查是否使用默认密码
-                    if login_resp.user.is_default_password {
+                    // 必须修改密码的用户直接进入个人中心
+                    if login_resp.user.must_change_password {
+                        nav.push(crate::Route::Profile {});
+                    } else if login_resp.user.is_default_password {
                         warning.set(Some(
                             "您正在使用默认密码，为了账号安全，请立即前往个人中心修改密码！"
                                 .to_string(),
@@ -106,6 +120,20 @@ pub fn Login() -> Element {
                         }
                     }
 
+                    div { class: "form-group",
+                        label { r#for: "totp_code", "两步验证码：" }
+                        input {
+                            r#type: "text",
+                            id: "totp_code",
+                            name: "totp_code",
+                            inputmode: "numeric",
+                            maxlength: "6",
+                            placeholder: "未开启两步验证可留空",
+                            value: "{totp_code}",
+                            oninput: move |evt| totp_code.set(evt.value().clone())
+                        }
+                    }
+
                     if let Some(err) = error.read().as_ref() {
                         div { class: "error-message", "{err}" }
                     }
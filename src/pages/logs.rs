@@ -1,19 +1,41 @@
 use crate::api;
+use crate::components::{use_confirm, use_current_user, use_toast};
+use crate::models::{PermissionDenialSummary, RequestLogStats};
 use dioxus::prelude::*;
 use gloo_timers::future::TimeoutFuture;
 
-const PAGE_SIZE: i32 = 20;
+const PAGE_SIZE_OPTIONS: [i32; 3] = [20, 50, 100];
+const DEFAULT_PAGE_SIZE: i32 = 20;
+const PAGE_SIZE_STORAGE_KEY: &str = "logs_page_size";
+/// 拒绝访问汇总统计的天数
+const DENIAL_WINDOW_DAYS: i64 = 7;
+/// 统计窗口内被拒绝达到该次数的用户会被突出显示
+const REPEATED_DENIAL_THRESHOLD: i64 = 5;
+/// 自动刷新的轮询间隔（毫秒）
+const AUTO_REFRESH_INTERVAL_MS: u32 = 5000;
 
 #[component]
 pub fn Logs() -> Element {
     let mut logs = use_signal(Vec::<api::RequestLog>::new);
     let mut total = use_signal(|| 0i64);
     let mut page = use_signal(|| 0);
+    let mut page_size = use_signal(load_page_size);
     let mut error = use_signal(|| None::<String>);
     let mut loading = use_signal(|| false);
     let loading_visible = use_signal(|| false);
     let mut filter_user = use_signal(String::new);
     let mut input_value = use_signal(String::new);
+    let mut delete_path = use_signal(String::new);
+    let mut toast = use_toast();
+    let mut confirmer = use_confirm();
+    let current_user = use_current_user();
+    // 请求代次：每次发起加载前递增，过期的响应不再写回状态
+    let mut request_gen = use_signal(|| 0u64);
+    let mut auto_refresh = use_signal(|| false);
+    // 轮询代次：每次开关自动刷新时递增，旧的轮询任务发现代次变化后退出
+    let mut poll_gen = use_signal(|| 0u64);
+    // 按耗时排序当前页：None 为默认顺序，Some(true) 为耗时从高到低
+    let mut elapsed_sort = use_signal(|| None::<bool>);
 
     {
         let loading = loading;
@@ -34,13 +56,16 @@ pub fn Logs() -> Element {
         });
     }
 
-    let load_logs = move || {
+    let mut load_logs = move || {
         let current_page = *page.read();
         let filter_value = filter_user.read().clone();
+        let size = *page_size.peek();
+        let generation = *request_gen.peek() + 1;
+        request_gen.set(generation);
 
         spawn(async move {
             loading.set(true);
-            let offset = (current_page * PAGE_SIZE).max(0);
+            let offset = (current_page * size).max(0);
             let trimmed = filter_value.trim().to_string();
             let user_qq = if trimmed.is_empty() {
                 None
@@ -48,7 +73,11 @@ pub fn Logs() -> Element {
                 Some(trimmed)
             };
 
-            match api::get_request_logs(Some(PAGE_SIZE), Some(offset), user_qq).await {
+            let result = api::get_request_logs(Some(size), Some(offset), user_qq).await;
+            if *request_gen.peek() != generation {
+                return;
+            }
+            match result {
                 Ok(response) => {
                     logs.set(response.logs);
                     total.set(response.total);
@@ -73,7 +102,7 @@ pub fn Logs() -> Element {
     let next_page = move |_| {
         let current = *page.read();
         let total_logs = *total.read();
-        let total_pages = calc_total_pages(total_logs);
+        let total_pages = calc_total_pages(total_logs, *page_size.read());
         if total_pages == 0 {
             return;
         }
@@ -96,6 +125,17 @@ pub fn Logs() -> Element {
         load_logs();
     };
 
+    let change_page_size = move |evt: Event<FormData>| {
+        if let Ok(size) = evt.value().parse::<i32>() {
+            if PAGE_SIZE_OPTIONS.contains(&size) {
+                page_size.set(size);
+                save_page_size(size);
+                page.set(0);
+                load_logs();
+            }
+        }
+    };
+
     let reset_filter = move |_| {
         filter_user.set(String::new());
         input_value.set(String::new());
@@ -103,18 +143,121 @@ pub fn Logs() -> Element {
         load_logs();
     };
 
+    // 按当前筛选的用户和填写的路径批量删除日志，至少需要一个条件
+    let delete_logs = move |_| {
+        let user_qq = filter_user.read().trim().to_string();
+        let path = delete_path.read().trim().to_string();
+        if user_qq.is_empty() && path.is_empty() {
+            toast.error("请先按用户QQ筛选或填写请求路径，再执行批量删除");
+            return;
+        }
+
+        let mut conditions = Vec::new();
+        if !user_qq.is_empty() {
+            conditions.push(format!("用户 {}", user_qq));
+        }
+        if !path.is_empty() {
+            conditions.push(format!("路径 {}", path));
+        }
+        let answer = confirmer.confirm(format!(
+            "确定要删除 {} 的全部日志吗？此操作不可恢复。",
+            conditions.join("、")
+        ));
+
+        spawn(async move {
+            if !answer.await {
+                return;
+            }
+            loading.set(true);
+            let user_qq = Some(user_qq).filter(|qq| !qq.is_empty());
+            let path = Some(path).filter(|path| !path.is_empty());
+            match api::delete_request_logs(user_qq, path).await {
+                Ok(message) => {
+                    toast.success(message);
+                    delete_path.set(String::new());
+                    page.set(0);
+                    load_logs();
+                }
+                Err(e) => {
+                    toast.error(format!("删除日志失败: {}", e));
+                }
+            }
+            loading.set(false);
+        });
+    };
+
+    // 自动刷新：只拉取比当前第一页最新一条更新的日志并插到顶部，不重新加载整页
+    let mut toggle_auto_refresh = move |enabled: bool| {
+        auto_refresh.set(enabled);
+        let generation = *poll_gen.peek() + 1;
+        poll_gen.set(generation);
+        if !enabled {
+            return;
+        }
+
+        spawn(async move {
+            loop {
+                TimeoutFuture::new(AUTO_REFRESH_INTERVAL_MS).await;
+                if *poll_gen.peek() != generation {
+                    break;
+                }
+                // 翻页查看历史或正在加载整页时跳过本轮
+                if *page.peek() != 0 || *loading.peek() {
+                    continue;
+                }
+
+                let since_id = logs.peek().iter().map(|log| log.id).max().unwrap_or(0);
+                let user_qq = Some(filter_user.peek().clone()).filter(|qq| !qq.is_empty());
+                let size = *page_size.peek();
+                let load_gen = *request_gen.peek();
+
+                let Ok(response) = api::get_request_logs_since(since_id, user_qq, size).await
+                else {
+                    continue;
+                };
+                if *poll_gen.peek() != generation || *request_gen.peek() != load_gen {
+                    continue;
+                }
+                if response.logs.is_empty() {
+                    continue;
+                }
+
+                total.set(response.total);
+                let mut merged = response.logs;
+                merged.extend(logs.peek().iter().cloned());
+                merged.truncate(size.max(0) as usize);
+                logs.set(merged);
+            }
+        });
+    };
+
     use_effect(move || {
         load_logs();
     });
 
-    let logs_snapshot = logs.read().clone();
+    let mut logs_snapshot = logs.read().clone();
+    let elapsed_sort_value = *elapsed_sort.read();
+    if let Some(descending) = elapsed_sort_value {
+        // 没有耗时的记录始终排在最后
+        logs_snapshot.sort_by_key(|log| match log.elapsed_ms {
+            Some(ms) if descending => (0, -ms),
+            Some(ms) => (0, ms),
+            None => (1, 0),
+        });
+    }
+    let elapsed_header = match elapsed_sort_value {
+        None => "耗时 ⇅",
+        Some(true) => "耗时 ↓",
+        Some(false) => "耗时 ↑",
+    };
     let total_count = *total.read();
     let current_page_value = *page.read();
     let is_loading = *loading.read();
     let show_loading_indicator = *loading_visible.read();
     let error_message = error.read().clone();
+    let page_size_value = *page_size.read();
 
-    let total_pages = calc_total_pages(total_count);
+    let total_pages = calc_total_pages(total_count, page_size_value);
     let displayed_page = if total_pages > 0 {
         current_page_value + 1
     } else {
@@ -125,7 +268,7 @@ pub fn Logs() -> Element {
     let showing_range = if logs_snapshot.is_empty() || total_count == 0 {
         None
     } else {
-        let start = (current_page_value as i64) * PAGE_SIZE as i64 + 1;
+        let start = (current_page_value as i64) * page_size_value as i64 + 1;
         let end = start + logs_snapshot.len() as i64 - 1;
         Some((start, end))
     };
@@ -149,10 +292,21 @@ pub fn Logs() -> Element {
         "第 0 / 0 页".to_string()
     };
 
+    let can_view_metrics = current_user
+        .read()
+        .as_ref()
+        .is_some_and(|user| user.permissions.iter().any(|p| p == "查看日志"));
+
     rsx! {
         div { class: "page-container",
             h1 { "系统日志" }
 
+            if can_view_metrics {
+                MetricsPanel {}
+                LogStatsPanel {}
+                DenialsPanel {}
+            }
+
             div { class: "toolbar",
                 form {
                     class: "search-box",
@@ -179,12 +333,46 @@ pub fn Logs() -> Element {
                         "重置"
                     }
                 }
+                select {
+                    class: "page-size-select",
+                    value: "{page_size_value}",
+                    onchange: change_page_size,
+                    disabled: is_loading,
+                    for size in PAGE_SIZE_OPTIONS {
+                        option { value: "{size}", "每页 {size} 条" }
+                    }
+                }
                 button {
                     class: "btn-secondary",
                     onclick: move |_| load_logs(),
                     disabled: is_loading,
                     "刷新"
                 }
+                label { class: "checkbox-label",
+                    input {
+                        r#type: "checkbox",
+                        checked: *auto_refresh.read(),
+                        onchange: move |evt| toggle_auto_refresh(evt.checked()),
+                    }
+                    "自动刷新"
+                }
+            }
+
+            div { class: "toolbar",
+                input {
+                    r#type: "text",
+                    placeholder: "按请求路径删除，例如 /shop/purchase",
+                    value: "{delete_path}",
+                    oninput: move |evt| delete_path.set(evt.value().clone()),
+                    disabled: is_loading,
+                }
+                button {
+                    class: "btn-danger",
+                    onclick: delete_logs,
+                    disabled: is_loading,
+                    title: "删除当前筛选用户和/或指定路径的日志",
+                    "批量删除"
+                }
             }
 
             if show_loading_indicator {
@@ -208,13 +396,29 @@ pub fn Logs() -> Element {
                                 th { "路径" }
                                 th { "用户QQ" }
                                 th { "状态码" }
+                                th {
+                                    class: "sortable-header",
+                                    title: "按耗时排序当前页",
+                                    onclick: move |_| {
+                                        let next = match *elapsed_sort.peek() {
+                                            None => Some(true),
+                                            Some(true) => Some(false),
+                                            Some(false) => None,
+                                        };
+                                        elapsed_sort.set(next);
+                                    },
+                                    "{elapsed_header}"
+                                }
                                 th { "请求体" }
                             }
                         }
                         tbody {
                             for log in logs_snapshot.iter() {
                                 tr { key: "{log.id}",
-                                    td { "{log.id}" }
+                                    td {
+                                        title: log.trace_id.as_deref().map(|id| format!("追踪 ID: {}", id)).unwrap_or_default(),
+                                        "{log.id}"
+                                    }
                                     td { class: "mono-cell", "{log.timestamp}" }
                                     td {
                                         span {
@@ -236,6 +440,13 @@ pub fn Logs() -> Element {
                                             "{log.status}"
                                         }
                                     }
+                                    td { class: "mono-cell",
+                                        if let Some(ms) = log.elapsed_ms {
+                                            "{ms} ms"
+                                        } else {
+                                            "-"
+                                        }
+                                    }
                                     td {
                                         div {
                                             class: "log-body-cell mono-cell",
@@ -273,11 +484,243 @@ pub fn Logs() -> Element {
     }
 }
 
-fn calc_total_pages(total: i64) -> i32 {
-    if total <= 0 {
+/// 日志页顶部的服务运行概况：总请求数、错误率和运行时长
+#[component]
+fn MetricsPanel() -> Element {
+    let mut metrics = use_signal(|| None::<api::MetricsReport>);
+    let mut error = use_signal(|| None::<String>);
+
+    let load_metrics = move || {
+        spawn(async move {
+            match api::get_metrics().await {
+                Ok(report) => {
+                    metrics.set(Some(report));
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    use_effect(load_metrics);
+
+    rsx! {
+        div { class: "metrics-panel",
+            if let Some(report) = metrics.read().as_ref() {
+                div { class: "metrics-item",
+                    span { class: "metrics-label", "总请求数" }
+                    span { class: "metrics-value", "{report.requests.total}" }
+                }
+                div { class: "metrics-item",
+                    span { class: "metrics-label", "错误率" }
+                    span {
+                        class: if report.requests.error_rate_percent > 5.0 { "metrics-value text-danger" } else { "metrics-value" },
+                        title: "错误 {report.requests.errors} 次",
+                        "{report.requests.error_rate_percent:.2}%"
+                    }
+                }
+                div { class: "metrics-item",
+                    span { class: "metrics-label", "运行时长" }
+                    span { class: "metrics-value", "{report.uptime_human}" }
+                }
+            } else if let Some(err) = error.read().as_ref() {
+                span { class: "metrics-error", "{err}" }
+            } else {
+                span { class: "metrics-label", "正在加载运行指标..." }
+            }
+            button {
+                class: "btn-small btn-secondary",
+                onclick: move |_| load_metrics(),
+                "刷新"
+            }
+        }
+    }
+}
+
+/// 按状态码类别和请求方法汇总一段时间内的请求，起止日期留空时统计最近 24 小时
+#[component]
+fn LogStatsPanel() -> Element {
+    let mut stats = use_signal(|| None::<RequestLogStats>);
+    let mut error = use_signal(|| None::<String>);
+    let mut start_date = use_signal(String::new);
+    let mut end_date = use_signal(String::new);
+
+    let load_stats = move || {
+        let start = Some(start_date.read().clone()).filter(|date| !date.is_empty());
+        let end = Some(end_date.read().clone()).filter(|date| !date.is_empty());
+        spawn(async move {
+            match api::get_request_log_stats(start, end).await {
+                Ok(result) => {
+                    stats.set(Some(result));
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    use_hook(load_stats);
+
+    rsx! {
+        div { class: "metrics-panel log-stats-panel",
+            if let Some(report) = stats.read().as_ref() {
+                div { class: "metrics-item",
+                    span { class: "metrics-label", title: "{report.start} 至 {report.end}", "请求数" }
+                    span { class: "metrics-value", "{report.total}" }
+                }
+                div { class: "log-stats-detail",
+                    div { class: "log-stats-bar",
+                        for bucket in report.by_status.iter().filter(|bucket| bucket.count > 0) {
+                            div {
+                                key: "{bucket.key}",
+                                class: "log-stats-segment {status_bucket_class(&bucket.key)}",
+                                style: "flex-grow: {bucket.count};",
+                                title: "{bucket.key}: {bucket.count}",
+                            }
+                        }
+                    }
+                    div { class: "log-stats-legend",
+                        for bucket in report.by_status.iter() {
+                            span { key: "{bucket.key}", class: status_bucket_class(&bucket.key),
+                                "{bucket.key} {bucket.count}"
+                            }
+                        }
+                        for method in report.by_method.iter() {
+                            span { key: "{method.key}", class: method_badge_class(&method.key),
+                                "{method.key} {method.count}"
+                            }
+                        }
+                    }
+                }
+            } else if error.read().is_none() {
+                span { class: "metrics-label", "正在加载日志统计..." }
+            }
+            if let Some(err) = error.read().as_ref() {
+                span { class: "metrics-error", "{err}" }
+            }
+            input {
+                r#type: "date",
+                title: "起始日期（包含）",
+                value: "{start_date}",
+                oninput: move |evt| start_date.set(evt.value()),
+            }
+            input {
+                r#type: "date",
+                title: "结束日期（不包含）",
+                value: "{end_date}",
+                oninput: move |evt| end_date.set(evt.value()),
+            }
+            button {
+                class: "btn-small btn-secondary",
+                onclick: move |_| load_stats(),
+                "统计"
+            }
+        }
+    }
+}
+
+fn status_bucket_class(bucket: &str) -> &'static str {
+    match bucket {
+        "2xx" => "badge badge-success",
+        "3xx" => "badge badge-info",
+        "4xx" => "badge badge-warning",
+        "5xx" => "badge badge-danger",
+        _ => "badge",
+    }
+}
+
+/// 近期被拒绝访问（403）的用户汇总，反复被拒绝的用户突出显示，便于发现越权试探
+#[component]
+fn DenialsPanel() -> Element {
+    let mut denials = use_signal(Vec::<PermissionDenialSummary>::new);
+    let mut error = use_signal(|| None::<String>);
+
+    let load_denials = move || {
+        spawn(async move {
+            match api::get_permission_denials(DENIAL_WINDOW_DAYS).await {
+                Ok(list) => {
+                    denials.set(list);
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    use_effect(load_denials);
+
+    rsx! {
+        div { class: "info-section",
+            h2 { "近 {DENIAL_WINDOW_DAYS} 天拒绝访问统计" }
+            button {
+                class: "btn-small btn-secondary",
+                onclick: move |_| load_denials(),
+                "刷新"
+            }
+            if let Some(err) = error.read().as_ref() {
+                div { class: "error-message", "{err}" }
+            } else if denials.read().is_empty() {
+                div { class: "empty-state", "暂无被拒绝的请求" }
+            } else {
+                table { class: "data-table",
+                    thead {
+                        tr {
+                            th { "用户QQ" }
+                            th { "拒绝次数" }
+                            th { "涉及接口数" }
+                            th { "最近一次" }
+                        }
+                    }
+                    tbody {
+                        for denial in denials.read().iter() {
+                            tr {
+                                key: "{denial.user_qq}",
+                                td { "{denial.user_qq}" }
+                                td {
+                                    "{denial.denials}"
+                                    if denial.denials >= REPEATED_DENIAL_THRESHOLD {
+                                        span {
+                                            class: "badge badge-danger",
+                                            title: "近期多次尝试访问无权限的接口",
+                                            "频繁"
+                                        }
+                                    }
+                                }
+                                td { "{denial.paths}" }
+                                td { "{denial.last_denied}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn calc_total_pages(total: i64, page_size: i32) -> i32 {
+    if total <= 0 || page_size <= 0 {
         0
     } else {
-        (((total - 1) / PAGE_SIZE as i64) + 1) as i32
+        (((total - 1) / page_size as i64) + 1) as i32
+    }
+}
+
+/// 从localStorage读取每页条数，非法值回退为默认值
+fn load_page_size() -> i32 {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(PAGE_SIZE_STORAGE_KEY).ok().flatten())
+        .and_then(|value| value.parse::<i32>().ok())
+        .filter(|size| PAGE_SIZE_OPTIONS.contains(size))
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+}
+
+/// 保存每页条数到localStorage
+fn save_page_size(size: i32) {
+    if let Some(storage) =
+        web_sys::window().and_then(|window| window.local_storage().ok().flatten())
+    {
+        let _ = storage.set_item(PAGE_SIZE_STORAGE_KEY, &size.to_string());
     }
 }
 
@@ -0,0 +1,106 @@
+use crate::api;
+use crate::components::MarkdownText;
+use dioxus::prelude::*;
+
+/// 单条LP申请详情，供通知等处的链接直接跳转
+#[component]
+pub fn LpDetail(id: i64) -> Element {
+    let detail = use_resource(use_reactive!(
+        |id| async move { api::get_lp_detail(id).await }
+    ));
+
+    rsx! {
+        div { class: "page-container",
+            h1 { "LP申请 #{id}" }
+
+            div { class: "toolbar",
+                Link { to: crate::Route::LpManagement {},
+                    button { class: "btn-secondary", "返回LP管理" }
+                }
+            }
+
+            match &*detail.read() {
+                None => rsx! {
+                    div { class: "loading-message", "加载中..." }
+                },
+                Some(Err(e)) => rsx! {
+                    div { class: "error-message", "{e}" }
+                },
+                Some(Ok(resp)) => {
+                    let log = &resp.log;
+                    let type_name = resp
+                        .type_name
+                        .clone()
+                        .unwrap_or_else(|| format!("类型#{}", log.lp_type));
+                    rsx! {
+                        div { class: "info-section",
+                            table { class: "data-table",
+                                tbody {
+                                    tr {
+                                        th { "状态" }
+                                        td {
+                                            match log.status {
+                                                0 => rsx!(span { class: "badge badge-warning", "待审核" }),
+                                                1 => rsx!(span { class: "badge badge-success", "已通过" }),
+                                                2 => rsx!(span { class: "badge badge-danger", "已拒绝" }),
+                                                _ => rsx!(span { class: "badge", "未知" }),
+                                            }
+                                        }
+                                    }
+                                    tr {
+                                        th { "LP类型" }
+                                        td { "{type_name}" }
+                                    }
+                                    tr {
+                                        th { "数量" }
+                                        td { "{log.num}" }
+                                    }
+                                    tr {
+                                        th { "关联用户" }
+                                        td { "{log.user_qq}" }
+                                    }
+                                    tr {
+                                        th { "提交人" }
+                                        td { "{log.upload_user_qq}" }
+                                    }
+                                    tr {
+                                        th { "提交时间" }
+                                        td { "{log.upload_time}" }
+                                    }
+                                    tr {
+                                        th { "角色" }
+                                        td { {log.role.clone().unwrap_or_else(|| "-".to_string())} }
+                                    }
+                                    tr {
+                                        th { "理由" }
+                                        td {
+                                            MarkdownText { text: log.reason.clone() }
+                                        }
+                                    }
+                                    tr {
+                                        th { "图片" }
+                                        td {
+                                            if let Some(picture) = log.picture.as_ref().filter(|p| !p.is_empty()) {
+                                                a { href: "{picture}", target: "_blank", "{picture}" }
+                                            } else {
+                                                "-"
+                                            }
+                                        }
+                                    }
+                                    tr {
+                                        th { "处理人" }
+                                        td { {log.process_user_qq.clone().unwrap_or_else(|| "-".to_string())} }
+                                    }
+                                    tr {
+                                        th { "处理时间" }
+                                        td { {log.process_time.clone().unwrap_or_else(|| "-".to_string())} }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
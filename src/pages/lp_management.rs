@@ -1,10 +1,15 @@
 use crate::api;
-use crate::components::use_current_user;
-use crate::models::UserLpSummary;
+use crate::components::{use_current_user, MarkdownText, UserSuggestions};
+use crate::models::{LpTypeStat, UserLpSummary};
 use dioxus::prelude::*;
 use gloo_timers::future::TimeoutFuture;
 use std::collections::HashMap;
 
+/// 批量审批单次最多选择的条数，与后端默认上限一致
+const BATCH_MAX_IDS: usize = 500;
+/// “我审批的”每页条数
+const PROCESSED_PAGE_SIZE: i32 = 20;
+
 #[component]
 pub fn LpManagement() -> Element {
     let mut lp_logs = use_signal(Vec::<api::LpLog>::new);
@@ -13,6 +18,7 @@ pub fn LpManagement() -> Element {
     let mut selected_summary = use_signal(|| None::<UserLpSummary>);
     let mut selected_history = use_signal(Vec::<api::LpLog>::new);
     let mut lp_summaries = use_signal(Vec::<UserLpSummary>::new);
+    let mut type_stats = use_signal(Vec::<LpTypeStat>::new);
     let mut show_pending_only = use_signal(|| false);
     let mut error = use_signal(|| None::<String>);
     let mut success = use_signal(|| None::<String>);
@@ -20,6 +26,18 @@ pub fn LpManagement() -> Element {
     let loading_visible = use_signal(|| false);
     let current_user = use_current_user();
     let mut selected_ids = use_signal(Vec::<i64>::new);
+    // 请求代次：每次发起加载前递增，过期的响应不再写回状态
+    let mut request_gen = use_signal(|| 0u64);
+    let mut show_transfer = use_signal(|| false);
+    let mut transfer_from = use_signal(String::new);
+    let mut transfer_to = use_signal(String::new);
+    let mut transfer_num = use_signal(String::new);
+    let mut transfer_reason = use_signal(String::new);
+    let mut show_processed = use_signal(|| false);
+    let mut processed_logs = use_signal(Vec::<api::LpLog>::new);
+    let mut processed_total = use_signal(|| 0i64);
+    let mut processed_page = use_signal(|| 0i32);
+    let mut review_policy = use_signal(api::LpReviewPolicy::default);
 
     {
         let loading = loading;
@@ -40,11 +58,24 @@ pub fn LpManagement() -> Element {
         });
     }
 
-    let load_logs = move || {
+    let mut load_logs = move || {
+        let generation = *request_gen.peek() + 1;
+        request_gen.set(generation);
+
         spawn(async move {
             loading.set(true);
 
-            match api::get_lp_types().await {
+            let types_result = api::get_lp_types().await;
+            let logs_result = if *show_pending_only.peek() {
+                api::get_pending_lp_logs().await
+            } else {
+                api::get_lp_logs().await
+            };
+            if *request_gen.peek() != generation {
+                return;
+            }
+
+            match types_result {
                 Ok(types) => {
                     let map = types
                         .into_iter()
@@ -57,11 +88,8 @@ pub fn LpManagement() -> Element {
                 }
             }
 
-            match api::get_lp_logs().await {
-                Ok(mut logs) => {
-                    if *show_pending_only.peek() {
-                        logs.retain(|log| log.status == 0);
-                    }
+            match logs_result {
+                Ok(logs) => {
                     lp_logs.set(logs);
                 }
                 Err(e) => {
@@ -73,6 +101,25 @@ pub fn LpManagement() -> Element {
         });
     };
 
+    // 加载当前审批人处理过的LP申请
+    let load_processed = move |page: i32| {
+        spawn(async move {
+            loading.set(true);
+            match api::get_lp_processed_by_me(PROCESSED_PAGE_SIZE, page * PROCESSED_PAGE_SIZE).await
+            {
+                Ok(resp) => {
+                    processed_logs.set(resp.logs);
+                    processed_total.set(resp.total);
+                    processed_page.set(page);
+                }
+                Err(e) => {
+                    error.set(Some(format!("加载审批记录失败: {}", e)));
+                }
+            }
+            loading.set(false);
+        });
+    };
+
     let mut query_user_lp = move || {
         let raw = search_user.read().clone();
         let qq = raw.trim().to_string();
@@ -138,6 +185,49 @@ pub fn LpManagement() -> Element {
         });
     };
 
+    let export_summaries = move |_| {
+        spawn(async move {
+            loading.set(true);
+            if let Err(e) = api::export_lp_summaries().await {
+                error.set(Some(format!("导出LP汇总失败: {}", e)));
+            }
+            loading.set(false);
+        });
+    };
+
+    let export_user_history = move |qq: String| {
+        spawn(async move {
+            loading.set(true);
+            if let Err(e) = api::export_user_lp_history(&qq).await {
+                error.set(Some(format!("导出LP记录失败: {}", e)));
+            }
+            loading.set(false);
+        });
+    };
+
+    // 拒绝时询问理由：取消输入返回 None；要求必填时理由为空同样返回 None
+    let mut ask_reject_reason = move || -> Option<Option<String>> {
+        let required = review_policy.read().reject_reason_required;
+        let message = if required {
+            "请输入拒绝理由（必填）："
+        } else {
+            "请输入拒绝理由（可留空）："
+        };
+        let window = web_sys::window().expect("no global `window` exists");
+        let Ok(Some(input)) = window.prompt_with_message(message) else {
+            return None;
+        };
+        let reason = input.trim().to_string();
+        if reason.is_empty() {
+            if required {
+                error.set(Some("拒绝LP申请时必须填写理由".to_string()));
+                return None;
+            }
+            return Some(None);
+        }
+        Some(Some(reason))
+    };
+
     let mut process_lp = move |id: i64, status: i32| {
         let session_user = current_user.read().clone();
         let Some(user) = session_user else {
@@ -150,9 +240,18 @@ pub fn LpManagement() -> Element {
             return;
         }
 
+        let reason = if status == 2 {
+            let Some(reason) = ask_reject_reason() else {
+                return;
+            };
+            reason
+        } else {
+            None
+        };
+
         spawn(async move {
             loading.set(true);
-            match api::process_lp(id, status).await {
+            match api::process_lp(id, status, reason).await {
                 Ok(msg) => {
                     success.set(Some(msg));
                     error.set(None);
@@ -166,6 +265,75 @@ pub fn LpManagement() -> Element {
         });
     };
 
+    // 克隆LP申请给其他用户，适用于每周考勤等重复发放的奖励
+    let mut clone_lp = move |id: i64| {
+        let window = web_sys::window().expect("no global `window` exists");
+        let input = window.prompt_with_message("请输入新申请关联用户的QQ号：");
+        let Ok(Some(input)) = input else {
+            return;
+        };
+        let user_qq = input.trim().to_string();
+        if user_qq.is_empty() {
+            error.set(Some("QQ号不能为空".to_string()));
+            return;
+        }
+
+        spawn(async move {
+            loading.set(true);
+            match api::clone_lp(id, &user_qq).await {
+                Ok(resp) => {
+                    success.set(Some(format!("{}，新申请ID: {}", resp.message, resp.id)));
+                    error.set(None);
+                    load_logs();
+                }
+                Err(e) => {
+                    error.set(Some(format!("克隆失败: {}", e)));
+                }
+            }
+            loading.set(false);
+        });
+    };
+
+    // 在两个用户之间转移LP
+    let transfer_lp = move |evt: Event<FormData>| {
+        evt.prevent_default();
+
+        let from_qq = transfer_from.read().trim().to_string();
+        let to_qq = transfer_to.read().trim().to_string();
+        let reason = transfer_reason.read().trim().to_string();
+        let num = match transfer_num.read().trim().parse::<i32>() {
+            Ok(num) if num > 0 => num,
+            _ => {
+                error.set(Some("转移数量必须是正整数".to_string()));
+                return;
+            }
+        };
+        if from_qq.is_empty() || to_qq.is_empty() || reason.is_empty() {
+            error.set(Some("请填写转出用户、转入用户和理由".to_string()));
+            return;
+        }
+
+        spawn(async move {
+            loading.set(true);
+            match api::transfer_lp(&from_qq, &to_qq, num, &reason).await {
+                Ok(resp) => {
+                    success.set(Some(format!(
+                        "{}，转出记录 #{}，转入记录 #{}",
+                        resp.message, resp.debit_id, resp.credit_id
+                    )));
+                    error.set(None);
+                    transfer_num.set(String::new());
+                    transfer_reason.set(String::new());
+                    load_logs();
+                }
+                Err(e) => {
+                    error.set(Some(format!("LP转移失败: {}", e)));
+                }
+            }
+            loading.set(false);
+        });
+    };
+
     // 批量处理LP
     let mut batch_process_lp = move |status: i32| {
         let session_user = current_user.read().clone();
@@ -185,9 +353,18 @@ pub fn LpManagement() -> Element {
             return;
         }
 
+        let reason = if status == 2 {
+            let Some(reason) = ask_reject_reason() else {
+                return;
+            };
+            reason
+        } else {
+            None
+        };
+
         spawn(async move {
             loading.set(true);
-            match api::batch_process_lp(ids.clone(), status).await {
+            match api::batch_process_lp(ids.clone(), status, reason).await {
                 Ok(resp) => {
                     success.set(Some(format!(
                         "批量处理成功: 请求{}条，通过{}条",
@@ -210,6 +387,9 @@ pub fn LpManagement() -> Element {
         let mut ids = selected_ids.read().clone();
         if let Some(pos) = ids.iter().position(|x| *x == id) {
             ids.remove(pos);
+        } else if ids.len() >= BATCH_MAX_IDS {
+            error.set(Some(format!("单次最多批量处理 {} 条申请", BATCH_MAX_IDS)));
+            return;
         } else {
             ids.push(id);
         }
@@ -223,6 +403,7 @@ pub fn LpManagement() -> Element {
             .iter()
             .filter(|log| log.status == 0) // 只选择待审核的
             .filter_map(|log| log.id)
+            .take(BATCH_MAX_IDS) // 不超过后端单次批量处理的上限
             .collect();
 
         let current_selected = selected_ids.read().clone();
@@ -237,14 +418,54 @@ pub fn LpManagement() -> Element {
         load_logs();
     });
 
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(policy) = api::get_lp_review_policy().await {
+                review_policy.set(policy);
+            }
+        });
+    });
+
+    let mut load_type_stats = move || {
+        success.set(None);
+        spawn(async move {
+            loading.set(true);
+            match api::get_lp_type_stats().await {
+                Ok(data) => {
+                    type_stats.set(data);
+                    error.set(None);
+                }
+                Err(e) => {
+                    error.set(Some(format!("获取LP类型统计失败: {}", e)));
+                }
+            }
+            loading.set(false);
+        });
+    };
+
     let type_map_snapshot = lp_types.read().clone();
     let logs_snapshot = lp_logs.read().clone();
     let summary_snapshot = selected_summary.read().clone();
     let history_snapshot = selected_history.read().clone();
     let summaries_snapshot = lp_summaries.read().clone();
+    let type_stats_snapshot = type_stats.read().clone();
+    let max_approved_abs = type_stats_snapshot
+        .iter()
+        .map(|stat| stat.approved_num.abs())
+        .max()
+        .unwrap_or(0)
+        .max(1);
     let error_snapshot = error.read().clone();
     let is_loading = *loading.read();
     let searched_user_trimmed = search_user.read().trim().to_string();
+    let can_review = current_user
+        .read()
+        .as_ref()
+        .is_some_and(|user| user.permissions.contains(&"审核LP".to_string()));
+    let processed_page_value = *processed_page.read();
+    let processed_total_pages = ((*processed_total.read() + PROCESSED_PAGE_SIZE as i64 - 1)
+        / PROCESSED_PAGE_SIZE as i64)
+        .max(1) as i32;
     let resolve_type = |lp_type: i64| -> String {
         type_map_snapshot
             .get(&lp_type)
@@ -280,6 +501,67 @@ pub fn LpManagement() -> Element {
                     disabled: *loading.read(),
                     "刷新"
                 }
+
+                if can_review {
+                    button {
+                        class: "btn-info",
+                        onclick: move |_| {
+                            let visible = *show_transfer.read();
+                            show_transfer.set(!visible);
+                        },
+                        if *show_transfer.read() { "收起LP转移" } else { "LP转移" }
+                    }
+                }
+            }
+
+            if can_review && *show_transfer.read() {
+                div { class: "info-section",
+                    h2 { "LP转移" }
+                    form { onsubmit: transfer_lp,
+                        div { class: "form-group",
+                            label { "转出用户QQ：*" }
+                            input {
+                                r#type: "text",
+                                list: "lp-management-user-suggestions",
+                                value: "{transfer_from}",
+                                oninput: move |evt| transfer_from.set(evt.value())
+                            }
+                        }
+                        div { class: "form-group",
+                            label { "转入用户QQ：*" }
+                            input {
+                                r#type: "text",
+                                list: "lp-management-user-suggestions",
+                                value: "{transfer_to}",
+                                oninput: move |evt| transfer_to.set(evt.value())
+                            }
+                        }
+                        div { class: "form-group",
+                            label { "数量：*" }
+                            input {
+                                r#type: "number",
+                                min: "1",
+                                value: "{transfer_num}",
+                                oninput: move |evt| transfer_num.set(evt.value())
+                            }
+                        }
+                        div { class: "form-group",
+                            label { "理由：*" }
+                            textarea {
+                                value: "{transfer_reason}",
+                                oninput: move |evt| transfer_reason.set(evt.value())
+                            }
+                        }
+                        div { class: "form-actions",
+                            button {
+                                r#type: "submit",
+                                class: "btn-primary",
+                                disabled: is_loading,
+                                "确认转移"
+                            }
+                        }
+                    }
+                }
             }
 
             div { class: "toolbar",
@@ -287,10 +569,12 @@ pub fn LpManagement() -> Element {
                     input {
                         r#type: "text",
                         placeholder: "输入QQ号查询LP记录",
+                        list: "lp-management-user-suggestions",
                         value: "{search_user}",
                         oninput: move |evt| search_user.set(evt.value().clone()),
                         disabled: *loading.read()
                     }
+                    UserSuggestions { id: "lp-management-user-suggestions", query: search_user }
                     button {
                         class: "btn-info",
                         onclick: move |_| query_user_lp(),
@@ -309,6 +593,12 @@ pub fn LpManagement() -> Element {
                         disabled: *loading.read(),
                         "加载LP汇总"
                     }
+                    button {
+                        class: "btn-info",
+                        onclick: move |_| load_type_stats(),
+                        disabled: *loading.read(),
+                        "类型统计"
+                    }
                 }
             }
 
@@ -328,12 +618,31 @@ pub fn LpManagement() -> Element {
                 div { class: "info-section summary-section",
                     h2 { "用户LP汇总" }
                     p { class: "summary-subtitle", "{summary.nickname} ({summary.qq})" }
+                    button {
+                        class: "btn-secondary",
+                        onclick: {
+                            let qq = summary.qq.clone();
+                            move |_| export_user_history(qq.clone())
+                        },
+                        disabled: is_loading,
+                        "导出CSV"
+                    }
 
                     div { class: "summary-grid",
                         div { class: "summary-item",
                             span { "总LP" }
                             strong { "{summary.total_lp}" }
                         }
+                        div { class: "summary-item",
+                            span { "本月变化" }
+                            strong {
+                                if summary.monthly_delta > 0 {
+                                    "+{summary.monthly_delta}"
+                                } else {
+                                    "{summary.monthly_delta}"
+                                }
+                            }
+                        }
                         div { class: "summary-item",
                             span { "待审核" }
                             strong { "{summary.pending_count}" }
@@ -378,7 +687,14 @@ pub fn LpManagement() -> Element {
                             for log in history_snapshot.iter() {
                                 tr {
                                     key: "detail-{log.id.unwrap_or_default()}",
-                                    td { "{log.id.unwrap_or_default()}" }
+                                    td {
+                                        Link {
+                                            to: crate::Route::LpDetail {
+                                                id: log.id.unwrap_or_default(),
+                                            },
+                                            "{log.id.unwrap_or_default()}"
+                                        }
+                                    }
                                     td { "{log.upload_time}" }
                                     td { "{log.user_qq}" }
                                     td { "{resolve_type(log.lp_type)}" }
@@ -390,7 +706,7 @@ pub fn LpManagement() -> Element {
                                         match log.status {
                                             0 => rsx!(span { class: "badge badge-warning", "待处理" }),
                                             1 => rsx!(span { class: "badge badge-success", "已通过" }),
-                                            2 => rsx!(span { class: "badge badge-danger", "已拒绝" }),
+                                            2 => rsx!(span { class: "badge badge-danger", title: "{log.reject_reason.clone().unwrap_or_default()}", "已拒绝" }),
                                             _ => rsx!(span { class: "badge", "未知" }),
                                         }
                                     }
@@ -415,9 +731,54 @@ pub fn LpManagement() -> Element {
                 }
             }
 
+            if !type_stats_snapshot.is_empty() {
+                div { class: "info-section summary-section",
+                    h2 { "LP类型统计" }
+                    table { class: "data-table",
+                        thead {
+                            tr {
+                                th { "类型" }
+                                th { "申请数" }
+                                th { "已通过LP" }
+                                th { "" }
+                            }
+                        }
+                        tbody {
+                            for stat in type_stats_snapshot.iter() {
+                                tr {
+                                    key: "type-stat-{stat.lp_type_id}",
+                                    td { "{stat.name}" }
+                                    td { "{stat.log_count}" }
+                                    td {
+                                        class: if stat.approved_num > 0 { "text-success" } else if stat.approved_num < 0 { "text-danger" } else { "" },
+                                        "{stat.approved_num}"
+                                    }
+                                    td {
+                                        div { class: "stat-bar-track",
+                                            div {
+                                                class: if stat.approved_num < 0 { "stat-bar stat-bar-negative" } else { "stat-bar" },
+                                                style: "width: {stat.approved_num.abs() * 100 / max_approved_abs}%;",
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
             if !summaries_snapshot.is_empty() {
                 div { class: "table-container",
                     //h2 { class: "section-title", style: "text-align: center;", "LP汇总排行" }
+                    div { class: "batch-actions",
+                        button {
+                            class: "btn-secondary",
+                            onclick: export_summaries,
+                            disabled: is_loading,
+                            "导出CSV"
+                        }
+                    }
                     table { class: "data-table",
                         thead {
                             tr {
@@ -448,124 +809,222 @@ pub fn LpManagement() -> Element {
                 }
             }
 
-            div { class: "table-container",
-                // 批量操作按钮
-                div { class: "batch-actions",
-                    button {
-                        class: "btn-secondary",
-                        onclick: move |_| toggle_select_all(),
-                        "全选/取消全选"
-                    }
+            if can_review {
+                div { class: "toolbar",
                     button {
-                        class: "btn-primary",
-                        onclick: move |_| batch_process_lp(1),
-                        disabled: *loading.read() || selected_ids.read().is_empty(),
-                        "批量通过 ({selected_ids.read().len()})"
+                        class: if *show_processed.read() { "btn-secondary" } else { "btn-primary" },
+                        onclick: move |_| show_processed.set(false),
+                        "全部申请"
                     }
                     button {
-                        class: "btn-danger",
-                        onclick: move |_| batch_process_lp(2),
-                        disabled: *loading.read() || selected_ids.read().is_empty(),
-                        "批量拒绝 ({selected_ids.read().len()})"
+                        class: if *show_processed.read() { "btn-primary" } else { "btn-secondary" },
+                        onclick: move |_| {
+                            show_processed.set(true);
+                            load_processed(0);
+                        },
+                        "我审批的"
                     }
                 }
+            }
 
-                table { class: "data-table",
-                    thead {
-                        tr {
-                            th { "选择" }
-                            th { "ID" }
-                            th { "上传时间" }
-                            th { "上传者" }
-                            th { "关联用户" }
-                            th { "角色" }
-                            th { "类型" }
-                            th { "数量" }
-                            th { "原因" }
-                            th { "状态" }
-                            th { "处理人" }
-                            th { "处理时间" }
-                            th { "操作" }
-                        }
-                    }
-                    tbody {
-                        for log in logs_snapshot.iter() {
-                            {
-                                let log_id = log.id.unwrap_or_default();
-                                let is_selected = selected_ids.read().contains(&log_id);
-                                let is_pending = log.status == 0;
-
-                                rsx! {
+            if *show_processed.read() {
+                div { class: "table-container",
+                    if processed_logs.read().is_empty() {
+                        div { class: "empty-state", "暂无审批记录" }
+                    } else {
+                        table { class: "data-table",
+                            thead {
+                                tr {
+                                    th { "ID" }
+                                    th { "关联用户" }
+                                    th { "类型" }
+                                    th { "数量" }
+                                    th { "原因" }
+                                    th { "结果" }
+                                    th { "处理时间" }
+                                }
+                            }
+                            tbody {
+                                for log in processed_logs.read().iter() {
                                     tr {
-                                        key: "{log_id}",
+                                        key: "processed-{log.id.unwrap_or_default()}",
                                         td {
-                                            if is_pending {
-                                                input {
-                                                    r#type: "checkbox",
-                                                    checked: is_selected,
-                                                    onchange: move |_| toggle_select(log_id),
-                                                }
+                                            Link { to: crate::Route::LpDetail { id: log.id.unwrap_or_default() },
+                                                "{log.id.unwrap_or_default()}"
                                             }
                                         }
-                                        td { "{log_id}" }
-                                        td { "{log.upload_time}" }
-                                        td { "{log.upload_user_qq}" }
                                         td { "{log.user_qq}" }
-                                td {
-                                    if let Some(role) = &log.role {
-                                        "{role}"
-                                    } else {
-                                        "-"
+                                        td { "{resolve_type(log.lp_type)}" }
+                                        td { "{log.num}" }
+                                        td {
+                                            MarkdownText { text: log.reason.clone() }
+                                        }
+                                        td {
+                                            match log.status {
+                                                1 => rsx!(span { class: "badge badge-success", "已通过" }),
+                                                2 => rsx!(span { class: "badge badge-danger", title: "{log.reject_reason.clone().unwrap_or_default()}", "已拒绝" }),
+                                                _ => rsx!(span { class: "badge badge-warning", "待处理" }),
+                                            }
+                                        }
+                                        td { {log.process_time.clone().unwrap_or_else(|| "-".to_string())} }
                                     }
                                 }
-                                td { "{resolve_type(log.lp_type)}" }
-                                td {
-                                    class: if log.num > 0 { "text-success" } else if log.num < 0 { "text-danger" } else { "" },
-                                    "{log.num}"
+                            }
+                        }
+                        div { class: "pagination-bar",
+                            span { "第 {processed_page_value + 1} / {processed_total_pages} 页，共 {processed_total} 条" }
+                            div { class: "pagination-actions",
+                                button {
+                                    class: "btn-secondary",
+                                    disabled: is_loading || processed_page_value == 0,
+                                    onclick: move |_| load_processed(processed_page_value - 1),
+                                    "上一页"
                                 }
-                                td { "{log.reason}" }
-                                td {
-                                    match log.status {
-                                        0 => rsx!(span { class: "badge badge-warning", "待处理" }),
-                                        1 => rsx!(span { class: "badge badge-success", "已通过" }),
-                                        2 => rsx!(span { class: "badge badge-danger", "已拒绝" }),
-                                        _ => rsx!(span { class: "badge", "未知" }),
-                                    }
+                                button {
+                                    class: "btn-secondary",
+                                    disabled: is_loading || processed_page_value + 1 >= processed_total_pages,
+                                    onclick: move |_| load_processed(processed_page_value + 1),
+                                    "下一页"
                                 }
-                                td {
-                                    if let Some(processor) = &log.process_user_qq {
-                                        "{processor}"
-                                    } else {
-                                        "-"
+                            }
+                        }
+                    }
+                }
+            } else {
+                div { class: "table-container",
+                    // 批量操作按钮
+                    div { class: "batch-actions",
+                        button {
+                            class: "btn-secondary",
+                            onclick: move |_| toggle_select_all(),
+                            "全选/取消全选"
+                        }
+                        button {
+                            class: "btn-primary",
+                            onclick: move |_| batch_process_lp(1),
+                            disabled: *loading.read() || selected_ids.read().is_empty(),
+                            "批量通过 ({selected_ids.read().len()})"
+                        }
+                        button {
+                            class: "btn-danger",
+                            onclick: move |_| batch_process_lp(2),
+                            disabled: *loading.read() || selected_ids.read().is_empty(),
+                            "批量拒绝 ({selected_ids.read().len()})"
+                        }
+                    }
+
+                    table { class: "data-table",
+                        thead {
+                            tr {
+                                th { "选择" }
+                                th { "ID" }
+                                th { "上传时间" }
+                                th { "上传者" }
+                                th { "关联用户" }
+                                th { "角色" }
+                                th { "类型" }
+                                th { "数量" }
+                                th { "原因" }
+                                th { "状态" }
+                                th { "处理人" }
+                                th { "处理时间" }
+                                th { "操作" }
+                            }
+                        }
+                        tbody {
+                            for log in logs_snapshot.iter() {
+                                {
+                                    let log_id = log.id.unwrap_or_default();
+                                    let is_selected = selected_ids.read().contains(&log_id);
+                                    let is_pending = log.status == 0;
+
+                                    rsx! {
+                                        tr {
+                                            key: "{log_id}",
+                                            td {
+                                                if is_pending {
+                                                    input {
+                                                        r#type: "checkbox",
+                                                        checked: is_selected,
+                                                        onchange: move |_| toggle_select(log_id),
+                                                    }
+                                                }
+                                            }
+                                            td {
+                                                Link { to: crate::Route::LpDetail { id: log_id }, "{log_id}" }
+                                            }
+                                            td { "{log.upload_time}" }
+                                            td { "{log.upload_user_qq}" }
+                                            td { "{log.user_qq}" }
+                                    td {
+                                        if let Some(role) = &log.role {
+                                            "{role}"
+                                        } else {
+                                            "-"
+                                        }
                                     }
-                                }
-                                td {
-                                    if let Some(process_time) = &log.process_time {
-                                        "{process_time}"
-                                    } else {
-                                        "-"
+                                    td { "{resolve_type(log.lp_type)}" }
+                                    td {
+                                        class: if log.num > 0 { "text-success" } else if log.num < 0 { "text-danger" } else { "" },
+                                        "{log.num}"
                                     }
-                                }
-                                        td {
-                                            if log.status == 0 {
-                                                if let Some(id_val) = log.id {
-                                                    button {
-                                                        class: "btn-small btn-success",
-                                                        onclick: move |_| process_lp(id_val, 1),
-                                                        disabled: *loading.read(),
-                                                        "通过"
+                                    td {
+                                        MarkdownText { text: log.reason.clone() }
+                                    }
+                                    td {
+                                        match log.status {
+                                            0 => rsx!(span { class: "badge badge-warning", "待处理" }),
+                                            1 => rsx!(span { class: "badge badge-success", "已通过" }),
+                                            2 => rsx!(span { class: "badge badge-danger", title: "{log.reject_reason.clone().unwrap_or_default()}", "已拒绝" }),
+                                            _ => rsx!(span { class: "badge", "未知" }),
+                                        }
+                                    }
+                                    td {
+                                        if let Some(processor) = &log.process_user_qq {
+                                            "{processor}"
+                                        } else {
+                                            "-"
+                                        }
+                                    }
+                                    td {
+                                        if let Some(process_time) = &log.process_time {
+                                            "{process_time}"
+                                        } else {
+                                            "-"
+                                        }
+                                    }
+                                            td {
+                                                if log.status == 0 {
+                                                    if let Some(id_val) = log.id {
+                                                        button {
+                                                            class: "btn-small btn-success",
+                                                            onclick: move |_| process_lp(id_val, 1),
+                                                            disabled: *loading.read(),
+                                                            "通过"
+                                                        }
+                                                        button {
+                                                            class: "btn-small btn-danger",
+                                                            onclick: move |_| process_lp(id_val, 2),
+                                                            disabled: *loading.read(),
+                                                            "拒绝"
+                                                        }
+                                                    } else {
+                                                        span { class: "text-warning", "记录缺少ID" }
                                                     }
-                                                    button {
-                                                        class: "btn-small btn-danger",
-                                                        onclick: move |_| process_lp(id_val, 2),
-                                                        disabled: *loading.read(),
-                                                        "拒绝"
+                                                }
+                                                if can_review {
+                                                    if let Some(id_val) = log.id {
+                                                        button {
+                                                            class: "btn-small btn-secondary",
+                                                            title: "以此申请为模板为其他用户创建新申请",
+                                                            onclick: move |_| clone_lp(id_val),
+                                                            disabled: *loading.read(),
+                                                            "克隆"
+                                                        }
                                                     }
-                                                } else {
-                                                    span { class: "text-warning", "记录缺少ID" }
+                                                } else if log.status != 0 {
+                                                    "-"
                                                 }
-                                            } else {
-                                                "-"
                                             }
                                         }
                                     }
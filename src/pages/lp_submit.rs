@@ -1,6 +1,11 @@
 use crate::api;
-use crate::components::use_current_user;
+use crate::components::{use_confirm, use_current_user, UserSuggestions};
+use crate::models::LpDraft;
 use dioxus::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// 凭证图片文件选择框的元素ID
+const PICTURE_FILE_INPUT_ID: &str = "lp-picture-file";
 
 #[component]
 pub fn LpSubmit() -> Element {
@@ -13,18 +18,45 @@ pub fn LpSubmit() -> Element {
     let mut error = use_signal(|| None::<String>);
     let mut success_message = use_signal(|| None::<String>);
     let mut loading = use_signal(|| false);
+    let mut uploading = use_signal(|| false);
     let current_user = use_current_user();
+    let mut confirmer = use_confirm();
     let mut lp_types = use_signal(Vec::<api::LpType>::new);
+    // 幂等键：网络重试时复用，提交成功后再更换，避免重复创建申请
+    let mut submit_key = use_signal(|| uuid::Uuid::new_v4().to_string());
+    // 当前编辑中的草稿ID，直接提交成功后删除该草稿
+    let mut draft_id = use_signal(|| None::<i64>);
+    let mut drafts = use_signal(Vec::<LpDraft>::new);
+
+    let load_drafts = move || {
+        spawn(async move {
+            match api::get_lp_drafts().await {
+                Ok(list) => drafts.set(list),
+                Err(e) => error.set(Some(format!("加载草稿失败: {}", e))),
+            }
+        });
+    };
 
-    // 加载LP类型（只在组件挂载时执行一次）
+    use_hook(|| {
+        if current_user.read().is_some() {
+            load_drafts();
+        }
+    });
+
+    // 加载LP类型（只在组件挂载时执行一次），优先选中用户设置的默认类型
     use_hook(|| {
         spawn(async move {
             match api::get_lp_types().await {
                 Ok(types) => {
                     if !types.is_empty() {
+                        let default_type = api::get_profile()
+                            .await
+                            .ok()
+                            .and_then(|user| user.default_lp_type)
+                            .filter(|id| types.iter().any(|tp| tp.id == Some(*id)));
+                        let first_valid = types.iter().find_map(|tp| tp.id);
                         lp_types.set(types);
-                        let first_valid = lp_types.read().iter().find_map(|tp| tp.id);
-                        lp_type_id.set(first_valid);
+                        lp_type_id.set(default_type.or(first_valid));
                     }
                 }
                 Err(e) => {
@@ -34,6 +66,28 @@ pub fn LpSubmit() -> Element {
         });
     });
 
+    // 选择文件后立即上传，成功后把返回的地址填入图片链接
+    let upload_picture = move |_| {
+        let file = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|doc| doc.get_element_by_id(PICTURE_FILE_INPUT_ID))
+            .and_then(|el| el.dyn_into::<web_sys::HtmlInputElement>().ok())
+            .and_then(|input| input.files())
+            .and_then(|files| files.get(0));
+        let Some(file) = file else {
+            return;
+        };
+        uploading.set(true);
+        error.set(None);
+        spawn(async move {
+            match api::upload_image(&file).await {
+                Ok(url) => picture.set(url),
+                Err(e) => error.set(Some(format!("上传图片失败: {}", e))),
+            }
+            uploading.set(false);
+        });
+    };
+
     // 获取当前选中的LP类型名称（用于实时验证）
     let current_lp_type_name = use_memo(move || {
         let types = lp_types.read();
@@ -45,6 +99,123 @@ pub fn LpSubmit() -> Element {
             .unwrap_or_default()
     });
 
+    // 把当前选中的类型保存为默认类型，下次打开页面时自动选中
+    let save_default_type = move |_| {
+        let Some(type_id) = *lp_type_id.read() else {
+            return;
+        };
+        spawn(async move {
+            let request = api::UpdateProfileRequest {
+                default_lp_type: Some(type_id),
+                ..Default::default()
+            };
+            match api::update_profile(request).await {
+                Ok(_) => {
+                    error.set(None);
+                    success_message.set(Some("已设为默认LP类型".to_string()));
+                }
+                Err(e) => error.set(Some(format!("设置默认类型失败: {}", e))),
+            }
+        });
+    };
+
+    // 保存为草稿：不做必填项检查，草稿只对自己可见
+    let save_draft = move |_| {
+        let text_or_none = |value: String| {
+            let value = value.trim().to_string();
+            (!value.is_empty()).then_some(value)
+        };
+        let request = api::SaveLpDraftRequest {
+            id: *draft_id.read(),
+            user_qq: text_or_none(user_qq.read().clone()),
+            lp_type: *lp_type_id.read(),
+            num: Some(*lp_num.read()),
+            reason: reason.read().trim().to_string(),
+            picture: text_or_none(picture.read().clone()),
+            role: text_or_none(role.read().clone()),
+        };
+        spawn(async move {
+            loading.set(true);
+            match api::save_lp_draft(request).await {
+                Ok(id) => {
+                    draft_id.set(Some(id));
+                    error.set(None);
+                    success_message.set(Some("草稿已保存".to_string()));
+                    load_drafts();
+                }
+                Err(e) => {
+                    error.set(Some(format!("保存草稿失败: {}", e)));
+                    success_message.set(None);
+                }
+            }
+            loading.set(false);
+        });
+    };
+
+    // 把草稿内容载入表单继续编辑
+    let mut edit_draft = move |draft: LpDraft| {
+        draft_id.set(Some(draft.id));
+        user_qq.set(draft.user_qq.unwrap_or_default());
+        if draft.lp_type.is_some() {
+            lp_type_id.set(draft.lp_type);
+        }
+        lp_num.set(draft.num.unwrap_or(1));
+        reason.set(draft.reason);
+        role.set(draft.role.unwrap_or_default());
+        picture.set(draft.picture.unwrap_or_default());
+        error.set(None);
+        success_message.set(None);
+    };
+
+    let mut clear_form = move || {
+        draft_id.set(None);
+        user_qq.set(String::new());
+        reason.set(String::new());
+        role.set(String::new());
+        picture.set(String::new());
+        lp_num.set(1);
+    };
+
+    let submit_draft = move |id: i64| {
+        spawn(async move {
+            loading.set(true);
+            match api::submit_lp_draft(id).await {
+                Ok(resp) => {
+                    if *draft_id.read() == Some(id) {
+                        clear_form();
+                    }
+                    error.set(None);
+                    success_message.set(Some(resp.message));
+                    load_drafts();
+                }
+                Err(e) => {
+                    error.set(Some(format!("提交草稿失败: {}", e)));
+                    success_message.set(None);
+                }
+            }
+            loading.set(false);
+        });
+    };
+
+    let delete_draft = move |id: i64| {
+        spawn(async move {
+            if !confirmer.confirm("确定要删除此草稿吗？").await {
+                return;
+            }
+            match api::delete_lp_draft(id).await {
+                Ok(msg) => {
+                    if *draft_id.read() == Some(id) {
+                        draft_id.set(None);
+                    }
+                    error.set(None);
+                    success_message.set(Some(msg));
+                    load_drafts();
+                }
+                Err(e) => error.set(Some(format!("删除草稿失败: {}", e))),
+            }
+        });
+    };
+
     let on_submit = move |evt: Event<FormData>| {
         evt.prevent_default();
 
@@ -104,6 +275,8 @@ pub fn LpSubmit() -> Element {
         }
 
         // 使用API提交LP
+        let idempotency_key = submit_key.read().clone();
+
         spawn(async move {
             loading.set(true);
             let current_user = session_user;
@@ -127,18 +300,22 @@ pub fn LpSubmit() -> Element {
                 } else {
                     Some(role_val.clone())
                 },
+                &idempotency_key,
             )
             .await
             {
                 Ok(resp) => {
+                    submit_key.set(uuid::Uuid::new_v4().to_string());
                     success_message.set(Some(resp.message));
                     error.set(None);
+                    // 由草稿提交的申请，提交后删除该草稿
+                    let submitted_draft = *draft_id.read();
+                    if let Some(id) = submitted_draft {
+                        let _ = api::delete_lp_draft(id).await;
+                        load_drafts();
+                    }
                     // 清空表单
-                    user_qq.set(String::new());
-                    reason.set(String::new());
-                    role.set(String::new());
-                    picture.set(String::new());
-                    lp_num.set(1);
+                    clear_form();
                 }
                 Err(e) => {
                     error.set(Some(format!("提交失败: {}", e)));
@@ -182,10 +359,12 @@ pub fn LpSubmit() -> Element {
                             label { "关联用户QQ：*" }
                             input {
                                 r#type: "text",
-                                placeholder: "请输入用户QQ号",
+                                placeholder: "请输入用户QQ号或昵称",
+                                list: "lp-submit-user-suggestions",
                                 value: "{user_qq}",
                                 oninput: move |evt| user_qq.set(evt.value().clone())
                             }
+                            UserSuggestions { id: "lp-submit-user-suggestions", query: user_qq }
                         }
 
                         div { class: "form-group",
@@ -204,6 +383,13 @@ pub fn LpSubmit() -> Element {
                                     }
                                 }
                             }
+                            button {
+                                r#type: "button",
+                                class: "btn-small btn-secondary",
+                                onclick: save_default_type,
+                                disabled: *loading.read() || lp_type_id.read().is_none(),
+                                "设为默认"
+                            }
                         }
 
                         div { class: "form-group",
@@ -247,7 +433,7 @@ pub fn LpSubmit() -> Element {
                         div { class: "form-group",
                             label { "原因说明：*" }
                             textarea {
-                                placeholder: "请详细说明原因...",
+                                placeholder: "请详细说明原因，支持 Markdown",
                                 rows: "4",
                                 value: "{reason}",
                                 oninput: move |evt| reason.set(evt.value().clone()),
@@ -270,11 +456,21 @@ pub fn LpSubmit() -> Element {
                             label { "凭证图片链接：" }
                             input {
                                 r#type: "text",
-                                placeholder: "可选，提供图片URL",
+                                placeholder: "可选，提供图片URL或上传图片",
                                 value: "{picture}",
                                 oninput: move |evt| picture.set(evt.value().clone()),
                                 disabled: *loading.read()
                             }
+                            input {
+                                id: PICTURE_FILE_INPUT_ID,
+                                r#type: "file",
+                                accept: "image/png,image/jpeg,image/gif,image/webp",
+                                onchange: upload_picture,
+                                disabled: *loading.read() || *uploading.read()
+                            }
+                            if *uploading.read() {
+                                span { class: "form-tip", "图片上传中..." }
+                            }
                         }
 
                         if let Some(err) = error.read().as_ref() {
@@ -293,14 +489,72 @@ pub fn LpSubmit() -> Element {
                             button {
                                 r#type: "submit",
                                 class: "btn-primary",
-                                disabled: *loading.read(),
+                                disabled: *loading.read() || *uploading.read(),
                                 "提交申请"
                             }
+                            button {
+                                r#type: "button",
+                                class: "btn-secondary",
+                                onclick: save_draft,
+                                disabled: *loading.read() || *uploading.read(),
+                                if draft_id.read().is_some() { "更新草稿" } else { "保存草稿" }
+                            }
                             Link { to: crate::Route::LpManagement {},
                                 button { r#type: "button", class: "btn-secondary", "返回" }
                             }
                         }
                     }
+
+                    if !drafts.read().is_empty() {
+                        h2 { "我的草稿" }
+                        p { class: "form-tip", "草稿只有自己可见，提交后才会进入审批" }
+                        table { class: "data-table",
+                            thead {
+                                tr {
+                                    th { "关联用户" }
+                                    th { "数量" }
+                                    th { "原因" }
+                                    th { "最后保存" }
+                                    th { "操作" }
+                                }
+                            }
+                            tbody {
+                                for draft in drafts.read().iter().cloned() {
+                                    tr { key: "{draft.id}",
+                                        td { "{draft.user_qq.clone().unwrap_or_default()}" }
+                                        td {
+                                            {draft.num.map(|num| num.to_string()).unwrap_or_default()}
+                                        }
+                                        td { "{draft.reason}" }
+                                        td { "{draft.updated_at}" }
+                                        td {
+                                            button {
+                                                class: "btn-small btn-secondary",
+                                                disabled: *loading.read(),
+                                                onclick: {
+                                                    let draft = draft.clone();
+                                                    move |_| edit_draft(draft.clone())
+                                                },
+                                                "编辑"
+                                            }
+                                            button {
+                                                class: "btn-small btn-primary",
+                                                disabled: *loading.read(),
+                                                onclick: move |_| submit_draft(draft.id),
+                                                "提交"
+                                            }
+                                            button {
+                                                class: "btn-small btn-danger",
+                                                disabled: *loading.read(),
+                                                onclick: move |_| delete_draft(draft.id),
+                                                "删除"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                 }
             }
         }
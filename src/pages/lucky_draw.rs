@@ -1,17 +1,34 @@
 use crate::api;
-use crate::components::use_current_user;
+use crate::components::{
+    use_async_state, use_confirm, use_current_user, use_toast, AsyncStatus, MarkdownText,
+};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use dioxus::prelude::*;
-use gloo_timers::future::TimeoutFuture;
+use std::collections::{HashMap, HashSet};
+
+/// 抽奖列表每页条数
+const DRAW_PAGE_SIZE: i32 = 20;
+
+/// 重复抽奖最多的总期数，与后端一致
+const MAX_DRAW_OCCURRENCES: i32 = 52;
 
 #[component]
 pub fn LuckyDraw() -> Element {
     let mut draws = use_signal(Vec::<api::LuckyDraw>::new);
+    // 未开奖抽奖当前符合条件的人数（抽奖ID -> 人数）
+    let mut eligible_counts = use_signal(HashMap::<i64, i64>::new);
+    // 上次开奖没有产生中奖者的抽奖，显示“重试开奖”
+    let mut no_winner_draws = use_signal(HashSet::<i64>::new);
     let mut shop_items = use_signal(Vec::<api::ShopItem>::new); // 添加商品列表
-    let mut error = use_signal(|| None::<String>);
-    let mut success = use_signal(|| None::<String>);
-    let mut loading = use_signal(|| false);
-    let loading_visible = use_signal(|| false);
+                                                                // 列表筛选与分页：状态为空表示全部
+    let mut status_filter = use_signal(|| None::<i32>);
+    let mut creator_filter = use_signal(String::new);
+    let mut page = use_signal(|| 0i32);
+    let mut total = use_signal(|| 0i64);
+    // 批量删除选中的抽奖ID（仅当前页）
+    let mut selected_ids = use_signal(Vec::<i64>::new);
+    let mut state = use_async_state();
+    let mut confirmer = use_confirm();
     let mut show_create_form = use_signal(|| false);
 
     // 创建抽奖表单字段
@@ -21,41 +38,56 @@ pub fn LuckyDraw() -> Element {
     let mut min_lp_input = use_signal(|| "0".to_string());
     let mut plan_time_input = use_signal(String::new);
     let mut description = use_signal(String::new);
+    let mut recurrence_input = use_signal(|| "none".to_string());
+    // 服务器时间和时区，计划开奖时间按服务器时区解释
+    let mut server_time = use_signal(|| None::<api::ServerTime>);
+    let mut occurrences_input = use_signal(|| "4".to_string());
 
-    let current_user = use_current_user();
+    // 编辑未开奖抽奖的表单字段
+    let mut editing_draw_id = use_signal(|| None::<i64>);
+    let mut edit_num = use_signal(String::new);
+    let mut edit_min_lp = use_signal(String::new);
+    let mut edit_plan_time = use_signal(String::new);
+    let mut edit_description = use_signal(String::new);
 
-    {
-        let loading = loading;
-        let mut loading_visible = loading_visible;
-        use_effect(move || {
-            if *loading.read() {
-                let loading = loading;
-                let mut loading_visible = loading_visible;
-                spawn(async move {
-                    TimeoutFuture::new(180).await;
-                    if *loading.read() {
-                        loading_visible.set(true);
-                    }
-                });
-            } else {
-                loading_visible.set(false);
-            }
-        });
-    }
+    let current_user = use_current_user();
+    let mut toast = use_toast();
 
     let load_draws = move || {
         spawn(async move {
-            loading.set(true);
-            match api::get_lucky_draws().await {
-                Ok(draw_list) => {
+            state.start();
+            let status = *status_filter.peek();
+            let creator = creator_filter.peek().trim().to_string();
+            let offset = *page.peek() * DRAW_PAGE_SIZE;
+            match api::get_lucky_draws(status, Some(creator), Some(DRAW_PAGE_SIZE), Some(offset))
+                .await
+            {
+                Ok(resp) => {
+                    total.set(resp.total);
+                    let draw_list = resp.draws;
+                    let pending_ids: Vec<i64> = draw_list
+                        .iter()
+                        .filter(|draw| draw.status == 0)
+                        .filter_map(|draw| draw.id)
+                        .collect();
                     draws.set(draw_list);
-                    error.set(None);
+                    selected_ids.set(Vec::new());
+                    state.clear_error();
+
+                    eligible_counts.set(HashMap::new());
+                    for draw_id in pending_ids {
+                        spawn(async move {
+                            if let Ok(eligible) = api::get_lucky_draw_eligible(draw_id).await {
+                                eligible_counts.write().insert(draw_id, eligible.count);
+                            }
+                        });
+                    }
                 }
                 Err(e) => {
-                    error.set(Some(format!("加载失败: {}", e)));
+                    state.set_error(format!("加载失败: {}", e));
                 }
             }
-            loading.set(false);
+            state.finish();
         });
     };
 
@@ -82,23 +114,99 @@ pub fn LuckyDraw() -> Element {
 
     let execute_draw = move |id: i64| {
         spawn(async move {
-            loading.set(true);
+            state.start();
             match api::execute_lucky_draw(id).await {
+                Ok(result) => match result.outcome {
+                    api::DrawOutcome::Drawn => {
+                        no_winner_draws.write().remove(&id);
+                        state.set_success(format!(
+                            "{} 中奖者: {}",
+                            result.message,
+                            result.winners.join(", ")
+                        ));
+                        load_draws();
+                    }
+                    api::DrawOutcome::NoEligibleUsers => {
+                        // 没有中奖者不是错误，抽奖仍可降低门槛后重试
+                        no_winner_draws.write().insert(id);
+                        state.clear_error();
+                        toast.info(result.message);
+                    }
+                },
+                Err(e) => {
+                    state.set_error(format!("抽奖失败: {}", e));
+                    // 可能已被定时任务或他人开奖，刷新列表显示最新状态
+                    load_draws();
+                }
+            }
+            state.finish();
+        });
+    };
+
+    let retry_draw = move |id: i64, current_min_lp: i32| {
+        spawn(async move {
+            let window = web_sys::window().expect("no global `window` exists");
+            let input = window.prompt_with_message_and_default(
+                "上次开奖没有符合条件的参与者。请输入新的最低LP要求（不能高于当前要求）：",
+                &current_min_lp.to_string(),
+            );
+            let Ok(Some(input)) = input else {
+                return;
+            };
+            let min_lp_require = match input.trim().parse::<i32>() {
+                Ok(value) if value >= 0 => value,
+                _ => {
+                    state.set_error("最低LP要求必须是非负整数");
+                    return;
+                }
+            };
+
+            state.start();
+            match api::retry_lucky_draw(id, Some(min_lp_require)).await {
                 Ok(result) => {
-                    let message = if let Some(winner) = result.winner {
-                        format!("{} 中奖者: {}", result.message, winner)
-                    } else {
-                        result.message
-                    };
-                    success.set(Some(message));
-                    error.set(None);
+                    no_winner_draws.write().remove(&id);
+                    state.set_success(format!(
+                        "{} 中奖者: {}",
+                        result.message,
+                        result.winners.join(", ")
+                    ));
+                    load_draws();
+                }
+                Err(e) => {
+                    state.set_error(format!("重新开奖失败: {}", e));
+                }
+            }
+            state.finish();
+        });
+    };
+
+    let duplicate_draw = move |id: i64, plan_time: String| {
+        spawn(async move {
+            let window = web_sys::window().expect("no global `window` exists");
+            let input = window.prompt_with_message_and_default(
+                "复制此抽奖的奖品、数量、LP要求和描述。请输入新的计划开奖时间（YYYY-MM-DD HH:MM:SS）：",
+                &plan_time,
+            );
+            let Ok(Some(input)) = input else {
+                return;
+            };
+            let plan_time = input.trim().to_string();
+            if plan_time.is_empty() {
+                state.set_error("请输入计划开奖时间");
+                return;
+            }
+
+            state.start();
+            match api::duplicate_draw(id, plan_time).await {
+                Ok(resp) => {
+                    state.set_success(format!("{} (ID: {})", resp.message, resp.id));
                     load_draws();
                 }
                 Err(e) => {
-                    error.set(Some(format!("抽奖失败: {}", e)));
+                    state.set_error(format!("复制失败: {}", e));
                 }
             }
-            loading.set(false);
+            state.finish();
         });
     };
 
@@ -107,36 +215,193 @@ pub fn LuckyDraw() -> Element {
         load_shop_items(); // 同时加载商品列表
     });
 
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(time) = api::get_server_time().await {
+                server_time.set(Some(time));
+            }
+        });
+    });
+
     let delete_draw = move |draw_id: i64| {
         spawn(async move {
-            // 使用 web_sys 的 confirm
-            let window = web_sys::window().expect("no global `window` exists");
-            let result =
-                window.confirm_with_message("确定要删除此抽奖吗？如果未开奖，库存将会被恢复。");
+            let confirmed = confirmer
+                .confirm("确定要删除此抽奖吗？如果未开奖，库存将会被恢复。")
+                .await;
 
-            if let Ok(true) = result {
-                loading.set(true);
+            if confirmed {
+                state.start();
                 match api::delete_lucky_draw(draw_id).await {
                     Ok(msg) => {
-                        success.set(Some(msg));
-                        error.set(None);
+                        state.set_success(msg);
+                        load_draws();
+                    }
+                    Err(e) => {
+                        state.set_error(format!("删除失败: {}", e));
+                    }
+                }
+                state.finish();
+            }
+        });
+    };
+
+    let cancel_draw = move |draw_id: i64| {
+        spawn(async move {
+            let confirmed = confirmer
+                .confirm("确定要取消此抽奖吗？记录会保留，关联商品的库存将被恢复。")
+                .await;
+
+            if confirmed {
+                state.start();
+                match api::cancel_lucky_draw(draw_id).await {
+                    Ok(msg) => {
+                        state.set_success(msg);
                         load_draws();
                     }
                     Err(e) => {
-                        error.set(Some(format!("删除失败: {}", e)));
+                        state.set_error(format!("取消失败: {}", e));
                     }
                 }
-                loading.set(false);
+                state.finish();
             }
         });
     };
 
+    let mut toggle_select = move |id: i64| {
+        let mut ids = selected_ids.read().clone();
+        if let Some(pos) = ids.iter().position(|x| *x == id) {
+            ids.remove(pos);
+        } else {
+            ids.push(id);
+        }
+        selected_ids.set(ids);
+    };
+
+    let mut toggle_select_all = move || {
+        let all_ids: Vec<i64> = draws.read().iter().filter_map(|draw| draw.id).collect();
+        if selected_ids.read().len() == all_ids.len() {
+            selected_ids.set(Vec::new());
+        } else {
+            selected_ids.set(all_ids);
+        }
+    };
+
+    // 批量删除：选中项包含未开奖抽奖时再确认一次是否一并删除（会恢复库存），否则跳过它们
+    let batch_delete = move |_| {
+        let ids = selected_ids.read().clone();
+        if ids.is_empty() {
+            return;
+        }
+        let pending_count = draws
+            .read()
+            .iter()
+            .filter(|draw| draw.status == 0 && draw.id.is_some_and(|id| ids.contains(&id)))
+            .count();
+
+        spawn(async move {
+            let confirmed = confirmer
+                .confirm(format!("确定要删除选中的 {} 个抽奖吗？", ids.len()))
+                .await;
+            if !confirmed {
+                return;
+            }
+            let include_pending = pending_count > 0
+                && confirmer
+                    .confirm(format!(
+                        "其中 {} 个抽奖尚未开奖，是否一并删除并恢复库存？选择“取消”将跳过它们。",
+                        pending_count
+                    ))
+                    .await;
+
+            state.start();
+            match api::batch_delete_lucky_draws(&ids, include_pending).await {
+                Ok(result) => {
+                    let mut message = result.message;
+                    if !result.skipped_pending.is_empty() {
+                        message
+                            .push_str(&format!("，跳过未开奖 {} 个", result.skipped_pending.len()));
+                    }
+                    if !result.not_found.is_empty() {
+                        message.push_str(&format!("，{} 个已不存在", result.not_found.len()));
+                    }
+                    state.set_success(message);
+                    load_draws();
+                }
+                Err(e) => {
+                    state.set_error(format!("批量删除失败: {}", e));
+                }
+            }
+            state.finish();
+        });
+    };
+
+    let mut start_edit = move |draw: api::LuckyDraw| {
+        editing_draw_id.set(draw.id);
+        edit_num.set(draw.num.to_string());
+        edit_min_lp.set(draw.min_lp_require.to_string());
+        edit_plan_time.set(to_datetime_local(&draw.plan_time));
+        edit_description.set(draw.description.unwrap_or_default());
+        show_create_form.set(false);
+    };
+
+    let update_draw = move |evt: Event<FormData>| {
+        evt.prevent_default();
+
+        let Some(draw_id) = *editing_draw_id.read() else {
+            return;
+        };
+
+        let num = match edit_num.read().trim().parse::<i32>() {
+            Ok(value) if value > 0 => value,
+            _ => {
+                state.set_error("数量必须为大于0的整数");
+                return;
+            }
+        };
+        let min_lp_require = match edit_min_lp.read().trim().parse::<i32>() {
+            Ok(value) if value >= 0 => value,
+            _ => {
+                state.set_error("最低LP要求必须为非负整数");
+                return;
+            }
+        };
+        let plan_time = match parse_datetime_local(&edit_plan_time.read()) {
+            Ok(dt) => dt,
+            Err(msg) => {
+                state.set_error(msg);
+                return;
+            }
+        };
+
+        let payload = api::UpdateDrawPayload {
+            num,
+            min_lp_require,
+            plan_time,
+            description: edit_description.read().trim().to_string(),
+        };
+
+        spawn(async move {
+            state.start();
+            match api::update_lucky_draw(draw_id, payload).await {
+                Ok(msg) => {
+                    state.set_success(msg);
+                    editing_draw_id.set(None);
+                    load_draws();
+                }
+                Err(e) => {
+                    state.set_error(format!("修改失败: {}", e));
+                }
+            }
+            state.finish();
+        });
+    };
+
     let create_draw = move |evt: Event<FormData>| {
         evt.prevent_default();
 
         let session_user = current_user.read().clone();
         let Some(user) = session_user else {
-            error.set(Some("请先登录后再发起抽奖".to_string()));
+            state.set_error("请先登录后再发起抽奖");
             return;
         };
 
@@ -151,14 +416,14 @@ pub fn LuckyDraw() -> Element {
 
         let plan_raw = plan_time_input.read().clone();
         if plan_raw.is_empty() {
-            error.set(Some("请选择计划开奖时间".to_string()));
+            state.set_error("请选择计划开奖时间");
             return;
         }
 
         let plan_time = match parse_datetime_local(&plan_raw) {
             Ok(dt) => dt,
             Err(msg) => {
-                error.set(Some(msg));
+                state.set_error(msg);
                 return;
             }
         };
@@ -166,11 +431,11 @@ pub fn LuckyDraw() -> Element {
         let num = match num {
             Ok(value) if value > 0 => value,
             Ok(_) => {
-                error.set(Some("数量必须大于0".to_string()));
+                state.set_error("数量必须大于0");
                 return;
             }
             Err(msg) => {
-                error.set(Some(msg));
+                state.set_error(msg);
                 return;
             }
         };
@@ -178,11 +443,11 @@ pub fn LuckyDraw() -> Element {
         let min_lp = match min_lp {
             Ok(value) if value >= 0 => value,
             Ok(_) => {
-                error.set(Some("最低LP要求不能为负数".to_string()));
+                state.set_error("最低LP要求不能为负数");
                 return;
             }
             Err(msg) => {
-                error.set(Some(msg));
+                state.set_error(msg);
                 return;
             }
         };
@@ -196,13 +461,29 @@ pub fn LuckyDraw() -> Element {
                 match trimmed.parse::<i64>() {
                     Ok(id) => Some(id),
                     Err(_) => {
-                        error.set(Some("物品ID必须为整数".to_string()));
+                        state.set_error("物品ID必须为整数");
                         return;
                     }
                 }
             }
         };
 
+        let recurrence = recurrence_input.read().clone();
+        let occurrences = if recurrence == "none" {
+            None
+        } else {
+            match occurrences_input.read().trim().parse::<i32>() {
+                Ok(value) if (2..=MAX_DRAW_OCCURRENCES).contains(&value) => Some(value),
+                _ => {
+                    state.set_error(format!(
+                        "重复总期数必须为 2 到 {} 之间的整数",
+                        MAX_DRAW_OCCURRENCES
+                    ));
+                    return;
+                }
+            }
+        };
+
         let fitting_value = fitting.read().clone();
         let description_value = description.read().clone();
 
@@ -214,14 +495,15 @@ pub fn LuckyDraw() -> Element {
             min_lp_require: min_lp,
             plan_time,
             description: optional_trim(description_value),
+            recurrence,
+            occurrences,
         };
 
         spawn(async move {
-            loading.set(true);
+            state.start();
             match api::create_lucky_draw(payload).await {
                 Ok(resp) => {
-                    success.set(Some(format!("{} (ID: {})", resp.message, resp.id)));
-                    error.set(None);
+                    state.set_success(format!("{} (ID: {})", resp.message, resp.id));
                     // 清空表单
                     item_id_input.set(String::new());
                     fitting.set(String::new());
@@ -229,17 +511,37 @@ pub fn LuckyDraw() -> Element {
                     min_lp_input.set("0".to_string());
                     plan_time_input.set(String::new());
                     description.set(String::new());
+                    recurrence_input.set("none".to_string());
+                    occurrences_input.set("4".to_string());
                     show_create_form.set(false);
                     load_draws();
                 }
                 Err(e) => {
-                    error.set(Some(format!("创建失败: {}", e)));
+                    state.set_error(format!("创建失败: {}", e));
                 }
             }
-            loading.set(false);
+            state.finish();
         });
     };
 
+    let apply_filter = move |evt: Event<FormData>| {
+        evt.prevent_default();
+        page.set(0);
+        load_draws();
+    };
+
+    let total_count = *total.read();
+    let current_page = *page.read();
+    let total_pages = ((total_count + DRAW_PAGE_SIZE as i64 - 1) / DRAW_PAGE_SIZE as i64) as i32;
+    let has_prev_page = current_page > 0;
+    let has_next_page = current_page + 1 < total_pages;
+    let pagination_label = format!(
+        "共 {} 条，第 {} / {} 页",
+        total_count,
+        if total_pages > 0 { current_page + 1 } else { 0 },
+        total_pages
+    );
+
     rsx! {
         div { class: "page-container",
             h1 { "抽奖活动管理" }
@@ -260,28 +562,44 @@ pub fn LuckyDraw() -> Element {
                             description.set(String::new());
                         }
                     },
-                    disabled: *loading.read(),
+                    disabled: state.is_loading(),
                     if *show_create_form.read() { "取消创建" } else { "创建抽奖" }
                 }
                 button {
                     class: "btn-secondary",
                     onclick: move |_| load_draws(),
-                    disabled: *loading.read(),
+                    disabled: state.is_loading(),
                     "刷新"
                 }
             }
 
-            if *loading_visible.read() {
-                div { class: "loading-message", "处理中..." }
-            }
-
-            if let Some(err) = error.read().as_ref() {
-                div { class: "error-message", "{err}" }
+            form { class: "search-box", onsubmit: apply_filter,
+                select {
+                    value: status_filter().map(|s| s.to_string()).unwrap_or_default(),
+                    onchange: move |evt| status_filter.set(evt.value().parse::<i32>().ok()),
+                    disabled: state.is_loading(),
+                    option { value: "", "全部状态" }
+                    option { value: "0", "未开奖" }
+                    option { value: "1", "已开奖" }
+                    option { value: "2", "无人中奖" }
+                    option { value: "3", "已取消" }
+                }
+                input {
+                    r#type: "text",
+                    placeholder: "按创建人QQ筛选（留空为全部）",
+                    value: "{creator_filter}",
+                    oninput: move |evt| creator_filter.set(evt.value().clone()),
+                    disabled: state.is_loading()
+                }
+                button {
+                    r#type: "submit",
+                    class: "btn-secondary",
+                    disabled: state.is_loading(),
+                    "筛选"
+                }
             }
 
-            if let Some(succ) = success.read().as_ref() {
-                div { class: "success-message", "{succ}" }
-            }
+            AsyncStatus { state, loading_text: "处理中..." }
 
             if *show_create_form.read() {
                 div { class: "form-container",
@@ -292,7 +610,7 @@ pub fn LuckyDraw() -> Element {
                             select {
                                 value: "{item_id_input}",
                                 onchange: move |evt| item_id_input.set(evt.value().clone()),
-                                disabled: *loading.read(),
+                                disabled: state.is_loading(),
                                 option { value: "", "不关联商品（自定义奖品）" }
                                 for item in shop_items.read().iter() {
                                     option {
@@ -314,7 +632,7 @@ pub fn LuckyDraw() -> Element {
                                 placeholder: "例如：颜色、尺寸等",
                                 value: "{fitting}",
                                 oninput: move |evt| fitting.set(evt.value().clone()),
-                                disabled: *loading.read()
+                                disabled: state.is_loading()
                             }
                         }
 
@@ -325,7 +643,7 @@ pub fn LuckyDraw() -> Element {
                                 min: "1",
                                 value: "{num_input}",
                                 oninput: move |evt| num_input.set(evt.value().clone()),
-                                disabled: *loading.read()
+                                disabled: state.is_loading()
                             }
                         }
 
@@ -336,7 +654,7 @@ pub fn LuckyDraw() -> Element {
                                 min: "0",
                                 value: "{min_lp_input}",
                                 oninput: move |evt| min_lp_input.set(evt.value().clone()),
-                                disabled: *loading.read()
+                                disabled: state.is_loading()
                             }
                         }
 
@@ -346,7 +664,49 @@ pub fn LuckyDraw() -> Element {
                                 r#type: "datetime-local",
                                 value: "{plan_time_input}",
                                 oninput: move |evt| plan_time_input.set(evt.value().clone()),
-                                disabled: *loading.read()
+                                disabled: state.is_loading()
+                            }
+                            if let Some(time) = server_time.read().as_ref() {
+                                p {
+                                    style: "font-size: 13px; color: #666; margin-top: 0.5rem;",
+                                    "按服务器时区（UTC{time.timezone}）填写，服务器当前时间：{time.now}"
+                                }
+                                if time.utc_offset_seconds != browser_utc_offset_seconds() {
+                                    p {
+                                        style: "font-size: 13px; color: #d9534f;",
+                                        "注意：当前浏览器所在时区与服务器不同"
+                                    }
+                                }
+                            }
+                        }
+
+                        div { class: "form-group",
+                            label { "重复：" }
+                            select {
+                                value: "{recurrence_input}",
+                                onchange: move |evt| recurrence_input.set(evt.value().clone()),
+                                disabled: state.is_loading(),
+                                option { value: "none", "不重复" }
+                                option { value: "daily", "每天" }
+                                option { value: "weekly", "每周" }
+                            }
+                        }
+
+                        if recurrence_input.read().as_str() != "none" {
+                            div { class: "form-group",
+                                label { "总期数（含本期）：*" }
+                                input {
+                                    r#type: "number",
+                                    min: "2",
+                                    max: "{MAX_DRAW_OCCURRENCES}",
+                                    value: "{occurrences_input}",
+                                    oninput: move |evt| occurrences_input.set(evt.value().clone()),
+                                    disabled: state.is_loading()
+                                }
+                                p {
+                                    style: "font-size: 13px; color: #666; margin-top: 0.5rem;",
+                                    "每期开奖后自动按周期创建下一期；关联商品时每期都会扣除库存，库存不足时停止重复。"
+                                }
                             }
                         }
 
@@ -354,10 +714,10 @@ pub fn LuckyDraw() -> Element {
                             label { "活动描述：" }
                             textarea {
                                 rows: "3",
-                                placeholder: "可填写奖品说明、参与方式等",
+                                placeholder: "可填写奖品说明、参与方式等，支持 Markdown",
                                 value: "{description}",
                                 oninput: move |evt| description.set(evt.value().clone()),
-                                disabled: *loading.read()
+                                disabled: state.is_loading()
                             }
                         }
 
@@ -365,7 +725,7 @@ pub fn LuckyDraw() -> Element {
                             button {
                                 r#type: "submit",
                                 class: "btn-primary",
-                                disabled: *loading.read(),
+                                disabled: state.is_loading(),
                                 "提交"
                             }
                         }
@@ -373,10 +733,89 @@ pub fn LuckyDraw() -> Element {
                 }
             }
 
+            if let Some(draw_id) = *editing_draw_id.read() {
+                div { class: "form-container",
+                    h2 { "修改抽奖 #{draw_id}" }
+                    form { onsubmit: update_draw,
+                        div { class: "form-group",
+                            label { "奖品数量：*" }
+                            input {
+                                r#type: "number",
+                                min: "1",
+                                value: "{edit_num}",
+                                oninput: move |evt| edit_num.set(evt.value().clone()),
+                                disabled: state.is_loading()
+                            }
+                        }
+
+                        div { class: "form-group",
+                            label { "最低LP要求：*" }
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                value: "{edit_min_lp}",
+                                oninput: move |evt| edit_min_lp.set(evt.value().clone()),
+                                disabled: state.is_loading()
+                            }
+                        }
+
+                        div { class: "form-group",
+                            label { "计划开奖时间：*" }
+                            input {
+                                r#type: "datetime-local",
+                                value: "{edit_plan_time}",
+                                oninput: move |evt| edit_plan_time.set(evt.value().clone()),
+                                disabled: state.is_loading()
+                            }
+                        }
+
+                        div { class: "form-group",
+                            label { "活动描述：" }
+                            textarea {
+                                rows: "3",
+                                value: "{edit_description}",
+                                oninput: move |evt| edit_description.set(evt.value().clone()),
+                                disabled: state.is_loading()
+                            }
+                        }
+
+                        div { class: "form-actions",
+                            button {
+                                r#type: "submit",
+                                class: "btn-primary",
+                                disabled: state.is_loading(),
+                                "保存修改"
+                            }
+                            button {
+                                r#type: "button",
+                                class: "btn-secondary",
+                                onclick: move |_| editing_draw_id.set(None),
+                                "取消"
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { class: "toolbar",
+                button {
+                    class: "btn-secondary",
+                    onclick: move |_| toggle_select_all(),
+                    "全选/取消全选"
+                }
+                button {
+                    class: "btn-danger",
+                    onclick: batch_delete,
+                    disabled: state.is_loading() || selected_ids.read().is_empty(),
+                    "批量删除 ({selected_ids.read().len()})"
+                }
+            }
+
             div { class: "table-container",
                 table { class: "data-table",
                     thead {
                         tr {
+                            th { "选择" }
                             th { "ID" }
                             th { "创建时间" }
                             th { "创建人" }
@@ -394,6 +833,15 @@ pub fn LuckyDraw() -> Element {
                         for draw in draws.read().iter() {
                             tr {
                                 key: "{draw.id.unwrap_or(0)}",
+                                td {
+                                    if let Some(id) = draw.id {
+                                        input {
+                                            r#type: "checkbox",
+                                            checked: selected_ids.read().contains(&id),
+                                            onchange: move |_| toggle_select(id),
+                                        }
+                                    }
+                                }
                                 td { "{draw.id.unwrap_or(0)}" }
                                 td { "{draw.create_time}" }
                                 td { "{draw.create_qq}" }
@@ -417,14 +865,34 @@ pub fn LuckyDraw() -> Element {
                                     }
                                 }
                                 td { "{draw.num}" }
-                                td { "{draw.min_lp_require}" }
+                                td {
+                                    "{draw.min_lp_require}"
+                                    if draw.status == 0 {
+                                        if let Some(count) = draw.id.and_then(|id| eligible_counts.read().get(&id).copied()) {
+                                            br {}
+                                            span {
+                                                style: "font-size: 12px; color: #666;",
+                                                "当前符合 {count} 人"
+                                            }
+                                        }
+                                    }
+                                }
                                 td { "{draw.plan_time}" }
                                 td {
                                     match draw.status {
                                         0 => rsx!(span { class: "badge badge-warning", "未开奖" }),
                                         1 => rsx!(span { class: "badge badge-success", "已开奖" }),
+                                        2 => rsx!(span { class: "badge", "无人中奖" }),
+                                        3 => rsx!(span { class: "badge badge-danger", "已取消" }),
                                         _ => rsx!(span { class: "badge", "未知" }),
                                     }
+                                    if let Some(label) = recurrence_label(&draw.recurrence) {
+                                        span {
+                                            class: "badge badge-info",
+                                            title: "之后还会自动创建 {draw.recurrence_remaining} 期",
+                                            "{label}"
+                                        }
+                                    }
                                 }
                                 td {
                                     if let Some(winner) = draw.winner_qq.as_ref() {
@@ -435,7 +903,7 @@ pub fn LuckyDraw() -> Element {
                                 }
                                 td {
                                     if let Some(desc) = draw.description.as_ref() {
-                                        "{desc}"
+                                        MarkdownText { text: desc.clone() }
                                     } else {
                                         "-"
                                     }
@@ -455,9 +923,47 @@ pub fn LuckyDraw() -> Element {
                                                             }
                                                         }
                                                     },
-                                                    disabled: *loading.read(),
+                                                    disabled: state.is_loading(),
                                                     "开奖"
                                                 }
+                                                if draw.id.is_some_and(|id| no_winner_draws.read().contains(&id)) {
+                                                    button {
+                                                        class: "btn-small btn-warning",
+                                                        onclick: {
+                                                            let draw_id = draw.id;
+                                                            let min_lp = draw.min_lp_require;
+                                                            move |_| {
+                                                                if let Some(id) = draw_id {
+                                                                    retry_draw(id, min_lp);
+                                                                }
+                                                            }
+                                                        },
+                                                        disabled: state.is_loading(),
+                                                        "重试开奖"
+                                                    }
+                                                }
+                                                button {
+                                                    class: "btn-small btn-secondary",
+                                                    onclick: {
+                                                        let draw = draw.clone();
+                                                        move |_| start_edit(draw.clone())
+                                                    },
+                                                    disabled: state.is_loading(),
+                                                    "编辑"
+                                                }
+                                                button {
+                                                    class: "btn-small btn-warning",
+                                                    onclick: {
+                                                        let draw_id = draw.id;
+                                                        move |_| {
+                                                            if let Some(id) = draw_id {
+                                                                cancel_draw(id);
+                                                            }
+                                                        }
+                                                    },
+                                                    disabled: state.is_loading(),
+                                                    "取消"
+                                                }
                                                 button {
                                                     class: "btn-small btn-danger",
                                                     onclick: {
@@ -468,14 +974,27 @@ pub fn LuckyDraw() -> Element {
                                                             }
                                                         }
                                                     },
-                                                    disabled: *loading.read(),
+                                                    disabled: state.is_loading(),
                                                     "删除"
                                                 }
                                             } else {
                                                 span { "-" }
                                             }
                                         } else {
-                                            span { style: "color: #999; font-size: 13px;", "已开奖" }
+                                            span { style: "color: #999; font-size: 13px;",
+                                                if draw.status == 3 { "已取消" } else { "已开奖" }
+                                            }
+                                        }
+                                        if let Some(id) = draw.id {
+                                            button {
+                                                class: "btn-small btn-secondary",
+                                                onclick: {
+                                                    let plan_time = draw.plan_time.clone();
+                                                    move |_| duplicate_draw(id, plan_time.clone())
+                                                },
+                                                disabled: state.is_loading(),
+                                                "复制"
+                                            }
                                         }
                                     }
                                 }
@@ -484,6 +1003,30 @@ pub fn LuckyDraw() -> Element {
                     }
                 }
             }
+
+            div { class: "pagination-bar",
+                span { "{pagination_label}" }
+                div { class: "pagination-actions",
+                    button {
+                        class: "btn-secondary",
+                        onclick: move |_| {
+                            page -= 1;
+                            load_draws();
+                        },
+                        disabled: !has_prev_page || state.is_loading(),
+                        "上一页"
+                    }
+                    button {
+                        class: "btn-secondary",
+                        onclick: move |_| {
+                            page += 1;
+                            load_draws();
+                        },
+                        disabled: !has_next_page || state.is_loading(),
+                        "下一页"
+                    }
+                }
+            }
         }
     }
 }
@@ -501,6 +1044,13 @@ fn parse_datetime_local(input: &str) -> Result<String, String> {
     }
 }
 
+/// 把 "YYYY-MM-DD HH:MM:SS" 转换为 datetime-local 输入框使用的 "YYYY-MM-DDTHH:MM"
+fn to_datetime_local(value: &str) -> String {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M").to_string())
+        .unwrap_or_default()
+}
+
 fn optional_trim(value: String) -> Option<String> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
@@ -509,3 +1059,17 @@ fn optional_trim(value: String) -> Option<String> {
         Some(trimmed.to_string())
     }
 }
+
+/// 重复周期的显示名称，不重复时为 None
+fn recurrence_label(recurrence: &str) -> Option<&'static str> {
+    match recurrence {
+        "daily" => Some("每天重复"),
+        "weekly" => Some("每周重复"),
+        _ => None,
+    }
+}
+
+/// 浏览器所在时区相对 UTC 的偏移（秒，东区为正）
+fn browser_utc_offset_seconds() -> i32 {
+    (-js_sys::Date::new_0().get_timezone_offset() * 60.0) as i32
+}
@@ -1,10 +1,13 @@
 mod login;
 mod logs;
+mod lp_detail;
 mod lp_management;
 mod lp_submit;
 mod lucky_draw;
 mod my_shop;
+mod notifications;
 mod profile;
+mod public_leaderboard;
 mod register;
 mod roles;
 mod shop;
@@ -13,11 +16,14 @@ mod users;
 
 pub use login::Login;
 pub use logs::Logs;
+pub use lp_detail::LpDetail;
 pub use lp_management::LpManagement;
 pub use lp_submit::LpSubmit;
 pub use lucky_draw::LuckyDraw;
 pub use my_shop::MyShop;
+pub use notifications::Notifications;
 pub use profile::Profile;
+pub use public_leaderboard::PublicLeaderboard;
 pub use register::Register;
 pub use roles::Roles;
 pub use shop::Shop;
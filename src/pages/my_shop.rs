@@ -1,63 +1,95 @@
 use crate::api;
-use crate::components::use_current_user;
+use crate::components::{use_async_state, use_current_user, AsyncStatus, ExpiryBadge};
+use crate::models::SellerStats;
 use dioxus::prelude::*;
-use gloo_timers::future::TimeoutFuture;
+use wasm_bindgen::JsCast;
+
+/// 批量导入商品的 CSV 文件选择框元素ID
+const IMPORT_FILE_INPUT_ID: &str = "shop-import-file";
 
 #[component]
 pub fn MyShop() -> Element {
     let mut my_items = use_signal(Vec::<api::ShopItem>::new);
-    let mut error = use_signal(|| None::<String>);
-    let mut success = use_signal(|| None::<String>);
-    let mut loading = use_signal(|| false);
-    let loading_visible = use_signal(|| false);
+    let mut seller_stats = use_signal(|| None::<SellerStats>);
+    let mut state = use_async_state();
     let mut show_create_form = use_signal(|| false);
     let current_user = use_current_user();
 
-    {
-        let loading = loading;
-        let mut loading_visible = loading_visible;
-        use_effect(move || {
-            if *loading.read() {
-                let loading = loading;
-                let mut loading_visible = loading_visible;
-                spawn(async move {
-                    TimeoutFuture::new(180).await;
-                    if *loading.read() {
-                        loading_visible.set(true);
-                    }
-                });
-            } else {
-                loading_visible.set(false);
-            }
-        });
-    }
-
     // 创建商品表单字段
     let mut item_name = use_signal(String::new);
     let mut item_price = use_signal(String::new);
     let mut item_count = use_signal(|| "1".to_string());
     let mut item_location = use_signal(String::new);
+    let mut item_threshold = use_signal(String::new);
+    let mut item_expires_at = use_signal(String::new);
+
+    // 转让商品：正在转让的商品ID及目标卖家QQ
+    let mut transfer_item_id = use_signal(|| None::<i64>);
+    let mut transfer_qq = use_signal(String::new);
+    let mut restock_item_id = use_signal(|| None::<i64>);
+    let mut restock_delta = use_signal(String::new);
+
+    // 最近一次 CSV 导入的逐行结果
+    let mut import_results = use_signal(Vec::<api::ImportItemLine>::new);
 
     let mut load_my_items = move || {
         let session = current_user.read().clone();
         let Some(user) = session else {
-            error.set(Some("请先登录".to_string()));
+            state.set_error("请先登录");
             return;
         };
 
         let seller_qq = user.qq.clone();
         spawn(async move {
-            loading.set(true);
+            state.start();
             match api::get_shop_items(Some(&seller_qq)).await {
                 Ok(item_list) => {
                     my_items.set(item_list);
-                    error.set(None);
+                    state.clear_error();
+                }
+                Err(e) => {
+                    state.set_error(format!("加载失败: {}", e));
+                }
+            }
+            state.finish();
+
+            // 销售统计加载失败不影响商品列表
+            seller_stats.set(api::get_seller_stats(&seller_qq).await.ok());
+        });
+    };
+
+    // 选择 CSV 文件后立即导入，任一行无效时服务端不会上架任何商品
+    let import_csv = move |_| {
+        let input = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|doc| doc.get_element_by_id(IMPORT_FILE_INPUT_ID))
+            .and_then(|el| el.dyn_into::<web_sys::HtmlInputElement>().ok());
+        let Some(input) = input else {
+            return;
+        };
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        // 清空选择，便于修改文件后重新选择同一文件
+        input.set_value("");
+
+        spawn(async move {
+            state.start();
+            match api::import_shop_items(&file).await {
+                Ok(resp) => {
+                    if resp.success {
+                        state.set_success(resp.message);
+                        load_my_items();
+                    } else {
+                        state.set_error(resp.message);
+                    }
+                    import_results.set(resp.results);
                 }
                 Err(e) => {
-                    error.set(Some(format!("加载失败: {}", e)));
+                    state.set_error(format!("导入失败: {}", e));
                 }
             }
-            loading.set(false);
+            state.finish();
         });
     };
 
@@ -66,7 +98,7 @@ pub fn MyShop() -> Element {
 
         let session = current_user.read().clone();
         let Some(user) = session else {
-            error.set(Some("请先登录".to_string()));
+            state.set_error("请先登录");
             return;
         };
 
@@ -76,7 +108,7 @@ pub fn MyShop() -> Element {
         let location = item_location.read().trim().to_string();
 
         if name.is_empty() || price_text.is_empty() || location.is_empty() {
-            error.set(Some("请填写所有必填项".to_string()));
+            state.set_error("请填写所有必填项");
             return;
         }
 
@@ -85,43 +117,123 @@ pub fn MyShop() -> Element {
             .map(|value| value > 0.0)
             .unwrap_or(false);
         if !price_valid {
-            error.set(Some("价格必须为大于0的数字".to_string()));
+            state.set_error("价格必须为大于0的数字");
             return;
         }
 
         let count = count_text.parse::<i32>().unwrap_or(0);
         if count <= 0 {
-            error.set(Some("库存必须为正整数".to_string()));
+            state.set_error("库存必须为正整数");
             return;
         }
 
+        let threshold_text = item_threshold.read().trim().to_string();
+        let low_stock_threshold = if threshold_text.is_empty() {
+            None
+        } else {
+            match threshold_text.parse::<i32>() {
+                Ok(value) if value >= 0 => Some(value),
+                _ => {
+                    state.set_error("库存提醒阈值必须为非负整数");
+                    return;
+                }
+            }
+        };
+
+        // datetime-local 输入框的值形如 2024-01-01T12:00，由后端统一规范化
+        let expires_at_text = item_expires_at.read().trim().to_string();
+        let expires_at = (!expires_at_text.is_empty()).then_some(expires_at_text);
+
         let payload = api::CreateItemPayload {
             count,
             price: price_text.clone(),
             name: name.clone(),
             seller: user.qq.clone(),
             location: location.clone(),
+            low_stock_threshold,
+            expires_at,
         };
 
         spawn(async move {
-            loading.set(true);
+            state.start();
             match api::create_shop_item(payload).await {
                 Ok(resp) => {
-                    success.set(Some(format!("{} (ID: {})", resp.message, resp.id)));
-                    error.set(None);
+                    state.set_success(format!("{} (ID: {})", resp.message, resp.id));
                     // 清空表单
                     item_name.set(String::new());
                     item_price.set(String::new());
                     item_count.set("1".to_string());
                     item_location.set(String::new());
+                    item_threshold.set(String::new());
+                    item_expires_at.set(String::new());
                     show_create_form.set(false);
                     load_my_items();
                 }
                 Err(e) => {
-                    error.set(Some(format!("创建失败: {}", e)));
+                    state.set_error(format!("创建失败: {}", e));
+                }
+            }
+            state.finish();
+        });
+    };
+
+    let submit_transfer = move |evt: Event<FormData>| {
+        evt.prevent_default();
+
+        let Some(item_id) = *transfer_item_id.read() else {
+            return;
+        };
+        let new_seller = transfer_qq.read().trim().to_string();
+        if new_seller.is_empty() {
+            state.set_error("请填写新卖家QQ");
+            return;
+        }
+
+        spawn(async move {
+            state.start();
+            match api::transfer_shop_item(item_id, new_seller).await {
+                Ok(message) => {
+                    state.set_success(message);
+                    transfer_item_id.set(None);
+                    transfer_qq.set(String::new());
+                    load_my_items();
+                }
+                Err(e) => {
+                    state.set_error(format!("转让失败: {}", e));
+                }
+            }
+            state.finish();
+        });
+    };
+
+    let submit_restock = move |evt: Event<FormData>| {
+        evt.prevent_default();
+
+        let Some(item_id) = *restock_item_id.read() else {
+            return;
+        };
+        let delta = match restock_delta.read().trim().parse::<i32>() {
+            Ok(delta) if delta != 0 => delta,
+            _ => {
+                state.set_error("补货数量必须是非零整数");
+                return;
+            }
+        };
+
+        spawn(async move {
+            state.start();
+            match api::restock_shop_item(item_id, delta).await {
+                Ok(resp) => {
+                    state.set_success(format!("{}，当前库存 {}", resp.message, resp.count));
+                    restock_item_id.set(None);
+                    restock_delta.set(String::new());
+                    load_my_items();
+                }
+                Err(e) => {
+                    state.set_error(format!("补货失败: {}", e));
                 }
             }
-            loading.set(false);
+            state.finish();
         });
     };
 
@@ -144,6 +256,23 @@ pub fn MyShop() -> Element {
                     }
                 }
             } else {
+                if let Some(stats) = seller_stats.read().as_ref() {
+                    div { class: "summary-grid",
+                        div { class: "summary-item",
+                            span { "累计售出" }
+                            strong { "{stats.items_sold}" }
+                        }
+                        div { class: "summary-item",
+                            span { "销售总额" }
+                            strong { "{stats.revenue:.2}" }
+                        }
+                        div { class: "summary-item",
+                            span { "买家数" }
+                            strong { "{stats.distinct_buyers}" }
+                        }
+                    }
+                }
+
                 div { class: "toolbar",
                     button {
                         class: "btn-primary",
@@ -151,30 +280,74 @@ pub fn MyShop() -> Element {
                             let current = *show_create_form.read();
                             show_create_form.set(!current);
                         },
-                        disabled: *loading.read(),
+                        disabled: state.is_loading(),
                         if *show_create_form.read() { "取消创建" } else { "创建新商品" }
                     }
                     button {
                         class: "btn-secondary",
                         onclick: move |_| load_my_items(),
-                        disabled: *loading.read(),
+                        disabled: state.is_loading(),
                         "刷新"
                     }
+                    label {
+                        class: "btn-secondary",
+                        r#for: IMPORT_FILE_INPUT_ID,
+                        title: "CSV 每行为 name,price,count,location，可带表头",
+                        "批量导入CSV"
+                    }
+                    input {
+                        id: IMPORT_FILE_INPUT_ID,
+                        r#type: "file",
+                        accept: ".csv,text/csv",
+                        style: "display: none;",
+                        onchange: import_csv,
+                        disabled: state.is_loading()
+                    }
                     Link { to: crate::Route::Shop {},
                         button { class: "btn-secondary", "返回商店" }
                     }
                 }
 
-                if *loading_visible.read() {
-                    div { class: "loading-message", "处理中..." }
-                }
+                AsyncStatus { state, loading_text: "处理中..." }
 
-                if let Some(err) = error.read().as_ref() {
-                    div { class: "error-message", "{err}" }
-                }
-
-                if let Some(succ) = success.read().as_ref() {
-                    div { class: "success-message", "{succ}" }
+                if !import_results.read().is_empty() {
+                    div { class: "info-section",
+                        h3 {
+                            "导入结果"
+                            button {
+                                class: "btn-small btn-secondary",
+                                style: "margin-left: 0.5rem;",
+                                onclick: move |_| import_results.set(Vec::new()),
+                                "关闭"
+                            }
+                        }
+                        table { class: "data-table",
+                            thead {
+                                tr {
+                                    th { "行号" }
+                                    th { "商品名称" }
+                                    th { "结果" }
+                                }
+                            }
+                            tbody {
+                                for line in import_results.read().iter() {
+                                    tr { key: "{line.line}",
+                                        td { "{line.line}" }
+                                        td { "{line.name}" }
+                                        td {
+                                            if let Some(error) = &line.error {
+                                                span { class: "text-danger", "{error}" }
+                                            } else if let Some(id) = line.id {
+                                                span { class: "text-success", "已上架 #{id}" }
+                                            } else {
+                                                "有效"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                 }
 
                 if *show_create_form.read() {
@@ -188,7 +361,7 @@ pub fn MyShop() -> Element {
                                     placeholder: "例如：限量周边",
                                     value: "{item_name}",
                                     oninput: move |evt| item_name.set(evt.value().clone()),
-                                    disabled: *loading.read()
+                                    disabled: state.is_loading()
                                 }
                             }
 
@@ -199,7 +372,7 @@ pub fn MyShop() -> Element {
                                     placeholder: "所需价格（元），可输入小数",
                                     value: "{item_price}",
                                     oninput: move |evt| item_price.set(evt.value().clone()),
-                                    disabled: *loading.read()
+                                    disabled: state.is_loading()
                                 }
                             }
 
@@ -211,7 +384,7 @@ pub fn MyShop() -> Element {
                                     placeholder: "初始库存",
                                     value: "{item_count}",
                                     oninput: move |evt| item_count.set(evt.value().clone()),
-                                    disabled: *loading.read()
+                                    disabled: state.is_loading()
                                 }
                             }
 
@@ -222,15 +395,38 @@ pub fn MyShop() -> Element {
                                     placeholder: "例如：线上发货 / XX自提点",
                                     value: "{item_location}",
                                     oninput: move |evt| item_location.set(evt.value().clone()),
-                                    disabled: *loading.read()
+                                    disabled: state.is_loading()
+                                }
+                            }
+
+                            div { class: "form-group",
+                                label { "库存提醒阈值：" }
+                                input {
+                                    r#type: "number",
+                                    min: "0",
+                                    placeholder: "库存降到该数量时提醒，留空不提醒",
+                                    value: "{item_threshold}",
+                                    oninput: move |evt| item_threshold.set(evt.value().clone()),
+                                    disabled: state.is_loading()
                                 }
                             }
 
+                            div { class: "form-group",
+                                label { "下架时间：" }
+                                input {
+                                    r#type: "datetime-local",
+                                    value: "{item_expires_at}",
+                                    oninput: move |evt| item_expires_at.set(evt.value().clone()),
+                                    disabled: state.is_loading()
+                                }
+                                div { class: "form-tip", "到期后商品自动从商店下架，留空表示长期有效" }
+                            }
+
                             div { class: "form-actions",
                                 button {
                                     r#type: "submit",
                                     class: "btn-primary",
-                                    disabled: *loading.read(),
+                                    disabled: state.is_loading(),
                                     "创建"
                                 }
                             }
@@ -238,6 +434,76 @@ pub fn MyShop() -> Element {
                     }
                 }
 
+                if let Some(item_id) = *transfer_item_id.read() {
+                    div { class: "form-container",
+                        h2 { "转让商品 #{item_id}" }
+                        form { onsubmit: submit_transfer,
+                            div { class: "form-group",
+                                label { "新卖家QQ：*" }
+                                input {
+                                    r#type: "text",
+                                    placeholder: "接手该商品的用户QQ",
+                                    value: "{transfer_qq}",
+                                    oninput: move |evt| transfer_qq.set(evt.value().clone()),
+                                    disabled: state.is_loading()
+                                }
+                            }
+                            div { class: "form-actions",
+                                button {
+                                    r#type: "submit",
+                                    class: "btn-primary",
+                                    disabled: state.is_loading(),
+                                    "确认转让"
+                                }
+                                button {
+                                    r#type: "button",
+                                    class: "btn-secondary",
+                                    onclick: move |_| {
+                                        transfer_item_id.set(None);
+                                        transfer_qq.set(String::new());
+                                    },
+                                    "取消"
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if let Some(item_id) = *restock_item_id.read() {
+                    div { class: "form-container",
+                        h2 { "补货商品 #{item_id}" }
+                        form { onsubmit: submit_restock,
+                            div { class: "form-group",
+                                label { "补货数量：*" }
+                                input {
+                                    r#type: "number",
+                                    placeholder: "增加的库存，负数表示减少",
+                                    value: "{restock_delta}",
+                                    oninput: move |evt| restock_delta.set(evt.value().clone()),
+                                    disabled: state.is_loading()
+                                }
+                            }
+                            div { class: "form-actions",
+                                button {
+                                    r#type: "submit",
+                                    class: "btn-primary",
+                                    disabled: state.is_loading(),
+                                    "确认补货"
+                                }
+                                button {
+                                    r#type: "button",
+                                    class: "btn-secondary",
+                                    onclick: move |_| {
+                                        restock_item_id.set(None);
+                                        restock_delta.set(String::new());
+                                    },
+                                    "取消"
+                                }
+                            }
+                        }
+                    }
+                }
+
                 div { class: "table-container",
                     h2 { "" }
                     if my_items.read().is_empty() {
@@ -253,6 +519,7 @@ pub fn MyShop() -> Element {
                                     th { "状态" }
                                     th { "交易地点" }
                                     th { "卖家" }
+                                    th { "操作" }
                                 }
                             }
                             tbody {
@@ -266,12 +533,44 @@ pub fn MyShop() -> Element {
                                         td {
                                             if item.count > 0 {
                                                 span { class: "badge badge-success", "在售" }
+                                                if item.low_stock_threshold.is_some_and(|threshold| item.count <= threshold) {
+                                                    span {
+                                                        class: "badge badge-warning",
+                                                        title: "库存已降到提醒阈值",
+                                                        "库存偏低"
+                                                    }
+                                                }
                                             } else {
                                                 span { class: "badge badge-warning", "已售罄" }
                                             }
+                                            if let Some(expires_at) = &item.expires_at {
+                                                ExpiryBadge { expires_at: expires_at.clone() }
+                                            }
                                         }
                                         td { "{item.location}" }
                                         td { "{item.seller}" }
+                                        td {
+                                            if let Some(id) = item.id {
+                                                button {
+                                                    class: "btn-small btn-secondary",
+                                                    onclick: move |_| {
+                                                        transfer_item_id.set(Some(id));
+                                                        transfer_qq.set(String::new());
+                                                    },
+                                                    disabled: state.is_loading(),
+                                                    "转让"
+                                                }
+                                                button {
+                                                    class: "btn-small btn-primary",
+                                                    onclick: move |_| {
+                                                        restock_item_id.set(Some(id));
+                                                        restock_delta.set(String::new());
+                                                    },
+                                                    disabled: state.is_loading(),
+                                                    "补货"
+                                                }
+                                            }
+                                        }
                                     }
                                 }
                             }
@@ -0,0 +1,128 @@
+use crate::api;
+use crate::components::{use_toast, use_unread_notifications};
+use crate::models::UserNotification;
+use dioxus::prelude::*;
+
+/// 每页显示的通知数
+const PAGE_SIZE: i32 = 20;
+
+fn kind_label(kind: &str) -> &'static str {
+    match kind {
+        "lp_processed" => "LP审批",
+        "draw_won" => "抽奖中奖",
+        "low_stock" => "库存提醒",
+        _ => "通知",
+    }
+}
+
+#[component]
+pub fn Notifications() -> Element {
+    let mut notifications = use_signal(Vec::<UserNotification>::new);
+    let mut total = use_signal(|| 0i64);
+    let mut page = use_signal(|| 0i32);
+    let mut loading = use_signal(|| false);
+    let mut unread = use_unread_notifications();
+    let mut toast = use_toast();
+
+    let load_page = move |target: i32| {
+        spawn(async move {
+            loading.set(true);
+            match api::get_notifications(PAGE_SIZE, target * PAGE_SIZE).await {
+                Ok(resp) => {
+                    notifications.set(resp.notifications);
+                    total.set(resp.total);
+                    unread.set(resp.unread);
+                    page.set(target);
+                }
+                Err(e) => toast.error(format!("加载通知失败: {}", e)),
+            }
+            loading.set(false);
+        });
+    };
+
+    use_hook(|| load_page(0));
+
+    let mark_read = move |id: i64| {
+        spawn(async move {
+            match api::mark_notification_read(id).await {
+                Ok(_) => {
+                    if let Some(item) = notifications
+                        .write()
+                        .iter_mut()
+                        .find(|item| item.id == id && !item.read)
+                    {
+                        item.read = true;
+                        let remaining = (*unread.peek() - 1).max(0);
+                        unread.set(remaining);
+                    }
+                }
+                Err(e) => toast.error(format!("标记已读失败: {}", e)),
+            }
+        });
+    };
+
+    let page_value = *page.read();
+    let total_pages = ((*total.read() + PAGE_SIZE as i64 - 1) / PAGE_SIZE as i64).max(1) as i32;
+    let is_loading = *loading.read();
+
+    rsx! {
+        div { class: "page-container",
+            h1 { "我的通知" }
+            p { class: "form-tip", "未读 {unread} 条，共 {total} 条" }
+
+            if notifications.read().is_empty() && !is_loading {
+                div { class: "empty-state", "暂无通知" }
+            } else {
+                table { class: "data-table",
+                    thead {
+                        tr {
+                            th { "类型" }
+                            th { "内容" }
+                            th { "时间" }
+                            th { "操作" }
+                        }
+                    }
+                    tbody {
+                        for item in notifications.read().iter().cloned() {
+                            tr { key: "{item.id}",
+                                class: if item.read { "" } else { "notification-unread" },
+                                td { "{kind_label(&item.kind)}" }
+                                td { "{item.message}" }
+                                td { "{item.created_at}" }
+                                td {
+                                    if item.read {
+                                        span { class: "form-tip", "已读" }
+                                    } else {
+                                        button {
+                                            class: "btn-small btn-secondary",
+                                            onclick: move |_| mark_read(item.id),
+                                            "标记已读"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { class: "pagination-bar",
+                span { "第 {page_value + 1} / {total_pages} 页" }
+                div { class: "pagination-actions",
+                    button {
+                        class: "btn-secondary",
+                        onclick: move |_| load_page(page_value - 1),
+                        disabled: page_value == 0 || is_loading,
+                        "上一页"
+                    }
+                    button {
+                        class: "btn-secondary",
+                        onclick: move |_| load_page(page_value + 1),
+                        disabled: page_value + 1 >= total_pages || is_loading,
+                        "下一页"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::api;
-use crate::components::use_app_context;
+use crate::components::{use_app_context, use_toast};
 use crate::models::SessionUser;
 use dioxus::prelude::*;
 
@@ -11,14 +11,20 @@ pub fn Profile() -> Element {
     let mut profile = use_signal(|| None::<api::UserInfo>);
     let mut nickname = use_signal(String::new);
     let mut birthday = use_signal(String::new);
+    let mut avatar_url = use_signal(String::new);
+    let mut email = use_signal(String::new);
     let mut old_password = use_signal(String::new);
     let mut new_password = use_signal(String::new);
     let mut confirm_password = use_signal(String::new);
-    let mut error = use_signal(|| None::<String>);
-    let mut success = use_signal(|| None::<String>);
+    let mut toast = use_toast();
     let mut loading_profile = use_signal(|| false);
     let mut saving_profile = use_signal(|| false);
     let mut saving_password = use_signal(|| false);
+    // 两步验证：申请开启后返回的密钥，以及用户输入的确认验证码
+    let mut totp_setup = use_signal(|| None::<api::TotpSetupResponse>);
+    let mut totp_code = use_signal(String::new);
+    let mut saving_totp = use_signal(|| false);
+    let mut show_activity = use_signal(|| false);
 
     let load_profile = move || {
         spawn(async move {
@@ -27,21 +33,24 @@ pub fn Profile() -> Element {
                 Ok(user) => {
                     nickname.set(user.nickname.clone());
                     birthday.set(user.birthday.clone().unwrap_or_default());
+                    avatar_url.set(user.avatar_url.clone().unwrap_or_default());
+                    email.set(user.email.clone().unwrap_or_default());
                     profile.set(Some(user.clone()));
                     current_user.set(Some(SessionUser {
                         qq: user.qq.clone(),
                         nickname: user.nickname.clone(),
                         birthday: user.birthday.clone(),
+                        avatar_url: user.avatar_url.clone(),
+                        email: user.email.clone(),
                         main_role_id: None,
                         role_name: user.role_name.clone(),
                         permissions: user.permissions.clone(),
+                        must_change_password: user.must_change_password,
+                        is_protected_admin: user.is_protected_admin,
                     }));
-                    error.set(None);
-                    success.set(None);
                 }
                 Err(e) => {
-                    error.set(Some(format!("加载个人信息失败: {}", e)));
-                    success.set(None);
+                    toast.error(format!("加载个人信息失败: {}", e));
                 }
             }
             loading_profile.set(false);
@@ -57,12 +66,25 @@ pub fn Profile() -> Element {
 
         let nickname_val = nickname.read().trim().to_string();
         if nickname_val.is_empty() {
-            error.set(Some("昵称不能为空".to_string()));
-            success.set(None);
+            toast.error("昵称不能为空");
             return;
         }
 
         let birthday_val = birthday.read().trim().to_string();
+        let avatar_val = avatar_url.read().trim().to_string();
+        if !(avatar_val.is_empty()
+            || avatar_val.starts_with("http://")
+            || avatar_val.starts_with("https://"))
+        {
+            toast.error("头像地址必须以 http:// 或 https:// 开头");
+            return;
+        }
+
+        let email_val = email.read().trim().to_string();
+        if !email_val.is_empty() && !email_val.contains('@') {
+            toast.error("邮箱格式不正确");
+            return;
+        }
 
         let mut request = api::UpdateProfileRequest::default();
         request.nickname = Some(nickname_val.clone());
@@ -71,28 +93,36 @@ pub fn Profile() -> Element {
         } else {
             Some(birthday_val.clone())
         };
+        // 空字符串表示清除头像
+        request.avatar_url = Some(avatar_val);
+        // 空字符串表示清除邮箱
+        request.email = Some(email_val);
 
         saving_profile.set(true);
-        success.set(None);
         spawn(async move {
             match api::update_profile(request).await {
                 Ok(user) => {
                     nickname.set(user.nickname.clone());
                     birthday.set(user.birthday.clone().unwrap_or_default());
+                    avatar_url.set(user.avatar_url.clone().unwrap_or_default());
+                    email.set(user.email.clone().unwrap_or_default());
                     profile.set(Some(user.clone()));
                     current_user.set(Some(SessionUser {
                         qq: user.qq.clone(),
                         nickname: user.nickname.clone(),
                         birthday: user.birthday.clone(),
+                        avatar_url: user.avatar_url.clone(),
+                        email: user.email.clone(),
                         main_role_id: None,
                         role_name: user.role_name.clone(),
                         permissions: user.permissions.clone(),
+                        must_change_password: user.must_change_password,
+                        is_protected_admin: user.is_protected_admin,
                     }));
-                    error.set(None);
-                    success.set(Some("个人信息已更新".to_string()));
+                    toast.success("个人信息已更新");
                 }
                 Err(e) => {
-                    error.set(Some(format!("更新失败: {}", e)));
+                    toast.error(format!("更新失败: {}", e));
                 }
             }
             saving_profile.set(false);
@@ -107,57 +137,113 @@ pub fn Profile() -> Element {
         let confirm_pwd = confirm_password.read().trim().to_string();
 
         if old_pwd.is_empty() {
-            error.set(Some("请输入原密码".to_string()));
-            success.set(None);
+            toast.error("请输入原密码");
             return;
         }
 
         if new_pwd.len() < 6 {
-            error.set(Some("新密码至少需要6位字符".to_string()));
-            success.set(None);
+            toast.error("新密码至少需要6位字符");
             return;
         }
 
         if new_pwd != confirm_pwd {
-            error.set(Some("两次输入的新密码不一致".to_string()));
-            success.set(None);
+            toast.error("两次输入的新密码不一致");
             return;
         }
 
         saving_password.set(true);
-        success.set(None);
         spawn(async move {
             match api::change_password(old_pwd.clone(), new_pwd.clone()).await {
                 Ok(msg) => {
-                    error.set(None);
-                    success.set(Some(msg));
+                    // 修改成功后解除强制改密限制
+                    let session = current_user.read().clone();
+                    if let Some(mut user) = session.filter(|u| u.must_change_password) {
+                        user.must_change_password = false;
+                        current_user.set(Some(user));
+                    }
+                    toast.success(msg);
                     old_password.set(String::new());
                     new_password.set(String::new());
                     confirm_password.set(String::new());
                 }
                 Err(e) => {
-                    error.set(Some(format!("修改密码失败: {}", e)));
+                    toast.error(format!("修改密码失败: {}", e));
                 }
             }
             saving_password.set(false);
         });
     };
 
+    let on_enable_totp = move |_| {
+        saving_totp.set(true);
+        spawn(async move {
+            match api::enable_totp().await {
+                Ok(setup) => {
+                    toast.info(setup.message.clone());
+                    totp_setup.set(Some(setup));
+                    totp_code.set(String::new());
+                }
+                Err(e) => {
+                    toast.error(format!("开启两步验证失败: {}", e));
+                }
+            }
+            saving_totp.set(false);
+        });
+    };
+
+    let on_verify_totp = move |evt: Event<FormData>| {
+        evt.prevent_default();
+
+        let code = totp_code.read().trim().to_string();
+        if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+            toast.error("请输入6位数字验证码");
+            return;
+        }
+
+        saving_totp.set(true);
+        spawn(async move {
+            match api::verify_totp(code).await {
+                Ok(msg) => {
+                    toast.success(msg);
+                    totp_setup.set(None);
+                    totp_code.set(String::new());
+                    if let Some(user) = profile.write().as_mut() {
+                        user.totp_enabled = true;
+                    }
+                }
+                Err(e) => {
+                    toast.error(format!("验证失败: {}", e));
+                }
+            }
+            saving_totp.set(false);
+        });
+    };
+
     let profile_snapshot = profile.read().clone();
+    let must_change_password = current_user
+        .read()
+        .as_ref()
+        .is_some_and(|user| user.must_change_password);
     let loading_flag = *loading_profile.read();
     let saving_profile_flag = *saving_profile.read();
     let saving_password_flag = *saving_password.read();
+    let saving_totp_flag = *saving_totp.read();
+    let totp_enabled = profile
+        .read()
+        .as_ref()
+        .map(|u| u.totp_enabled)
+        .unwrap_or(false);
+    let totp_setup_snapshot = totp_setup.read().clone();
+    let show_activity_flag = *show_activity.read();
 
     rsx! {
         div { class: "page-container",
             h1 { "个人中心" }
 
-            if let Some(err) = error.read().as_ref() {
-                div { class: "error-message", "{err}" }
-            }
-
-            if let Some(msg) = success.read().as_ref() {
-                div { class: "success-message", "{msg}" }
+            if must_change_password {
+                div { class: "warning-message",
+                    "您的账号需要先修改密码，修改完成后才能使用其他功能。"
+                }
             }
 
             if loading_flag && profile_snapshot.is_none() {
@@ -166,6 +252,9 @@ pub fn Profile() -> Element {
 
             if let Some(user) = profile_snapshot.as_ref() {
                 div { class: "profile-card",
+                    if let Some(avatar) = &user.avatar_url {
+                        img { class: "profile-avatar", src: "{avatar}", alt: "头像" }
+                    }
                     h2 { "{user.nickname} ({user.qq})" }
                     if let Some(role) = &user.role_name {
                         p { class: "profile-role", "角色: {role}" }
@@ -188,89 +277,273 @@ pub fn Profile() -> Element {
                 }
             }
 
-            div { class: "profile-layout",
-                div { class: "profile-panel",
-                    h2 { "基本信息" }
-                    form { onsubmit: on_save_profile,
-                        div { class: "form-group",
-                            label { r#for: "nickname", "昵称" }
-                            input {
-                                id: "nickname",
-                                r#type: "text",
-                                value: "{nickname}",
-                                oninput: move |evt| nickname.set(evt.value().clone()),
-                                disabled: saving_profile_flag || loading_flag,
+            if !must_change_password {
+                div { class: "tab-bar",
+                    button {
+                        class: if show_activity_flag { "tab-button" } else { "tab-button active" },
+                        onclick: move |_| show_activity.set(false),
+                        "账号设置"
+                    }
+                    button {
+                        class: if show_activity_flag { "tab-button active" } else { "tab-button" },
+                        onclick: move |_| show_activity.set(true),
+                        "我的操作记录"
+                    }
+                }
+            }
+
+            if show_activity_flag && !must_change_password {
+                MyActivity {}
+            } else {
+                div { class: "profile-layout",
+                    div { class: "profile-panel",
+                        h2 { "基本信息" }
+                        form { onsubmit: on_save_profile,
+                            div { class: "form-group",
+                                label { r#for: "nickname", "昵称" }
+                                input {
+                                    id: "nickname",
+                                    r#type: "text",
+                                    value: "{nickname}",
+                                    oninput: move |evt| nickname.set(evt.value().clone()),
+                                    disabled: saving_profile_flag || loading_flag,
+                                }
                             }
-                        }
-                        div { class: "form-group",
-                            label { r#for: "birthday", "生日" }
-                            input {
-                                id: "birthday",
-                                r#type: "date",
-                                value: "{birthday}",
-                                oninput: move |evt| birthday.set(evt.value().clone()),
-                                disabled: saving_profile_flag || loading_flag,
+                            div { class: "form-group",
+                                label { r#for: "birthday", "生日" }
+                                input {
+                                    id: "birthday",
+                                    r#type: "date",
+                                    value: "{birthday}",
+                                    oninput: move |evt| birthday.set(evt.value().clone()),
+                                    disabled: saving_profile_flag || loading_flag,
+                                }
                             }
-                        }
-                        button {
-                            class: "btn-primary",
-                            r#type: "submit",
-                            disabled: saving_profile_flag,
-                            if saving_profile_flag {
-                                "保存中..."
-                            } else {
-                                "保存资料"
+                            div { class: "form-group",
+                                label { r#for: "avatar_url", "头像地址" }
+                                input {
+                                    id: "avatar_url",
+                                    r#type: "url",
+                                    placeholder: "https://example.com/avatar.png，留空则不显示头像",
+                                    value: "{avatar_url}",
+                                    oninput: move |evt| avatar_url.set(evt.value().clone()),
+                                    disabled: saving_profile_flag || loading_flag,
+                                }
+                            }
+                            div { class: "form-group",
+                                label { r#for: "email", "邮箱" }
+                                input {
+                                    id: "email",
+                                    r#type: "email",
+                                    placeholder: "用于接收LP审批、中奖等通知，可留空",
+                                    value: "{email}",
+                                    oninput: move |evt| email.set(evt.value().clone()),
+                                    disabled: saving_profile_flag || loading_flag,
+                                }
+                            }
+                            button {
+                                class: "btn-primary",
+                                r#type: "submit",
+                                disabled: saving_profile_flag,
+                                if saving_profile_flag {
+                                    "保存中..."
+                                } else {
+                                    "保存资料"
+                                }
                             }
                         }
                     }
-                }
 
-                div { class: "profile-panel",
-                    h2 { "修改密码" }
-                    form { onsubmit: on_change_password,
-                        div { class: "form-group",
-                            label { r#for: "old_password", "原密码" }
-                            input {
-                                id: "old_password",
-                                r#type: "password",
-                                value: "{old_password}",
-                                oninput: move |evt| old_password.set(evt.value().clone()),
-                                disabled: saving_password_flag,
+                    div { class: "profile-panel",
+                        h2 { "修改密码" }
+                        form { onsubmit: on_change_password,
+                            div { class: "form-group",
+                                label { r#for: "old_password", "原密码" }
+                                input {
+                                    id: "old_password",
+                                    r#type: "password",
+                                    value: "{old_password}",
+                                    oninput: move |evt| old_password.set(evt.value().clone()),
+                                    disabled: saving_password_flag,
+                                }
                             }
-                        }
-                        div { class: "form-group",
-                            label { r#for: "new_password", "新密码" }
-                            input {
-                                id: "new_password",
-                                r#type: "password",
-                                value: "{new_password}",
-                                oninput: move |evt| new_password.set(evt.value().clone()),
+                            div { class: "form-group",
+                                label { r#for: "new_password", "新密码" }
+                                input {
+                                    id: "new_password",
+                                    r#type: "password",
+                                    value: "{new_password}",
+                                    oninput: move |evt| new_password.set(evt.value().clone()),
+                                    disabled: saving_password_flag,
+                                }
+                            }
+                            div { class: "form-group",
+                                label { r#for: "confirm_password", "确认新密码" }
+                                input {
+                                    id: "confirm_password",
+                                    r#type: "password",
+                                    value: "{confirm_password}",
+                                    oninput: move |evt| confirm_password.set(evt.value().clone()),
+                                    disabled: saving_password_flag,
+                                }
+                            }
+                            button {
+                                class: "btn-secondary",
+                                r#type: "submit",
                                 disabled: saving_password_flag,
+                                if saving_password_flag {
+                                    "提交中..."
+                                } else {
+                                    "更新密码"
+                                }
                             }
                         }
-                        div { class: "form-group",
-                            label { r#for: "confirm_password", "确认新密码" }
-                            input {
-                                id: "confirm_password",
-                                r#type: "password",
-                                value: "{confirm_password}",
-                                oninput: move |evt| confirm_password.set(evt.value().clone()),
-                                disabled: saving_password_flag,
+                    }
+
+                    div { class: "profile-panel",
+                        h2 { "两步验证" }
+                        if totp_enabled {
+                            p { "已开启，登录时需要输入身份验证器中的6位验证码。" }
+                        } else if let Some(setup) = totp_setup_snapshot.as_ref() {
+                            p { "请在身份验证器中导入以下密钥，然后输入生成的验证码完成开启：" }
+                            div { class: "form-group",
+                                label { "密钥" }
+                                input { r#type: "text", value: "{setup.secret}", readonly: true }
                             }
+                            div { class: "form-group",
+                                label { "otpauth 地址" }
+                                input { r#type: "text", value: "{setup.otpauth_url}", readonly: true }
+                            }
+                            form { onsubmit: on_verify_totp,
+                                div { class: "form-group",
+                                    label { r#for: "totp_code", "验证码" }
+                                    input {
+                                        id: "totp_code",
+                                        r#type: "text",
+                                        inputmode: "numeric",
+                                        maxlength: "6",
+                                        placeholder: "6位数字",
+                                        value: "{totp_code}",
+                                        oninput: move |evt| totp_code.set(evt.value().clone()),
+                                        disabled: saving_totp_flag,
+                                    }
+                                }
+                                button {
+                                    class: "btn-primary",
+                                    r#type: "submit",
+                                    disabled: saving_totp_flag,
+                                    if saving_totp_flag {
+                                        "验证中..."
+                                    } else {
+                                        "确认开启"
+                                    }
+                                }
+                            }
+                        } else {
+                            p { "开启后，登录时除密码外还需要输入身份验证器中的验证码。" }
+                            button {
+                                class: "btn-secondary",
+                                onclick: on_enable_totp,
+                                disabled: saving_totp_flag,
+                                "开启两步验证"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 每页显示的操作记录条数
+const ACTIVITY_PAGE_SIZE: i32 = 20;
+
+/// 当前用户自己的操作记录，敏感字段已由后端遮盖
+#[component]
+fn MyActivity() -> Element {
+    let mut logs = use_signal(Vec::<api::RequestLog>::new);
+    let mut total = use_signal(|| 0i64);
+    let mut page = use_signal(|| 0i32);
+    let mut loading = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    let mut load_page = move |target: i32| {
+        page.set(target);
+        spawn(async move {
+            loading.set(true);
+            match api::get_my_activity(ACTIVITY_PAGE_SIZE, target * ACTIVITY_PAGE_SIZE).await {
+                Ok(response) => {
+                    logs.set(response.logs);
+                    total.set(response.total);
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(format!("加载操作记录失败: {}", e))),
+            }
+            loading.set(false);
+        });
+    };
+
+    use_hook(move || load_page(0));
+
+    let current_page = *page.read();
+    let total_pages =
+        ((*total.read() + ACTIVITY_PAGE_SIZE as i64 - 1) / ACTIVITY_PAGE_SIZE as i64).max(1) as i32;
+    let is_loading = *loading.read();
+
+    rsx! {
+        div { class: "profile-panel",
+            h2 { "我的操作记录" }
+
+            if let Some(err) = error.read().as_ref() {
+                div { class: "error-message", "{err}" }
+            }
+
+            if logs.read().is_empty() && !is_loading {
+                p { "暂无操作记录" }
+            } else {
+                table { class: "data-table",
+                    thead {
+                        tr {
+                            th { "时间" }
+                            th { "方法" }
+                            th { "路径" }
+                            th { "状态" }
+                            th { "内容" }
                         }
-                        button {
-                            class: "btn-secondary",
-                            r#type: "submit",
-                            disabled: saving_password_flag,
-                            if saving_password_flag {
-                                "提交中..."
-                            } else {
-                                "更新密码"
+                    }
+                    tbody {
+                        for log in logs.read().iter() {
+                            tr { key: "{log.id}",
+                                td { "{log.timestamp}" }
+                                td { "{log.method}" }
+                                td { "{log.path}" }
+                                td { "{log.status}" }
+                                td { class: "log-body-cell",
+                                    "{log.body.clone().unwrap_or_default()}"
+                                }
                             }
                         }
                     }
                 }
             }
+
+            div { class: "pagination-bar",
+                span { "第 {current_page + 1} / {total_pages} 页，共 {total} 条" }
+                div { class: "pagination-actions",
+                    button {
+                        class: "btn-secondary",
+                        disabled: current_page == 0 || is_loading,
+                        onclick: move |_| load_page(current_page - 1),
+                        "上一页"
+                    }
+                    button {
+                        class: "btn-secondary",
+                        disabled: current_page + 1 >= total_pages || is_loading,
+                        onclick: move |_| load_page(current_page + 1),
+                        "下一页"
+                    }
+                }
+            }
         }
     }
 }
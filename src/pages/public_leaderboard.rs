@@ -0,0 +1,58 @@
+use crate::api;
+use crate::models::LeaderboardEntry;
+use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+
+/// 排行榜自动刷新间隔（毫秒），便于挂在大屏上长期展示
+const REFRESH_INTERVAL_MS: u32 = 60_000;
+
+/// 公开LP排行榜，无需登录即可访问，只展示昵称和LP总数
+#[component]
+pub fn PublicLeaderboard() -> Element {
+    let mut entries = use_signal(|| None::<Result<Vec<LeaderboardEntry>, String>>);
+
+    use_future(move || async move {
+        loop {
+            entries.set(Some(api::get_public_leaderboard().await));
+            TimeoutFuture::new(REFRESH_INTERVAL_MS).await;
+        }
+    });
+
+    rsx! {
+        div { class: "page-container",
+            h1 { "LP排行榜" }
+
+            match &*entries.read() {
+                None => rsx! {
+                    div { class: "loading-message", "加载中..." }
+                },
+                Some(Err(e)) => rsx! {
+                    div { class: "error-message", "{e}" }
+                },
+                Some(Ok(list)) if list.is_empty() => rsx! {
+                    div { class: "empty-state", "暂无数据" }
+                },
+                Some(Ok(list)) => rsx! {
+                    table { class: "data-table",
+                        thead {
+                            tr {
+                                th { "排名" }
+                                th { "昵称" }
+                                th { "LP" }
+                            }
+                        }
+                        tbody {
+                            for (index, entry) in list.iter().enumerate() {
+                                tr { key: "{index}",
+                                    td { "{index + 1}" }
+                                    td { "{entry.nickname}" }
+                                    td { "{entry.total_lp}" }
+                                }
+                            }
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
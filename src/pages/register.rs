@@ -1,5 +1,21 @@
 use crate::api;
+use crate::components::use_app_context;
+use crate::models::SessionUser;
+use crate::pow;
 use dioxus::prelude::*;
+use gloo_timers::future::TimeoutFuture;
+use std::collections::HashMap;
+
+/// 每批尝试的候选解数量，批次之间让出主线程，避免页面卡死
+const POW_BATCH_SIZE: u64 = 5_000;
+
+/// QQ号位数范围，与后端校验一致
+const QQ_MIN_DIGITS: usize = 5;
+const QQ_MAX_DIGITS: usize = 12;
+/// 昵称最大字符数
+const NICKNAME_MAX_CHARS: usize = 32;
+/// 密码最小长度
+const PASSWORD_MIN_LEN: usize = 6;
 
 #[component]
 pub fn Register() -> Element {
@@ -8,31 +24,31 @@ pub fn Register() -> Element {
     let mut password = use_signal(String::new);
     let mut confirm_password = use_signal(String::new);
     let mut birthday = use_signal(String::new);
+    let mut email = use_signal(String::new);
     let mut error = use_signal(|| None::<String>);
+    // 各输入框的校验错误（字段名 -> 提示），在对应输入框下方显示
+    let mut field_errors = use_signal(HashMap::<&'static str, String>::new);
     let mut success = use_signal(|| false);
+    let mut verifying = use_signal(|| false);
+    let nav = use_navigator();
+    let mut current_user = use_app_context().current_user;
 
     let on_submit = move |evt: Event<FormData>| {
         evt.prevent_default();
 
-        let qq_val = qq.read().clone();
-        let nickname_val = nickname.read().clone();
+        let qq_val = qq.read().trim().to_string();
+        let nickname_val = nickname.read().trim().to_string();
         let pwd_val = password.read().clone();
         let confirm_pwd = confirm_password.read().clone();
         let birthday_val = birthday.read().clone();
+        let email_val = email.read().trim().to_string();
 
-        // 验证
-        if qq_val.is_empty() || nickname_val.is_empty() || pwd_val.is_empty() {
-            error.set(Some("请填写所有必填项".to_string()));
-            return;
-        }
-
-        if pwd_val != confirm_pwd {
-            error.set(Some("两次输入的密码不一致".to_string()));
-            return;
-        }
-
-        if pwd_val.len() < 6 {
-            error.set(Some("密码长度至少6位".to_string()));
+        // 提交前逐项校验，出错时标出具体字段，不再请求后端
+        let errors = validate_fields(&qq_val, &nickname_val, &pwd_val, &confirm_pwd, &email_val);
+        let has_errors = !errors.is_empty();
+        field_errors.set(errors);
+        if has_errors {
+            error.set(None);
             return;
         }
 
@@ -42,16 +58,64 @@ pub fn Register() -> Element {
             Some(birthday_val)
         };
 
+        let email_opt = if email_val.is_empty() {
+            None
+        } else {
+            Some(email_val)
+        };
+
         // 使用API调用替代直接数据库访问
         spawn(async move {
+            // 后端开启了工作量证明时，先求解挑战再注册
+            let (pow_nonce, pow_solution) = match api::get_register_challenge().await {
+                Ok(api::RegisterChallenge {
+                    enabled: true,
+                    nonce: Some(nonce),
+                    difficulty,
+                }) => {
+                    verifying.set(true);
+                    let solution = solve_challenge(&nonce, difficulty).await;
+                    verifying.set(false);
+                    (Some(nonce), Some(solution))
+                }
+                Ok(_) => (None, None),
+                Err(e) => {
+                    error.set(Some(format!("注册失败: {}", e)));
+                    return;
+                }
+            };
+
             let req = api::RegisterRequest {
                 qq: qq_val,
                 nickname: nickname_val,
                 password: pwd_val,
                 birthday: birthday_opt,
+                email: email_opt,
+                pow_nonce,
+                pow_solution,
             };
 
             match api::register(req).await {
+                // 后端签发了登录凭证时直接进入系统
+                Ok(api::RegisterResponse {
+                    user: Some(user),
+                    token: Some(_),
+                    ..
+                }) => {
+                    current_user.set(Some(SessionUser {
+                        qq: user.qq,
+                        nickname: user.nickname,
+                        birthday: user.birthday,
+                        avatar_url: user.avatar_url,
+                        email: user.email,
+                        main_role_id: None,
+                        role_name: user.role_name,
+                        permissions: user.permissions,
+                        must_change_password: user.must_change_password,
+                        is_protected_admin: user.is_protected_admin,
+                    }));
+                    nav.push(crate::Route::Home {});
+                }
                 Ok(_) => {
                     success.set(true);
                     error.set(None);
@@ -60,6 +124,7 @@ pub fn Register() -> Element {
                     password.set(String::new());
                     confirm_password.set(String::new());
                     birthday.set(String::new());
+                    email.set(String::new());
                 }
                 Err(e) => {
                     error.set(Some(format!("注册失败: {}", e)));
@@ -81,8 +146,15 @@ pub fn Register() -> Element {
                             id: "qq",
                             name: "qq",
                             placeholder: "请输入QQ号",
+                            class: if field_errors.read().contains_key("qq") { "input-error" },
                             value: "{qq}",
-                            oninput: move |evt| qq.set(evt.value().clone())
+                            oninput: move |evt| {
+                                qq.set(evt.value().clone());
+                                field_errors.write().remove("qq");
+                            }
+                        }
+                        if let Some(msg) = field_errors.read().get("qq") {
+                            div { class: "field-error", "{msg}" }
                         }
                     }
 
@@ -93,8 +165,15 @@ pub fn Register() -> Element {
                             id: "nickname",
                             name: "nickname",
                             placeholder: "请输入昵称",
+                            class: if field_errors.read().contains_key("nickname") { "input-error" },
                             value: "{nickname}",
-                            oninput: move |evt| nickname.set(evt.value().clone())
+                            oninput: move |evt| {
+                                nickname.set(evt.value().clone());
+                                field_errors.write().remove("nickname");
+                            }
+                        }
+                        if let Some(msg) = field_errors.read().get("nickname") {
+                            div { class: "field-error", "{msg}" }
                         }
                     }
 
@@ -104,9 +183,16 @@ pub fn Register() -> Element {
                             r#type: "password",
                             id: "password",
                             name: "password",
-                            placeholder: "请输入密码（至少6位）",
+                            placeholder: "请输入密码（至少{PASSWORD_MIN_LEN}位）",
+                            class: if field_errors.read().contains_key("password") { "input-error" },
                             value: "{password}",
-                            oninput: move |evt| password.set(evt.value().clone())
+                            oninput: move |evt| {
+                                password.set(evt.value().clone());
+                                field_errors.write().remove("password");
+                            }
+                        }
+                        if let Some(msg) = field_errors.read().get("password") {
+                            div { class: "field-error", "{msg}" }
                         }
                     }
 
@@ -117,8 +203,15 @@ pub fn Register() -> Element {
                             id: "confirm_password",
                             name: "confirm_password",
                             placeholder: "请再次输入密码",
+                            class: if field_errors.read().contains_key("confirm_password") { "input-error" },
                             value: "{confirm_password}",
-                            oninput: move |evt| confirm_password.set(evt.value().clone())
+                            oninput: move |evt| {
+                                confirm_password.set(evt.value().clone());
+                                field_errors.write().remove("confirm_password");
+                            }
+                        }
+                        if let Some(msg) = field_errors.read().get("confirm_password") {
+                            div { class: "field-error", "{msg}" }
                         }
                     }
 
@@ -133,10 +226,33 @@ pub fn Register() -> Element {
                         }
                     }
 
+                    div { class: "form-group",
+                        label { r#for: "email", "邮箱：" }
+                        input {
+                            r#type: "email",
+                            id: "email",
+                            name: "email",
+                            placeholder: "选填，用于接收通知",
+                            class: if field_errors.read().contains_key("email") { "input-error" },
+                            value: "{email}",
+                            oninput: move |evt| {
+                                email.set(evt.value().clone());
+                                field_errors.write().remove("email");
+                            }
+                        }
+                        if let Some(msg) = field_errors.read().get("email") {
+                            div { class: "field-error", "{msg}" }
+                        }
+                    }
+
                     if let Some(err) = error.read().as_ref() {
                         div { class: "error-message", "{err}" }
                     }
 
+                    if *verifying.read() {
+                        div { class: "loading-message", "正在进行人机验证，请稍候..." }
+                    }
+
                     if *success.read() {
                         div { class: "success-message",
                             "注册成功！"
@@ -145,7 +261,12 @@ pub fn Register() -> Element {
                     }
 
                     div { class: "form-actions",
-                        button { r#type: "submit", class: "btn-primary", "注册" }
+                        button {
+                            r#type: "submit",
+                            class: "btn-primary",
+                            disabled: *verifying.read(),
+                            "注册"
+                        }
                         Link { to: crate::Route::Login {},
                             button { r#type: "button", class: "btn-secondary", "返回登录" }
                         }
@@ -155,3 +276,59 @@ pub fn Register() -> Element {
         }
     }
 }
+
+/// 分批求解注册挑战，批次之间让出主线程
+async fn solve_challenge(nonce: &str, difficulty: u32) -> String {
+    let mut start = 0u64;
+    loop {
+        if let Some(solution) = pow::solve_range(nonce, difficulty, start, POW_BATCH_SIZE) {
+            return solution;
+        }
+        start += POW_BATCH_SIZE;
+        TimeoutFuture::new(0).await;
+    }
+}
+
+/// 校验注册表单，返回每个出错字段的提示，全部通过时为空
+fn validate_fields(
+    qq: &str,
+    nickname: &str,
+    password: &str,
+    confirm_password: &str,
+    email: &str,
+) -> HashMap<&'static str, String> {
+    let mut errors = HashMap::new();
+
+    if qq.is_empty() {
+        errors.insert("qq", "请输入QQ号".to_string());
+    } else if !qq.chars().all(|c| c.is_ascii_digit()) {
+        errors.insert("qq", "QQ号只能包含数字".to_string());
+    } else if !(QQ_MIN_DIGITS..=QQ_MAX_DIGITS).contains(&qq.len()) {
+        errors.insert(
+            "qq",
+            format!("QQ号长度应为{}-{}位", QQ_MIN_DIGITS, QQ_MAX_DIGITS),
+        );
+    }
+
+    if nickname.is_empty() {
+        errors.insert("nickname", "请输入昵称".to_string());
+    } else if nickname.chars().count() > NICKNAME_MAX_CHARS {
+        errors.insert(
+            "nickname",
+            format!("昵称不能超过{}个字符", NICKNAME_MAX_CHARS),
+        );
+    }
+
+    if password.len() < PASSWORD_MIN_LEN {
+        errors.insert("password", format!("密码长度至少{}位", PASSWORD_MIN_LEN));
+    }
+    if password != confirm_password {
+        errors.insert("confirm_password", "两次输入的密码不一致".to_string());
+    }
+
+    if !email.is_empty() && !email.contains('@') {
+        errors.insert("email", "邮箱格式不正确".to_string());
+    }
+
+    errors
+}
@@ -1,6 +1,7 @@
 use crate::api;
+use crate::components::{use_async_state, use_confirm, use_toast, AsyncStatus};
+use crate::models::RoleMember;
 use dioxus::prelude::*;
-use gloo_timers::future::TimeoutFuture;
 
 #[component]
 pub fn Roles() -> Element {
@@ -8,79 +9,35 @@ pub fn Roles() -> Element {
     let mut permissions = use_signal(Vec::<api::Permission>::new);
     let mut selected_role = use_signal(|| None::<api::Role>);
     let mut role_permissions = use_signal(Vec::<String>::new);
+    let mut role_members = use_signal(Vec::<RoleMember>::new);
+    // 正在查看的权限及拥有它的角色
+    let mut permission_roles = use_signal(|| None::<(String, Vec<api::Role>)>);
+    // 因仍有用户使用而被阻止删除的角色及提示
+    let mut delete_blocked = use_signal(|| None::<(i64, String)>);
     let mut users = use_signal(Vec::<api::User>::new);
 
     let mut new_role_name = use_signal(String::new);
     let mut new_role_desc = use_signal(String::new);
-    let mut selected_user_qq = use_signal(String::new);
+    let mut selected_user_qqs = use_signal(Vec::<String>::new);
     let mut selected_role_id = use_signal(|| 0i64);
 
-    let mut error = use_signal(|| None::<String>);
-    let mut success = use_signal(|| None::<String>);
-    let mut loading = use_signal(|| false);
-    let loading_visible = use_signal(|| false);
-
-    {
-        let loading = loading;
-        let mut loading_visible = loading_visible;
-        use_effect(move || {
-            if *loading.read() {
-                let loading = loading;
-                let mut loading_visible = loading_visible;
-                spawn(async move {
-                    TimeoutFuture::new(180).await;
-                    if *loading.read() {
-                        loading_visible.set(true);
-                    }
-                });
-            } else {
-                loading_visible.set(false);
-            }
-        });
-    }
-
-    // 自动清除成功消息
-    {
-        let success = success;
-        use_effect(move || {
-            if success.read().is_some() {
-                let mut success = success;
-                spawn(async move {
-                    TimeoutFuture::new(3000).await;
-                    success.set(None);
-                });
-            }
-        });
-    }
-
-    // 自动清除错误消息
-    {
-        let error = error;
-        use_effect(move || {
-            if error.read().is_some() {
-                let mut error = error;
-                spawn(async move {
-                    TimeoutFuture::new(4000).await;
-                    error.set(None);
-                });
-            }
-        });
-    }
+    let mut toast = use_toast();
+    let mut confirmer = use_confirm();
+    let mut state = use_async_state();
 
     // 加载角色列表
     let load_roles = move || {
         spawn(async move {
-            loading.set(true);
+            state.start();
             match api::get_roles().await {
                 Ok(role_list) => {
                     roles.set(role_list);
-                    error.set(None);
                 }
                 Err(e) => {
-                    error.set(Some(format!("加载角色失败: {}", e)));
+                    toast.error(format!("加载角色失败: {}", e));
                 }
             }
-            loading.set(false);
+            state.finish();
         });
     };
 
@@ -92,7 +49,7 @@ pub fn Roles() -> Element {
                     permissions.set(perm_list);
                 }
                 Err(e) => {
-                    error.set(Some(format!("加载权限失败: {}", e)));
+                    toast.error(format!("加载权限失败: {}", e));
                 }
             }
         });
@@ -106,25 +63,41 @@ pub fn Roles() -> Element {
                     users.set(user_list);
                 }
                 Err(e) => {
-                    error.set(Some(format!("加载用户失败: {}", e)));
+                    toast.error(format!("加载用户失败: {}", e));
                 }
             }
         });
     };
 
-    // 选择角色并加载其权限
+    // 加载角色成员
+    let load_role_members = move |role_id: i64| {
+        spawn(async move {
+            match api::get_role_users(role_id).await {
+                Ok(members) => {
+                    role_members.set(members);
+                }
+                Err(e) => {
+                    toast.error(format!("加载角色成员失败: {}", e));
+                }
+            }
+        });
+    };
+
+    // 选择角色并加载其权限和成员
     let mut select_role = move |role: api::Role| {
         let role_id = role.role_id;
         selected_role.set(Some(role));
+        role_members.set(Vec::new());
+        delete_blocked.set(None);
+        load_role_members(role_id);
 
         spawn(async move {
             match api::get_role_permissions(role_id).await {
                 Ok(perms) => {
                     role_permissions.set(perms);
-                    error.set(None);
                 }
                 Err(e) => {
-                    error.set(Some(format!("加载角色权限失败: {}", e)));
+                    toast.error(format!("加载角色权限失败: {}", e));
                 }
             }
         });
@@ -136,12 +109,12 @@ pub fn Roles() -> Element {
         let desc = new_role_desc.read().clone();
 
         if name.is_empty() {
-            error.set(Some("角色名称不能为空".to_string()));
+            toast.error("角色名称不能为空");
             return;
         }
 
         spawn(async move {
-            loading.set(true);
+            state.start();
             match api::create_role(
                 name.clone(),
                 if desc.is_empty() { None } else { Some(desc) },
@@ -149,39 +122,61 @@ pub fn Roles() -> Element {
             .await
             {
                 Ok(msg) => {
-                    success.set(Some(msg));
-                    error.set(None);
+                    toast.success(msg);
                     new_role_name.set(String::new());
                     new_role_desc.set(String::new());
                     load_roles();
                 }
                 Err(e) => {
-                    error.set(Some(format!("创建角色失败: {}", e)));
-                    loading.set(false);
+                    toast.error(format!("创建角色失败: {}", e));
+                    state.finish();
                 }
             }
         });
     };
 
-    // 删除角色
-    let delete_role = move |role_id: i64| {
+    // 删除角色，强制删除时提示受影响的用户会改为成员角色
+    let delete_role = move |role_id: i64, force: bool| {
         spawn(async move {
-            loading.set(true);
-            match api::delete_role(role_id).await {
+            let message = if force {
+                "确定要强制删除此角色吗？使用该角色的用户将改为成员角色。"
+            } else {
+                "确定要删除此角色吗？"
+            };
+            if !confirmer.confirm(message).await {
+                return;
+            }
+            state.start();
+            match api::delete_role(role_id, force).await {
                 Ok(msg) => {
-                    success.set(Some(msg));
-                    error.set(None);
+                    toast.success(msg);
+                    delete_blocked.set(None);
                     selected_role.set(None);
                     load_roles();
+                    load_users();
                 }
-                Err(e) => {
-                    error.set(Some(format!("删除角色失败: {}", e)));
-                    loading.set(false);
+                Err(api::DeleteRoleError::InUse(msg)) => {
+                    delete_blocked.set(Some((role_id, msg)));
+                    state.finish();
+                }
+                Err(api::DeleteRoleError::Failed(e)) => {
+                    toast.error(format!("删除角色失败: {}", e));
+                    state.finish();
                 }
             }
         });
     };
 
+    // 查看拥有某个权限的所有角色
+    let view_permission_roles = move |permission_name: String| {
+        spawn(async move {
+            match api::get_permission_roles(&permission_name).await {
+                Ok(role_list) => permission_roles.set(Some((permission_name, role_list))),
+                Err(e) => toast.error(format!("加载权限对应角色失败: {}", e)),
+            }
+        });
+    };
+
     // 给角色分配权限
     let grant_permission = move |permission_name: String| {
         let Some(role) = selected_role.read().clone() else {
@@ -190,21 +185,20 @@ pub fn Roles() -> Element {
         let role_id = role.role_id;
 
         spawn(async move {
-            loading.set(true);
+            state.start();
             match api::grant_permission_to_role(role_id, permission_name).await {
                 Ok(msg) => {
-                    success.set(Some(msg));
-                    error.set(None);
+                    toast.success(msg);
                     // 重新加载角色权限
                     if let Ok(perms) = api::get_role_permissions(role_id).await {
                         role_permissions.set(perms)
                     }
                 }
                 Err(e) => {
-                    error.set(Some(format!("分配权限失败: {}", e)));
+                    toast.error(format!("分配权限失败: {}", e));
                 }
             }
-            loading.set(false);
+            state.finish();
         });
     };
 
@@ -216,51 +210,66 @@ pub fn Roles() -> Element {
         let role_id = role.role_id;
 
         spawn(async move {
-            loading.set(true);
+            state.start();
             match api::revoke_permission_from_role(role_id, permission_name).await {
                 Ok(msg) => {
-                    success.set(Some(msg));
-                    error.set(None);
+                    toast.success(msg);
                     // 重新加载角色权限
                     if let Ok(perms) = api::get_role_permissions(role_id).await {
                         role_permissions.set(perms)
                     }
                 }
                 Err(e) => {
-                    error.set(Some(format!("移除权限失败: {}", e)));
+                    toast.error(format!("移除权限失败: {}", e));
                 }
             }
-            loading.set(false);
+            state.finish();
         });
     };
 
-    // 给用户分配角色
+    // 给选中的用户批量分配角色
     let mut assign_role = move || {
-        let user_qq = selected_user_qq.read().clone();
+        let user_qqs = selected_user_qqs.read().clone();
         let role_id = *selected_role_id.read();
 
-        if user_qq.is_empty() || role_id == 0 {
-            error.set(Some("请选择用户和角色".to_string()));
+        if user_qqs.is_empty() || role_id == 0 {
+            toast.error("请选择用户和角色");
             return;
         }
 
         spawn(async move {
-            loading.set(true);
-            match api::assign_role_to_user(user_qq.clone(), role_id).await {
-                Ok(msg) => {
-                    success.set(Some(format!("{} - 已为用户 {} 分配角色", msg, user_qq)));
-                    error.set(None);
-                    selected_user_qq.set(String::new());
+            state.start();
+            match api::assign_role_bulk(role_id, user_qqs).await {
+                Ok(resp) => {
+                    if resp.skipped > 0 {
+                        let skipped: Vec<String> = resp
+                            .results
+                            .iter()
+                            .filter(|line| line.status != "assigned")
+                            .map(|line| match line.status.as_str() {
+                                "skipped_protected" => {
+                                    format!("{}（受保护的管理员）", line.user_qq)
+                                }
+                                _ => format!("{}（用户不存在）", line.user_qq),
+                            })
+                            .collect();
+                        toast.info(format!("{}：{}", resp.message, skipped.join("、")));
+                    } else {
+                        toast.success(resp.message);
+                    }
+                    selected_user_qqs.set(Vec::new());
                     selected_role_id.set(0);
                     // 刷新用户列表以显示更新后的角色
                     load_users();
+                    if let Some(role) = selected_role.peek().as_ref() {
+                        load_role_members(role.role_id);
+                    }
                 }
                 Err(e) => {
-                    error.set(Some(format!("分配角色失败: {}", e)));
-                    success.set(None);
+                    toast.error(format!("分配角色失败: {}", e));
                 }
             }
-            loading.set(false);
+            state.finish();
         });
     };
 
@@ -283,25 +292,12 @@ pub fn Roles() -> Element {
                         load_permissions();
                         load_users();
                     },
-                    disabled: *loading.read(),
+                    disabled: state.is_loading(),
                     "刷新"
                 }
             }
 
-            if *loading_visible.read() {
-                div { class: "loading-message", "加载中..." }
-            }
-
-            // Toast 通知容器
-            div { class: "toast-container",
-                if let Some(err) = error.read().as_ref() {
-                    div { class: "toast toast-error", "{err}" }
-                }
-
-                if let Some(succ) = success.read().as_ref() {
-                    div { class: "toast toast-success", "{succ}" }
-                }
-            }
+            AsyncStatus { state, loading_text: "加载中..." }
 
             div { class: "permission-layout",
                 // 左侧：角色列表和创建表单
@@ -355,7 +351,7 @@ pub fn Roles() -> Element {
                     button {
                         class: "btn-primary",
                         onclick: move |_| create_role(),
-                        disabled: *loading.read(),
+                        disabled: state.is_loading(),
                         "创建角色"
                     }
                 }
@@ -372,13 +368,27 @@ pub fn Roles() -> Element {
                             class: "btn-danger btn-small",
                             onclick: {
                                 let role_id = role.role_id;
-                                move |_| delete_role(role_id)
+                                move |_| delete_role(role_id, false)
                             },
-                            disabled: *loading.read(),
+                            disabled: state.is_loading(),
                             style: "margin-bottom: 1.5rem; display: inline-flex; align-items: center; gap: 0.375rem;",
                             "删除此角色"
                         }
 
+                        if let Some((blocked_id, msg)) = delete_blocked.read().clone() {
+                            if blocked_id == role.role_id {
+                                div { class: "warning-message",
+                                    p { "{msg}" }
+                                    button {
+                                        class: "btn-danger btn-small",
+                                        onclick: move |_| delete_role(blocked_id, true),
+                                        disabled: state.is_loading(),
+                                        "仍然删除（这些用户将改为成员角色）"
+                                    }
+                                }
+                            }
+                        }
+
                         h3 {
                             style: "display: flex; align-items: center; gap: 0.5rem;",
                             "权限配置"
@@ -388,24 +398,30 @@ pub fn Roles() -> Element {
                                 {
                                     let has_perm = role_permissions.read().contains(&perm.name);
                                     let perm_name = perm.name.clone();
+                                    let view_name = perm.name.clone();
 
                                     rsx! {
                                         div {
                                             key: "{perm.name}",
                                             class: "permission-item",
-                                            span { "{perm.name}" }
+                                            span {
+                                                style: "cursor: pointer;",
+                                                title: "查看拥有此权限的角色",
+                                                onclick: move |_| view_permission_roles(view_name.clone()),
+                                                "{perm.name}"
+                                            }
                                             if has_perm {
                                                 button {
                                                     class: "btn-small btn-danger",
                                                     onclick: move |_| revoke_permission(perm_name.clone()),
-                                                    disabled: *loading.read(),
+                                                    disabled: state.is_loading(),
                                                     "移除"
                                                 }
                                             } else {
                                                 button {
                                                     class: "btn-small btn-success",
                                                     onclick: move |_| grant_permission(perm_name.clone()),
-                                                    disabled: *loading.read(),
+                                                    disabled: state.is_loading(),
                                                     "添加"
                                                 }
                                             }
@@ -415,6 +431,29 @@ pub fn Roles() -> Element {
                             }
                         }
 
+                        if let Some((perm_name, perm_roles)) = permission_roles.read().as_ref() {
+                            div { class: "current-permissions",
+                                h3 {
+                                    style: "display: flex; align-items: center; gap: 0.5rem;",
+                                    "拥有「{perm_name}」的角色（{perm_roles.len()}）"
+                                    button {
+                                        class: "btn-small btn-secondary",
+                                        onclick: move |_| permission_roles.set(None),
+                                        "关闭"
+                                    }
+                                }
+                                if perm_roles.is_empty() {
+                                    p { "暂无角色拥有该权限" }
+                                } else {
+                                    ul {
+                                        for role in perm_roles.iter() {
+                                            li { key: "{role.role_id}", "{role.name}" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
                         div { class: "current-permissions",
                             h3 {
                                 style: "display: flex; align-items: center; gap: 0.5rem;",
@@ -431,6 +470,22 @@ pub fn Roles() -> Element {
                                 }
                             }
                         }
+
+                        div { class: "current-permissions",
+                            h3 {
+                                style: "display: flex; align-items: center; gap: 0.5rem;",
+                                "拥有此角色的用户（{role_members.read().len()}）"
+                            }
+                            if role_members.read().is_empty() {
+                                p { "暂无用户使用该角色" }
+                            } else {
+                                ul {
+                                    for member in role_members.read().iter() {
+                                        li { key: "{member.qq}", "{member.nickname} ({member.qq})" }
+                                    }
+                                }
+                            }
+                        }
                     } else {
                         div {
                             style: "display: flex; flex-direction: column; align-items: center; justify-content: center; padding: 4rem 2rem; color: #999;",
@@ -448,17 +503,30 @@ pub fn Roles() -> Element {
                     div { style: "margin-bottom: 0;",
                         label {
                             style: "display: block; margin-bottom: 0.625rem; font-weight: 600; color: #333; font-size: 14px;",
-                            "选择用户"
+                            "选择用户（已选 {selected_user_qqs.read().len()} 人）"
                         }
-                        select {
-                            style: "width: 100%; padding: 0.875rem 1.125rem; border: 2px solid #e0e0e0; border-radius: 10px; font-size: 14px; background: #f8f9fa; color: #333;",
-                            value: "{selected_user_qq}",
-                            onchange: move |evt| selected_user_qq.set(evt.value().clone()),
-                            option { value: "", "-- 请选择用户 --" }
+                        div { class: "user-multi-select",
                             for user in users.read().iter() {
-                                option {
+                                label {
                                     key: "{user.qq}",
-                                    value: "{user.qq}",
+                                    class: "checkbox-label",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: selected_user_qqs.read().contains(&user.qq),
+                                        onchange: {
+                                            let qq = user.qq.clone();
+                                            move |evt: Event<FormData>| {
+                                                let mut selected = selected_user_qqs.write();
+                                                if evt.checked() {
+                                                    if !selected.contains(&qq) {
+                                                        selected.push(qq.clone());
+                                                    }
+                                                } else {
+                                                    selected.retain(|item| item != &qq);
+                                                }
+                                            }
+                                        },
+                                    }
                                     "{user.nickname} ({user.qq})"
                                 }
                             }
@@ -491,7 +559,7 @@ pub fn Roles() -> Element {
                         class: "btn-primary",
                         style: "height: 46px; padding: 0 2rem; white-space: nowrap;",
                         onclick: move |_| assign_role(),
-                        disabled: *loading.read(),
+                        disabled: state.is_loading(),
                         "分配角色"
                     }
                 }
@@ -1,17 +1,30 @@
 use crate::api;
-use crate::components::use_current_user;
+use crate::components::{use_current_user, use_toast, ExpiryBadge};
+use crate::models::ShopSeller;
 use dioxus::prelude::*;
 use gloo_timers::future::TimeoutFuture;
 
+const PAGE_SIZE: i32 = 12;
+
 #[component]
 pub fn Shop() -> Element {
     let mut items = use_signal(Vec::<api::ShopItem>::new);
+    let mut total = use_signal(|| 0i64);
+    let mut page = use_signal(|| 0i32);
     let mut search_keyword = use_signal(String::new);
-    let mut error = use_signal(|| None::<String>);
-    let mut success = use_signal(|| None::<String>);
+    let mut applied_keyword = use_signal(String::new);
+    let mut sellers = use_signal(Vec::<ShopSeller>::new);
+    let mut selected_seller = use_signal(String::new);
+    // 价格区间输入，点击搜索后与关键字一起生效
+    let mut min_price_input = use_signal(String::new);
+    let mut max_price_input = use_signal(String::new);
+    let mut applied_price_range = use_signal(|| (None::<f64>, None::<f64>));
+    let mut toast = use_toast();
     let mut loading = use_signal(|| false);
     let loading_visible = use_signal(|| false);
     let current_user = use_current_user();
+    // 请求代次：每次发起加载前递增，过期的响应不再写回状态
+    let mut request_gen = use_signal(|| 0u64);
 
     {
         let loading = loading;
@@ -32,130 +45,146 @@ pub fn Shop() -> Element {
         });
     }
 
-    // 自动清除成功消息
-    {
-        let success = success;
-        use_effect(move || {
-            if success.read().is_some() {
-                let mut success = success;
-                spawn(async move {
-                    TimeoutFuture::new(3000).await;
-                    success.set(None);
-                });
-            }
-        });
-    }
-
-    // 自动清除错误消息
-    {
-        let error = error;
-        use_effect(move || {
-            if error.read().is_some() {
-                let mut error = error;
-                spawn(async move {
-                    TimeoutFuture::new(4000).await;
-                    error.set(None);
-                });
-            }
-        });
-    }
-
-    // 搜索商品
-    let search_items = move || {
-        let keyword = search_keyword.read().clone();
-        let keyword_lower = keyword.trim().to_lowercase();
+    // 按当前页码和已应用的关键字从服务端加载商品
+    let mut load_items = move || {
+        let current_page = *page.peek();
+        let keyword = applied_keyword.peek().clone();
+        let seller = selected_seller.peek().clone();
+        let price_range = *applied_price_range.peek();
+        let generation = *request_gen.peek() + 1;
+        request_gen.set(generation);
 
         spawn(async move {
             loading.set(true);
-            success.set(None);
-            match api::get_shop_items(None).await {
-                Ok(item_list) => {
-                    let filtered = filter_shop_items(item_list, &keyword_lower);
-                    let count = filtered.len();
-                    items.set(filtered);
-                    error.set(None);
-                    if keyword_lower.is_empty() {
-                        success.set(None);
-                    } else {
-                        success.set(Some(format!("共找到 {} 件匹配商品", count)));
-                    }
+            let offset = current_page * PAGE_SIZE;
+            let keyword_opt = if keyword.is_empty() {
+                None
+            } else {
+                Some(keyword.clone())
+            };
+            let seller_opt = if seller.is_empty() {
+                None
+            } else {
+                Some(seller.clone())
+            };
+            let result =
+                api::get_shop_items_page(keyword_opt, seller_opt, price_range, PAGE_SIZE, offset)
+                    .await;
+            if *request_gen.peek() != generation {
+                return;
+            }
+            match result {
+                Ok(resp) => {
+                    items.set(resp.items);
+                    total.set(resp.total);
                 }
                 Err(e) => {
-                    error.set(Some(format!("加载失败: {}", e)));
+                    toast.error(format!("加载失败: {}", e));
                 }
             }
             loading.set(false);
         });
     };
 
-    // 初始加载所有商品
-    let load_all_items = move || {
-        spawn(async move {
-            loading.set(true);
-            match api::get_shop_items(None).await {
-                Ok(item_list) => {
-                    items.set(item_list);
-                    error.set(None);
-                }
-                Err(e) => {
-                    error.set(Some(format!("加载失败: {}", e)));
-                }
+    // 搜索商品
+    let mut search_items = move || {
+        let price_range = match (
+            parse_price_bound(&min_price_input.read()),
+            parse_price_bound(&max_price_input.read()),
+        ) {
+            (Ok(Some(min)), Ok(Some(max))) if min > max => {
+                toast.error("最低价格不能高于最高价格");
+                return;
             }
-            loading.set(false);
-        });
+            (Ok(min), Ok(max)) => (min, max),
+            (Err(message), _) | (_, Err(message)) => {
+                toast.error(message);
+                return;
+            }
+        };
+        let keyword = search_keyword.read().trim().to_string();
+        applied_keyword.set(keyword);
+        applied_price_range.set(price_range);
+        page.set(0);
+        load_items();
+    };
+
+    let prev_page = move |_| {
+        let current = *page.read();
+        if current > 0 {
+            page.set(current - 1);
+            load_items();
+        }
+    };
+
+    let next_page = move |_| {
+        let current = *page.read();
+        let total_pages = calc_total_pages(*total.read());
+        if current < total_pages - 1 {
+            page.set(current + 1);
+            load_items();
+        }
     };
 
     let mut purchase = move |item_id: i64| {
         let session = current_user.read().clone();
         let Some(user) = session else {
-            error.set(Some("请先登录后再购买商品".to_string()));
+            toast.error("请先登录后再购买商品");
             return;
         };
 
         let buyer = user.qq.clone();
-        let keyword_lower = search_keyword.read().clone().trim().to_lowercase();
+        let idempotency_key = uuid::Uuid::new_v4().to_string();
 
         spawn(async move {
             loading.set(true);
-            match api::purchase_shop_item(buyer, item_id, 1).await {
+            match api::purchase_shop_item(buyer, item_id, 1, &idempotency_key).await {
                 Ok(message) => {
-                    success.set(Some(message));
-                    error.set(None);
-                    // 购买后重新加载,保持当前的搜索状态
-                    if keyword_lower.is_empty() {
-                        match api::get_shop_items(None).await {
-                            Ok(item_list) => {
-                                items.set(item_list);
-                            }
-                            Err(e) => {
-                                error.set(Some(format!("刷新商品失败: {}", e)));
-                            }
-                        }
-                    } else {
-                        match api::get_shop_items(None).await {
-                            Ok(item_list) => {
-                                let filtered = filter_shop_items(item_list, &keyword_lower);
-                                items.set(filtered);
-                            }
-                            Err(e) => {
-                                error.set(Some(format!("刷新商品失败: {}", e)));
-                            }
-                        }
-                    }
+                    toast.success(message);
+                    // 购买后重新加载当前页,保持当前的搜索状态
+                    load_items();
                 }
                 Err(e) => {
-                    error.set(Some(format!("购买失败: {}", e)));
+                    toast.error(format!("购买失败: {}", e));
                 }
             }
             loading.set(false);
         });
     };
 
+    // 按卖家筛选
+    let mut filter_seller = move |seller: String| {
+        selected_seller.set(seller);
+        page.set(0);
+        load_items();
+    };
+
     // 初始加载
     use_effect(move || {
-        load_all_items();
+        load_items();
+    });
+
+    // 加载有在售商品的卖家，供筛选下拉框使用
+    use_effect(move || {
+        spawn(async move {
+            match api::get_shop_sellers().await {
+                Ok(list) => sellers.set(list),
+                Err(e) => toast.error(format!("加载卖家列表失败: {}", e)),
+            }
+        });
     });
 
+    let total_count = *total.read();
+    let current_page_value = *page.read();
+    let total_pages = calc_total_pages(total_count);
+    let has_prev_page = current_page_value > 0;
+    let has_next_page = current_page_value < total_pages - 1;
+    let pagination_label = if total_pages > 0 {
+        format!("第 {} / {} 页", current_page_value + 1, total_pages)
+    } else {
+        "第 0 / 0 页".to_string()
+    };
+
     rsx! {
         div { class: "page-container",
             h1 {
@@ -172,6 +201,26 @@ pub fn Shop() -> Element {
                         oninput: move |evt| search_keyword.set(evt.value().clone()),
                         disabled: *loading.read()
                     }
+                    input {
+                        r#type: "number",
+                        min: "0",
+                        step: "0.01",
+                        style: "width: 7rem;",
+                        placeholder: "最低价格",
+                        value: "{min_price_input}",
+                        oninput: move |evt| min_price_input.set(evt.value().clone()),
+                        disabled: *loading.read()
+                    }
+                    input {
+                        r#type: "number",
+                        min: "0",
+                        step: "0.01",
+                        style: "width: 7rem;",
+                        placeholder: "最高价格",
+                        value: "{max_price_input}",
+                        oninput: move |evt| max_price_input.set(evt.value().clone()),
+                        disabled: *loading.read()
+                    }
                     button {
                         class: "btn-primary",
                         onclick: move |_| search_items(),
@@ -180,6 +229,18 @@ pub fn Shop() -> Element {
                     }
                 }
 
+                select {
+                    value: "{selected_seller}",
+                    disabled: *loading.read(),
+                    onchange: move |evt| filter_seller(evt.value()),
+                    option { value: "", "全部卖家" }
+                    for seller in sellers.read().iter() {
+                        option { key: "{seller.qq}", value: "{seller.qq}",
+                            "{seller.nickname} ({seller.item_count})"
+                        }
+                    }
+                }
+
                 Link { to: crate::Route::MyShop {},
                     button {
                         class: "btn-secondary",
@@ -196,7 +257,7 @@ pub fn Shop() -> Element {
                 }
                 button {
                     class: "btn-secondary",
-                    onclick: move |_| load_all_items(),
+                    onclick: move |_| load_items(),
                     disabled: *loading.read(),
                     "刷新"
                 }
@@ -206,17 +267,6 @@ pub fn Shop() -> Element {
                 div { class: "loading-message", "加载中..." }
             }
 
-            // Toast 通知容器
-            div { class: "toast-container",
-                if let Some(err) = error.read().as_ref() {
-                    div { class: "toast toast-error", "{err}" }
-                }
-
-                if let Some(succ) = success.read().as_ref() {
-                    div { class: "toast toast-success", "{succ}" }
-                }
-            }
-
             if items.read().is_empty() {
                 div {
                     style: "display: flex; flex-direction: column; align-items: center; justify-content: center; padding: 6rem 2rem; color: #999;",
@@ -239,6 +289,9 @@ pub fn Shop() -> Element {
                                 } else {
                                     span { class: "badge badge-warning", "已售罄" }
                                 }
+                                if let Some(expires_at) = &item.expires_at {
+                                    ExpiryBadge { expires_at: expires_at.clone() }
+                                }
                             }
                             button {
                                 class: "btn-primary",
@@ -265,25 +318,47 @@ pub fn Shop() -> Element {
                 div {
                     class: "stats",
                     style: "display: flex; align-items: center; justify-content: center; gap: 0.5rem;",
-                    "共 {items.read().len()} 件商品"
+                    "共 {total_count} 件商品"
+                }
+
+                div { class: "pagination-bar",
+                    span { "{pagination_label}" }
+                    div { class: "pagination-actions",
+                        button {
+                            class: "btn-secondary",
+                            onclick: prev_page,
+                            disabled: !has_prev_page || *loading.read(),
+                            "上一页"
+                        }
+                        button {
+                            class: "btn-secondary",
+                            onclick: next_page,
+                            disabled: !has_next_page || *loading.read(),
+                            "下一页"
+                        }
+                    }
                 }
             }
         }
     }
 }
 
-fn filter_shop_items(items: Vec<api::ShopItem>, keyword: &str) -> Vec<api::ShopItem> {
-    if keyword.is_empty() {
-        return items;
+fn calc_total_pages(total: i64) -> i32 {
+    if total <= 0 {
+        0
+    } else {
+        (((total - 1) / PAGE_SIZE as i64) + 1) as i32
     }
+}
 
-    items
-        .into_iter()
-        .filter(|item| {
-            let name = item.name.to_lowercase();
-            let location = item.location.to_lowercase();
-            let seller = item.seller.to_lowercase();
-            name.contains(keyword) || location.contains(keyword) || seller.contains(keyword)
-        })
-        .collect()
+/// 解析价格区间的一端，留空表示不限
+fn parse_price_bound(raw: &str) -> Result<Option<f64>, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    match raw.parse::<f64>() {
+        Ok(price) if price.is_finite() && price >= 0.0 => Ok(Some(price)),
+        _ => Err("价格必须是非负数".to_string()),
+    }
 }
@@ -10,14 +10,30 @@ enum TransactionType {
 
 #[component]
 pub fn ShopTransactions() -> Element {
-    let mut purchases = use_signal(Vec::<api::ShopLog>::new);
-    let mut sales = use_signal(Vec::<api::ShopLog>::new);
+    let mut purchases_total = use_signal(|| 0i64);
+    let mut sales_total = use_signal(|| 0i64);
     let mut all_transactions = use_signal(Vec::<TransactionType>::new);
     let mut error = use_signal(|| None::<String>);
     let mut loading = use_signal(|| false);
     let mut current_page = use_signal(|| 1);
-    let page_size = 10;
+    let page_size: usize = 10;
     let current_user = use_current_user();
+    let mut detail = use_signal(|| None::<api::ShopLog>);
+
+    // 查看单条交易详情
+    let view_detail = move |id: i64| {
+        spawn(async move {
+            match api::get_transaction(id).await {
+                Ok(transaction) => {
+                    detail.set(Some(transaction));
+                    error.set(None);
+                }
+                Err(e) => {
+                    error.set(Some(format!("获取交易详情失败: {}", e)));
+                }
+            }
+        });
+    };
 
     let mut load_transactions = move || {
         let session = current_user.read().clone();
@@ -27,13 +43,15 @@ pub fn ShopTransactions() -> Element {
         };
 
         let user_qq = user.qq.clone();
+        // 购买和销售各取当前页，合并后一起显示
+        let offset = (*current_page.peek() - 1) * page_size;
 
         spawn(async move {
             loading.set(true);
-            match api::get_shop_transactions(&user_qq).await {
+            match api::get_shop_transactions(&user_qq, page_size as i32, offset as i32).await {
                 Ok(transactions) => {
-                    purchases.set(transactions.purchases.clone());
-                    sales.set(transactions.sales.clone());
+                    purchases_total.set(transactions.purchases_total);
+                    sales_total.set(transactions.sales_total);
 
                     // 合并并按时间排序
                     let mut merged: Vec<TransactionType> = Vec::new();
@@ -72,6 +90,8 @@ pub fn ShopTransactions() -> Element {
         load_transactions();
     });
 
+    let total_count = *purchases_total.read() + *sales_total.read();
+
     rsx! {
         div { class: "page-container",
             h1 { "我的交易记录" }
@@ -101,10 +121,9 @@ pub fn ShopTransactions() -> Element {
                     div { class: "empty-state", "暂无交易记录" }
                 } else {
                     {
-                        let total = all_transactions.read().len();
+                        let total = (*purchases_total.read()).max(*sales_total.read()) as usize;
                         let total_pages = total.div_ceil(page_size);
                         let current = *current_page.read();
-                        let start = (current - 1) * page_size;
 
                         rsx! {
                             table { class: "data-table",
@@ -117,10 +136,11 @@ pub fn ShopTransactions() -> Element {
                                         th { "交易对方" }
                                         th { "地点" }
                                         th { "交易时间" }
+                                        th { "操作" }
                                     }
                                 }
                                 tbody {
-                                    for (idx, transaction) in all_transactions.read().iter().enumerate().skip(start).take(page_size) {
+                                    for (idx, transaction) in all_transactions.read().iter().enumerate() {
                                         {
                                             match transaction {
                                                 TransactionType::Purchase(purchase) => rsx! {
@@ -135,6 +155,15 @@ pub fn ShopTransactions() -> Element {
                                                         td { "{purchase.seller}" }
                                                         td { "{purchase.location}" }
                                                         td { "{purchase.time}" }
+                                                        td {
+                                                            if let Some(id) = purchase.id {
+                                                                button {
+                                                                    class: "btn-small btn-info",
+                                                                    onclick: move |_| view_detail(id),
+                                                                    "详情"
+                                                                }
+                                                            }
+                                                        }
                                                     }
                                                 },
                                                 TransactionType::Sale(sale) => rsx! {
@@ -149,6 +178,15 @@ pub fn ShopTransactions() -> Element {
                                                         td { "{sale.buyer}" }
                                                         td { "{sale.location}" }
                                                         td { "{sale.time}" }
+                                                        td {
+                                                            if let Some(id) = sale.id {
+                                                                button {
+                                                                    class: "btn-small btn-info",
+                                                                    onclick: move |_| view_detail(id),
+                                                                    "详情"
+                                                                }
+                                                            }
+                                                        }
                                                     }
                                                 }
                                             }
@@ -166,6 +204,7 @@ pub fn ShopTransactions() -> Element {
                                         onclick: move |_| {
                                             if current > 1 {
                                                 current_page.set(current - 1);
+                                                load_transactions();
                                             }
                                         },
                                         "上一页"
@@ -181,6 +220,7 @@ pub fn ShopTransactions() -> Element {
                                         onclick: move |_| {
                                             if current < total_pages {
                                                 current_page.set(current + 1);
+                                                load_transactions();
                                             }
                                         },
                                         "下一页"
@@ -192,8 +232,51 @@ pub fn ShopTransactions() -> Element {
                 }
             }
 
+            if let Some(tx) = detail.read().as_ref() {
+                div { class: "info-section",
+                    h2 { "交易详情 #{tx.id.unwrap_or_default()}" }
+                    table { class: "data-table",
+                        tbody {
+                            tr {
+                                th { "商品名称" }
+                                td { "{tx.name}" }
+                            }
+                            tr {
+                                th { "数量" }
+                                td { "{tx.count}" }
+                            }
+                            tr {
+                                th { "价格" }
+                                td { "{tx.price} 元" }
+                            }
+                            tr {
+                                th { "买家" }
+                                td { "{tx.buyer}" }
+                            }
+                            tr {
+                                th { "卖家" }
+                                td { "{tx.seller}" }
+                            }
+                            tr {
+                                th { "地点" }
+                                td { "{tx.location}" }
+                            }
+                            tr {
+                                th { "交易时间" }
+                                td { "{tx.time}" }
+                            }
+                        }
+                    }
+                    button {
+                        class: "btn-secondary btn-small",
+                        onclick: move |_| detail.set(None),
+                        "关闭"
+                    }
+                }
+            }
+
             div { class: "stats",
-                "共 {purchases.read().len()} 条购买记录，{sales.read().len()} 条销售记录（共 {all_transactions.read().len()} 条）"
+                "共 {purchases_total} 条购买记录，{sales_total} 条销售记录（共 {total_count} 条）"
             }
         }
     }
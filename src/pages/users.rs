@@ -1,4 +1,6 @@
 use crate::api;
+use crate::components::use_current_user;
+use crate::models::EffectivePermission;
 use dioxus::prelude::*;
 use gloo_timers::future::TimeoutFuture;
 
@@ -6,9 +8,17 @@ use gloo_timers::future::TimeoutFuture;
 pub fn Users() -> Element {
     let mut users = use_signal(Vec::<api::User>::new);
     let mut search_keyword = use_signal(String::new);
+    // 按主角色筛选，None 表示全部角色
+    let mut role_filter = use_signal(|| None::<i64>);
+    let mut roles = use_signal(Vec::<api::Role>::new);
     let mut error = use_signal(|| None::<String>);
     let mut loading = use_signal(|| false);
     let loading_visible = use_signal(|| false);
+    // 正在查看权限的用户及其权限来源
+    let mut permission_view = use_signal(|| None::<(String, Vec<EffectivePermission>)>);
+    // 正在查看时间线的用户
+    let mut timeline_user = use_signal(|| None::<String>);
+    let current_user = use_current_user();
 
     {
         let loading = loading;
@@ -29,11 +39,13 @@ pub fn Users() -> Element {
         });
     }
 
-    // 加载所有用户
+    // 按当前角色和关键字筛选条件加载用户
     let load_users = move || {
+        let role_id = *role_filter.peek();
+        let keyword = search_keyword.peek().clone();
         spawn(async move {
             loading.set(true);
-            match api::get_users().await {
+            match api::get_users_filtered(role_id, &keyword).await {
                 Ok(user_list) => {
                     users.set(user_list);
                     error.set(None);
@@ -46,57 +58,69 @@ pub fn Users() -> Element {
         });
     };
 
-    // 搜索用户(前端过滤)
-    let on_search = move || {
-        let keyword = search_keyword.read().clone().to_lowercase();
+    // 删除用户
+    let delete_user = move |qq: String| {
         spawn(async move {
             loading.set(true);
-            match api::get_users().await {
-                Ok(user_list) => {
-                    let filtered: Vec<_> = if keyword.is_empty() {
-                        user_list
-                    } else {
-                        user_list
-                            .into_iter()
-                            .filter(|u| {
-                                u.qq.to_lowercase().contains(&keyword)
-                                    || u.nickname.to_lowercase().contains(&keyword)
-                            })
-                            .collect()
-                    };
-                    users.set(filtered);
+            match api::delete_user(&qq).await {
+                Ok(_) => {
                     error.set(None);
+                    load_users(); // 重新加载列表
                 }
                 Err(e) => {
-                    error.set(Some(format!("搜索失败: {}", e)));
+                    error.set(Some(format!("删除失败: {}", e)));
+                    loading.set(false);
                 }
             }
-            loading.set(false);
         });
     };
 
-    // 删除用户
-    let delete_user = move |qq: String| {
+    // 禁用/启用账号
+    let toggle_active = move |qq: String, active: bool| {
         spawn(async move {
             loading.set(true);
-            match api::delete_user(&qq).await {
+            match api::set_user_active(&qq, active).await {
                 Ok(_) => {
                     error.set(None);
-                    load_users(); // 重新加载列表
+                    load_users();
                 }
                 Err(e) => {
-                    error.set(Some(format!("删除失败: {}", e)));
+                    error.set(Some(format!("操作失败: {}", e)));
                     loading.set(false);
                 }
             }
         });
     };
 
+    // 查看用户权限
+    let view_permissions = move |qq: String| {
+        spawn(async move {
+            match api::get_user_permissions(&qq).await {
+                Ok(perms) => {
+                    permission_view.set(Some((qq, perms)));
+                    error.set(None);
+                }
+                Err(e) => {
+                    error.set(Some(format!("获取权限失败: {}", e)));
+                }
+            }
+        });
+    };
+
     // 初始加载
     use_effect(move || {
         load_users();
     });
 
+    // 角色筛选下拉框的选项
+    use_hook(|| {
+        spawn(async move {
+            if let Ok(role_list) = api::get_roles().await {
+                roles.set(role_list);
+            }
+        });
+    });
+
     rsx! {
         div { class: "page-container",
             h1 { "用户管理" }
@@ -110,9 +134,22 @@ pub fn Users() -> Element {
                         oninput: move |evt| search_keyword.set(evt.value().clone()),
                         disabled: *loading.read()
                     }
+                    select {
+                        class: "page-size-select",
+                        value: role_filter().map(|id| id.to_string()).unwrap_or_default(),
+                        onchange: move |evt| {
+                            role_filter.set(evt.value().parse::<i64>().ok());
+                            load_users();
+                        },
+                        disabled: *loading.read(),
+                        option { value: "", "全部角色" }
+                        for role in roles.read().iter() {
+                            option { key: "{role.role_id}", value: "{role.role_id}", "{role.name}" }
+                        }
+                    }
                     button {
                         class: "btn-primary",
-                        onclick: move |_| on_search(),
+                        onclick: move |_| load_users(),
                         disabled: *loading.read(),
                         "搜索"
                     }
@@ -146,55 +183,249 @@ pub fn Users() -> Element {
                     }
                     tbody {
                         for user in users.read().iter() {
-                            tr {
-                                key: "{user.qq}",
-                                td { "{user.qq}" }
-                                td { "{user.nickname}" }
-                                td {
-                                    if let Some(role_name) = &user.role_name {
-                                        span {
-                                            style: "padding: 2px 8px; background: #e3f2fd; color: #1976d2; border-radius: 4px; font-size: 12px;",
-                                            "{role_name}"
+                            {
+                                // 当前登录的受保护管理员不能禁用或删除自己，不显示对应按钮
+                                let is_protected = current_user
+                                    .read()
+                                    .as_ref()
+                                    .is_some_and(|current| {
+                                        current.is_protected_admin && current.qq == user.qq
+                                    });
+                                rsx! {
+                                    tr {
+                                        key: "{user.qq}",
+                                        td { "{user.qq}" }
+                                        td {
+                                            "{user.nickname}"
+                                            if !user.is_active {
+                                                span { class: "badge badge-danger", style: "margin-left: 6px;", "已禁用" }
+                                            }
                                         }
-                                    } else {
-                                        span { style: "color: #999;", "无角色" }
+                                        td {
+                                            if let Some(role_name) = &user.role_name {
+                                                span {
+                                                    style: "padding: 2px 8px; background: #e3f2fd; color: #1976d2; border-radius: 4px; font-size: 12px;",
+                                                    "{role_name}"
+                                                }
+                                            } else {
+                                                span { style: "color: #999;", "无角色" }
+                                            }
+                                        }
+                                        td {
+                                            if let Some(birthday) = &user.birthday {
+                                                "{birthday}"
+                                            } else {
+                                                "-"
+                                            }
+                                        }
+                                        td {
+                                            Link {
+                                                to: crate::Route::Roles {},
+                                                button {
+                                                    class: "btn-small btn-info",
+                                                    title: "跳转到角色管理页面设置用户角色",
+                                                    "设置角色"
+                                                }
+                                            }
+                                            button {
+                                                class: "btn-small btn-secondary",
+                                                onclick: {
+                                                    let qq = user.qq.clone();
+                                                    move |_| view_permissions(qq.clone())
+                                                },
+                                                "查看权限"
+                                            }
+                                            button {
+                                                class: "btn-small btn-secondary",
+                                                onclick: {
+                                                    let qq = user.qq.clone();
+                                                    move |_| timeline_user.set(Some(qq.clone()))
+                                                },
+                                                "时间线"
+                                            }
+                                            if is_protected {
+                                                span { class: "badge", "受保护的管理员" }
+                                            } else if user.is_active {
+                                                button {
+                                                    class: "btn-small btn-warning",
+                                                    onclick: {
+                                                        let qq = user.qq.clone();
+                                                        move |_| toggle_active(qq.clone(), false)
+                                                    },
+                                                    disabled: *loading.read(),
+                                                    "禁用"
+                                                }
+                                            } else {
+                                                button {
+                                                    class: "btn-small btn-info",
+                                                    onclick: {
+                                                        let qq = user.qq.clone();
+                                                        move |_| toggle_active(qq.clone(), true)
+                                                    },
+                                                    disabled: *loading.read(),
+                                                    "启用"
+                                                }
+                                            }
+                                            if !is_protected {
+                                                button {
+                                                    class: "btn-small btn-danger",
+                                                    onclick: {
+                                                        let qq = user.qq.clone();
+                                                        move |_| delete_user(qq.clone())
+                                                    },
+                                                    disabled: *loading.read(),
+                                                    "删除用户"
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { class: "stats",
+                "共 {users.read().len()} 个用户"
+            }
+
+            if let Some((qq, perms)) = permission_view.read().as_ref() {
+                div { class: "info-section",
+                    h2 { "用户 {qq} 的权限" }
+                    if perms.is_empty() {
+                        p { "该用户暂无任何权限" }
+                    } else {
+                        table { class: "data-table",
+                            thead {
+                                tr {
+                                    th { "权限" }
+                                    th { "来源角色" }
+                                }
+                            }
+                            tbody {
+                                for perm in perms.iter() {
+                                    tr {
+                                        key: "{perm.permission}",
+                                        td { "{perm.permission}" }
+                                        td { "{perm.via_role}" }
                                     }
                                 }
-                                td {
-                                    if let Some(birthday) = &user.birthday {
-                                        "{birthday}"
-                                    } else {
-                                        "-"
+                            }
+                        }
+                    }
+                    button {
+                        class: "btn-secondary btn-small",
+                        onclick: move |_| permission_view.set(None),
+                        "关闭"
+                    }
+                }
+            }
+
+            if let Some(qq) = timeline_user.read().clone() {
+                UserTimeline {
+                    key: "{qq}",
+                    qq: qq.clone(),
+                    on_close: move |_| timeline_user.set(None),
+                }
+            }
+        }
+    }
+}
+
+/// 时间线每页条数
+const TIMELINE_PAGE_SIZE: i32 = 20;
+
+/// 用户时间线：按时间倒序合并显示LP记录、商店交易和请求日志
+#[component]
+fn UserTimeline(qq: String, on_close: EventHandler<()>) -> Element {
+    let mut page = use_signal(|| 0i32);
+
+    let target_qq = qq.clone();
+    let timeline = use_resource(move || {
+        let qq = target_qq.clone();
+        async move {
+            let offset = *page.read() * TIMELINE_PAGE_SIZE;
+            api::get_user_timeline(&qq, TIMELINE_PAGE_SIZE, offset).await
+        }
+    });
+
+    let current_page = *page.read();
+
+    rsx! {
+        div { class: "info-section",
+            h2 { "用户 {qq} 的时间线" }
+            match &*timeline.read() {
+                None => rsx! {
+                    div { class: "loading-message", "加载中..." }
+                },
+                Some(Err(e)) => rsx! {
+                    div { class: "error-message", "获取时间线失败: {e}" }
+                },
+                Some(Ok(resp)) => {
+                    let total_pages = ((resp.total + TIMELINE_PAGE_SIZE as i64 - 1)
+                        / TIMELINE_PAGE_SIZE as i64) as i32;
+                    rsx! {
+                        if resp.events.is_empty() {
+                            p { "暂无记录" }
+                        } else {
+                            table { class: "data-table",
+                                thead {
+                                    tr {
+                                        th { "时间" }
+                                        th { "类型" }
+                                        th { "详情" }
                                     }
                                 }
-                                td {
-                                    Link {
-                                        to: crate::Route::Roles {},
-                                        button {
-                                            class: "btn-small btn-info",
-                                            title: "跳转到角色管理页面设置用户角色",
-                                            "设置角色"
+                                tbody {
+                                    for (idx, event) in resp.events.iter().enumerate() {
+                                        tr {
+                                            key: "{idx}",
+                                            td { "{event.timestamp}" }
+                                            td { {timeline_kind_badge(&event.kind)} }
+                                            td { "{event.detail}" }
                                         }
                                     }
-                                    button {
-                                        class: "btn-small btn-danger",
-                                        onclick: {
-                                            let qq = user.qq.clone();
-                                            move |_| delete_user(qq.clone())
-                                        },
-                                        disabled: *loading.read(),
-                                        "删除用户"
-                                    }
+                                }
+                            }
+                        }
+                        div { class: "pagination-bar",
+                            span { "共 {resp.total} 条，第 {current_page + 1} / {total_pages.max(1)} 页" }
+                            div { class: "pagination-actions",
+                                button {
+                                    class: "btn-secondary",
+                                    disabled: current_page == 0,
+                                    onclick: move |_| page -= 1,
+                                    "上一页"
+                                }
+                                button {
+                                    class: "btn-secondary",
+                                    disabled: current_page + 1 >= total_pages,
+                                    onclick: move |_| page += 1,
+                                    "下一页"
                                 }
                             }
                         }
                     }
                 }
             }
-
-            div { class: "stats",
-                "共 {users.read().len()} 个用户"
+            button {
+                class: "btn-secondary btn-small",
+                onclick: move |_| on_close.call(()),
+                "关闭"
             }
         }
     }
 }
+
+fn timeline_kind_badge(kind: &str) -> Element {
+    let (class, label) = match kind {
+        "lp" => ("badge badge-warning", "LP"),
+        "purchase" => ("badge badge-info", "购买"),
+        "sale" => ("badge badge-success", "销售"),
+        _ => ("badge", "请求"),
+    };
+    rsx! {
+        span { class: "{class}", "{label}" }
+    }
+}
@@ -0,0 +1,32 @@
+//! 注册用的工作量证明：前端寻找一个 `solution`，使
+//! `SHA-256("{nonce}:{solution}")` 的前导零比特数不少于 `difficulty`，后端只需计算一次哈希即可校验。
+
+use sha2::{Digest, Sha256};
+
+/// 计算哈希的前导零比特数
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+/// 检查解答是否满足难度要求
+pub fn verify(nonce: &str, solution: &str, difficulty: u32) -> bool {
+    let hash = Sha256::digest(format!("{}:{}", nonce, solution).as_bytes());
+    leading_zero_bits(&hash) >= difficulty
+}
+
+/// 从 `start` 开始依次尝试最多 `attempts` 个候选解，找到时返回解答。
+/// 分批调用可以让浏览器在求解过程中保持响应
+pub fn solve_range(nonce: &str, difficulty: u32, start: u64, attempts: u64) -> Option<String> {
+    (start..start.saturating_add(attempts))
+        .map(|candidate| candidate.to_string())
+        .find(|candidate| verify(nonce, candidate, difficulty))
+}